@@ -0,0 +1,323 @@
+//! subset of the clap 1.2 c abi this plugin speaks, declared by hand after
+//! clap's headers: entry, factory, plugin, process, events, and the audio
+//! ports, note ports and params extensions
+
+use core::ffi::{c_char, c_void};
+
+pub const CLAP_VERSION: ClapVersion = ClapVersion {
+    major: 1,
+    minor: 2,
+    revision: 2,
+};
+pub const CLAP_NAME_SIZE: usize = 256;
+pub const CLAP_PATH_SIZE: usize = 1024;
+pub const CLAP_INVALID_ID: u32 = u32::MAX;
+pub const CLAP_BEATTIME_FACTOR: f64 = (1i64 << 31) as f64;
+
+pub const CLAP_PLUGIN_FACTORY_ID: &core::ffi::CStr = c"clap.plugin-factory";
+pub const CLAP_EXT_AUDIO_PORTS: &core::ffi::CStr = c"clap.audio-ports";
+pub const CLAP_EXT_NOTE_PORTS: &core::ffi::CStr = c"clap.note-ports";
+pub const CLAP_EXT_PARAMS: &core::ffi::CStr = c"clap.params";
+pub const CLAP_PORT_STEREO: &core::ffi::CStr = c"stereo";
+
+pub const CLAP_PROCESS_ERROR: i32 = 0;
+pub const CLAP_PROCESS_CONTINUE: i32 = 1;
+
+pub const CLAP_CORE_EVENT_SPACE_ID: u16 = 0;
+pub const CLAP_EVENT_NOTE_ON: u16 = 0;
+pub const CLAP_EVENT_NOTE_OFF: u16 = 1;
+pub const CLAP_EVENT_PARAM_VALUE: u16 = 5;
+pub const CLAP_EVENT_MIDI: u16 = 10;
+
+pub const CLAP_TRANSPORT_HAS_TEMPO: u32 = 1 << 0;
+pub const CLAP_TRANSPORT_HAS_BEATS_TIMELINE: u32 = 1 << 1;
+pub const CLAP_TRANSPORT_IS_PLAYING: u32 = 1 << 4;
+
+pub const CLAP_AUDIO_PORT_IS_MAIN: u32 = 1 << 0;
+pub const CLAP_NOTE_DIALECT_CLAP: u32 = 1 << 0;
+pub const CLAP_NOTE_DIALECT_MIDI: u32 = 1 << 1;
+
+pub const CLAP_PARAM_IS_AUTOMATABLE: u32 = 1 << 5;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ClapVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub revision: u32,
+}
+
+#[repr(C)]
+pub struct ClapPluginEntry {
+    pub clap_version: ClapVersion,
+    pub init: unsafe extern "C" fn(plugin_path: *const c_char) -> bool,
+    pub deinit: unsafe extern "C" fn(),
+    pub get_factory: unsafe extern "C" fn(factory_id: *const c_char) -> *const c_void,
+}
+
+#[repr(C)]
+pub struct ClapPluginFactory {
+    pub get_plugin_count: unsafe extern "C" fn(factory: *const ClapPluginFactory) -> u32,
+    pub get_plugin_descriptor: unsafe extern "C" fn(
+        factory: *const ClapPluginFactory,
+        index: u32,
+    ) -> *const ClapPluginDescriptor,
+    pub create_plugin: unsafe extern "C" fn(
+        factory: *const ClapPluginFactory,
+        host: *const ClapHost,
+        plugin_id: *const c_char,
+    ) -> *const ClapPlugin,
+}
+
+#[repr(C)]
+pub struct ClapPluginDescriptor {
+    pub clap_version: ClapVersion,
+    pub id: *const c_char,
+    pub name: *const c_char,
+    pub vendor: *const c_char,
+    pub url: *const c_char,
+    pub manual_url: *const c_char,
+    pub support_url: *const c_char,
+    pub version: *const c_char,
+    pub description: *const c_char,
+    /// null-terminated
+    pub features: *const *const c_char,
+}
+
+// only ever points at static strings
+unsafe impl Sync for ClapPluginDescriptor {}
+
+/// null-terminated array of static strings
+#[repr(transparent)]
+pub struct Features<const N: usize>(pub [*const c_char; N]);
+
+unsafe impl<const N: usize> Sync for Features<N> {}
+
+#[repr(C)]
+pub struct ClapHost {
+    pub clap_version: ClapVersion,
+    pub host_data: *mut c_void,
+    pub name: *const c_char,
+    pub vendor: *const c_char,
+    pub url: *const c_char,
+    pub version: *const c_char,
+    pub get_extension:
+        unsafe extern "C" fn(host: *const ClapHost, extension_id: *const c_char) -> *const c_void,
+    pub request_restart: unsafe extern "C" fn(host: *const ClapHost),
+    pub request_process: unsafe extern "C" fn(host: *const ClapHost),
+    pub request_callback: unsafe extern "C" fn(host: *const ClapHost),
+}
+
+#[repr(C)]
+pub struct ClapPlugin {
+    pub desc: *const ClapPluginDescriptor,
+    pub plugin_data: *mut c_void,
+    pub init: unsafe extern "C" fn(plugin: *const ClapPlugin) -> bool,
+    pub destroy: unsafe extern "C" fn(plugin: *const ClapPlugin),
+    pub activate: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        sample_rate: f64,
+        min_frames_count: u32,
+        max_frames_count: u32,
+    ) -> bool,
+    pub deactivate: unsafe extern "C" fn(plugin: *const ClapPlugin),
+    pub start_processing: unsafe extern "C" fn(plugin: *const ClapPlugin) -> bool,
+    pub stop_processing: unsafe extern "C" fn(plugin: *const ClapPlugin),
+    pub reset: unsafe extern "C" fn(plugin: *const ClapPlugin),
+    pub process:
+        unsafe extern "C" fn(plugin: *const ClapPlugin, process: *const ClapProcess) -> i32,
+    pub get_extension:
+        unsafe extern "C" fn(plugin: *const ClapPlugin, id: *const c_char) -> *const c_void,
+    pub on_main_thread: unsafe extern "C" fn(plugin: *const ClapPlugin),
+}
+
+#[repr(C)]
+pub struct ClapProcess {
+    pub steady_time: i64,
+    pub frames_count: u32,
+    /// null if the host has no transport
+    pub transport: *const ClapEventTransport,
+    pub audio_inputs: *const ClapAudioBuffer,
+    pub audio_outputs: *mut ClapAudioBuffer,
+    pub audio_inputs_count: u32,
+    pub audio_outputs_count: u32,
+    pub in_events: *const ClapInputEvents,
+    pub out_events: *const ClapOutputEvents,
+}
+
+#[repr(C)]
+pub struct ClapAudioBuffer {
+    /// a buffer per channel, null if offered at 64-bit only
+    pub data32: *mut *mut f32,
+    pub data64: *mut *mut f64,
+    pub channel_count: u32,
+    pub latency: u32,
+    pub constant_mask: u64,
+}
+
+#[repr(C)]
+pub struct ClapInputEvents {
+    pub ctx: *mut c_void,
+    pub size: unsafe extern "C" fn(list: *const ClapInputEvents) -> u32,
+    pub get:
+        unsafe extern "C" fn(list: *const ClapInputEvents, index: u32) -> *const ClapEventHeader,
+}
+
+#[repr(C)]
+pub struct ClapOutputEvents {
+    pub ctx: *mut c_void,
+    pub try_push:
+        unsafe extern "C" fn(list: *const ClapOutputEvents, event: *const ClapEventHeader) -> bool,
+}
+
+#[repr(C)]
+pub struct ClapEventHeader {
+    /// bytes of event, header included
+    pub size: u32,
+    /// frame offset into the process block
+    pub time: u32,
+    pub space_id: u16,
+    pub type_: u16,
+    pub flags: u32,
+}
+
+#[repr(C)]
+pub struct ClapEventNote {
+    pub header: ClapEventHeader,
+    pub note_id: i32,
+    pub port_index: i16,
+    pub channel: i16,
+    pub key: i16,
+    pub velocity: f64,
+}
+
+#[repr(C)]
+pub struct ClapEventParamValue {
+    pub header: ClapEventHeader,
+    pub param_id: u32,
+    pub cookie: *mut c_void,
+    pub note_id: i32,
+    pub port_index: i16,
+    pub channel: i16,
+    pub key: i16,
+    pub value: f64,
+}
+
+#[repr(C)]
+pub struct ClapEventMidi {
+    pub header: ClapEventHeader,
+    pub port_index: u16,
+    pub data: [u8; 3],
+}
+
+#[repr(C)]
+pub struct ClapEventTransport {
+    pub header: ClapEventHeader,
+    pub flags: u32,
+    /// in beats scaled by `CLAP_BEATTIME_FACTOR`
+    pub song_pos_beats: i64,
+    pub song_pos_seconds: i64,
+    pub tempo: f64,
+    pub tempo_inc: f64,
+    pub loop_start_beats: i64,
+    pub loop_end_beats: i64,
+    pub loop_start_seconds: i64,
+    pub loop_end_seconds: i64,
+    pub bar_start: i64,
+    pub bar_number: i32,
+    pub tsig_num: u16,
+    pub tsig_denom: u16,
+}
+
+#[repr(C)]
+pub struct ClapPluginAudioPorts {
+    pub count: unsafe extern "C" fn(plugin: *const ClapPlugin, is_input: bool) -> u32,
+    pub get: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        index: u32,
+        is_input: bool,
+        info: *mut ClapAudioPortInfo,
+    ) -> bool,
+}
+
+#[repr(C)]
+pub struct ClapAudioPortInfo {
+    pub id: u32,
+    pub name: [c_char; CLAP_NAME_SIZE],
+    pub flags: u32,
+    pub channel_count: u32,
+    pub port_type: *const c_char,
+    pub in_place_pair: u32,
+}
+
+#[repr(C)]
+pub struct ClapPluginNotePorts {
+    pub count: unsafe extern "C" fn(plugin: *const ClapPlugin, is_input: bool) -> u32,
+    pub get: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        index: u32,
+        is_input: bool,
+        info: *mut ClapNotePortInfo,
+    ) -> bool,
+}
+
+#[repr(C)]
+pub struct ClapNotePortInfo {
+    pub id: u32,
+    pub supported_dialects: u32,
+    pub preferred_dialect: u32,
+    pub name: [c_char; CLAP_NAME_SIZE],
+}
+
+#[repr(C)]
+pub struct ClapPluginParams {
+    pub count: unsafe extern "C" fn(plugin: *const ClapPlugin) -> u32,
+    pub get_info: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        param_index: u32,
+        param_info: *mut ClapParamInfo,
+    ) -> bool,
+    pub get_value:
+        unsafe extern "C" fn(plugin: *const ClapPlugin, param_id: u32, value: *mut f64) -> bool,
+    pub value_to_text: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        param_id: u32,
+        value: f64,
+        display: *mut c_char,
+        size: u32,
+    ) -> bool,
+    pub text_to_value: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        param_id: u32,
+        display: *const c_char,
+        value: *mut f64,
+    ) -> bool,
+    pub flush: unsafe extern "C" fn(
+        plugin: *const ClapPlugin,
+        in_events: *const ClapInputEvents,
+        out_events: *const ClapOutputEvents,
+    ),
+}
+
+#[repr(C)]
+pub struct ClapParamInfo {
+    pub id: u32,
+    pub flags: u32,
+    pub cookie: *mut c_void,
+    pub name: [c_char; CLAP_NAME_SIZE],
+    pub module: [c_char; CLAP_PATH_SIZE],
+    pub min_value: f64,
+    pub max_value: f64,
+    pub default_value: f64,
+}
+
+/// copy `src` into c string buffer `dst`, truncated to fit its terminator
+pub fn write_str(dst: &mut [c_char], src: &str) {
+    let len = src.len().min(dst.len().saturating_sub(1));
+    for (dst, src) in dst.iter_mut().zip(&src.as_bytes()[..len]) {
+        *dst = *src as c_char;
+    }
+    if let Some(end) = dst.get_mut(len) {
+        *end = 0;
+    }
+}
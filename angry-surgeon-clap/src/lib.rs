@@ -0,0 +1,665 @@
+//! clap plugin build of the mangler, so it lives inside a daw session: the
+//! linux host's `SystemHandler` run from the process callback, knobs of both
+//! banks exposed as automatable parameters, host notes and midi fed to the
+//! input handler as a controller's would be, and the step clock following
+//! host transport
+//!
+//! events are applied at the start of the block they arrive in. onsets, banks
+//! and mapping resolve against the host's working directory, as for the
+//! terminal binary
+
+mod abi;
+
+use abi::*;
+use angry_surgeon_linux::{
+    alloc_check, audio, fs, input, mapping, output,
+    params::{BankParams, Param, Params},
+    resample,
+    transport::{self, MAX_CYCLE_TICKS},
+    tui,
+};
+use color_eyre::Result;
+use core::ffi::{c_char, c_void, CStr};
+use std::sync::{
+    mpsc::{Sender, SyncSender},
+    Arc,
+};
+
+const PLUGIN_ID: &CStr = c"org.franular.angry-surgeon";
+const VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(v) => v,
+        Err(_) => panic!(),
+    };
+/// stereo in and out
+const CHANNELS: usize = 2;
+/// midi messages queued by the process callback before the input thread
+/// drains them; further ones dropped rather than allocating
+const MIDI_QUEUE_LEN: usize = 256;
+/// longest midi message passed on
+const MIDI_LEN: usize = 3;
+/// knobs of each bank: name, then range and default in the units its param
+/// takes, as written by the input handler
+const KNOBS: [(&str, f64, f64, f64); 15] = [
+    ("gain", 0., 1., 0.5),
+    ("width", 0., 1., 0.5),
+    ("speed", 0., 2., 1.),
+    ("roll", 0., 1., 1.),
+    ("drift", 0., 1., 0.),
+    ("phrase drift", 0., 1., 0.),
+    ("humanize", 0., 1., 0.),
+    ("cutoff", 0., 1., 1.),
+    ("resonance", 0., 1., 0.),
+    ("accent", 1., 3., 1.5),
+    ("attack", 0., 0.5, 0.),
+    ("release", 0., 2., 0.),
+    ("send", 0., 1., 0.),
+    ("sensitivity", 0., 1., 0.),
+    ("density", 0., 1., 0.),
+];
+
+static FEATURES: Features<4> = Features([
+    c"instrument".as_ptr(),
+    c"sampler".as_ptr(),
+    c"stereo".as_ptr(),
+    core::ptr::null(),
+]);
+
+static DESCRIPTOR: ClapPluginDescriptor = ClapPluginDescriptor {
+    clap_version: CLAP_VERSION,
+    id: PLUGIN_ID.as_ptr(),
+    name: c"angry surgeon".as_ptr(),
+    vendor: c"franular".as_ptr(),
+    url: c"".as_ptr(),
+    manual_url: c"".as_ptr(),
+    support_url: c"".as_ptr(),
+    version: VERSION.as_ptr(),
+    description: c"sample mangler for midi controllers".as_ptr(),
+    features: FEATURES.0.as_ptr(),
+};
+
+#[allow(non_upper_case_globals)]
+#[no_mangle]
+pub static clap_entry: ClapPluginEntry = ClapPluginEntry {
+    clap_version: CLAP_VERSION,
+    init: entry_init,
+    deinit: entry_deinit,
+    get_factory: entry_get_factory,
+};
+
+static FACTORY: ClapPluginFactory = ClapPluginFactory {
+    get_plugin_count: factory_get_plugin_count,
+    get_plugin_descriptor: factory_get_plugin_descriptor,
+    create_plugin: factory_create_plugin,
+};
+
+static AUDIO_PORTS: ClapPluginAudioPorts = ClapPluginAudioPorts {
+    count: audio_ports_count,
+    get: audio_ports_get,
+};
+
+static NOTE_PORTS: ClapPluginNotePorts = ClapPluginNotePorts {
+    count: note_ports_count,
+    get: note_ports_get,
+};
+
+static PARAMS: ClapPluginParams = ClapPluginParams {
+    count: params_count,
+    get_info: params_get_info,
+    get_value: params_get_value,
+    value_to_text: params_value_to_text,
+    text_to_value: params_text_to_value,
+    flush: params_flush,
+};
+
+/// param of knob `index` in `KNOBS`
+fn knob(params: &BankParams, index: usize) -> Option<&Param> {
+    Some(match index {
+        0 => &params.gain,
+        1 => &params.width,
+        2 => &params.pitch,
+        3 => &params.roll,
+        4 => &params.kit_drift,
+        5 => &params.phrase_drift,
+        6 => &params.humanize,
+        7 => &params.cutoff,
+        8 => &params.resonance,
+        9 => &params.accent,
+        10 => &params.attack,
+        11 => &params.release,
+        12 => &params.send,
+        13 => &params.sensitivity,
+        14 => &params.density,
+        _ => return None,
+    })
+}
+
+/// bank index and knob index of param `id`, if any
+fn param_id(id: u32) -> Option<(usize, usize)> {
+    let id = id as usize;
+    (id < audio::BANK_COUNT * KNOBS.len()).then_some((id / KNOBS.len(), id % KNOBS.len()))
+}
+
+/// everything built at a sample rate, kept across deactivation while that
+/// holds
+struct Engine {
+    sample_rate: u32,
+    handler: audio::SystemHandler,
+    midi_tx: SyncSender<([u8; MIDI_LEN], usize)>,
+    tui_tx: Sender<tui::Cmd>,
+    /// held open, as the input handler errs once it hangs up
+    _input_tx: Sender<input::Cmd>,
+    follow: transport::Follow,
+    ticks: heapless::Vec<usize, MAX_CYCLE_TICKS>,
+    scratch: Vec<f32>,
+    capture: Vec<f32>,
+}
+
+impl Engine {
+    /// build handlers for `sample_rate` and blocks up to `max_len` frames;
+    /// midi handled and log lines printed on threads of their own
+    fn new(params: Arc<Params>, sample_rate: u32, max_len: usize) -> Result<Self> {
+        params
+            .sample_rate
+            .store(sample_rate, std::sync::atomic::Ordering::Relaxed);
+        params
+            .channels
+            .store(CHANNELS as u16, std::sync::atomic::Ordering::Relaxed);
+        fs::CAPTURE.assign_sample_rate(sample_rate);
+
+        let (audio_tx, audio_rx) = std::sync::mpsc::channel::<audio::Cmd>();
+        let (input_tx, input_rx) = std::sync::mpsc::channel::<input::Cmd>();
+        let (tui_tx, tui_rx) = std::sync::mpsc::channel::<tui::Cmd>();
+        let mapping = mapping::Mapping::from_args(&[])?;
+        let output = output::Output::from_args(&[])?;
+        let mut input_handler =
+            input::InputHandler::new(params.clone(), audio_tx, tui_tx.clone(), input_rx, mapping);
+        let handler = audio::SystemHandler::new(params, audio_rx, tui_tx.clone(), None, output)?;
+
+        let (midi_tx, midi_rx) =
+            std::sync::mpsc::sync_channel::<([u8; MIDI_LEN], usize)>(MIDI_QUEUE_LEN);
+        std::thread::spawn(move || {
+            while let Ok((message, len)) = midi_rx.recv() {
+                if let Err(e) = input_handler.push_midi(&message[..len]) {
+                    eprintln!("angry-surgeon: {}", e);
+                }
+            }
+        });
+        // no terminal to draw; log lines, banks to write and takes to resample
+        // only
+        std::thread::spawn(move || {
+            while let Ok(cmd) = tui_rx.recv() {
+                match cmd {
+                    tui::Cmd::Log(msg) => eprintln!("angry-surgeon: {}", msg),
+                    tui::Cmd::SaveBank(path, bytes) => match tui::write_bank(&path, bytes) {
+                        Ok(()) => eprintln!("angry-surgeon: saved to ./{}!", path),
+                        Err(e) => eprintln!("angry-surgeon: not saved to ./{}: {}", path, e),
+                    },
+                    tui::Cmd::Recorded(take) => match resample::resample(&take) {
+                        Ok(path) => eprintln!("angry-surgeon: resampled ./{}", path),
+                        Err(e) => eprintln!("angry-surgeon: no resample: {}", e),
+                    },
+                    _ => (),
+                }
+            }
+        });
+
+        Ok(Self {
+            sample_rate,
+            handler,
+            midi_tx,
+            tui_tx,
+            _input_tx: input_tx,
+            follow: transport::Follow::new(sample_rate),
+            ticks: heapless::Vec::new(),
+            scratch: Vec::with_capacity(audio::SCRATCH_LEN.max(max_len * CHANNELS)),
+            capture: Vec::with_capacity(max_len * CHANNELS),
+        })
+    }
+}
+
+struct Plugin {
+    clap: ClapPlugin,
+    params: Arc<Params>,
+    engine: Option<Engine>,
+}
+
+impl Plugin {
+    /// write param values and pass midi and notes of `events` on, notes
+    /// only while active
+    ///
+    /// # Safety
+    /// `events` null or a valid host event list
+    unsafe fn apply(&self, events: *const ClapInputEvents) {
+        let Some(events) = (unsafe { events.as_ref() }) else {
+            return;
+        };
+        for index in 0..unsafe { (events.size)(events) } {
+            let header = unsafe { (events.get)(events, index) };
+            let Some(header_ref) = (unsafe { header.as_ref() }) else {
+                continue;
+            };
+            if header_ref.space_id != CLAP_CORE_EVENT_SPACE_ID {
+                continue;
+            }
+            let message = match header_ref.type_ {
+                CLAP_EVENT_PARAM_VALUE => {
+                    let event = unsafe { &*(header as *const ClapEventParamValue) };
+                    if let Some((bank, index)) = param_id(event.param_id) {
+                        let (_, min, max, _) = KNOBS[index];
+                        let value = event.value.clamp(min, max) as f32;
+                        if let Some(param) = knob(&self.params.banks[bank], index) {
+                            param.write(value);
+                        }
+                    }
+                    continue;
+                }
+                CLAP_EVENT_NOTE_ON | CLAP_EVENT_NOTE_OFF => {
+                    let event = unsafe { &*(header as *const ClapEventNote) };
+                    let Ok(key) = u8::try_from(event.key) else {
+                        continue;
+                    };
+                    let status = match header_ref.type_ {
+                        CLAP_EVENT_NOTE_ON => 0x90,
+                        _ => 0x80,
+                    };
+                    let channel = event.channel.clamp(0, 15) as u8;
+                    let velocity = (event.velocity.clamp(0., 1.) * 127.).round() as u8;
+                    ([status | channel, key.min(127), velocity], MIDI_LEN)
+                }
+                CLAP_EVENT_MIDI => {
+                    let event = unsafe { &*(header as *const ClapEventMidi) };
+                    let len = match event.data[0] & 0xf0 {
+                        0xc0 | 0xd0 => 2,
+                        0xf0 => 1,
+                        _ => MIDI_LEN,
+                    };
+                    (event.data, len)
+                }
+                _ => continue,
+            };
+            if let Some(engine) = &self.engine {
+                let _ = engine.midi_tx.try_send(message);
+            }
+        }
+    }
+
+    /// render a block, following host transport
+    ///
+    /// # Safety
+    /// `process` a valid host process block for this plugin's ports
+    unsafe fn process(&mut self, process: &ClapProcess) -> Result<()> {
+        unsafe { self.apply(process.in_events) };
+        let Some(engine) = self.engine.as_mut() else {
+            return Err(color_eyre::Report::msg("processing while inactive"));
+        };
+        let len = process.frames_count as usize;
+        let position = unsafe { process.transport.as_ref() }
+            .filter(|v| {
+                let flags = CLAP_TRANSPORT_IS_PLAYING
+                    | CLAP_TRANSPORT_HAS_TEMPO
+                    | CLAP_TRANSPORT_HAS_BEATS_TIMELINE;
+                v.flags & flags == flags
+            })
+            .map(|v| transport::Position {
+                beats: v.song_pos_beats as f64 / CLAP_BEATTIME_FACTOR,
+                bpm: v.tempo,
+            });
+
+        engine.capture.clear();
+        let input = match process.audio_inputs_count {
+            0 => None,
+            _ => unsafe { process.audio_inputs.as_ref() },
+        };
+        if let Some(input) = input.filter(|v| !v.data32.is_null() && v.channel_count > 0) {
+            let channels = input.channel_count as usize;
+            let data = unsafe { core::slice::from_raw_parts(input.data32, channels) };
+            for frame in 0..len {
+                engine
+                    .capture
+                    .extend(data.iter().map(|v| unsafe { *v.add(frame) }));
+            }
+            fs::CAPTURE.push(&engine.capture, channels);
+            self.params.dry.push(&engine.capture, channels);
+        }
+
+        let Engine {
+            handler,
+            tui_tx,
+            follow,
+            ticks,
+            scratch,
+            ..
+        } = engine;
+        let (ret, allocs) = alloc_check::guard(|| {
+            follow.cycle(position, len, handler, tui_tx, ticks)?;
+            scratch.resize(len * CHANNELS, 0.);
+            handler.tick_at(scratch, CHANNELS, ticks)
+        });
+        if allocs > 0 {
+            let msg = format!("{} allocations in audio callback", allocs);
+            let _ = tui_tx.send(tui::Cmd::Log(msg));
+        }
+        ret?;
+
+        let output = match process.audio_outputs_count {
+            0 => None,
+            _ => unsafe { process.audio_outputs.as_ref() },
+        };
+        if let Some(output) = output.filter(|v| !v.data32.is_null()) {
+            let channels = (output.channel_count as usize).min(CHANNELS);
+            let data = unsafe { core::slice::from_raw_parts(output.data32, channels) };
+            for (channel, dst) in data.iter().enumerate() {
+                let dst = unsafe { core::slice::from_raw_parts_mut(*dst, len) };
+                for (dst, frame) in dst.iter_mut().zip(scratch.chunks_exact(CHANNELS)) {
+                    *dst = frame[channel];
+                }
+            }
+        }
+        params_latency(&self.params, len, engine.sample_rate);
+        Ok(())
+    }
+}
+
+/// report block length as buffer and latency, as the host owns both
+fn params_latency(params: &Params, len: usize, sample_rate: u32) {
+    let latency = len as u64 * 1_000_000 / sample_rate as u64;
+    params
+        .buffer_frames
+        .store(len as u32, std::sync::atomic::Ordering::Relaxed);
+    params
+        .latency
+        .store(latency as u32, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// # Safety
+/// `plugin` created by `factory_create_plugin`, not yet destroyed
+unsafe fn plugin<'a>(plugin: *const ClapPlugin) -> &'a mut Plugin {
+    unsafe { &mut *((*plugin).plugin_data as *mut Plugin) }
+}
+
+unsafe extern "C" fn entry_init(_plugin_path: *const c_char) -> bool {
+    true
+}
+
+unsafe extern "C" fn entry_deinit() {}
+
+unsafe extern "C" fn entry_get_factory(factory_id: *const c_char) -> *const c_void {
+    if !factory_id.is_null() && unsafe { CStr::from_ptr(factory_id) } == CLAP_PLUGIN_FACTORY_ID {
+        &FACTORY as *const _ as *const c_void
+    } else {
+        core::ptr::null()
+    }
+}
+
+unsafe extern "C" fn factory_get_plugin_count(_factory: *const ClapPluginFactory) -> u32 {
+    1
+}
+
+unsafe extern "C" fn factory_get_plugin_descriptor(
+    _factory: *const ClapPluginFactory,
+    index: u32,
+) -> *const ClapPluginDescriptor {
+    match index {
+        0 => &DESCRIPTOR,
+        _ => core::ptr::null(),
+    }
+}
+
+unsafe extern "C" fn factory_create_plugin(
+    _factory: *const ClapPluginFactory,
+    _host: *const ClapHost,
+    plugin_id: *const c_char,
+) -> *const ClapPlugin {
+    if plugin_id.is_null() || unsafe { CStr::from_ptr(plugin_id) } != PLUGIN_ID {
+        return core::ptr::null();
+    }
+    let plugin = Box::into_raw(Box::new(Plugin {
+        clap: ClapPlugin {
+            desc: &DESCRIPTOR,
+            plugin_data: core::ptr::null_mut(),
+            init: plugin_init,
+            destroy: plugin_destroy,
+            activate: plugin_activate,
+            deactivate: plugin_deactivate,
+            start_processing: plugin_start_processing,
+            stop_processing: plugin_stop_processing,
+            reset: plugin_reset,
+            process: plugin_process,
+            get_extension: plugin_get_extension,
+            on_main_thread: plugin_on_main_thread,
+        },
+        params: Arc::new(Params::new()),
+        engine: None,
+    }));
+    unsafe {
+        (*plugin).clap.plugin_data = plugin as *mut c_void;
+        &(*plugin).clap
+    }
+}
+
+unsafe extern "C" fn plugin_init(_plugin: *const ClapPlugin) -> bool {
+    true
+}
+
+unsafe extern "C" fn plugin_destroy(plugin: *const ClapPlugin) {
+    let plugin = unsafe { (*plugin).plugin_data as *mut Plugin };
+    drop(unsafe { Box::from_raw(plugin) });
+}
+
+unsafe extern "C" fn plugin_activate(
+    plugin: *const ClapPlugin,
+    sample_rate: f64,
+    _min_frames_count: u32,
+    max_frames_count: u32,
+) -> bool {
+    let plugin = unsafe { self::plugin(plugin) };
+    let sample_rate = sample_rate as u32;
+    let max_len = max_frames_count as usize;
+    match &mut plugin.engine {
+        Some(engine) if engine.sample_rate == sample_rate => {
+            engine.scratch.reserve(max_len * CHANNELS);
+            engine.capture.reserve(max_len * CHANNELS);
+        }
+        engine => match Engine::new(plugin.params.clone(), sample_rate, max_len) {
+            Ok(v) => *engine = Some(v),
+            Err(e) => {
+                eprintln!("angry-surgeon: {}", e);
+                return false;
+            }
+        },
+    }
+    true
+}
+
+/// engine kept, so banks and phrases outlast the host pausing dsp
+unsafe extern "C" fn plugin_deactivate(_plugin: *const ClapPlugin) {}
+
+unsafe extern "C" fn plugin_start_processing(_plugin: *const ClapPlugin) -> bool {
+    true
+}
+
+unsafe extern "C" fn plugin_stop_processing(_plugin: *const ClapPlugin) {}
+
+unsafe extern "C" fn plugin_reset(plugin: *const ClapPlugin) {
+    let plugin = unsafe { self::plugin(plugin) };
+    if let Some(engine) = &mut plugin.engine {
+        engine.follow = transport::Follow::new(engine.sample_rate);
+        engine.handler.stop();
+    }
+}
+
+unsafe extern "C" fn plugin_process(plugin: *const ClapPlugin, process: *const ClapProcess) -> i32 {
+    let plugin = unsafe { self::plugin(plugin) };
+    let Some(process) = (unsafe { process.as_ref() }) else {
+        return CLAP_PROCESS_ERROR;
+    };
+    match unsafe { plugin.process(process) } {
+        Ok(()) => CLAP_PROCESS_CONTINUE,
+        Err(e) => {
+            if let Some(engine) = &plugin.engine {
+                let _ = engine.tui_tx.send(tui::Cmd::Log(e.to_string()));
+            }
+            CLAP_PROCESS_ERROR
+        }
+    }
+}
+
+unsafe extern "C" fn plugin_get_extension(
+    _plugin: *const ClapPlugin,
+    id: *const c_char,
+) -> *const c_void {
+    if id.is_null() {
+        return core::ptr::null();
+    }
+    let id = unsafe { CStr::from_ptr(id) };
+    if id == CLAP_EXT_AUDIO_PORTS {
+        &AUDIO_PORTS as *const _ as *const c_void
+    } else if id == CLAP_EXT_NOTE_PORTS {
+        &NOTE_PORTS as *const _ as *const c_void
+    } else if id == CLAP_EXT_PARAMS {
+        &PARAMS as *const _ as *const c_void
+    } else {
+        core::ptr::null()
+    }
+}
+
+unsafe extern "C" fn plugin_on_main_thread(_plugin: *const ClapPlugin) {}
+
+/// a stereo port each way, input feeding live capture and dry throughput
+unsafe extern "C" fn audio_ports_count(_plugin: *const ClapPlugin, _is_input: bool) -> u32 {
+    1
+}
+
+unsafe extern "C" fn audio_ports_get(
+    _plugin: *const ClapPlugin,
+    index: u32,
+    is_input: bool,
+    info: *mut ClapAudioPortInfo,
+) -> bool {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        return false;
+    };
+    if index != 0 {
+        return false;
+    }
+    info.id = 0;
+    write_str(&mut info.name, if is_input { "in" } else { "out" });
+    info.flags = CLAP_AUDIO_PORT_IS_MAIN;
+    info.channel_count = CHANNELS as u32;
+    info.port_type = CLAP_PORT_STEREO.as_ptr();
+    info.in_place_pair = CLAP_INVALID_ID;
+    true
+}
+
+/// a note port in, played as the midi controller's pads and knobs
+unsafe extern "C" fn note_ports_count(_plugin: *const ClapPlugin, is_input: bool) -> u32 {
+    is_input as u32
+}
+
+unsafe extern "C" fn note_ports_get(
+    _plugin: *const ClapPlugin,
+    index: u32,
+    is_input: bool,
+    info: *mut ClapNotePortInfo,
+) -> bool {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        return false;
+    };
+    if index != 0 || !is_input {
+        return false;
+    }
+    info.id = 0;
+    info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
+    info.preferred_dialect = CLAP_NOTE_DIALECT_MIDI;
+    write_str(&mut info.name, "midi in");
+    true
+}
+
+unsafe extern "C" fn params_count(_plugin: *const ClapPlugin) -> u32 {
+    (audio::BANK_COUNT * KNOBS.len()) as u32
+}
+
+unsafe extern "C" fn params_get_info(
+    _plugin: *const ClapPlugin,
+    param_index: u32,
+    param_info: *mut ClapParamInfo,
+) -> bool {
+    let (Some(info), Some((bank, index))) = (unsafe { param_info.as_mut() }, param_id(param_index))
+    else {
+        return false;
+    };
+    let (name, min, max, default) = KNOBS[index];
+    let bank = (b'a' + bank as u8) as char;
+    info.id = param_index;
+    info.flags = CLAP_PARAM_IS_AUTOMATABLE;
+    info.cookie = core::ptr::null_mut();
+    write_str(&mut info.name, &format!("{} {}", bank, name));
+    write_str(&mut info.module, &format!("bank {}", bank));
+    info.min_value = min;
+    info.max_value = max;
+    info.default_value = default;
+    true
+}
+
+unsafe extern "C" fn params_get_value(
+    plugin: *const ClapPlugin,
+    param_id: u32,
+    value: *mut f64,
+) -> bool {
+    let plugin = unsafe { self::plugin(plugin) };
+    let (Some(value), Some((bank, index))) = (unsafe { value.as_mut() }, self::param_id(param_id))
+    else {
+        return false;
+    };
+    let last = knob(&plugin.params.banks[bank], index).and_then(Param::last);
+    *value = last.map_or(KNOBS[index].3, |v| v as f64);
+    true
+}
+
+unsafe extern "C" fn params_value_to_text(
+    _plugin: *const ClapPlugin,
+    param_id: u32,
+    value: f64,
+    display: *mut c_char,
+    size: u32,
+) -> bool {
+    if display.is_null() || self::param_id(param_id).is_none() {
+        return false;
+    }
+    let display = unsafe { core::slice::from_raw_parts_mut(display, size as usize) };
+    write_str(display, &format!("{:.2}", value));
+    true
+}
+
+unsafe extern "C" fn params_text_to_value(
+    _plugin: *const ClapPlugin,
+    param_id: u32,
+    display: *const c_char,
+    value: *mut f64,
+) -> bool {
+    let (Some(value), Some(_)) = (unsafe { value.as_mut() }, self::param_id(param_id)) else {
+        return false;
+    };
+    if display.is_null() {
+        return false;
+    }
+    let display = unsafe { CStr::from_ptr(display) };
+    match display.to_str().ok().and_then(|v| v.trim().parse().ok()) {
+        Some(v) => {
+            *value = v;
+            true
+        }
+        None => false,
+    }
+}
+
+/// param values written outside processing
+unsafe extern "C" fn params_flush(
+    plugin: *const ClapPlugin,
+    in_events: *const ClapInputEvents,
+    _out_events: *const ClapOutputEvents,
+) {
+    let plugin = unsafe { self::plugin(plugin) };
+    unsafe { plugin.apply(in_events) };
+}
@@ -0,0 +1,122 @@
+#ifndef ANGRY_SURGEON_H
+#define ANGRY_SURGEON_H
+
+/* generated by cbindgen from src/capi.rs; do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define AS_BANK_COUNT 2
+
+#define AS_PAD_COUNT 8
+
+#define AS_MAX_PHRASE_COUNT 128
+
+#define AS_MAX_PHRASE_LEN (1 << (AS_PAD_COUNT - 1))
+
+/**
+ * outcome of a call
+ */
+typedef enum AsStatus {
+  AsStatus_Ok,
+  /**
+   * null handle, buffer or path, or bank out of range
+   */
+  AsStatus_BadArgument,
+  AsStatus_BadFormat,
+  AsStatus_DataNotFound,
+  AsStatus_Unsupported,
+  /**
+   * file io failed
+   */
+  AsStatus_Io,
+} AsStatus;
+
+typedef enum AsEventKind {
+  AsEventKind_Sync,
+  AsEventKind_Hold,
+  AsEventKind_Loop,
+} AsEventKind;
+
+/**
+ * opaque system handle
+ */
+typedef struct AsSystem AsSystem;
+
+/**
+ * pad event; `index` read unless sync, `len` in 1/256 steps only for loops
+ */
+typedef struct AsEvent {
+  /**
+   * an `AsEventKind`, as an integer so out-of-range values from c are
+   * rejected rather than undefined
+   */
+  uint32_t kind;
+  uint8_t index;
+  uint16_t len;
+} AsEvent;
+
+/**
+ * create system ticking `ticks_per_step` times a step, rand seeded with
+ * `seed`; free with `as_system_free`
+ */
+struct AsSystem *as_system_new(uint16_t ticks_per_step, uint64_t seed);
+
+/**
+ * free system; null ignored
+ *
+ * # Safety
+ * `system` from `as_system_new`, not freed before
+ */
+void as_system_free(struct AsSystem *system);
+
+/**
+ * one clock tick of every bank
+ *
+ * # Safety
+ * `system` from `as_system_new`, not yet freed
+ */
+enum AsStatus as_system_tick(struct AsSystem *system);
+
+/**
+ * assign tempo in bpm, as clocked by ticks
+ *
+ * # Safety
+ * `system` from `as_system_new`, not yet freed
+ */
+enum AsStatus as_system_assign_tempo(struct AsSystem *system, float tempo);
+
+/**
+ * render `len` interleaved samples of `channels` into `buffer`, overwriting
+ * it, master width and fade applied
+ *
+ * # Safety
+ * `system` from `as_system_new`, not yet freed; `buffer` valid for `len`
+ * writes
+ */
+enum AsStatus as_system_read(struct AsSystem *system,
+                             float *buffer,
+                             size_t len,
+                             size_t channels,
+                             uint32_t sample_rate);
+
+/**
+ * push pad `event` to `bank`, as a full-velocity hit
+ *
+ * # Safety
+ * `system` from `as_system_new`, not yet freed
+ */
+enum AsStatus as_system_push_event(struct AsSystem *system, size_t bank, struct AsEvent event);
+
+/**
+ * load .bd bank at utf-8 `path` into `bank`, fitted to its capacity
+ *
+ * # Safety
+ * `system` from `as_system_new`, not yet freed; `path` null-terminated
+ */
+enum AsStatus as_system_load_bank(struct AsSystem *system, size_t bank, const char *path);
+
+#endif /* ANGRY_SURGEON_H */
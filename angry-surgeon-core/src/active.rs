@@ -1,9 +1,6 @@
 //! stateful data types
 
-use std::mem::MaybeUninit;
-
-use crate::{pads, passive, Error, FileHandler};
-use embedded_io::SeekFrom;
+use crate::{pads, passive, prefetch::Prefetch, Error, Fs};
 use tinyrand::Rand;
 
 #[cfg(not(feature = "std"))]
@@ -11,34 +8,38 @@ use tinyrand::Rand;
 use micromath::F32Ext;
 
 #[derive(Clone)]
-pub(crate) struct Wav<F: FileHandler> {
+pub(crate) struct Wav<F: Fs> {
     pub steps: Option<u16>,
     pub file: F::File,
     pub pcm_start: u64,
     pub pcm_len: u64,
     pub sample_rate: u32,
+    /// of path, keying prefetched blocks
+    pub id: u64,
+    /// onset start in bytes from pcm start, keying sample cache with `id`
+    pub origin: u64,
+    /// playhead in bytes from pcm start; reads go through prefetch, which
+    /// seeks file itself
+    pub pos: u64,
 }
 
-impl<F: FileHandler> Wav<F> {
-    pub fn pos(&mut self, fs: &mut F) -> Result<u64, F::Error> {
-        Ok(fs.stream_position(&mut self.file)? - self.pcm_start)
-    }
-
-    pub fn seek(&mut self, offset: i64, fs: &mut F) -> Result<(), F::Error> {
-        fs.seek(
-            &mut self.file,
-            SeekFrom::Start(self.pcm_start + offset.rem_euclid(self.pcm_len as i64) as u64),
-        )
-        .map(|_| ())
+#[maybe_async::maybe_async]
+impl<F: Fs> Wav<F> {
+    pub fn seek(&mut self, offset: i64) {
+        self.pos = offset.rem_euclid(self.pcm_len as i64) as u64;
     }
 
     // read that loops without crossfade as fallback
-    pub fn read(&mut self, mut bytes: &mut [u8], fs: &mut F) -> Result<(), F::Error> {
+    pub async fn read(
+        &mut self,
+        mut bytes: &mut [u8],
+        cache: &mut Prefetch,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
         while !bytes.is_empty() {
-            let len = bytes.len().min((self.pcm_len - self.pos(fs)?) as usize);
-            let n = fs.read(&mut self.file, &mut bytes[..len])?;
+            let n = cache.read(self, bytes, fs).await?;
             if n == 0 {
-                self.seek(0, fs)?;
+                self.seek(0);
             }
             bytes = &mut bytes[n..];
         }
@@ -46,15 +47,20 @@ impl<F: FileHandler> Wav<F> {
     }
 }
 
-pub(crate) struct Onset<F: FileHandler> {
+pub(crate) struct Onset<F: Fs> {
     /// pad index of source onset
     pub index: u8,
     pub pan: f32,
     pub wav: Wav<F>,
     pub start: u64,
+    pub gain: f32,
+    /// speed multiplier of pad transposition
+    pub pitch: f32,
+    /// frames read since trigger, for envelope
+    pub age: u32,
 }
 
-pub(crate) enum Event<F: FileHandler> {
+pub(crate) enum Event<F: Fs> {
     Sync,
     Hold {
         onset: Onset<F>,
@@ -67,145 +73,164 @@ pub(crate) enum Event<F: FileHandler> {
     },
 }
 
-impl<F: FileHandler> Event<F> {
+#[maybe_async::maybe_async]
+impl<F: Fs> Event<F> {
+    /// trans to `input`, ringing onset replaced out at `velocity` if
+    /// polyphonic, or onset ended out as tail if tails are on
     #[allow(clippy::too_many_arguments)]
-    pub fn trans<const PADS: usize, const STEPS: usize>(
+    pub async fn trans<const PADS: usize, const STEPS: usize>(
         &mut self,
         input: &passive::Event,
         bank: &pads::Bank<PADS, STEPS>,
         kit_index: u8,
-        kit_drift: f32,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
         grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
+        velocity: f32,
         rand: &mut impl Rand,
         fs: &mut F,
     ) -> Result<(), Error<F::Error>> {
+        let voices = match input {
+            // ring ended onsets out as tails if on
+            passive::Event::Sync => voices.filter(|v| v.tails()),
+            // ring onsets out on fresh hits if polyphonic
+            _ => voices.filter(|v| v.enabled()),
+        };
         match input {
             passive::Event::Sync => {
                 if let Event::Hold { onset, .. } | Event::Loop { onset, .. } = self {
-                    grain.fade(Some(&mut onset.wav), fs)?;
-                    // close old file
-                    fs.close(&onset.wav.file)?;
-                    *self = Event::Sync;
+                    grain.fade(Some(&mut onset.wav), fs).await?;
+                }
+                match (core::mem::replace(self, Event::Sync), voices) {
+                    (Event::Hold { onset, .. } | Event::Loop { onset, .. }, Some(voices)) => {
+                        voices.tail(onset, velocity, grain, fs).await?
+                    }
+                    (mut event, _) => event.release(fs).await?,
                 }
             }
             passive::Event::Hold { index } => {
-                match self {
-                    Event::Sync => {
-                        if let Some(kit) = bank.generate_kit(kit_index, kit_drift, rand) {
-                            grain.fade(None, fs)?;
-                            // replace onset; no old file to close
-                            if let Some(onset) = kit.onset_seek(
-                                None,
-                                *index,
-                                pads::Kit::<PADS>::generate_pan(*index),
-                                fs,
-                            )? {
-                                *self = Event::Hold { onset, tick: 0 };
-                            }
-                        }
+                if let Event::Loop { .. } = self {
+                    // recast event variant with same onset
+                    if let Event::Loop { onset, .. } = core::mem::replace(self, Event::Sync) {
+                        *self = Event::Hold { onset, tick: 0 };
                     }
-                    Event::Hold { onset, .. } => {
-                        if let Some(kit) = bank.generate_kit(kit_index, kit_drift, rand) {
-                            grain.fade(Some(&mut onset.wav), fs)?;
-                            // close old file and replace onset
-                            if let Some(onset) = kit.onset_seek(
-                                Some(&onset.wav.file),
-                                *index,
-                                pads::Kit::<PADS>::generate_pan(*index),
-                                fs,
-                            )? {
-                                *self = Event::Hold { onset, tick: 0 };
-                            }
-                        }
+                } else if let Some((kit_index, kit)) =
+                    bank.generate_kit(kit_index, *index, kit_drift, rand)
+                {
+                    match self {
+                        Event::Hold { .. } if voices.is_some() => (),
+                        Event::Hold { onset, .. } => grain.fade(Some(&mut onset.wav), fs).await?,
+                        _ => grain.fade(None, fs).await?,
                     }
-                    Event::Loop { onset, .. } => {
-                        // recast event variant with same onset
-                        let uninit: &mut MaybeUninit<Onset<F>> =
-                            unsafe { core::mem::transmute(onset) };
-                        let mut onset = unsafe {
-                            core::mem::replace(uninit, MaybeUninit::uninit()).assume_init()
-                        };
-                        // i don't know either, girl
-                        onset.wav.file = fs.try_clone(&onset.wav.file)?;
-                        *self = Event::Hold { onset, tick: 0 };
+                    // open before closing old, so it sounds on if none opens
+                    let pan = pads::Kit::<PADS>::generate_pan(*index);
+                    let layer = kit.layer(*index, layer_cursor.get(kit_index, *index), rand);
+                    if let Some(onset) = kit.onset_seek(*index, layer, pan, fs).await? {
+                        let event = Event::Hold { onset, tick: 0 };
+                        self.replace(event, voices, velocity, grain, fs).await?;
                     }
                 }
             }
-            passive::Event::Loop { index, len } => {
-                match self {
-                    Event::Sync => {
-                        if let Some(kit) = bank.generate_kit(kit_index, kit_drift, rand) {
-                            grain.fade(None, fs)?;
-                            // replace onset; no old file to close
-                            if let Some(onset) = kit.onset_seek(
-                                None,
-                                *index,
-                                pads::Kit::<PADS>::generate_pan(*index),
-                                fs,
-                            )? {
-                                *self = Event::Loop {
-                                    onset,
-                                    tick: 0,
-                                    len: *len,
-                                };
-                            }
-                        }
+            passive::Event::Loop { index, len } => match self {
+                Event::Hold { onset, tick } | Event::Loop { onset, tick, .. }
+                    if onset.index == *index =>
+                {
+                    // recast event variant with same onset
+                    let tick = *tick;
+                    if let Event::Hold { onset, .. } | Event::Loop { onset, .. } =
+                        core::mem::replace(self, Event::Sync)
+                    {
+                        *self = Event::Loop {
+                            onset,
+                            tick,
+                            len: *len,
+                        };
                     }
-                    Event::Hold { onset, tick } | Event::Loop { onset, tick, .. } => {
-                        if onset.index == *index {
-                            // recast event variant with same Onset
-                            let uninit: &mut MaybeUninit<Onset<F>> =
-                                unsafe { core::mem::transmute(onset) };
-                            let mut onset = unsafe {
-                                core::mem::replace(uninit, MaybeUninit::uninit()).assume_init()
-                            };
-                            // i don't know either, girl
-                            onset.wav.file = fs.try_clone(&onset.wav.file)?;
-                            *self = Event::Loop {
+                }
+                _ => {
+                    if let Some((kit_index, kit)) =
+                        bank.generate_kit(kit_index, *index, kit_drift, rand)
+                    {
+                        let tick = match self {
+                            Event::Sync => {
+                                grain.fade(None, fs).await?;
+                                0
+                            }
+                            Event::Hold { onset, tick } | Event::Loop { onset, tick, .. } => {
+                                if voices.is_none() {
+                                    grain.fade(Some(&mut onset.wav), fs).await?;
+                                }
+                                *tick
+                            }
+                        };
+                        // open before closing old, so it sounds on if none opens
+                        let pan = pads::Kit::<PADS>::generate_pan(*index);
+                        let next = layer_cursor.get(kit_index, *index);
+                        let layer = kit.layer(*index, next, rand);
+                        if let Some(onset) = kit.onset_seek(*index, layer, pan, fs).await? {
+                            let event = Event::Loop {
                                 onset,
-                                tick: *tick,
+                                tick,
                                 len: *len,
                             };
-                        } else if let Some(kit) = bank.generate_kit(kit_index, kit_drift, rand) {
-                            grain.fade(Some(&mut onset.wav), fs)?;
-                            // close old file and replace onset
-                            if let Some(onset) = kit.onset_seek(
-                                Some(&onset.wav.file),
-                                *index,
-                                pads::Kit::<PADS>::generate_pan(*index),
-                                fs,
-                            )? {
-                                *self = Event::Loop {
-                                    onset,
-                                    tick: *tick,
-                                    len: *len,
-                                };
-                            }
+                            self.replace(event, voices, velocity, grain, fs).await?;
                         }
                     }
                 }
+            },
+        }
+        Ok(())
+    }
+
+    /// replace with `event`, ringing onset replaced, if any, out at
+    /// `velocity` in `voices` if given, else closing its file
+    async fn replace(
+        &mut self,
+        event: Event<F>,
+        voices: Option<&mut pads::Voices<F>>,
+        velocity: f32,
+        grain: &mut pads::GrainReader,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        match (core::mem::replace(self, event), voices) {
+            (Event::Hold { onset, .. } | Event::Loop { onset, .. }, Some(voices)) => {
+                voices.ring(onset, velocity, grain, fs).await
             }
+            (mut event, _) => event.release(fs).await,
+        }
+    }
+
+    /// sync, closing file of onset, if any
+    pub async fn release(&mut self, fs: &mut F) -> Result<(), F::Error> {
+        if let Event::Hold { onset, .. } | Event::Loop { onset, .. } =
+            core::mem::replace(self, Event::Sync)
+        {
+            fs.close(&onset.wav.file).await?;
         }
         Ok(())
     }
 }
 
 /// active event and reverse
-pub(crate) struct Active<F: FileHandler> {
+pub(crate) struct Active<F: Fs> {
     pub event: Event<F>,
     pub reverse: bool,
+    /// gain multiplier of event
+    pub velocity: f32,
 }
 
-impl<F: FileHandler> Default for Active<F> {
+impl<F: Fs> Default for Active<F> {
     fn default() -> Self {
         Self {
             event: Event::Sync,
             reverse: false,
+            velocity: 1.,
         }
     }
 }
 
-impl<F: FileHandler> Active<F> {
+impl<F: Fs> Active<F> {
     pub fn non_sync(&mut self) -> Option<&mut Event<F>> {
         if !matches!(self.event, Event::Sync) {
             Some(&mut self.event)
@@ -214,20 +239,21 @@ impl<F: FileHandler> Active<F> {
         }
     }
 
-    /// in-/decrement tick w/ `self.reverse` ^ `xor_reverse`
+    /// in-/decrement tick, reversed if `self.reverse` or overridden by
+    /// `live_reverse`
     /// the seeks are done in BankHandler::tick() for single crossfade
-    pub fn tick(&mut self, xor_reverse: bool, ticks_per_step: u16) {
+    pub fn tick(&mut self, live_reverse: bool, ticks_per_step: u16) {
         match &mut self.event {
             Event::Sync => (),
             Event::Hold { tick, .. } => {
-                if self.reverse ^ xor_reverse {
+                if self.reverse || live_reverse {
                     *tick -= ticks_per_step as i16;
                 } else {
                     *tick += ticks_per_step as i16;
                 }
             }
             Event::Loop { tick, .. } => {
-                if self.reverse ^ xor_reverse {
+                if self.reverse || live_reverse {
                     *tick -= ticks_per_step as i16;
                 } else {
                     *tick += ticks_per_step as i16;
@@ -237,162 +263,705 @@ impl<F: FileHandler> Active<F> {
     }
 }
 
-pub(crate) struct Input<F: FileHandler> {
+/// live input event fired, and its push delay into the step before, none if
+/// held past it awaiting launch
+pub(crate) type Launched = (passive::Event, Option<u8>);
+
+pub(crate) struct Input<F: Fs> {
     pub buffer: passive::Step,
+    /// gain multiplier of buffered event
+    pub velocity: f32,
+    /// whether buffered event was held past a tick awaiting launch
+    held: bool,
+    /// event fired unquantized since last tick, and its delay into the step,
+    /// if any
+    pub free: Option<(passive::Event, u8)>,
     pub active: Active<F>,
 }
 
-impl<F: FileHandler> Default for Input<F> {
+impl<F: Fs> Default for Input<F> {
     fn default() -> Self {
         Self {
             buffer: passive::Step::default(),
+            velocity: 1.,
+            held: false,
+            free: None,
             active: Active::default(),
         }
     }
 }
 
-impl<F: FileHandler> Input<F> {
+#[maybe_async::maybe_async]
+impl<F: Fs> Input<F> {
+    /// fire buffered event if `launch`, else hold it for a later tick; returns
+    /// event fired since last tick, if any, and its push delay into the
+    /// previous step, none if held past it
     #[allow(clippy::too_many_arguments)]
-    pub fn tick<const PADS: usize, const STEPS: usize>(
+    pub async fn tick<const PADS: usize, const STEPS: usize>(
         &mut self,
+        launch: bool,
         ticks_per_step: u16,
         bank: &pads::Bank<PADS, STEPS>,
         kit_index: u8,
-        kit_drift: f32,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
         grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
         rand: &mut impl Rand,
         fs: &mut F,
-    ) -> Result<Option<passive::Event>, Error<F::Error>> {
+    ) -> Result<Option<Launched>, Error<F::Error>> {
         self.active.reverse = self.buffer.reverse;
-        if let Some(event) = self.buffer.event.take() {
+        self.held |= !launch && self.buffer.event.is_some();
+        if let Some(event) = self.buffer.event.take_if(|_| launch) {
             self.active
                 .event
-                .trans(&event, bank, kit_index, kit_drift, grain, rand, fs)?;
-            return Ok(Some(event));
+                .trans(
+                    &event,
+                    bank,
+                    kit_index,
+                    kit_drift,
+                    layer_cursor,
+                    grain,
+                    voices,
+                    self.active.velocity,
+                    rand,
+                    fs,
+                )
+                .await?;
+            self.active.velocity = core::mem::replace(&mut self.velocity, 1.);
+            let delay = core::mem::take(&mut self.buffer.delay);
+            let held = core::mem::take(&mut self.held);
+            return Ok(Some((event, (!held).then_some(delay))));
         } else {
             self.active.tick(false, ticks_per_step);
         }
-        Ok(None)
+        Ok(self.free.take().map(|(event, delay)| (event, Some(delay))))
+    }
+}
+
+/// phrases a long record take may span
+pub(crate) const CHAIN_LEN: usize = 4;
+
+/// step index of phrase of `len` steps a tick before `step` from song start,
+/// so the tick incrementing it lands on `step`
+fn before(step: u32, len: u16) -> u16 {
+    let len = len.max(1) as u32;
+    ((step % len + len - 1) % len) as u16
+}
+
+/// step index of phrase of `len` steps played at `rate` a tick before `step`
+/// from song start, and whether the tick landing on `step` holds it at
+/// halftime
+fn before_rated(step: u32, len: u16, rate: passive::Rate) -> (u16, bool) {
+    match rate {
+        passive::Rate::Half if step.is_multiple_of(2) => (before(step / 2, len), false),
+        passive::Rate::Half => (((step / 2) % len.max(1) as u32) as u16, true),
+        passive::Rate::Normal => (before(step, len), false),
+        // second of the pair played a tick before
+        passive::Rate::Double => (before((step % len.max(1) as u32) * 2, len), false),
     }
 }
 
 /// running phrase reading from **last** passive::Phrase.len steps of source
 /// passive::Phrase
-pub(crate) struct Phrase<F: FileHandler> {
+pub(crate) struct Phrase<F: Fs> {
     /// step index sans drift
     pub step_index: u16,
     pub active: Active<F>,
+    /// step event waiting out its delay in 1/256 steps, and its velocity, if
+    /// any
+    pub pending: Option<(passive::Event, u16, f32)>,
+    /// second event of a double time step, queued behind `pending`
+    after: Option<(passive::Event, u16, f32)>,
+    /// whether next tick holds the step playing at halftime
+    hold: bool,
+    /// whether last step held an event
+    pub fired: bool,
+    /// whether last step was substituted by drift
+    pub drifted: bool,
+}
+
+impl<F: Fs> Default for Phrase<F> {
+    fn default() -> Self {
+        Self {
+            step_index: 0,
+            active: Active::default(),
+            pending: None,
+            after: None,
+            hold: false,
+            fired: false,
+            drifted: false,
+        }
+    }
+}
+
+#[maybe_async::maybe_async]
+impl<F: Fs> Phrase<F> {
+    /// stop running `phrase`, if any, closing file of its onset
+    async fn release(phrase: &mut Option<Self>, fs: &mut F) -> Result<(), F::Error> {
+        match phrase.take() {
+            Some(mut phrase) => phrase.active.event.release(fs).await,
+            None => Ok(()),
+        }
+    }
+
+    /// fire step event now or leave it pending; returns step event and its
+    /// recorded delay, if any
+    #[allow(clippy::too_many_arguments)]
+    async fn step<const PADS: usize, const STEPS: usize>(
+        &mut self,
+        (step, drifted): (passive::Step, bool),
+        quantize: passive::Quantize,
+        rate: passive::Rate,
+        live_reverse: bool,
+        ticks_per_step: u16,
+        bank: &pads::Bank<PADS, STEPS>,
+        kit_index: u8,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
+        humanize: &pads::Humanize,
+        grain: &mut pads::GrainReader,
+        mut voices: Option<&mut pads::Voices<F>>,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<Option<(passive::Event, u8)>, Error<F::Error>> {
+        self.fired = step.event.is_some();
+        self.drifted = drifted;
+        self.hold = rate == passive::Rate::Half;
+        // fire events left pending from last step
+        let leftover = self.pending.is_some();
+        for (event, _, velocity) in [self.pending.take(), self.after.take()]
+            .into_iter()
+            .flatten()
+        {
+            let voices = voices.as_deref_mut();
+            self.active
+                .event
+                .trans(
+                    &event,
+                    bank,
+                    kit_index,
+                    kit_drift,
+                    layer_cursor,
+                    grain,
+                    voices,
+                    self.active.velocity,
+                    rand,
+                    fs,
+                )
+                .await?;
+            self.active.velocity = velocity;
+        }
+        self.active.reverse = step.reverse;
+        if let Some(event) = step.event {
+            let (delay, velocity) = self.timing(&step, quantize, rate, false, bank, humanize, rand);
+            if delay == 0 {
+                self.active
+                    .event
+                    .trans(
+                        &event,
+                        bank,
+                        kit_index,
+                        kit_drift,
+                        layer_cursor,
+                        grain,
+                        voices,
+                        self.active.velocity,
+                        rand,
+                        fs,
+                    )
+                    .await?;
+                self.active.velocity = velocity;
+            } else {
+                self.pending = Some((event, delay, velocity));
+                if !leftover {
+                    self.active.tick(live_reverse, ticks_per_step);
+                }
+            }
+            return Ok(Some((event, step.delay)));
+        } else if !leftover {
+            self.active.tick(live_reverse, ticks_per_step);
+        }
+        Ok(None)
+    }
+
+    /// hold step playing over another tick at halftime, firing events left
+    /// pending from it
+    #[allow(clippy::too_many_arguments)]
+    async fn hold<const PADS: usize, const STEPS: usize>(
+        &mut self,
+        live_reverse: bool,
+        ticks_per_step: u16,
+        bank: &pads::Bank<PADS, STEPS>,
+        kit_index: u8,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
+        humanize: &pads::Humanize,
+        grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        let step = passive::Step {
+            reverse: self.active.reverse,
+            ..Default::default()
+        };
+        self.step(
+            (step, self.drifted),
+            passive::Quantize::default(),
+            passive::Rate::Normal,
+            live_reverse,
+            ticks_per_step,
+            bank,
+            kit_index,
+            kit_drift,
+            layer_cursor,
+            humanize,
+            grain,
+            voices,
+            rand,
+            fs,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// queue second step event of a double time tick halfway through it;
+    /// returns step event and its recorded delay, if any
+    fn queue<const PADS: usize, const STEPS: usize>(
+        &mut self,
+        (step, drifted): (passive::Step, bool),
+        quantize: passive::Quantize,
+        bank: &pads::Bank<PADS, STEPS>,
+        humanize: &pads::Humanize,
+        rand: &mut impl Rand,
+    ) -> Option<(passive::Event, u8)> {
+        self.drifted |= drifted;
+        let event = step.event?;
+        self.fired = true;
+        let rate = passive::Rate::Double;
+        let (delay, velocity) = self.timing(&step, quantize, rate, true, bank, humanize, rand);
+        match self.pending {
+            None => self.pending = Some((event, delay, velocity)),
+            Some(_) => self.after = Some((event, delay, velocity)),
+        }
+        Some((event, step.delay))
+    }
+
+    /// delay in 1/256 ticks and velocity of event of `step` played at
+    /// `rate`, the later of a double time pair if `second`
+    #[allow(clippy::too_many_arguments)]
+    fn timing<const PADS: usize, const STEPS: usize>(
+        &self,
+        step: &passive::Step,
+        quantize: passive::Quantize,
+        rate: passive::Rate,
+        second: bool,
+        bank: &pads::Bank<PADS, STEPS>,
+        humanize: &pads::Humanize,
+        rand: &mut impl Rand,
+    ) -> (u16, f32) {
+        let groove = bank.groove.as_ref().and_then(|v| v.step(self.step_index));
+        let (jitter, scale) = humanize.generate(rand);
+        let delay =
+            (quantize.snap(step.delay) + groove.map(|v| v.delay as u16).unwrap_or(0) + jitter)
+                .min(256);
+        let velocity = groove.map(|v| v.velocity).unwrap_or(1.) * scale;
+        (rate.place(delay, second), velocity)
+    }
+
+    /// fire pending event mid-step
+    #[allow(clippy::too_many_arguments)]
+    pub async fn fire<const PADS: usize, const STEPS: usize>(
+        &mut self,
+        live_reverse: bool,
+        ticks_per_step: u16,
+        bank: &pads::Bank<PADS, STEPS>,
+        kit_index: u8,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
+        grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        if let Some((event, delay, velocity)) = self.pending.take() {
+            self.active
+                .event
+                .trans(
+                    &event,
+                    bank,
+                    kit_index,
+                    kit_drift,
+                    layer_cursor,
+                    grain,
+                    voices,
+                    self.active.velocity,
+                    rand,
+                    fs,
+                )
+                .await?;
+            self.active.velocity = velocity;
+            self.pending = self.after.take();
+            // offset fresh tick by part of step already elapsed so next sync
+            // lines up
+            let elapsed = ((delay as u32 * ticks_per_step as u32 + 128) / 256) as i16;
+            let reverse = self.active.reverse || live_reverse;
+            if let Event::Hold { tick, .. } | Event::Loop { tick, .. } = &mut self.active.event {
+                if *tick == 0 {
+                    *tick = if reverse { elapsed } else { -elapsed };
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
-pub(crate) struct Record<const STEPS: usize, F: FileHandler> {
+/// segment of long take at `link`, oldest first, ending with `newest`
+fn segment<'p, const STEPS: usize>(
+    newest: &'p passive::Phrase<STEPS>,
+    chain: &'p heapless::Deque<passive::Phrase<STEPS>, { CHAIN_LEN - 1 }>,
+    link: usize,
+) -> &'p passive::Phrase<STEPS> {
+    chain.iter().nth(link).unwrap_or(newest)
+}
+
+pub(crate) struct Record<const STEPS: usize, F: Fs> {
     /// running step queue
     queue: heapless::HistoryBuffer<passive::Step, STEPS>,
-    /// trimmed source phrase, if any
+    /// last step, held back a tick so late input can be attributed to it
+    last: Option<passive::Step>,
+    /// whether a long take is running, spilling full queues to `spilled`
+    armed: bool,
+    /// queues filled since long take began, oldest first
+    spilled: heapless::Deque<[passive::Step; STEPS], CHAIN_LEN>,
+    /// steps queued since last spill
+    filled: usize,
+    /// trimmed source phrase, if any; newest segment of a long take
     pub source_phrase: Option<passive::Phrase<STEPS>>,
+    /// older segments of trimmed long take, oldest first
+    chain: heapless::Deque<passive::Phrase<STEPS>, { CHAIN_LEN - 1 }>,
+    /// segment playing, counted from oldest
+    link: usize,
     /// active phrase, if any
     pub active_phrase: Option<Phrase<F>>,
 }
 
-impl<const STEPS: usize, F: FileHandler> Default for Record<STEPS, F> {
+impl<const STEPS: usize, F: Fs> Default for Record<STEPS, F> {
     fn default() -> Self {
         Self {
             queue: heapless::HistoryBuffer::new(),
+            last: None,
+            armed: false,
+            spilled: heapless::Deque::new(),
+            filled: 0,
             source_phrase: None,
+            chain: heapless::Deque::new(),
+            link: 0,
             active_phrase: None,
         }
     }
 }
 
-impl<const STEPS: usize, F: FileHandler> Record<STEPS, F> {
+#[maybe_async::maybe_async]
+impl<const STEPS: usize, F: Fs> Record<STEPS, F> {
     #[allow(clippy::too_many_arguments)]
-    pub fn tick<const PADS: usize>(
+    pub async fn tick<const PADS: usize>(
         &mut self,
-        xor_reverse: bool,
+        live_reverse: bool,
         ticks_per_step: u16,
         bank: &pads::Bank<PADS, STEPS>,
         kit_index: u8,
-        kit_drift: f32,
-        phrase_drift: f32,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
+        phrase_drift: &mut pads::Drift,
+        humanize: &pads::Humanize,
         grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
         rand: &mut impl Rand,
         fs: &mut F,
-    ) -> Result<Option<passive::Event>, Error<F::Error>> {
-        if let Some(source_phrase) = self.source_phrase.as_ref() {
-            if let Some(active_phrase) = self.active_phrase.as_mut() {
-                // increment step
-                active_phrase.step_index = (active_phrase.step_index + 1) % source_phrase.len;
-                let step =
-                    source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
+    ) -> Result<Option<(passive::Event, u8)>, Error<F::Error>> {
+        let Some(newest) = self.source_phrase.as_ref() else {
+            return Ok(None);
+        };
+        let active_phrase = if let Some(active_phrase) = self.active_phrase.as_mut() {
+            if active_phrase.hold {
+                active_phrase
+                    .hold(
+                        live_reverse,
+                        ticks_per_step,
+                        bank,
+                        kit_index,
+                        kit_drift,
+                        layer_cursor,
+                        humanize,
+                        grain,
+                        voices,
+                        rand,
+                        fs,
+                    )
+                    .await?;
+                return Ok(None);
+            }
+            Self::advance(newest, &self.chain, &mut self.link, active_phrase);
+            active_phrase
+        } else {
+            // start active phrase from empty
+            self.link = 0;
+            self.active_phrase.insert(Phrase::default())
+        };
+        let source_phrase = segment(newest, &self.chain, self.link);
+        let step = source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
+        let event = active_phrase
+            .step(
+                step,
+                source_phrase.quantize,
+                newest.rate,
+                live_reverse,
+                ticks_per_step,
+                bank,
+                kit_index,
+                kit_drift,
+                layer_cursor,
+                humanize,
+                grain,
+                voices,
+                rand,
+                fs,
+            )
+            .await?;
+        if newest.rate != passive::Rate::Double {
+            return Ok(event);
+        }
+        // second step of pair
+        Self::advance(newest, &self.chain, &mut self.link, active_phrase);
+        let source_phrase = segment(newest, &self.chain, self.link);
+        let step = source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
+        let second = active_phrase.queue(step, source_phrase.quantize, bank, humanize, rand);
+        Ok(event.or(second))
+    }
 
-                active_phrase.active.reverse = step.reverse;
-                // process event
-                if let Some(ref event) = step.event {
-                    active_phrase
-                        .active
-                        .event
-                        .trans(event, bank, kit_index, kit_drift, grain, rand, fs)?;
-                    return Ok(Some(*event));
-                } else {
-                    active_phrase.active.tick(xor_reverse, ticks_per_step);
+    /// increment step of running phrase, on to next segment of long take, if
+    /// any, past the end of one
+    fn advance(
+        newest: &passive::Phrase<STEPS>,
+        chain: &heapless::Deque<passive::Phrase<STEPS>, { CHAIN_LEN - 1 }>,
+        link: &mut usize,
+        active_phrase: &mut Phrase<F>,
+    ) {
+        let len = segment(newest, chain, *link).len;
+        active_phrase.step_index = (active_phrase.step_index + 1) % len;
+        if active_phrase.step_index == 0 {
+            *link = (*link + 1) % (chain.len() + 1);
+        }
+    }
+
+    /// realign running phrase, if any, so next tick plays step `step` of it
+    /// counted from song start
+    pub fn locate(&mut self, step: u32) {
+        if let (Some(newest), Some(active_phrase)) =
+            (self.source_phrase.as_ref(), self.active_phrase.as_mut())
+        {
+            let len = self.chain.iter().chain([newest]).map(|v| v.len).sum();
+            let (mut step_index, hold) = before_rated(step, len, newest.rate);
+            active_phrase.hold = hold;
+            self.link = 0;
+            for phrase in self.chain.iter() {
+                if step_index < phrase.len {
+                    break;
                 }
-            } else {
-                // start active phrase from empty
-                let step = source_phrase.generate_step(0, phrase_drift, rand);
-                let mut event = Event::Sync;
-                let ret = if let Some(ref source) = step.event {
-                    event.trans(source, bank, kit_index, kit_drift, grain, rand, fs)?;
-                    Some(*source)
-                } else {
-                    None
-                };
-                self.active_phrase = Some(Phrase {
-                    step_index: 0,
-                    active: Active {
-                        event,
-                        reverse: step.reverse,
-                    },
-                });
-                return Ok(ret);
+                step_index -= phrase.len;
+                self.link += 1;
             }
+            active_phrase.step_index = step_index;
+        }
+    }
+
+    /// toggle drift lock of step now playing, returning new lock, if any
+    pub fn toggle_lock(&mut self) -> Option<bool> {
+        let active_phrase = self.active_phrase.as_ref()?;
+        let source_phrase = match self.chain.iter_mut().nth(self.link) {
+            Some(phrase) => phrase,
+            None => self.source_phrase.as_mut()?,
+        };
+        Some(source_phrase.toggle_lock(active_phrase.step_index))
+    }
+
+    /// set playback quantization of every segment of trimmed take
+    pub fn assign_quantize(&mut self, quantize: passive::Quantize) {
+        for phrase in self.chain.iter_mut().chain(self.source_phrase.as_mut()) {
+            phrase.quantize = quantize;
+        }
+    }
+
+    /// set playback rate of every segment of trimmed take
+    pub fn assign_rate(&mut self, rate: passive::Rate) {
+        for phrase in self.chain.iter_mut().chain(self.source_phrase.as_mut()) {
+            phrase.rate = rate;
         }
-        Ok(None)
+    }
+
+    pub fn activity(&self) -> Option<pads::Activity> {
+        let source_phrase = segment(self.source_phrase.as_ref()?, &self.chain, self.link);
+        let active_phrase = self.active_phrase.as_ref()?;
+        Some(pads::Activity {
+            step_index: active_phrase.step_index % source_phrase.len,
+            len: source_phrase.len,
+            fired: active_phrase.fired,
+            drifted: active_phrase.drifted,
+        })
     }
 
     pub fn push(&mut self, step: passive::Step) {
+        if let Some(last) = self.last.replace(step) {
+            self.write(last);
+        }
+    }
+
+    /// begin a long take, kept whole past one queue up to `CHAIN_LEN` of them
+    /// until next saved
+    pub fn arm(&mut self) {
+        self.armed = true;
+        self.spilled.clear();
+        self.filled = 0;
+    }
+
+    /// queue step, spilling full queue of long take, if any
+    fn write(&mut self, step: passive::Step) {
         self.queue.write(step);
+        if self.armed {
+            self.filled += 1;
+            if self.filled == STEPS {
+                let mut spill = [passive::Step::default(); STEPS];
+                for (step, queued) in spill.iter_mut().zip(self.queue.oldest_ordered()) {
+                    *step = *queued;
+                }
+                if self.spilled.is_full() {
+                    self.spilled.pop_front();
+                }
+                let _ = self.spilled.push_back(spill);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// steps of long take, oldest first
+    fn taken(&self) -> impl Iterator<Item = &passive::Step> {
+        let queued = self.queue.len() - self.filled;
+        self.spilled
+            .iter()
+            .flatten()
+            .chain(self.queue.oldest_ordered().skip(queued))
+    }
+
+    /// push live input event fired `delay` 1/256 steps late, attributing it to
+    /// the last step if that step is free
+    pub fn push_late(&mut self, event: passive::Event, delay: u8, reverse: bool) {
+        match self.last.as_mut() {
+            Some(last) if last.event.is_none() => {
+                last.event = Some(event);
+                last.delay = delay;
+                self.push(passive::Step {
+                    event: None,
+                    reverse,
+                    delay: 0,
+                    lock: false,
+                });
+            }
+            _ => self.push(passive::Step {
+                event: Some(event),
+                reverse,
+                delay: 0,
+                lock: false,
+            }),
+        }
     }
 
-    pub fn trim(&mut self, len: u16) {
+    /// trim source phrase to `len` steps, dropping older segments of long take
+    /// if under `max_len`, or save running steps as one of at most `max_len`
+    /// steps, chained after older segments if long take armed
+    pub async fn trim(&mut self, len: u16, max_len: u16, fs: &mut F) -> Result<(), F::Error> {
         if let Some(phrase) = self.source_phrase.as_mut() {
-            phrase.len = len;
+            phrase.len = len.clamp(1, max_len);
+            if len < max_len {
+                self.chain.clear();
+            }
         } else {
-            self.save();
+            self.save(max_len);
         }
-        self.active_phrase = None;
+        Phrase::release(&mut self.active_phrase, fs).await
     }
 
-    pub fn take(&mut self) -> Option<passive::Phrase<STEPS>> {
-        self.active_phrase = None;
-        self.source_phrase.take()
+    /// segments of trimmed take, oldest first; empty if none
+    pub async fn take(
+        &mut self,
+        fs: &mut F,
+    ) -> Result<heapless::Vec<passive::Phrase<STEPS>, CHAIN_LEN>, F::Error> {
+        Phrase::release(&mut self.active_phrase, fs).await?;
+        let mut take = heapless::Vec::new();
+        if let Some(newest) = self.source_phrase.take() {
+            while let Some(phrase) = self.chain.pop_front() {
+                let _ = take.push(phrase);
+            }
+            let _ = take.push(newest);
+        }
+        Ok(take)
     }
 
-    fn save(&mut self) {
-        let mut steps = [passive::Step::default(); STEPS];
-        let (front, back) = self.queue.as_slices();
-        if !front.is_empty() {
-            steps[..front.len()].copy_from_slice(front);
+    /// save newest `max_len` steps at most, and older steps of long take, if
+    /// armed, as segments of `max_len` steps at most; nothing if no steps, as
+    /// an empty phrase has no step to play
+    fn save(&mut self, max_len: u16) {
+        if let Some(last) = self.last.take() {
+            self.write(last);
         }
-        if !back.is_empty() {
-            steps[front.len()..][..back.len()].copy_from_slice(back);
+        let len = self.queue.len().min(max_len as usize);
+        if len == 0 {
+            self.armed = false;
+            return;
+        }
+        let mut steps = [passive::Step::default(); STEPS];
+        for (step, queued) in steps[STEPS - len..]
+            .iter_mut()
+            .zip(self.queue.oldest_ordered().skip(self.queue.len() - len))
+        {
+            *step = *queued;
         }
         self.source_phrase = Some(passive::Phrase {
             steps,
-            len: self.queue.len() as u16,
+            len: len as u16,
+            quantize: passive::Quantize::default(),
+            follow: passive::Follow::Next,
+            rate: passive::Rate::default(),
         });
+        self.chain.clear();
+        if core::mem::take(&mut self.armed) {
+            let taken = self.spilled.len() * STEPS + self.filled;
+            // back from newest segment, keeping newest if too long to chain
+            let mut end = taken.saturating_sub(len);
+            while end > 0 && !self.chain.is_full() {
+                let len = end.min(max_len as usize);
+                let mut steps = [passive::Step::default(); STEPS];
+                for (step, taken) in steps[STEPS - len..]
+                    .iter_mut()
+                    .zip(self.taken().skip(end - len))
+                {
+                    *step = *taken;
+                }
+                let _ = self.chain.push_front(passive::Phrase {
+                    steps,
+                    len: len as u16,
+                    quantize: passive::Quantize::default(),
+                    follow: passive::Follow::Next,
+                    rate: passive::Rate::default(),
+                });
+                end -= len;
+            }
+        }
     }
 }
 
-pub(crate) struct Sequence<const PHRASES: usize, F: FileHandler> {
+pub(crate) struct Sequence<const PHRASES: usize, F: Fs> {
     /// sequence index sans drift
     phrase_index: u16,
     /// sequence of source phrase indices
@@ -401,109 +970,236 @@ pub(crate) struct Sequence<const PHRASES: usize, F: FileHandler> {
     source_phrase: Option<u8>,
     /// active phrase, if any
     pub active_phrase: Option<Phrase<F>>,
+    /// stopped by follow action
+    stopped: bool,
 }
 
-impl<const PHRASES: usize, F: FileHandler> Default for Sequence<PHRASES, F> {
+impl<const PHRASES: usize, F: Fs> Default for Sequence<PHRASES, F> {
     fn default() -> Self {
         Self {
             phrase_index: 0,
             phrases: heapless::HistoryBuffer::new(),
             source_phrase: None,
             active_phrase: None,
+            stopped: false,
         }
     }
 }
 
-impl<const PHRASES: usize, F: FileHandler> Sequence<PHRASES, F> {
+#[maybe_async::maybe_async]
+impl<const PHRASES: usize, F: Fs> Sequence<PHRASES, F> {
     #[allow(clippy::too_many_arguments)]
-    pub fn tick<const PADS: usize, const STEPS: usize>(
+    pub async fn tick<const PADS: usize, const STEPS: usize>(
         &mut self,
-        xor_reverse: bool,
+        live_reverse: bool,
         ticks_per_step: u16,
         bank: &pads::Bank<PADS, STEPS>,
         kit_index: u8,
-        kit_drift: f32,
-        phrase_drift: f32,
+        kit_drift: &mut pads::Drift,
+        layer_cursor: &mut pads::LayerCursor<PADS>,
+        phrase_drift: &mut pads::Drift,
+        humanize: &pads::Humanize,
         grain: &mut pads::GrainReader,
+        voices: Option<&mut pads::Voices<F>>,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<Option<(passive::Event, u8)>, Error<F::Error>> {
+        if self.stopped {
+            return Ok(None);
+        }
+        if let Some(active_phrase) = self.active_phrase.as_mut().filter(|v| v.hold) {
+            active_phrase
+                .hold(
+                    live_reverse,
+                    ticks_per_step,
+                    bank,
+                    kit_index,
+                    kit_drift,
+                    layer_cursor,
+                    humanize,
+                    grain,
+                    voices,
+                    rand,
+                    fs,
+                )
+                .await?;
+            return Ok(None);
+        }
+        let Some(source_phrase) = self.advance(bank, phrase_drift, rand, fs).await? else {
+            return Ok(None);
+        };
+        let Some(active_phrase) = self.active_phrase.as_mut() else {
+            return Ok(None);
+        };
+        // process step
+        let step = source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
+        let event = active_phrase
+            .step(
+                step,
+                source_phrase.quantize,
+                source_phrase.rate,
+                live_reverse,
+                ticks_per_step,
+                bank,
+                kit_index,
+                kit_drift,
+                layer_cursor,
+                humanize,
+                grain,
+                voices,
+                rand,
+                fs,
+            )
+            .await?;
+        if source_phrase.rate != passive::Rate::Double {
+            return Ok(event);
+        }
+        // second step of pair
+        let mut second = None;
+        if let Some(source_phrase) = self.advance(bank, phrase_drift, rand, fs).await? {
+            if let Some(active_phrase) = self.active_phrase.as_mut() {
+                let step =
+                    source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
+                second = active_phrase.queue(step, source_phrase.quantize, bank, humanize, rand);
+            }
+        }
+        Ok(event.or(second))
+    }
+
+    /// increment step of running phrase, following on past its end, or start
+    /// one from empty; returns source phrase of step now playing, if any
+    async fn advance<'b, const PADS: usize, const STEPS: usize>(
+        &mut self,
+        bank: &'b pads::Bank<PADS, STEPS>,
+        phrase_drift: &mut pads::Drift,
         rand: &mut impl Rand,
         fs: &mut F,
-    ) -> Result<Option<passive::Event>, Error<F::Error>> {
+    ) -> Result<Option<&'b passive::Phrase<STEPS>>, F::Error> {
         if let Some(active_phrase) = self.active_phrase.as_mut() {
             let source_phrase = self
                 .source_phrase
                 .and_then(|v| bank.phrases[v as usize].as_ref());
+            // follow action of completed phrase
+            let follow = source_phrase.map(|v| v.follow).unwrap_or_default();
 
-            let source_phrase = if source_phrase.is_some_and(|v| active_phrase.step_index < v.len) {
+            let next = active_phrase.step_index + 1;
+            let source_phrase = if source_phrase.is_some_and(|v| next < v.len) {
                 // increment step
-                active_phrase.step_index += 1;
+                active_phrase.step_index = next;
                 source_phrase.unwrap()
+            } else if follow == passive::Follow::Stop {
+                self.stopped = true;
+                Phrase::release(&mut self.active_phrase, fs).await?;
+                return Ok(None);
             } else if let Some(source_phrase) = Self::try_increment_phrase(
                 &mut self.phrase_index,
                 &self.phrases,
                 &mut self.source_phrase,
+                follow,
                 bank,
                 phrase_drift,
                 rand,
             ) {
-                // incremented phrase
-                active_phrase.step_index %= source_phrase.len;
+                // incremented phrase; chained segments, and long takes they
+                // open, play from their start
+                let chained = |v: passive::Follow| matches!(v, passive::Follow::Chain(_));
+                if chained(follow) || chained(source_phrase.follow) {
+                    active_phrase.step_index = 0;
+                } else {
+                    active_phrase.step_index = next % source_phrase.len;
+                }
                 source_phrase
             } else {
-                self.active_phrase = None;
+                Phrase::release(&mut self.active_phrase, fs).await?;
                 return Ok(None);
             };
-            // process step
-            let step = source_phrase.generate_step(active_phrase.step_index, phrase_drift, rand);
-            active_phrase.active.reverse = step.reverse;
-            if let Some(ref event) = step.event {
-                active_phrase
-                    .active
-                    .event
-                    .trans(event, bank, kit_index, kit_drift, grain, rand, fs)?;
-                return Ok(Some(*event));
-            } else {
-                active_phrase.active.tick(xor_reverse, ticks_per_step);
-            }
+            Ok(Some(source_phrase))
         } else if let Some(source_phrase) = Self::try_increment_phrase(
             &mut self.phrase_index,
             &self.phrases,
             &mut self.source_phrase,
+            passive::Follow::Next,
             bank,
             phrase_drift,
             rand,
         ) {
             // start active phrase from empty
-            let step = source_phrase.generate_step(0, phrase_drift, rand);
-            let mut event = Event::Sync;
-            let ret = if let Some(ref source) = step.event {
-                event.trans(source, bank, kit_index, kit_drift, grain, rand, fs)?;
-                Some(*source)
-            } else {
-                None
-            };
-            self.active_phrase = Some(Phrase {
-                step_index: 0,
-                active: Active {
-                    event,
-                    reverse: step.reverse,
-                },
-            });
-            return Ok(ret);
+            self.active_phrase = Some(Phrase::default());
+            Ok(Some(source_phrase))
         } else {
-            self.active_phrase = None;
+            Phrase::release(&mut self.active_phrase, fs).await?;
+            Ok(None)
         }
-        Ok(None)
     }
 
-    pub fn clear(&mut self) {
+    /// realign running phrase, if any, so next tick plays step `step` of it
+    /// counted from song start
+    pub fn locate<const PADS: usize, const STEPS: usize>(
+        &mut self,
+        bank: &pads::Bank<PADS, STEPS>,
+        step: u32,
+    ) {
+        let source_phrase = self
+            .source_phrase
+            .and_then(|v| bank.phrases[v as usize].as_ref());
+        if let (Some(source_phrase), Some(active_phrase)) =
+            (source_phrase, self.active_phrase.as_mut())
+        {
+            (active_phrase.step_index, active_phrase.hold) =
+                before_rated(step, source_phrase.len, source_phrase.rate);
+        }
+    }
+
+    pub fn activity<const PADS: usize, const STEPS: usize>(
+        &self,
+        bank: &pads::Bank<PADS, STEPS>,
+    ) -> Option<pads::Activity> {
+        let source_phrase = bank.phrases[self.source_phrase? as usize].as_ref()?;
+        let active_phrase = self.active_phrase.as_ref()?;
+        Some(pads::Activity {
+            step_index: active_phrase.step_index % source_phrase.len,
+            len: source_phrase.len,
+            fired: active_phrase.fired,
+            drifted: active_phrase.drifted,
+        })
+    }
+
+    /// phrase of the `len` step span now sounding, if any
+    pub fn excerpt<const PADS: usize, const STEPS: usize>(
+        &self,
+        bank: &pads::Bank<PADS, STEPS>,
+        len: u16,
+    ) -> Option<passive::Phrase<STEPS>> {
+        let source_phrase = bank.phrases[self.source_phrase? as usize].as_ref()?;
+        let active_phrase = self.active_phrase.as_ref()?;
+        Some(source_phrase.excerpt(active_phrase.step_index, len))
+    }
+
+    pub async fn clear(&mut self, fs: &mut F) -> Result<(), F::Error> {
         self.phrase_index = 0;
         self.phrases.clear();
         self.source_phrase = None;
-        self.active_phrase = None;
+        self.stopped = false;
+        Phrase::release(&mut self.active_phrase, fs).await
     }
 
-    pub fn push(&mut self, index: u8) {
+    /// push phrase at pad `index`, dropping oldest beyond `max_count`
+    pub fn push(&mut self, index: u8, max_count: usize) {
+        let excess = (self.phrases.len() + 1).saturating_sub(max_count);
+        if excess > 0 {
+            let kept = heapless::HistoryBuffer::<u8, PHRASES>::new();
+            let kept = self.phrases.oldest_ordered().skip(excess).fold(kept, |mut v, p| {
+                v.write(*p);
+                v
+            });
+            self.phrases = kept;
+        }
         self.phrases.write(index);
+        self.stopped = false;
+    }
+
+    pub fn phrases(&self) -> impl Iterator<Item = u8> + '_ {
+        self.phrases.oldest_ordered().copied()
     }
 
     /// associated method to appease borrow rules
@@ -511,21 +1207,30 @@ impl<const PHRASES: usize, F: FileHandler> Sequence<PHRASES, F> {
         phrase_index: &mut u16,
         phrases: &heapless::HistoryBuffer<u8, PHRASES>,
         source_phrase: &mut Option<u8>,
+        follow: passive::Follow,
         bank: &'d pads::Bank<PADS, STEPS>,
-        phrase_drift: f32,
+        phrase_drift: &mut pads::Drift,
         rand: &mut impl Rand,
     ) -> Option<&'d passive::Phrase<STEPS>> {
+        if let passive::Follow::Jump(index) | passive::Follow::Chain(index) = follow {
+            if let Some(phrase) = bank.phrases.get(index as usize).and_then(|v| v.as_ref()) {
+                *source_phrase = Some(index);
+                return Some(phrase);
+            }
+        }
         // try increment phrase
         let phrase_count = phrases
             .oldest_ordered()
             .filter(|v| bank.phrases[**v as usize].is_some())
             .count();
         if phrase_count != 0 {
-            *phrase_index = (*phrase_index + 1) % phrase_count as u16;
+            *phrase_index = if follow == passive::Follow::Random {
+                rand.next_lim_usize(phrase_count) as u16
+            } else {
+                (*phrase_index + 1) % phrase_count as u16
+            };
             *source_phrase = {
-                let drift = phrase_drift * phrase_count as f32;
-                let drift = rand.next_lim_usize(drift as usize + 1)
-                    + rand.next_bool(tinyrand::Probability::new(drift.fract() as f64)) as usize;
+                let drift = phrase_drift.generate(phrase_count, rand);
                 let index = (*phrase_index as usize + drift) % phrase_count;
                 phrases
                     .oldest_ordered()
@@ -0,0 +1,99 @@
+//! bank file format: version byte then postcard, with legacy json detected by
+//! its opening brace
+
+extern crate alloc;
+
+use crate::{
+    compat::{LegacyBank, SavedBank, UnscenedBank},
+    pads::Bank,
+    Error, Fs,
+};
+use alloc::vec::Vec;
+
+/// leading byte of binary banks; never valid opening json
+pub const BD_VERSION: u8 = 6;
+/// leading byte of binary banks saved while step direction was read relative
+/// to live reverse
+const LEGACY_VERSION: u8 = 2;
+/// leading byte of binary banks saved ahead of scenes
+const UNSCENED_VERSION: u8 = 3;
+/// leading byte of binary banks saved ahead of pad layers
+const UNLAYERED_VERSION: u8 = 4;
+/// leading byte of binary banks saved while loops counted whole steps
+const WHOLE_STEP_VERSION: u8 = 5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BdError {
+    /// binary bank of unknown version
+    Version(u8),
+    /// json or binary body failed to parse or encode
+    Malformed,
+}
+
+impl core::fmt::Display for BdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Version(v) => write!(f, "unknown .bd version {}", v),
+            Self::Malformed => write!(f, "malformed .bd"),
+        }
+    }
+}
+
+impl core::error::Error for BdError {}
+
+impl SavedBank {
+    /// parse binary or legacy bank. legacy steps were recorded with live
+    /// reverse folded into the direction heard, so read unchanged as absolute.
+    /// binary banks ahead of scenes or layers are read in their own shape, as
+    /// postcard can't tell fields missing. loops of all but current banks
+    /// counted whole steps
+    pub fn from_bd(bytes: &[u8]) -> Result<Self, BdError> {
+        match bytes.iter().find(|v| !v.is_ascii_whitespace()) {
+            Some(b'{') => serde_json::from_slice(bytes)
+                .map(Self::scale_loops)
+                .map_err(|_| BdError::Malformed),
+            Some(&LEGACY_VERSION | &UNSCENED_VERSION) => postcard::from_bytes(&bytes[1..])
+                .map(|v: UnscenedBank| Self::from(v).scale_loops())
+                .map_err(|_| BdError::Malformed),
+            Some(&UNLAYERED_VERSION) => postcard::from_bytes(&bytes[1..])
+                .map(|v: LegacyBank| Self::from(v).scale_loops())
+                .map_err(|_| BdError::Malformed),
+            Some(&WHOLE_STEP_VERSION) => postcard::from_bytes(&bytes[1..])
+                .map(Self::scale_loops)
+                .map_err(|_| BdError::Malformed),
+            Some(&BD_VERSION) => postcard::from_bytes(&bytes[1..]).map_err(|_| BdError::Malformed),
+            Some(v) => Err(BdError::Version(*v)),
+            None => Err(BdError::Malformed),
+        }
+    }
+}
+
+#[maybe_async::maybe_async]
+impl<const PADS: usize, const STEPS: usize> Bank<PADS, STEPS> {
+    /// encode as binary bank
+    pub fn to_bd(&self) -> Result<Vec<u8>, BdError> {
+        postcard::to_extend(self, alloc::vec![BD_VERSION]).map_err(|_| BdError::Malformed)
+    }
+
+    /// write as binary bank to `path`, replacing any existing bank
+    pub async fn save<F: Fs>(&self, path: &str, fs: &mut F) -> Result<(), Error<F::Error>> {
+        let bytes = self.to_bd().map_err(|_| Error::BadFormat)?;
+        let mut file = fs.create(path).await?;
+        let written = match fs.write_all(&mut file, &bytes).await {
+            Ok(()) => fs.flush(&mut file).await,
+            Err(e) => Err(e),
+        };
+        fs.close(&file).await?;
+        written
+    }
+}
+
+/// serialize array as length-prefixed sequence, as saved banks of any capacity
+/// expect, rather than as fixed tuple
+pub(crate) fn seq<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: serde::Serialize,
+{
+    serializer.collect_seq(array)
+}
@@ -0,0 +1,277 @@
+//! file browsing shared by frontends: a directory listing filtered by
+//! extension and optionally sidecar tag, walked by index
+
+extern crate alloc;
+
+use crate::{Error, Fs};
+use alloc::{format, string::String, vec::Vec};
+
+/// directory listing entry
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// name of entry leading up a directory
+const PARENT: &str = "..";
+
+/// optional tags of a file, read from a json sidecar beside it
+#[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Tags {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    /// musical key, e.g. "a minor"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<f32>,
+    /// kind of sound, e.g. "drums"
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+#[maybe_async::maybe_async]
+impl Tags {
+    /// sidecar path of file at `path`: its extension swapped for `tags`
+    pub fn sidecar(path: &str) -> String {
+        let stem = match path.rsplit_once('.') {
+            Some((stem, ext)) if !ext.contains('/') => stem,
+            _ => path,
+        };
+        format!("{}.tags", stem)
+    }
+
+    /// read sidecar tags of file at `path`; none if no sidecar opens
+    pub async fn load<F: Fs>(path: &str, fs: &mut F) -> Result<Option<Self>, Error<F::Error>> {
+        let Ok(mut file) = fs.open(&Self::sidecar(path)).await else {
+            return Ok(None);
+        };
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 256];
+        let read = loop {
+            match fs.read(&mut file, &mut buffer).await {
+                Ok(0) => break Ok(()),
+                Ok(n) => bytes.extend_from_slice(&buffer[..n]),
+                Err(e) => break Err(e),
+            }
+        };
+        fs.close(&file).await?;
+        read?;
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|_| Error::BadFormat)
+    }
+
+    /// write as sidecar of file at `path`, replacing any existing tags
+    pub async fn save<F: Fs>(&self, path: &str, fs: &mut F) -> Result<(), Error<F::Error>> {
+        let bytes = serde_json::to_vec(self).map_err(|_| Error::BadFormat)?;
+        let mut file = fs.create(&Self::sidecar(path)).await?;
+        let written = match fs.write_all(&mut file, &bytes).await {
+            Ok(()) => fs.flush(&mut file).await,
+            Err(e) => Err(e),
+        };
+        fs.close(&file).await?;
+        written
+    }
+
+    /// tag values as filtered on, bpm rounded with unit, e.g. "120bpm"
+    pub fn values(&self) -> impl Iterator<Item = String> + '_ {
+        [
+            self.genre.clone(),
+            self.key.clone(),
+            self.bpm.map(|v| format!("{}bpm", (v + 0.5) as u32)),
+            self.kind.clone(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// whether any tag value is `filter`, ignoring ascii case
+    pub fn matches(&self, filter: &str) -> bool {
+        self.values().any(|v| v.eq_ignore_ascii_case(filter))
+    }
+}
+
+pub struct Browser {
+    /// directory browsing started from, never climbed above
+    root: String,
+    /// directory now listed
+    dir: String,
+    /// extension of files listed
+    ext: String,
+    /// parent first unless at root, then subdirectories and files by name
+    entries: Vec<DirEntry>,
+    index: usize,
+    /// tag value files must carry to be listed, if any
+    filter: Option<String>,
+}
+
+#[maybe_async::maybe_async]
+impl Browser {
+    /// list `root` for subdirectories and files ending in `ext`
+    pub async fn open<F: Fs>(root: &str, ext: &str, fs: &mut F) -> Result<Self, Error<F::Error>> {
+        let mut ret = Self {
+            root: String::from(root),
+            dir: String::from(root),
+            ext: String::from(ext),
+            entries: Vec::new(),
+            index: 0,
+            filter: None,
+        };
+        ret.refresh(fs).await?;
+        Ok(ret)
+    }
+
+    /// subdirectories and files ending in ext of directory now browsed, by
+    /// name
+    async fn list<F: Fs>(&self, fs: &mut F) -> Result<Vec<DirEntry>, Error<F::Error>> {
+        let suffix = format!(".{}", self.ext);
+        let mut entries = fs.read_dir(&self.dir).await?;
+        entries.retain(|v| {
+            v.name != "." && v.name != PARENT && (v.is_dir || v.name.ends_with(&suffix))
+        });
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// relist directory now browsed, keeping index in range
+    pub async fn refresh<F: Fs>(&mut self, fs: &mut F) -> Result<(), Error<F::Error>> {
+        let mut entries = self.list(fs).await?;
+        if let Some(filter) = self.filter.as_deref() {
+            let mut tagged = Vec::with_capacity(entries.len());
+            for entry in entries {
+                // files with unreadable tags count as untagged
+                let path = format!("{}/{}", self.dir, entry.name);
+                let tags = if entry.is_dir {
+                    None
+                } else {
+                    Tags::load(&path, fs).await.ok().flatten()
+                };
+                if entry.is_dir || tags.is_some_and(|v| v.matches(filter)) {
+                    tagged.push(entry);
+                }
+            }
+            entries = tagged;
+        }
+        if self.dir != self.root {
+            entries.insert(
+                0,
+                DirEntry {
+                    name: String::from(PARENT),
+                    is_dir: true,
+                },
+            );
+        }
+        self.entries = entries;
+        self.index = self.index.min(self.entries.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// tag value files must carry to be listed, if any
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    /// list only files tagged `filter`, or all if none
+    pub async fn assign_filter<F: Fs>(
+        &mut self,
+        filter: Option<String>,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        self.filter = filter;
+        self.index = 0;
+        self.refresh(fs).await
+    }
+
+    /// distinct tag values of files in directory now browsed, filtered or
+    /// not, sorted
+    pub async fn tags<F: Fs>(&self, fs: &mut F) -> Result<Vec<String>, Error<F::Error>> {
+        let mut values = Vec::new();
+        for entry in self.list(fs).await? {
+            let path = format!("{}/{}", self.dir, entry.name);
+            if !entry.is_dir {
+                if let Ok(Some(tags)) = Tags::load(&path, fs).await {
+                    values.extend(tags.values());
+                }
+            }
+        }
+        values.sort();
+        values.dedup();
+        Ok(values)
+    }
+
+    pub fn increment(&mut self) {
+        if !self.entries.is_empty() {
+            self.index = (self.index + 1) % self.entries.len();
+        }
+    }
+
+    pub fn decrement(&mut self) {
+        if !self.entries.is_empty() {
+            self.index = (self.index + self.entries.len() - 1) % self.entries.len();
+        }
+    }
+
+    /// enter directory at index, or return path of file at index
+    pub async fn select<F: Fs>(&mut self, fs: &mut F) -> Result<Option<String>, Error<F::Error>> {
+        let Some(entry) = self.entries.get(self.index) else {
+            return Ok(None);
+        };
+        if !entry.is_dir {
+            return Ok(self.path());
+        }
+        let dir = if entry.name == PARENT {
+            match self.dir.rsplit_once('/') {
+                Some((parent, _)) => String::from(parent),
+                None => self.root.clone(),
+            }
+        } else {
+            format!("{}/{}", self.dir, entry.name)
+        };
+        let prev = core::mem::replace(&mut self.dir, dir);
+        self.index = 0;
+        if let Err(e) = self.refresh(fs).await {
+            // stay put in a directory that can't be listed
+            self.dir = prev;
+            self.refresh(fs).await?;
+            return Err(e);
+        }
+        Ok(None)
+    }
+
+    /// name of entry at index, if any
+    pub fn name(&self) -> Option<&str> {
+        self.entries.get(self.index).map(|v| v.name.as_str())
+    }
+
+    /// path of file at index, if any
+    pub fn path(&self) -> Option<String> {
+        self.entries
+            .get(self.index)
+            .filter(|v| !v.is_dir)
+            .map(|v| format!("{}/{}", self.dir, v.name))
+    }
+
+    /// paths of files listed, in order
+    pub fn paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.entries
+            .iter()
+            .filter(|v| !v.is_dir)
+            .map(|v| format!("{}/{}", self.dir, v.name))
+    }
+
+    /// `len` entry names around index, wrapping, index at `len / 2`; blank if
+    /// none listed
+    pub fn window(&self, len: usize) -> impl Iterator<Item = &str> {
+        (0..len).map(move |i| {
+            if self.entries.is_empty() {
+                ""
+            } else {
+                let index = (self.index as isize + i as isize - len as isize / 2)
+                    .rem_euclid(self.entries.len() as isize) as usize;
+                self.entries[index].name.as_str()
+            }
+        })
+    }
+}
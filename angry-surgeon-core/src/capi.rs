@@ -0,0 +1,308 @@
+//! c abi around `SystemHandler` over std files, for hosts not written in rust,
+//! e.g. max/msp or pd externals and c++ firmware. header generated by cbindgen
+//! into include/angry_surgeon.h
+//!
+//! every function taking a handle expects one from `as_system_new` not yet
+//! passed to `as_system_free`, used from one thread at a time
+
+use crate::{compat::SavedBank, passive, Error, FileHandler, Limits, SystemHandler};
+use core::ffi::{c_char, CStr};
+use embedded_io::{ErrorType, SeekFrom};
+use std::io::{Read, Seek, Write};
+
+pub const AS_BANK_COUNT: usize = 2;
+pub const AS_PAD_COUNT: usize = 8;
+pub const AS_MAX_PHRASE_COUNT: usize = 128;
+pub const AS_MAX_PHRASE_LEN: usize = 1 << (AS_PAD_COUNT - 1);
+
+/// file handler over std files, paths as the host process sees them
+pub struct StdFileHandler;
+
+impl ErrorType for StdFileHandler {
+    type Error = std::io::Error;
+}
+
+impl FileHandler for StdFileHandler {
+    type File = std::fs::File;
+
+    fn open(&mut self, path: &str) -> Result<Self::File, Self::Error> {
+        std::fs::File::open(path)
+    }
+
+    fn try_clone(&mut self, file: &Self::File) -> Result<Self::File, Self::Error> {
+        file.try_clone()
+    }
+
+    fn create(&mut self, path: &str) -> Result<Self::File, Error<Self::Error>> {
+        Ok(std::fs::File::create(path)?)
+    }
+
+    fn read_dir(&mut self, path: &str) -> Result<Vec<crate::DirEntry>, Error<Self::Error>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)?.filter_map(|v| v.ok()) {
+            // skip names not representable as &str
+            if let Some(name) = entry.file_name().to_str() {
+                entries.push(crate::DirEntry {
+                    name: name.into(),
+                    is_dir: entry.metadata()?.is_dir(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// closed on drop
+    fn close(&mut self, _file: &Self::File) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn read(&mut self, file: &mut Self::File, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        file.read(buf)
+    }
+
+    fn write(&mut self, file: &mut Self::File, buf: &[u8]) -> Result<usize, Error<Self::Error>> {
+        Ok(file.write(buf)?)
+    }
+
+    fn flush(&mut self, file: &mut Self::File) -> Result<(), Error<Self::Error>> {
+        Ok(file.flush()?)
+    }
+
+    fn seek(&mut self, file: &mut Self::File, pos: SeekFrom) -> Result<u64, Self::Error> {
+        file.seek(pos.into())
+    }
+}
+
+/// opaque system handle
+pub struct AsSystem(
+    SystemHandler<
+        AS_BANK_COUNT,
+        AS_PAD_COUNT,
+        AS_MAX_PHRASE_LEN,
+        AS_MAX_PHRASE_COUNT,
+        tinyrand::Wyrand,
+        StdFileHandler,
+    >,
+);
+
+/// outcome of a call
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AsStatus {
+    Ok,
+    /// null handle, buffer or path, or bank out of range
+    BadArgument,
+    BadFormat,
+    DataNotFound,
+    Unsupported,
+    /// file io failed
+    Io,
+}
+
+impl From<Error<std::io::Error>> for AsStatus {
+    fn from(value: Error<std::io::Error>) -> Self {
+        match value {
+            Error::BadFormat => Self::BadFormat,
+            Error::DataNotFound => Self::DataNotFound,
+            Error::Unsupported => Self::Unsupported,
+            Error::Other(_) => Self::Io,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AsEventKind {
+    Sync,
+    Hold,
+    Loop,
+}
+
+impl TryFrom<u32> for AsEventKind {
+    type Error = AsStatus;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            v if v == Self::Sync as u32 => Ok(Self::Sync),
+            v if v == Self::Hold as u32 => Ok(Self::Hold),
+            v if v == Self::Loop as u32 => Ok(Self::Loop),
+            _ => Err(AsStatus::BadArgument),
+        }
+    }
+}
+
+/// pad event; `index` read unless sync, `len` in 1/256 steps only for loops
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct AsEvent {
+    /// an `AsEventKind`, as an integer so out-of-range values from c are
+    /// rejected rather than undefined
+    pub kind: u32,
+    pub index: u8,
+    pub len: u16,
+}
+
+impl TryFrom<AsEvent> for passive::Event {
+    type Error = AsStatus;
+
+    fn try_from(value: AsEvent) -> Result<Self, Self::Error> {
+        let event = match AsEventKind::try_from(value.kind)? {
+            AsEventKind::Sync => Self::Sync,
+            AsEventKind::Hold => Self::Hold { index: value.index },
+            AsEventKind::Loop => Self::Loop {
+                index: value.index,
+                len: value.len,
+            },
+        };
+        if !matches!(event, Self::Sync) && value.index as usize >= AS_PAD_COUNT {
+            return Err(AsStatus::BadArgument);
+        }
+        Ok(event)
+    }
+}
+
+/// create system ticking `ticks_per_step` times a step, rand seeded with
+/// `seed`; free with `as_system_free`
+#[no_mangle]
+pub extern "C" fn as_system_new(ticks_per_step: u16, seed: u64) -> *mut AsSystem {
+    use tinyrand::Seeded;
+    let system = SystemHandler::new(
+        ticks_per_step,
+        tinyrand::Wyrand::seed(seed),
+        StdFileHandler,
+        Limits {
+            phrase_len: AS_MAX_PHRASE_LEN as u16,
+            phrase_count: AS_MAX_PHRASE_COUNT,
+        },
+    );
+    Box::into_raw(Box::new(AsSystem(system)))
+}
+
+/// free system; null ignored
+///
+/// # Safety
+/// `system` from `as_system_new`, not freed before
+#[no_mangle]
+pub unsafe extern "C" fn as_system_free(system: *mut AsSystem) {
+    if !system.is_null() {
+        drop(unsafe { Box::from_raw(system) });
+    }
+}
+
+/// one clock tick of every bank
+///
+/// # Safety
+/// `system` from `as_system_new`, not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn as_system_tick(system: *mut AsSystem) -> AsStatus {
+    let Some(system) = (unsafe { system.as_mut() }) else {
+        return AsStatus::BadArgument;
+    };
+    match system.0.tick() {
+        Ok(()) => AsStatus::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// assign tempo in bpm, as clocked by ticks
+///
+/// # Safety
+/// `system` from `as_system_new`, not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn as_system_assign_tempo(system: *mut AsSystem, tempo: f32) -> AsStatus {
+    let Some(system) = (unsafe { system.as_mut() }) else {
+        return AsStatus::BadArgument;
+    };
+    system.0.assign_tempo(tempo);
+    AsStatus::Ok
+}
+
+/// render `len` interleaved samples of `channels` into `buffer`, overwriting
+/// it, master width and fade applied
+///
+/// # Safety
+/// `system` from `as_system_new`, not yet freed; `buffer` valid for `len`
+/// writes
+#[no_mangle]
+pub unsafe extern "C" fn as_system_read(
+    system: *mut AsSystem,
+    buffer: *mut f32,
+    len: usize,
+    channels: usize,
+    sample_rate: u32,
+) -> AsStatus {
+    let Some(system) = (unsafe { system.as_mut() }) else {
+        return AsStatus::BadArgument;
+    };
+    if buffer.is_null() || channels == 0 || !len.is_multiple_of(channels) {
+        return AsStatus::BadArgument;
+    }
+    let buffer = unsafe { core::slice::from_raw_parts_mut(buffer, len) };
+    buffer.fill(0.);
+    if let Err(e) = system.0.read_all(buffer, channels, sample_rate) {
+        return e.into();
+    }
+    system.0.master(buffer, channels, sample_rate);
+    AsStatus::Ok
+}
+
+/// push pad `event` to `bank`, as a full-velocity hit
+///
+/// # Safety
+/// `system` from `as_system_new`, not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn as_system_push_event(
+    system: *mut AsSystem,
+    bank: usize,
+    event: AsEvent,
+) -> AsStatus {
+    let Some(system) = (unsafe { system.as_mut() }) else {
+        return AsStatus::BadArgument;
+    };
+    let system = &mut system.0;
+    let Some(bank_h) = system.banks.get_mut(bank) else {
+        return AsStatus::BadArgument;
+    };
+    let event = match passive::Event::try_from(event) {
+        Ok(event) => event,
+        Err(status) => return status,
+    };
+    match bank_h.push_event(event, &mut system.rand, &mut system.fs) {
+        Ok(()) => AsStatus::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// load .bd bank at utf-8 `path` into `bank`, fitted to its capacity
+///
+/// # Safety
+/// `system` from `as_system_new`, not yet freed; `path` null-terminated
+#[no_mangle]
+pub unsafe extern "C" fn as_system_load_bank(
+    system: *mut AsSystem,
+    bank: usize,
+    path: *const c_char,
+) -> AsStatus {
+    let Some(system) = (unsafe { system.as_mut() }) else {
+        return AsStatus::BadArgument;
+    };
+    let Some(bank_h) = system.0.banks.get_mut(bank) else {
+        return AsStatus::BadArgument;
+    };
+    if path.is_null() {
+        return AsStatus::BadArgument;
+    }
+    let Ok(path) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return AsStatus::BadArgument;
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return AsStatus::DataNotFound,
+        Err(_) => return AsStatus::Io,
+    };
+    let Ok(saved) = SavedBank::from_bd(&bytes) else {
+        return AsStatus::BadFormat;
+    };
+    bank_h.assign_bank(saved.into_bank().0);
+    AsStatus::Ok
+}
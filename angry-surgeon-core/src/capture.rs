@@ -0,0 +1,115 @@
+//! live input capture: a ring of the latest input read back as a 16-bit mono
+//! wav, so a file handler can serve it at a path of its choosing and external
+//! audio is mangled by the same pad, loop and phrase machinery as any onset
+//!
+//! while rolling, a read `n` samples into the wav hears input from ring
+//! length less `n` samples ago; grabbed, the ring holds still
+
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicUsize, Ordering};
+
+/// file name hosts serve the capture wav by
+pub const CAPTURE_NAME: &str = "live.wav";
+/// bytes of wav header ahead of pcm
+const HEADER_LEN: u64 = 44;
+
+/// lock-free ring of `LEN` mono samples, written only by the input callback
+/// and read by any number of others
+pub struct Capture<const LEN: usize> {
+    samples: [AtomicU16; LEN],
+    /// index next written, also the oldest sample held
+    head: AtomicUsize,
+    grabbed: AtomicBool,
+    sample_rate: AtomicU32,
+}
+
+impl<const LEN: usize> Capture<LEN> {
+    pub const fn new(sample_rate: u32) -> Self {
+        Self {
+            samples: [const { AtomicU16::new(0) }; LEN],
+            head: AtomicUsize::new(0),
+            grabbed: AtomicBool::new(false),
+            sample_rate: AtomicU32::new(sample_rate),
+        }
+    }
+
+    /// rate of input pushed, written into the wav header
+    pub fn assign_sample_rate(&self, sample_rate: u32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// hold ring contents still, dropping input pushed, or release it to roll
+    pub fn grab(&self, grabbed: bool) {
+        self.grabbed.store(grabbed, Ordering::Relaxed);
+    }
+
+    pub fn grabbed(&self) -> bool {
+        self.grabbed.load(Ordering::Relaxed)
+    }
+
+    /// push interleaved input of `channels` mixed down to mono, unless grabbed
+    pub fn push(&self, data: &[f32], channels: usize) {
+        if self.grabbed() {
+            return;
+        }
+        let mut head = self.head.load(Ordering::Relaxed);
+        for frame in data.chunks_exact(channels) {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            let word = (mono.clamp(-1., 1.) * i16::MAX as f32) as i16;
+            self.samples[head].store(word as u16, Ordering::Relaxed);
+            head = (head + 1) % LEN;
+        }
+        self.head.store(head, Ordering::Release);
+    }
+
+    /// bytes of wav, header and pcm
+    pub fn file_len(&self) -> u64 {
+        HEADER_LEN + LEN as u64 * 2
+    }
+
+    /// read wav bytes from `pos` into `buf`, oldest sample first; returns
+    /// bytes read, short only at end of wav
+    pub fn read_at(&self, pos: u64, buf: &mut [u8]) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let header = self.header();
+        let mut read = 0;
+        for (pos, byte) in (pos..self.file_len()).zip(buf.iter_mut()) {
+            *byte = match pos.checked_sub(HEADER_LEN) {
+                None => header[pos as usize],
+                Some(offset) => {
+                    let index = (head + (offset / 2) as usize) % LEN;
+                    let word = self.samples[index].load(Ordering::Relaxed);
+                    word.to_le_bytes()[(offset % 2) as usize]
+                }
+            };
+            read += 1;
+        }
+        read
+    }
+
+    fn header(&self) -> [u8; HEADER_LEN as usize] {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let data_len = LEN as u32 * 2;
+        let mut header = [0; HEADER_LEN as usize];
+        let fields: [&[u8]; 13] = [
+            b"RIFF",
+            &(HEADER_LEN as u32 - 8 + data_len).to_le_bytes(),
+            b"WAVE",
+            b"fmt ",
+            &16u32.to_le_bytes(), // `fmt ` chunk size
+            &1u16.to_le_bytes(),  // pcm integer format
+            &1u16.to_le_bytes(),  // 1 channel
+            &sample_rate.to_le_bytes(),
+            &(sample_rate * 2).to_le_bytes(), // byte rate
+            &2u16.to_le_bytes(),              // block align
+            &16u16.to_le_bytes(),             // 16 bits/sample
+            b"data",
+            &data_len.to_le_bytes(),
+        ];
+        let mut pos = 0;
+        for field in fields {
+            header[pos..pos + field.len()].copy_from_slice(field);
+            pos += field.len();
+        }
+        header
+    }
+}
@@ -0,0 +1,102 @@
+//! metronome click on step boundaries, accented on the first beat of each
+//! bar: on a dedicated output pair kept out of the mix, e.g. for a drummer's
+//! in-ear feed, or mixed into every pair for practice against the internal
+//! clock
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// steps per beat
+const BEAT_LEN: u32 = 4;
+/// beats per bar; the first accented
+const BAR_LEN: u32 = 4;
+/// click length in ms
+const CLICK_LEN: u32 = 30;
+/// click pitches in hz
+const BEAT_HZ: f32 = 1000.;
+const ACCENT_HZ: f32 = 2000.;
+
+pub struct Click {
+    /// sounds click; off by default
+    pub enabled: bool,
+    /// output pair counted from 0, overwritten with click, silent while off;
+    /// mixed into every pair if none, off if beyond output channels
+    pub route: Option<usize>,
+    pub level: f32,
+    ticks_per_beat: u32,
+    ticks: u32,
+    /// pitch of sounding click, if any
+    hz: Option<f32>,
+    /// frames into sounding click
+    phase: u32,
+}
+
+impl Click {
+    pub(crate) fn new(ticks_per_step: u16) -> Self {
+        Self {
+            enabled: false,
+            route: None,
+            level: 0.5,
+            ticks_per_beat: ticks_per_step as u32 * BEAT_LEN,
+            ticks: 0,
+            hz: None,
+            phase: 0,
+        }
+    }
+
+    /// sound click on each beat
+    pub(crate) fn tick(&mut self) {
+        if self.ticks.is_multiple_of(self.ticks_per_beat) {
+            let accent = (self.ticks / self.ticks_per_beat).is_multiple_of(BAR_LEN);
+            self.hz = Some(if accent { ACCENT_HZ } else { BEAT_HZ });
+            self.phase = 0;
+        }
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    /// realign beats with clock restart
+    pub(crate) fn stop(&mut self) {
+        self.ticks = 0;
+    }
+
+    /// realign beats with clock jump to `step`
+    pub(crate) fn locate(&mut self, step: u32) {
+        self.ticks = step.wrapping_mul(self.ticks_per_beat / BEAT_LEN);
+    }
+
+    /// overwrite routed pair of interleaved `buffer` of `channels` with click,
+    /// else mix it into every pair
+    pub(crate) fn process(&mut self, buffer: &mut [f32], channels: usize, sample_rate: u32) {
+        let route = match self.route {
+            Some(route) if (route + 1) * 2 > channels => return,
+            None if !self.enabled => return,
+            route => route,
+        };
+        let len = CLICK_LEN * sample_rate / 1000;
+        for frame in buffer.chunks_exact_mut(channels) {
+            let mut v = 0.;
+            if let Some(hz) = self.hz.filter(|_| self.enabled) {
+                let t = self.phase as f32 / sample_rate as f32;
+                // exponential decay to ~-60 db over click
+                let env = (-7. * self.phase as f32 / len as f32).exp();
+                v = f32::sin(core::f32::consts::TAU * hz * t) * env * self.level;
+                self.phase += 1;
+                if self.phase >= len {
+                    self.hz = None;
+                }
+            }
+            match route {
+                Some(route) => {
+                    frame[route * 2] = v;
+                    frame[route * 2 + 1] = v;
+                }
+                None => {
+                    for sample in frame.iter_mut() {
+                        *sample += v;
+                    }
+                }
+            }
+        }
+    }
+}
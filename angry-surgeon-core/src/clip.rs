@@ -0,0 +1,35 @@
+//! soft clipper over the summed output, so overs saturate rather than wrap
+
+/// cubic soft clipper; unity slope at `drive` 1, flattening into `ceiling`
+#[derive(Copy, Clone, PartialEq)]
+pub struct Clipper {
+    pub enabled: bool,
+    /// input gain into curve
+    pub drive: f32,
+    /// peak output magnitude
+    pub ceiling: f32,
+}
+
+impl Default for Clipper {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            drive: 1.,
+            ceiling: 0.98,
+        }
+    }
+}
+
+impl Clipper {
+    pub fn process(&self, buffer: &mut [f32]) {
+        if !self.enabled {
+            return;
+        }
+        let ceiling = self.ceiling.max(f32::EPSILON);
+        for sample in buffer.iter_mut() {
+            // x - x^3 / 3 peaks at 2 / 3 with zero slope at 1
+            let x = (*sample * self.drive / (1.5 * ceiling)).clamp(-1., 1.);
+            *sample = 1.5 * ceiling * (x - x * x * x / 3.);
+        }
+    }
+}
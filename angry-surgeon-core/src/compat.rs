@@ -0,0 +1,207 @@
+//! tolerant loading of banks saved with other pad or step capacities
+
+extern crate alloc;
+
+use crate::{pads, passive, scene};
+use alloc::vec::Vec;
+
+/// what changed loading a saved bank into this capacity
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Compat {
+    /// pad count bank was saved with
+    pub saved_pads: usize,
+    /// kits beyond pad capacity, dropped
+    pub kits_dropped: usize,
+    /// onsets of pads beyond capacity, dropped
+    pub onsets_dropped: usize,
+    /// phrases beyond pad capacity, dropped
+    pub phrases_dropped: usize,
+    /// phrases beyond step capacity, truncated to their opening steps
+    pub phrases_truncated: usize,
+    /// pool entries naming phrases beyond pad capacity, dropped
+    pub pool_entries_dropped: usize,
+}
+
+impl Compat {
+    /// whether bank loaded unchanged
+    pub fn is_clean(&self) -> bool {
+        self.kits_dropped == 0
+            && self.onsets_dropped == 0
+            && self.phrases_dropped == 0
+            && self.phrases_truncated == 0
+            && self.pool_entries_dropped == 0
+    }
+}
+
+impl core::fmt::Display for Compat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "saved with {} pads", self.saved_pads)?;
+        for (count, what) in [
+            (self.kits_dropped, "kits dropped"),
+            (self.onsets_dropped, "onsets dropped"),
+            (self.phrases_dropped, "phrases dropped"),
+            (self.phrases_truncated, "phrases truncated"),
+            (self.pool_entries_dropped, "pool entries dropped"),
+        ] {
+            if count > 0 {
+                write!(f, "; {} {}", count, what)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SavedKit {
+    onsets: Vec<Option<passive::Onset>>,
+    #[serde(default)]
+    locks: Vec<bool>,
+    #[serde(default)]
+    layers: Vec<pads::Layers>,
+}
+
+/// kit as saved ahead of layers, a single onset per pad
+#[derive(serde::Deserialize)]
+struct LegacyKit {
+    onsets: Vec<Option<passive::Onset>>,
+    locks: Vec<bool>,
+}
+
+/// binary bank as saved ahead of layers
+#[derive(serde::Deserialize)]
+pub(crate) struct LegacyBank {
+    kits: Vec<Option<LegacyKit>>,
+    phrases: Vec<Option<passive::SavedPhrase>>,
+    groove: Option<passive::Groove>,
+    pools: Vec<passive::Pool>,
+    scenes: Vec<scene::Scene>,
+}
+
+impl From<LegacyBank> for SavedBank {
+    fn from(legacy: LegacyBank) -> Self {
+        let kits = legacy.kits.into_iter().map(|v| {
+            v.map(|v| SavedKit {
+                onsets: v.onsets,
+                locks: v.locks,
+                layers: Vec::new(),
+            })
+        });
+        Self {
+            kits: kits.collect(),
+            phrases: legacy.phrases,
+            groove: legacy.groove,
+            pools: legacy.pools,
+            scenes: legacy.scenes,
+        }
+    }
+}
+
+/// binary bank as saved ahead of scenes and layers
+#[derive(serde::Deserialize)]
+pub(crate) struct UnscenedBank {
+    kits: Vec<Option<LegacyKit>>,
+    phrases: Vec<Option<passive::SavedPhrase>>,
+    groove: Option<passive::Groove>,
+    pools: Vec<passive::Pool>,
+}
+
+impl From<UnscenedBank> for SavedBank {
+    fn from(legacy: UnscenedBank) -> Self {
+        Self::from(LegacyBank {
+            kits: legacy.kits,
+            phrases: legacy.phrases,
+            groove: legacy.groove,
+            pools: legacy.pools,
+            scenes: Vec::new(),
+        })
+    }
+}
+
+/// bank as saved, of whatever capacity it was saved with
+#[derive(serde::Deserialize)]
+pub struct SavedBank {
+    kits: Vec<Option<SavedKit>>,
+    phrases: Vec<Option<passive::SavedPhrase>>,
+    #[serde(default)]
+    groove: Option<passive::Groove>,
+    #[serde(default)]
+    pools: Vec<passive::Pool>,
+    #[serde(default)]
+    scenes: Vec<scene::Scene>,
+}
+
+impl SavedBank {
+    /// scale loop lengths of banks saved while loops counted whole steps
+    pub(crate) fn scale_loops(mut self) -> Self {
+        for phrase in self.phrases.iter_mut().flatten() {
+            phrase.scale_loops();
+        }
+        self
+    }
+
+    /// fit into `PADS` pads of `STEPS` steps, padding missing entries and
+    /// dropping or truncating those beyond capacity
+    pub fn into_bank<const PADS: usize, const STEPS: usize>(
+        self,
+    ) -> (pads::Bank<PADS, STEPS>, Compat) {
+        let mut compat = Compat {
+            saved_pads: self.kits.len().max(self.phrases.len()),
+            ..Default::default()
+        };
+        let mut bank = pads::Bank::<PADS, STEPS>::default();
+        for (index, saved) in self.kits.into_iter().enumerate() {
+            let Some(saved) = saved else {
+                continue;
+            };
+            if index >= PADS {
+                compat.kits_dropped += 1;
+                continue;
+            }
+            let mut kit = pads::Kit::<PADS>::default();
+            for (index, onset) in saved.onsets.into_iter().enumerate() {
+                match kit.onsets.get_mut(index) {
+                    Some(slot) => *slot = onset,
+                    None => compat.onsets_dropped += onset.is_some() as usize,
+                }
+            }
+            for (index, layers) in saved.layers.into_iter().enumerate() {
+                match kit.layers.get_mut(index) {
+                    Some(slot) => *slot = layers,
+                    None => compat.onsets_dropped += layers.onsets.len(),
+                }
+            }
+            for (lock, saved) in kit.locks.iter_mut().zip(saved.locks) {
+                *lock = saved;
+            }
+            bank.kits[index] = Some(kit);
+        }
+        for (index, saved) in self.phrases.into_iter().enumerate() {
+            let Some(saved) = saved else {
+                continue;
+            };
+            if index >= PADS {
+                compat.phrases_dropped += 1;
+                continue;
+            }
+            if saved.len() > STEPS {
+                compat.phrases_truncated += 1;
+            }
+            bank.phrases[index] = Some(saved.into());
+        }
+        bank.groove = self.groove;
+        bank.pools = self.pools;
+        for pool in bank.pools.iter_mut() {
+            let len = pool.phrases.len();
+            pool.phrases.retain(|v| (*v as usize) < PADS);
+            compat.pool_entries_dropped += len - pool.phrases.len();
+        }
+        bank.scenes = self.scenes;
+        (bank, compat)
+    }
+}
+
+impl<const PADS: usize, const STEPS: usize> From<SavedBank> for pads::Bank<PADS, STEPS> {
+    fn from(saved: SavedBank) -> Self {
+        saved.into_bank().0
+    }
+}
@@ -0,0 +1,40 @@
+//! dc blocker over the summed output, so offsets from looped or frozen grains
+//! don't eat into headroom or thump on stop
+
+/// channels filtered; any beyond are left be
+const MAX_CHANNELS: usize = 8;
+/// corner in hz, well below anything audible
+const CUTOFF: f32 = 10.;
+
+/// one-pole one-zero high-pass per channel
+pub struct DcBlocker {
+    pub enabled: bool,
+    /// last input and output of each channel
+    state: [(f32, f32); MAX_CHANNELS],
+}
+
+impl Default for DcBlocker {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            state: [(0., 0.); MAX_CHANNELS],
+        }
+    }
+}
+
+impl DcBlocker {
+    /// filter interleaved `buffer` of `channels`
+    pub fn process(&mut self, buffer: &mut [f32], channels: usize, sample_rate: u32) {
+        if !self.enabled {
+            return;
+        }
+        let pole = 1. - core::f32::consts::TAU * CUTOFF / sample_rate.max(1) as f32;
+        for frame in buffer.chunks_exact_mut(channels) {
+            for (sample, (x1, y1)) in frame.iter_mut().zip(self.state.iter_mut()) {
+                *y1 = *sample - *x1 + pole * *y1;
+                *x1 = *sample;
+                *sample = *y1;
+            }
+        }
+    }
+}
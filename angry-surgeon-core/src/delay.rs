@@ -0,0 +1,77 @@
+//! tempo-synced stereo send/return delay over the bank sum
+
+extern crate alloc;
+
+use crate::pads;
+
+/// ring length in frames; ~680 ms at 48 khz
+pub const MAX_DELAY_LEN: usize = 32768;
+/// frames rendered per bank before summing into send, bounding scratch
+const SEND_LEN: usize = 1024;
+
+pub struct Delay {
+    /// delay time in beats; halved until it fits ring
+    pub division: f32,
+    /// in 0..1
+    pub feedback: f32,
+    pub(crate) tempo: f32,
+    ring: alloc::boxed::Box<[[f32; 2]]>,
+    head: usize,
+    /// single bank render
+    pub(crate) dry: alloc::boxed::Box<[f32]>,
+    /// summed bank sends
+    pub(crate) send: alloc::boxed::Box<[f32]>,
+}
+
+impl Delay {
+    pub(crate) fn new() -> Self {
+        Self {
+            division: 0.75,
+            feedback: 0.4,
+            tempo: 0.,
+            ring: alloc::vec![[0.; 2]; MAX_DELAY_LEN].into_boxed_slice(),
+            head: 0,
+            dry: alloc::vec![0.; SEND_LEN * 2].into_boxed_slice(),
+            send: alloc::vec![0.; SEND_LEN * 2].into_boxed_slice(),
+        }
+    }
+
+    /// whole frames of `channels` fitting scratch, in samples
+    pub(crate) fn chunk_len(channels: usize) -> usize {
+        SEND_LEN * 2 / channels * channels
+    }
+
+    /// delay length in frames, if clock running
+    fn len(&self, sample_rate: u32) -> Option<usize> {
+        if self.tempo <= 0. {
+            return None;
+        }
+        let mut len = self.division * 60. / self.tempo * sample_rate as f32;
+        while len >= MAX_DELAY_LEN as f32 {
+            len /= 2.;
+        }
+        Some((len as usize).max(1))
+    }
+
+    /// feed summed sends of first `len` samples of interleaved `channels` into
+    /// ring, leaving return in dry scratch
+    pub(crate) fn process(&mut self, len: usize, channels: usize, sample_rate: u32) {
+        let delay_len = self.len(sample_rate);
+        let dry = &mut self.dry[..len];
+        dry.fill(0.);
+        let Some(delay_len) = delay_len else {
+            return;
+        };
+        for (ret, send) in dry.chunks_exact_mut(channels).zip(self.send.chunks_exact(channels)) {
+            // first pair, or mono to both sides
+            let send = [send[0], send[1.min(channels - 1)]];
+            let tap = self.ring[(self.head + MAX_DELAY_LEN - delay_len) % MAX_DELAY_LEN];
+            self.ring[self.head] = [
+                send[0] + tap[0] * self.feedback,
+                send[1] + tap[1] * self.feedback,
+            ];
+            self.head = (self.head + 1) % MAX_DELAY_LEN;
+            pads::add_stereo(ret, tap[0], tap[1]);
+        }
+    }
+}
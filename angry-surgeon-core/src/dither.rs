@@ -0,0 +1,64 @@
+//! requantization of f32 samples to integer pcm, with tpdf dither
+
+#[allow(unused_imports)]
+use micromath::F32Ext;
+use tinyrand::{Rand, Seeded};
+
+/// integer pcm word size
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum BitDepth {
+    #[default]
+    Sixteen,
+    TwentyFour,
+}
+
+impl BitDepth {
+    pub fn bits(self) -> u8 {
+        match self {
+            BitDepth::Sixteen => 16,
+            BitDepth::TwentyFour => 24,
+        }
+    }
+
+    /// packed word length in bytes
+    pub fn bytes(self) -> u16 {
+        self.bits() as u16 / 8
+    }
+
+    /// full scale word
+    fn max(self) -> f32 {
+        ((1 << (self.bits() - 1)) - 1) as f32
+    }
+}
+
+pub struct Dither {
+    pub depth: BitDepth,
+    /// add triangular noise of one lsb before rounding; truncate otherwise
+    pub enabled: bool,
+    rand: tinyrand::Wyrand,
+}
+
+impl Dither {
+    pub fn new(depth: BitDepth, enabled: bool) -> Self {
+        Self {
+            depth,
+            enabled,
+            rand: tinyrand::Wyrand::seed(0xd17e),
+        }
+    }
+
+    /// quantize `sample` in -1..=1 to signed word of `depth`
+    pub fn quantize(&mut self, sample: f32) -> i32 {
+        let max = self.depth.max();
+        let scaled = sample.clamp(-1., 1.) * max;
+        let word = if self.enabled {
+            // difference of two uniforms is triangular over -1..1 lsb
+            let a = self.rand.next_u32() as f32 / u32::MAX as f32;
+            let b = self.rand.next_u32() as f32 / u32::MAX as f32;
+            (scaled + a - b).round()
+        } else {
+            scaled
+        };
+        word.clamp(-max, max) as i32
+    }
+}
@@ -4,17 +4,78 @@
 use core::fmt::{Debug, Display};
 use embedded_io::{ErrorType, ReadExactError, SeekFrom};
 
+extern crate alloc;
+
+#[cfg(all(feature = "sync", feature = "async"))]
+compile_error!("features `sync` and `async` are exclusive; disable default features for `async`");
+#[cfg(not(any(feature = "sync", feature = "async")))]
+compile_error!("enable one of features `sync` or `async`");
+
+// file handler the core is built over: blocking, or async with the `async`
+// feature, where every method reaching it is async too
+#[cfg(feature = "async")]
+use AsyncFileHandler as Fs;
+#[cfg(feature = "sync")]
+use FileHandler as Fs;
+
 mod active;
+mod bd;
+mod browse;
+#[cfg(feature = "capi")]
+mod capi;
+mod capture;
+mod click;
+mod clip;
+mod compat;
+mod dc;
+mod delay;
+mod dither;
+mod librarian;
+mod loudness;
+mod meter;
+mod offset;
 mod pads;
 mod passive;
+mod prefetch;
+mod rehearse;
+mod samples;
+mod scene;
+mod soak;
 
-pub use pads::{Bank, SystemHandler, GRAIN_LEN};
-pub use passive::{Event, Onset, Rd, Wav};
+pub use bd::{BdError, BD_VERSION};
+pub use browse::{Browser, DirEntry, Tags};
+#[cfg(feature = "capi")]
+pub use capi::{AsEvent, AsEventKind, AsStatus, AsSystem, StdFileHandler};
+pub use capture::{Capture, CAPTURE_NAME};
+pub use click::Click;
+pub use clip::Clipper;
+pub use compat::{Compat, SavedBank};
+pub use dc::DcBlocker;
+pub use delay::{Delay, MAX_DELAY_LEN};
+pub use dither::{BitDepth, Dither};
+pub use librarian::{Collision, Merged};
+pub use loudness::Loudness;
+pub use meter::{Level, Meter};
+pub use offset::{ClockOffset, MAX_CLOCK_OFFSET};
+pub use pads::{
+    add_stereo, Accent, AccentPattern, Activity, Bank, Curve, DriftMode, Envelope, FilterMode,
+    Generator, Interpolation, Launch, Layers, Limits, MotionTarget, Release, Resample, Select,
+    Stereo, SystemHandler, MASTER_FADE_LEN, MAX_GRAIN_LEN, MAX_VOICES,
+};
+pub use passive::{
+    Event, Follow, Groove, GrooveStep, Onset, Pool, Quantize, Rate, Rd, Wav, LOOP_STEP,
+};
+pub use prefetch::{BLOCK_LEN, DEFAULT_PREFETCH, MAX_PREFETCH};
+pub use rehearse::{Rehearsal, MAX_REHEARSAL_BARS};
+pub use scene::{Scene, Snapshot};
+pub use soak::Soak;
 
 #[derive(Debug)]
 pub enum Error<E: Debug> {
     BadFormat,
     DataNotFound,
+    /// file handler lacks the operation, e.g. write on read-only media
+    Unsupported,
     Other(E),
 }
 
@@ -23,6 +84,7 @@ impl<E: Debug + Display> core::fmt::Display for Error<E> {
         match self {
             Self::BadFormat => write!(f, "bad format"),
             Self::DataNotFound => write!(f, "data not found"),
+            Self::Unsupported => write!(f, "unsupported"),
             Self::Other(e) => write!(f, "{}", e),
         }
     }
@@ -45,6 +107,19 @@ pub trait FileHandler: ErrorType {
     /// clone file handle
     fn try_clone(&mut self, file: &Self::File) -> Result<Self::File, Self::Error>;
 
+    /// create file handle for write, truncating any existing file;
+    /// unsupported unless implemented
+    fn create(&mut self, path: &str) -> Result<Self::File, Error<Self::Error>> {
+        let _ = path;
+        Err(Error::Unsupported)
+    }
+
+    /// list entries of directory at `path`; unsupported unless implemented
+    fn read_dir(&mut self, path: &str) -> Result<alloc::vec::Vec<DirEntry>, Error<Self::Error>> {
+        let _ = path;
+        Err(Error::Unsupported)
+    }
+
     /// close file
     fn close(&mut self, file: &Self::File) -> Result<(), Self::Error>;
 
@@ -65,6 +140,26 @@ pub trait FileHandler: ErrorType {
     /// The `Ok(0)` doesn't indicate EOF, unlike when called with a non-empty buffer.
     fn read(&mut self, file: &mut Self::File, buf: &mut [u8]) -> Result<usize, Self::Error>;
 
+    /// Write a buffer into this writer, returning how many bytes were written.
+    ///
+    /// If the writer is not currently ready to accept more bytes (for example, its buffer is full),
+    /// this function blocks until it is ready to accept at least one byte.
+    ///
+    /// Unsupported unless implemented.
+    fn write(&mut self, file: &mut Self::File, buf: &[u8]) -> Result<usize, Error<Self::Error>> {
+        let _ = (file, buf);
+        Err(Error::Unsupported)
+    }
+
+    /// Flush this output stream, ensuring that all intermediately buffered contents reach their
+    /// destination.
+    ///
+    /// Unsupported unless implemented.
+    fn flush(&mut self, file: &mut Self::File) -> Result<(), Error<Self::Error>> {
+        let _ = file;
+        Err(Error::Unsupported)
+    }
+
     /// Seek to an offset, in bytes, in a stream.
     fn seek(&mut self, file: &mut Self::File, pos: SeekFrom) -> Result<u64, Self::Error>;
 
@@ -84,8 +179,124 @@ pub trait FileHandler: ErrorType {
         Ok(())
     }
 
+    /// Write an entire buffer into this writer.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `write()` returns `Ok(0)`.
+    fn write_all(&mut self, file: &mut Self::File, buf: &[u8]) -> Result<(), Error<Self::Error>> {
+        let mut slice = buf;
+        while !slice.is_empty() {
+            match self.write(file, slice) {
+                Ok(0) => panic!("write() returned Ok(0)"),
+                Ok(n) => slice = &slice[n..],
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the current seek position from the start of the stream.
     fn stream_position(&mut self, file: &mut Self::File) -> Result<u64, Self::Error> {
         self.seek(file, SeekFrom::Current(0))
     }
 }
+
+/// as `FileHandler`, awaiting io rather than blocking, e.g. on sd reads
+/// inside an audio task
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncFileHandler: ErrorType {
+    type File;
+
+    /// open file handle
+    async fn open(&mut self, path: &str) -> Result<Self::File, Self::Error>;
+
+    /// clone file handle
+    async fn try_clone(&mut self, file: &Self::File) -> Result<Self::File, Self::Error>;
+
+    /// create file handle for write, truncating any existing file;
+    /// unsupported unless implemented
+    async fn create(&mut self, path: &str) -> Result<Self::File, Error<Self::Error>> {
+        let _ = path;
+        Err(Error::Unsupported)
+    }
+
+    /// list entries of directory at `path`; unsupported unless implemented
+    async fn read_dir(
+        &mut self,
+        path: &str,
+    ) -> Result<alloc::vec::Vec<DirEntry>, Error<Self::Error>> {
+        let _ = path;
+        Err(Error::Unsupported)
+    }
+
+    /// close file
+    async fn close(&mut self, file: &Self::File) -> Result<(), Self::Error>;
+
+    /// read some bytes into `buf`, returning how many; `Ok(0)` at end of file
+    async fn read(&mut self, file: &mut Self::File, buf: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// write some bytes of `buf`, returning how many; unsupported unless
+    /// implemented
+    async fn write(
+        &mut self,
+        file: &mut Self::File,
+        buf: &[u8],
+    ) -> Result<usize, Error<Self::Error>> {
+        let _ = (file, buf);
+        Err(Error::Unsupported)
+    }
+
+    /// flush buffered writes to their destination; unsupported unless
+    /// implemented
+    async fn flush(&mut self, file: &mut Self::File) -> Result<(), Error<Self::Error>> {
+        let _ = file;
+        Err(Error::Unsupported)
+    }
+
+    /// seek to an offset, in bytes, in a stream
+    async fn seek(&mut self, file: &mut Self::File, pos: SeekFrom) -> Result<u64, Self::Error>;
+
+    async fn read_exact(
+        &mut self,
+        file: &mut Self::File,
+        buf: &mut [u8],
+    ) -> Result<(), ReadExactError<Self::Error>> {
+        let mut slice = &mut buf[..];
+        while !slice.is_empty() {
+            let n = self.read(file, slice).await?;
+            if n == 0 {
+                return Err(ReadExactError::UnexpectedEof);
+            }
+            slice = &mut slice[n..];
+        }
+        Ok(())
+    }
+
+    /// write an entire buffer
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `write()` returns `Ok(0)`.
+    async fn write_all(
+        &mut self,
+        file: &mut Self::File,
+        buf: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        let mut slice = buf;
+        while !slice.is_empty() {
+            match self.write(file, slice).await {
+                Ok(0) => panic!("write() returned Ok(0)"),
+                Ok(n) => slice = &slice[n..],
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// current seek position from the start of the stream
+    async fn stream_position(&mut self, file: &mut Self::File) -> Result<u64, Self::Error> {
+        self.seek(file, SeekFrom::Current(0)).await
+    }
+}
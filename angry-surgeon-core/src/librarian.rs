@@ -0,0 +1,137 @@
+//! bank librarian: merge and split banks, for reorganizing material between
+//! sessions
+
+extern crate alloc;
+
+use crate::{pads::Bank, passive::Follow};
+use alloc::vec::Vec;
+
+/// how merge settles a kit, phrase or pool slot both banks fill
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Collision {
+    /// keep ours, dropping theirs
+    Keep,
+    /// take theirs, dropping ours
+    Replace,
+    /// move theirs to first slot free in ours, dropping it if none
+    #[default]
+    Relocate,
+}
+
+/// slots of the other bank a merge took as is, moved and dropped
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Merged {
+    pub taken: usize,
+    pub moved: usize,
+    pub dropped: usize,
+}
+
+impl core::fmt::Display for Merged {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} taken; {} moved; {} dropped",
+            self.taken, self.moved, self.dropped
+        )
+    }
+}
+
+impl Merged {
+    /// settle `theirs` into `ours` slot by slot, returning slot each of theirs
+    /// landed in, if any
+    fn settle<T, const N: usize>(
+        &mut self,
+        ours: &mut [Option<T>; N],
+        theirs: [Option<T>; N],
+        collision: Collision,
+    ) -> [Option<u8>; N] {
+        let mut landed = [None; N];
+        for (index, theirs) in theirs.into_iter().enumerate() {
+            let Some(theirs) = theirs else {
+                continue;
+            };
+            let slot = if ours[index].is_none() || collision == Collision::Replace {
+                self.taken += 1;
+                Some(index)
+            } else if collision == Collision::Relocate {
+                let free = ours.iter().position(|v| v.is_none());
+                if free.is_some() {
+                    self.moved += 1;
+                }
+                free
+            } else {
+                None
+            };
+            match slot {
+                Some(slot) => {
+                    ours[slot] = Some(theirs);
+                    landed[index] = Some(slot as u8);
+                }
+                None => self.dropped += 1,
+            }
+        }
+        landed
+    }
+}
+
+impl<const PADS: usize, const STEPS: usize> Bank<PADS, STEPS> {
+    /// merge kits, phrases, pools and groove of `other` in, settling slots
+    /// both fill per `collision`; pools and chains follow their phrases
+    /// wherever moved
+    pub fn merge(&mut self, other: Self, collision: Collision) -> Merged {
+        let mut merged = Merged::default();
+        merged.settle(&mut self.kits, other.kits, collision);
+        let landed = merged.settle(&mut self.phrases, other.phrases, collision);
+        // keep segments of long takes chained wherever moved
+        for slot in landed.iter().flatten() {
+            if let Some(phrase) = self.phrases[*slot as usize].as_mut() {
+                if let Follow::Chain(next) = phrase.follow {
+                    let next = landed.get(next as usize).copied().flatten();
+                    phrase.follow = next.map_or(Follow::Next, Follow::Chain);
+                }
+            }
+        }
+        for mut pool in other.pools {
+            pool.phrases = pool
+                .phrases
+                .iter()
+                .filter_map(|v| *landed.get(*v as usize)?)
+                .collect();
+            match self.pools.iter().position(|v| v.name == pool.name) {
+                None => {
+                    self.pools.push(pool);
+                    merged.taken += 1;
+                }
+                Some(index) if collision == Collision::Replace => {
+                    self.pools[index] = pool;
+                    merged.taken += 1;
+                }
+                Some(_) if collision == Collision::Relocate => {
+                    pool.name.push('+');
+                    self.pools.push(pool);
+                    merged.moved += 1;
+                }
+                Some(_) => merged.dropped += 1,
+            }
+        }
+        if self.groove.is_none() || collision == Collision::Replace && other.groove.is_some() {
+            self.groove = other.groove;
+        }
+        merged
+    }
+
+    /// bank per kit, holding it alone at its first slot, with groove but no
+    /// phrases or pools
+    pub fn split(&self) -> Vec<Self> {
+        self.kits
+            .iter()
+            .flatten()
+            .map(|kit| {
+                let mut bank = Self::default();
+                bank.kits[0] = Some(kit.clone());
+                bank.groove = self.groove.clone();
+                bank
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,194 @@
+//! integrated loudness of the master bus after itu-r bs.1770: k-weighted mean
+//! square over gated 400 ms blocks
+
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// channels metered; any beyond are left out
+const MAX_CHANNELS: usize = 8;
+/// block loudness histogram resolution in lu
+const BIN_LU: f32 = 0.1;
+/// blocks quieter than this in lufs never count
+const ABSOLUTE_GATE: f32 = -70.;
+/// histogram ceiling in lufs; louder blocks count in its top bin
+const CEILING: f32 = 10.;
+const BINS: usize = ((CEILING - ABSOLUTE_GATE) / BIN_LU) as usize;
+/// blocks quieter by this in lu than the absolute-gated mean never count
+const RELATIVE_GATE: f32 = 10.;
+
+/// transposed direct form ii biquad, normalized by a0
+#[derive(Copy, Clone, Default)]
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+}
+
+impl Biquad {
+    /// k-weighting pre-filter stage 1: high shelf modelling the head
+    fn shelf(sample_rate: u32) -> Self {
+        let k = (core::f32::consts::PI * 1681.9745 / sample_rate as f32).tan();
+        let q = 0.70717524;
+        let vh = 10f32.powf(3.9998438 / 20.);
+        let vb = vh.powf(0.49966677);
+        let a0 = 1. + k / q + k * k;
+        Self {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2. * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        }
+    }
+
+    /// k-weighting stage 2: rlb high-pass
+    fn highpass(sample_rate: u32) -> Self {
+        let k = (core::f32::consts::PI * 38.13547 / sample_rate as f32).tan();
+        let q = 0.50032704;
+        let a0 = 1. + k / q + k * k;
+        Self {
+            b: [1., -2., 1.],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        }
+    }
+
+    fn process(&self, state: &mut [f32; 2], x: f32) -> f32 {
+        let y = self.b[0] * x + state[0];
+        state[0] = self.b[1] * x - self.a[0] * y + state[1];
+        state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+fn lufs(power: f32) -> f32 {
+    -0.691 + 10. * power.max(f32::MIN_POSITIVE).log10()
+}
+
+fn power(lufs: f32) -> f32 {
+    10f32.powf((lufs + 0.691) / 10.)
+}
+
+pub struct Loudness {
+    pub enabled: bool,
+    /// integrated loudness monitored against, in lufs
+    pub target: f32,
+    sample_rate: u32,
+    shelf: Biquad,
+    highpass: Biquad,
+    /// filter states per channel per stage
+    states: [[[f32; 2]; 2]; MAX_CHANNELS],
+    /// summed weighted square and frames of current 100 ms sub-block
+    sum: f32,
+    frames: u32,
+    /// mean squares of last four sub-blocks, each 400 ms block overlapping
+    /// the last by 75%
+    subs: [f32; 4],
+    /// sub-blocks seen, up to four
+    subs_len: usize,
+    /// latest block loudness in lufs, if any
+    momentary: Option<f32>,
+    /// gated block count by loudness
+    histogram: alloc::boxed::Box<[u32]>,
+}
+
+impl Loudness {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            target: -14.,
+            sample_rate: 0,
+            shelf: Biquad::default(),
+            highpass: Biquad::default(),
+            states: [[[0.; 2]; 2]; MAX_CHANNELS],
+            sum: 0.,
+            frames: 0,
+            subs: [0.; 4],
+            subs_len: 0,
+            momentary: None,
+            histogram: alloc::vec![0; BINS].into_boxed_slice(),
+        }
+    }
+
+    /// restart integration, e.g. ahead of a set
+    pub fn reset(&mut self) {
+        self.states = [[[0.; 2]; 2]; MAX_CHANNELS];
+        self.sum = 0.;
+        self.frames = 0;
+        self.subs_len = 0;
+        self.momentary = None;
+        self.histogram.fill(0);
+    }
+
+    /// meter interleaved `buffer` of `channels`, summing channels unweighted
+    pub(crate) fn process(&mut self, buffer: &[f32], channels: usize, sample_rate: u32) {
+        if !self.enabled {
+            return;
+        }
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.shelf = Biquad::shelf(sample_rate);
+            self.highpass = Biquad::highpass(sample_rate);
+            self.reset();
+        }
+        let sub_len = sample_rate / 10;
+        for frame in buffer.chunks_exact(channels) {
+            for (sample, states) in frame.iter().zip(self.states.iter_mut()) {
+                let v = self.shelf.process(&mut states[0], *sample);
+                let v = self.highpass.process(&mut states[1], v);
+                self.sum += v * v;
+            }
+            self.frames += 1;
+            if self.frames >= sub_len {
+                self.push_sub();
+            }
+        }
+    }
+
+    /// close current sub-block, gating the block it completes
+    fn push_sub(&mut self) {
+        self.subs.rotate_left(1);
+        self.subs[3] = self.sum / self.frames as f32;
+        self.sum = 0.;
+        self.frames = 0;
+        self.subs_len = (self.subs_len + 1).min(4);
+        if self.subs_len < 4 {
+            return;
+        }
+        let block = lufs(self.subs.iter().sum::<f32>() / 4.);
+        self.momentary = Some(block);
+        if block > ABSOLUTE_GATE {
+            let bin = ((block - ABSOLUTE_GATE) / BIN_LU) as usize;
+            self.histogram[bin.min(BINS - 1)] += 1;
+        }
+    }
+
+    /// loudness of latest 400 ms block in lufs, if any
+    pub fn momentary(&self) -> Option<f32> {
+        self.momentary
+    }
+
+    /// gated loudness since reset in lufs, if any block passed the gates
+    pub fn integrated(&self) -> Option<f32> {
+        let bin_power = |bin: usize| power(ABSOLUTE_GATE + (bin as f32 + 0.5) * BIN_LU);
+        let mean = |from: usize| {
+            let (sum, count) = self.histogram[from..]
+                .iter()
+                .enumerate()
+                .fold((0., 0), |(sum, count), (bin, n)| {
+                    (sum + bin_power(from + bin) * *n as f32, count + n)
+                });
+            (count > 0).then(|| sum / count as f32)
+        };
+        let relative = lufs(mean(0)?) - RELATIVE_GATE;
+        let from = ((relative - ABSOLUTE_GATE) / BIN_LU).max(0.) as usize;
+        mean(from.min(BINS - 1)).map(lufs)
+    }
+
+    /// whether integrated loudness exceeds target
+    pub fn over(&self) -> bool {
+        self.integrated().is_some_and(|v| v > self.target)
+    }
+}
@@ -0,0 +1,52 @@
+//! peak and rms level over short windows of output, for gain staging
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// window metered over in ms
+const WINDOW_MS: u32 = 100;
+
+/// level of one window, linear over every channel
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Level {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+#[derive(Default)]
+pub struct Meter {
+    peak: f32,
+    /// summed square of current window
+    sum: f32,
+    /// samples summed into current window
+    len: u32,
+    /// level of last window completed, if any since last taken
+    level: Option<Level>,
+}
+
+impl Meter {
+    /// meter interleaved `buffer` of `channels`
+    pub(crate) fn process(&mut self, buffer: &[f32], channels: usize, sample_rate: u32) {
+        let window = (sample_rate * WINDOW_MS / 1000).max(1) * channels as u32;
+        for sample in buffer.iter() {
+            self.peak = self.peak.max(sample.abs());
+            self.sum += sample * sample;
+            self.len += 1;
+            if self.len >= window {
+                self.level = Some(Level {
+                    peak: self.peak,
+                    rms: (self.sum / self.len as f32).sqrt(),
+                });
+                self.peak = 0.;
+                self.sum = 0.;
+                self.len = 0;
+            }
+        }
+    }
+
+    /// level of last window completed since last taken, if any
+    pub fn take(&mut self) -> Option<Level> {
+        self.level.take()
+    }
+}
@@ -0,0 +1,104 @@
+//! clock offset: external clock ticks delayed, or fired ahead of time off the
+//! last tick interval, to line output up with the master device by ear or by
+//! measurement
+
+/// largest offset either way in ms
+pub const MAX_CLOCK_OFFSET: f32 = 100.;
+/// delayed ticks pending at once; further ones dropped
+const QUEUE_LEN: usize = 8;
+
+#[derive(Default)]
+pub struct ClockOffset {
+    /// frames ticks are delayed by; ahead of time if negative
+    frames: i64,
+    /// frames passed since stream start
+    now: u64,
+    /// frame last tick arrived at, if running
+    last: Option<u64>,
+    /// frames between last two ticks arrived, if known
+    interval: Option<u64>,
+    /// frames delayed ticks fall due at, in order
+    due: heapless::Deque<u64, QUEUE_LEN>,
+    /// frame next tick falls due ahead of time at, if predicted
+    predicted: Option<u64>,
+    /// ticks fired ahead of time yet to arrive
+    early: u32,
+}
+
+impl ClockOffset {
+    /// offset ticks by `ms`, clamped to `MAX_CLOCK_OFFSET` either way; delays
+    /// if positive
+    pub fn assign(&mut self, ms: f32, sample_rate: u32) {
+        let ms = ms.clamp(-MAX_CLOCK_OFFSET, MAX_CLOCK_OFFSET);
+        self.frames = (ms * sample_rate as f32 / 1000.) as i64;
+        if self.frames >= 0 {
+            self.predicted = None;
+        }
+    }
+
+    /// note a tick arriving now; whether it falls due at once, else it fires
+    /// from `next`. ahead of time, each tick also predicts the one after it
+    pub fn push(&mut self) -> bool {
+        if let Some(last) = self.last {
+            self.interval = Some(self.now - last);
+        }
+        self.last = Some(self.now);
+        let now = if self.early > 0 {
+            // fired already
+            self.early -= 1;
+            false
+        } else if self.frames > 0 {
+            let _ = self.due.push_back(self.now + self.frames as u64);
+            false
+        } else {
+            // came before its prediction, if any, so fire it here instead
+            self.predicted = None;
+            true
+        };
+        if self.frames < 0 {
+            if let Some(interval) = self.interval {
+                let lead = self.frames.unsigned_abs();
+                self.predicted = Some(self.now + interval.saturating_sub(lead).max(1));
+            }
+        }
+        now
+    }
+
+    /// frames into the next `len` a tick falls due after, if any, passing
+    /// them and taking the tick
+    pub fn next(&mut self, len: usize) -> Option<usize> {
+        let delayed = self.due.front().copied();
+        let (frame, predicted) = match (delayed, self.predicted) {
+            (Some(d), Some(p)) if p < d => (p, true),
+            (Some(d), _) => (d, false),
+            (None, Some(p)) => (p, true),
+            (None, None) => return None,
+        };
+        if frame >= self.now + len as u64 {
+            return None;
+        }
+        if predicted {
+            self.predicted = None;
+            self.early += 1;
+        } else {
+            self.due.pop_front();
+        }
+        let frames = frame.saturating_sub(self.now);
+        self.now += frames;
+        Some(frames as usize)
+    }
+
+    /// pass `len` frames with no tick due
+    pub fn advance(&mut self, len: usize) {
+        self.now += len as u64;
+    }
+
+    /// drop pending ticks and forget tick interval with clock stop
+    pub fn stop(&mut self) {
+        self.last = None;
+        self.interval = None;
+        self.due.clear();
+        self.predicted = None;
+        self.early = 0;
+    }
+}
@@ -1,13 +1,29 @@
 //! main logic-to-audio driver
 
-use crate::{active, passive, Error, FileHandler};
+use crate::{
+    active,
+    click::Click,
+    clip::Clipper,
+    dc::DcBlocker,
+    delay::Delay,
+    loudness::Loudness,
+    meter::Meter,
+    passive,
+    prefetch::{self, Prefetch, DEFAULT_PREFETCH},
+    rehearse::Rehearsal,
+    samples::SampleCache,
+    scene::{self, Recall, Snapshot},
+    Error, Fs,
+};
 use embedded_io::ReadExactError;
-use tinyrand::Rand;
+use tinyrand::{Probability, Rand, Seeded, Wyrand};
 
 #[cfg(not(feature = "std"))]
 #[allow(unused_imports)]
 use micromath::F32Ext;
 
+extern crate alloc;
+
 macro_rules! actives_mut {
     ($bank_hdlr:expr) => {
         [
@@ -26,10 +42,21 @@ macro_rules! actives_mut {
     };
 }
 
-/// grain length in frames
-pub const GRAIN_LEN: usize = 1024;
-/// crossfade length in frames
-const FADE_LEN: usize = 128;
+/// longest grain in frames, as by default; grain buffers sized by it
+pub const MAX_GRAIN_LEN: usize = 1024;
+/// shortest grain in frames, texture control halving down to it
+const MIN_GRAIN_LEN: usize = 128;
+/// max boost applied by bank normalization
+const MAX_TRIM: f32 = 4.;
+/// crossfade length in frames of longest grain, in proportion for shorter
+const MAX_FADE_LEN: usize = 128;
+/// most voices per bank
+pub const MAX_VOICES: usize = 16;
+/// micro-loop lengths stutter control steps through, halving from half a step
+const STUTTER_ROLLS: u8 = 5;
+
+/// loop read within, as byte offset it wraps to and length in ticks
+type Span = (i64, f32);
 
 #[derive(PartialEq)]
 enum FadeState {
@@ -38,54 +65,280 @@ enum FadeState {
     Fading,
 }
 
+/// of the onset a grain or fade was read from, so it plays out as such once
+/// another onset sounds
+#[derive(Copy, Clone)]
+struct Meta {
+    /// read speed multiplier of onset at unity bank pitch
+    rate: f32,
+    pan: f32,
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Self { rate: 1., pan: 0. }
+    }
+}
+
 struct Fade {
-    buffer: [i16; FADE_LEN + 1],
+    /// twice crossfade length, for material faded out at up to twice the
+    /// rate of what fades in
+    buffer: [i16; MAX_FADE_LEN * 2 + 1],
+    /// crossfade length in frames
+    len: usize,
     state: FadeState,
+    meta: Meta,
+    /// frame of buffer read, apart from grain index as read at own rate
+    index: f32,
 }
 
 impl Fade {
     fn new() -> Self {
         Self {
-            buffer: [0; FADE_LEN + 1],
+            buffer: [0; MAX_FADE_LEN * 2 + 1],
+            len: MAX_FADE_LEN,
             state: FadeState::None,
+            meta: Meta::default(),
+            index: 0.,
+        }
+    }
+
+    /// frame at index, held at either end of buffer
+    fn read(&self) -> f32 {
+        interpolate(&self.buffer[..=self.len * 2], self.index)
+    }
+}
+
+/// frame at fractional `index` of `buffer`, clamped within it
+fn interpolate(buffer: &[i16], index: f32) -> f32 {
+    let index = index.clamp(0., (buffer.len() - 2) as f32);
+    let word_a = buffer[index as usize] as f32 * (1. - index.fract());
+    let word_b = buffer[index as usize + 1] as f32 * index.fract();
+    (word_a + word_b) / i16::MAX as f32
+}
+
+/// frame at fractional `index` of `buffer` by 4-point hermite, two frames of
+/// `buffer` past where index may reach; frame before its first held as it
+fn hermite(buffer: &[i16], index: f32) -> f32 {
+    let index = index.clamp(0., (buffer.len() - 3) as f32);
+    let i = index as usize;
+    let t = index.fract();
+    let x = |i: usize| buffer[i] as f32 / i16::MAX as f32;
+    let (xm1, x0, x1, x2) = (x(i.saturating_sub(1)), x(i), x(i + 1), x(i + 2));
+    let c1 = 0.5 * (x1 - xm1);
+    let c2 = xm1 - 2.5 * x0 + 2. * x1 - 0.5 * x2;
+    let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+    ((c3 * t + c2) * t + c1) * t + x0
+}
+
+/// quarter sine over a crossfade `len` frames long, for equal power; read
+/// back from `len` to fade out
+fn window(len: usize) -> [f32; MAX_FADE_LEN + 1] {
+    core::array::from_fn(|i| {
+        f32::sin(core::f32::consts::FRAC_PI_2 * (i as f32 / len as f32).min(1.))
+    })
+}
+
+/// read speed ramped down to a stop and back up, sagging pitch as tape would
+#[derive(Copy, Clone)]
+struct Tape {
+    /// speed and gain multiplier in 0..=1
+    level: f32,
+    /// per-frame change of level, negative stopping
+    step: f32,
+}
+
+impl Tape {
+    /// level of next frame
+    fn next(&mut self) -> f32 {
+        self.level = (self.level + self.step).clamp(0., 1.);
+        self.level
+    }
+}
+
+/// parameter ramped by one-pole toward where last set, so stepwise knob
+/// rides don't click
+#[derive(Copy, Clone)]
+struct Smooth {
+    value: f32,
+    target: f32,
+}
+
+impl Smooth {
+    fn new(value: f32) -> Self {
+        Self {
+            value,
+            target: value,
+        }
+    }
+
+    /// value `frames` on at per-frame `coeff`
+    fn skip(&mut self, coeff: f32, frames: i32) -> f32 {
+        self.value = self.target + (self.value - self.target) * coeff.powi(frames);
+        self.value
+    }
+}
+
+/// bank gain, width and pitch as smoothed; copied into each read of a block
+/// so that every voice ramps alike
+#[derive(Copy, Clone)]
+struct Dezip {
+    gain: Smooth,
+    width: Smooth,
+    pitch: Smooth,
+    /// per-frame one-pole coefficient, stepwise at 0
+    coeff: f32,
+}
+
+impl Dezip {
+    /// gain, width and pitch of next frame
+    fn next(&mut self) -> (f32, f32, f32) {
+        (
+            self.gain.skip(self.coeff, 1),
+            self.width.skip(self.coeff, 1),
+            self.pitch.skip(self.coeff, 1),
+        )
+    }
+
+    fn skip(&mut self, frames: usize) {
+        for smooth in [&mut self.gain, &mut self.width, &mut self.pitch] {
+            smooth.skip(self.coeff, frames as i32);
+        }
+    }
+}
+
+/// playhead held in place, scrubbed back and forth over a window about it
+#[derive(Copy, Clone)]
+struct Freeze {
+    /// grain index window is centered on
+    center: f32,
+    /// frames from center scrubbed to
+    offset: f32,
+    /// frames from center at which scrub turns
+    edge: f32,
+    forward: bool,
+}
+
+impl Freeze {
+    /// frame at offset of grain `buffer`, its frame for interpolation
+    /// included, scrubbed `speed` onward over window `size` frames wide,
+    /// `jitter` of which randomizes where scrub turns
+    fn read(
+        &mut self,
+        buffer: &[i16],
+        speed: f32,
+        size: f32,
+        jitter: f32,
+        rand: &mut impl Rand,
+    ) -> f32 {
+        let len = (buffer.len() - 1) as f32;
+        let half = (size / 2.).min(len / 2.);
+        let center = self.center.clamp(half, len - 1. - half);
+        let index = (center + self.offset).clamp(0., len - 1.);
+        let word_a = buffer[index as usize] as f32 * (1. - index.fract());
+        let word_b = buffer[index as usize + 1] as f32 * index.fract();
+        if self.forward {
+            self.offset += speed;
+        } else {
+            self.offset -= speed;
+        }
+        if (self.forward && self.offset >= self.edge)
+            || (!self.forward && self.offset <= -self.edge)
+        {
+            self.forward = !self.forward;
+            let chance = rand.next_u32() as f32 / u32::MAX as f32;
+            self.edge = half * (1. - jitter.clamp(0., 1.) * chance);
         }
+        (word_a + word_b) / i16::MAX as f32
     }
 }
 
 pub(crate) struct GrainReader {
-    buffer: [i16; GRAIN_LEN + 1], // +1 frame for interpolation
-    window: [f32; FADE_LEN + 1], // for crossfade
+    buffer: [i16; MAX_GRAIN_LEN + 3], // +3 frames for interpolation
+    window: [f32; MAX_FADE_LEN + 1], // for crossfade
+    /// grain length in frames
+    len: usize,
     tail: Fade,
     head: Fade,
     index: f32,
+    /// of the onset grain was read from
+    meta: Meta,
+    interpolation: Interpolation,
+    cache: Prefetch,
+    tape: Tape,
+    /// held playhead, if frozen
+    freeze: Option<Freeze>,
+    /// freeze window width in frames and jitter in 0..=1
+    scrub: (f32, f32),
+    /// turns of freeze scrub
+    rand: Wyrand,
 }
 
+#[maybe_async::maybe_async]
 impl GrainReader {
     fn new() -> Self {
-        let window = core::array::from_fn(|i| {
-            0.5 - 0.5 * f32::cos(core::f32::consts::PI * i as f32 / FADE_LEN as f32)
-        });
         Self {
-            buffer: [0; GRAIN_LEN + 1],
-            window,
+            buffer: [0; MAX_GRAIN_LEN + 3],
+            window: window(MAX_FADE_LEN),
+            len: MAX_GRAIN_LEN,
             tail: Fade::new(),
             head: Fade::new(),
             index: 0.,
+            meta: Meta::default(),
+            interpolation: Interpolation::Linear,
+            cache: Prefetch::new(DEFAULT_PREFETCH),
+            tape: Tape {
+                level: 1.,
+                step: 0.,
+            },
+            freeze: None,
+            scrub: (0., 0.),
+            rand: Wyrand::seed(0xf2ee),
+        }
+    }
+
+    /// hold playhead where it reads, scrubbing window `size` frames wide with
+    /// `jitter` in 0..=1; none lets it run on
+    fn freeze(&mut self, scrub: Option<(f32, f32)>) {
+        match scrub {
+            Some((size, jitter)) => {
+                self.scrub = (size.clamp(1., self.len as f32 - 1.), jitter);
+                self.freeze.get_or_insert(Freeze {
+                    center: self.index,
+                    offset: 0.,
+                    edge: self.scrub.0 / 2.,
+                    forward: true,
+                });
+            }
+            None => self.freeze = None,
+        }
+    }
+
+    /// read grains `len` frames long from next refill, crossfading over a
+    /// like share of them
+    fn assign_len(&mut self, len: usize) {
+        let len = len.clamp(MIN_GRAIN_LEN, MAX_GRAIN_LEN);
+        if len == self.len {
+            return;
+        }
+        let fade_len = len * MAX_FADE_LEN / MAX_GRAIN_LEN;
+        self.len = len;
+        self.window = window(fade_len);
+        for fade in [&mut self.tail, &mut self.head] {
+            fade.len = fade_len;
+            fade.state = FadeState::None;
         }
+        // past grain end refills at once
+        self.index = self.index.min(len as f32);
     }
 
-    pub fn fade<F: FileHandler>(
+    pub async fn fade<F: Fs>(
         &mut self,
         wav: Option<&mut active::Wav<F>>,
         fs: &mut F,
     ) -> Result<(), F::Error> {
         if let Some(wav) = wav {
-            Self::fade_inner(
-                &mut self.tail,
-                &mut self.head,
-                wav,
-                fs,
-            )?;
+            Self::fade_inner(&mut self.tail, &mut self.head, &mut self.cache, wav, fs).await?;
         } else {
             // FIXME: this
             self.buffer.fill(0);
@@ -98,253 +351,630 @@ impl GrainReader {
         Ok(())
     }
 
-    fn fade_inner<F: FileHandler>(
+    async fn fade_inner<F: Fs>(
         tail: &mut Fade,
         head: &mut Fade,
+        cache: &mut Prefetch,
         wav: &mut active::Wav<F>,
         fs: &mut F,
     ) -> Result<(), F::Error> {
-        let edge_pos = wav.pos(fs)?;
-        if tail.state == FadeState::None {
-            tail.state = FadeState::Primed;
-            let bytes = bytemuck::cast_slice_mut(&mut tail.buffer);
-            wav.read(bytes, fs)?;
-        }
-        wav.seek(edge_pos as i64 - FADE_LEN as i64 * 2, fs)?;
-        if head.state == FadeState::None {
-            head.state = FadeState::Primed;
-            let bytes = bytemuck::cast_slice_mut(&mut head.buffer);
-            wav.read(bytes, fs)?;
-        }
-        wav.seek(edge_pos as i64, fs)?; // this is probably redundant
-        Ok(())
+        Self::prime(tail, false, cache, wav, fs).await?;
+        Self::prime(head, true, cache, wav, fs).await
     }
 
-    /// looping read with crossfade at eof
-    fn fill<F: FileHandler>(
-        &mut self,
+    /// read afresh from onset at next read, back from it if `reverse`, with no
+    /// crossfade
+    fn restart(&mut self, reverse: bool) {
+        self.index = if reverse { -1. } else { self.len as f32 };
+        self.tail.state = FadeState::None;
+        self.head.state = FadeState::None;
+    }
+
+    /// read on from where `other` reads, as it would have, less any
+    /// crossfade underway
+    fn resume(&mut self, other: &Self) {
+        self.buffer = other.buffer;
+        self.window = other.window;
+        self.len = other.len;
+        self.index = other.index;
+        self.meta = other.meta;
+        for fade in [&mut self.tail, &mut self.head] {
+            fade.len = other.tail.len;
+            fade.state = FadeState::None;
+        }
+    }
+
+    /// take over onset `other` reads from its next grain on, leaving `other`
+    /// to fade whatever it reads next in from silence
+    fn follow(&mut self, other: &mut Self) {
+        self.buffer.fill(0);
+        self.index = other.index;
+        self.tail.state = FadeState::None;
+        self.head.state = FadeState::None;
+        for fade in [&mut other.tail, &mut other.head] {
+            if fade.state != FadeState::Fading {
+                fade.buffer.fill(0);
+                fade.state = FadeState::Primed;
+            }
+        }
+    }
+
+    /// unless already fading, buffer what would have read next from `wav` in
+    /// direction of play, to crossfade out of
+    async fn prime<F: Fs>(
+        fade: &mut Fade,
+        reverse: bool,
+        cache: &mut Prefetch,
         wav: &mut active::Wav<F>,
         fs: &mut F,
     ) -> Result<(), F::Error> {
-        let mut slice = bytemuck::cast_slice_mut(&mut self.buffer[..]);
+        if fade.state != FadeState::None {
+            return Ok(());
+        }
+        fade.state = FadeState::Primed;
+        let edge_pos = wav.pos;
+        if reverse {
+            wav.seek(edge_pos as i64 - fade.len as i64 * 4);
+        }
+        wav.read(
+            bytemuck::cast_slice_mut(&mut fade.buffer[..=fade.len * 2]),
+            cache,
+            fs,
+        )
+        .await?;
+        wav.seek(edge_pos as i64);
+        Ok(())
+    }
+
+    /// ahead of refill, wrap to far edge of loop `span` in direction of play
+    /// if next grain would start outside it, crossfading
+    async fn wrap_loop<F: Fs>(
+        fade: &mut Fade,
+        reverse: bool,
+        span: Option<Span>,
+        cache: &mut Prefetch,
+        onset: &mut active::Onset<F>,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        let wav = &mut onset.wav;
+        let (Some((start, len)), Some(steps)) = (span, wav.steps) else {
+            return Ok(());
+        };
+        // all in bytes; next grain starts at pos forward, ends there reversed
+        let pos = wav.pos as i64;
+        let len = (len * wav.pcm_len as f32 / steps as f32) as i64 & !1;
+        // reversed loops span (start, end] so as to read back from end
+        let offset = (pos - start - reverse as i64 * 2).rem_euclid(wav.pcm_len as i64);
+        if offset >= len {
+            Self::prime(fade, reverse, cache, wav, fs).await?;
+            // always loop over len/loop_div steps **after** onset
+            wav.seek(if reverse { start + len } else { start });
+        }
+        Ok(())
+    }
+
+    /// looping read with crossfade at eof
+    async fn fill<F: Fs>(&mut self, wav: &mut active::Wav<F>, fs: &mut F) -> Result<(), F::Error> {
+        let mut slice = bytemuck::cast_slice_mut(&mut self.buffer[..=self.len + 2]);
         while !slice.is_empty() {
-            let len = slice.len().min((wav.pcm_len - wav.pos(fs)?) as usize);
-            let n = fs.read(&mut wav.file, &mut slice[..len])?;
+            let n = self.cache.read(wav, slice, fs).await?;
             if n == 0 {
                 // rewind to start/end with crossfade
-                Self::fade_inner(
-                    &mut self.tail,
-                    &mut self.head,
-                    wav,
-                    fs,
-                )?;
-                wav.seek(0, fs)?;
+                Self::fade_inner(&mut self.tail, &mut self.head, &mut self.cache, wav, fs).await?;
+                wav.seek(0);
             }
             slice = &mut slice[n..];
         }
         Ok(())
     }
 
-    fn sample(&mut self, index: usize) -> f32 {
+    /// crossfade `sample` read at grain index with any fade underway, and
+    /// pan of the mix; pan glides between onsets over the crossfade so one
+    /// filter serves both
+    fn crossfade(&mut self, sample: f32) -> (f32, f32) {
+        let glide = |from: &Meta, to: &Meta, t: f32| from.pan + (to.pan - from.pan) * t;
         if self.tail.state == FadeState::Fading {
-            if index < FADE_LEN {
-                return self.buffer[index] as f32 / i16::MAX as f32 * self.window[index]
-                    + self.tail.buffer[index] as f32 / i16::MAX as f32 * (1. - self.window[index]);
+            let len = self.tail.len;
+            if (self.index as usize) < len {
+                let (fade_in, fade_out) = self.weights(self.index, len);
+                let t = self.index / len as f32;
+                return (
+                    sample * fade_in + self.tail.read() * fade_out,
+                    glide(&self.tail.meta, &self.meta, t),
+                );
             }
             self.tail.state = FadeState::None;
         }
         if self.head.state == FadeState::Fading {
-            if index >= GRAIN_LEN - FADE_LEN {
-                let transposed = index + FADE_LEN - GRAIN_LEN;
-                return self.buffer[index] as f32 / i16::MAX as f32 * (1. - self.window[transposed])
-                    + self.head.buffer[transposed] as f32 / i16::MAX as f32 * (self.window[transposed]);
+            let len = self.head.len;
+            if self.index as usize >= self.len - len {
+                let transposed = self.index - (self.len - len) as f32;
+                let (fade_out, fade_in) = self.weights(transposed, len);
+                let t = 1. - transposed / len as f32;
+                return (
+                    sample * fade_in + self.head.read() * fade_out,
+                    glide(&self.head.meta, &self.meta, t),
+                );
             }
             self.head.state = FadeState::None;
         }
-        self.buffer[index] as f32 / i16::MAX as f32
+        (sample, self.meta.pan)
+    }
+
+    /// window at `pos` frames into a crossfade `len` long, and its
+    /// complement
+    fn weights(&self, pos: f32, len: usize) -> (f32, f32) {
+        let at = |pos: f32| {
+            let pos = pos.clamp(0., len as f32);
+            let index = (pos as usize).min(len - 1);
+            let fract = pos - index as f32;
+            self.window[index] * (1. - fract) + self.window[index + 1] * fract
+        };
+        (at(pos), at(len as f32 - pos))
     }
 
-    fn read_interpolated<F: FileHandler>(
+    /// next frame of `onset` read at bank `pitch`, and its pan; grain read so
+    /// far plays out as read, at rate and pan of `meta` from next refill
+    #[allow(clippy::too_many_arguments)]
+    async fn read_interpolated<F: Fs>(
         &mut self,
-        speed: f32,
+        pitch: f32,
+        meta: Meta,
         reverse: bool,
-        len: Option<f32>,
+        span: Option<Span>,
         onset: &mut active::Onset<F>,
         fs: &mut F,
-    ) -> Result<f32, F::Error> {
-        let wav = &mut onset.wav;
-        // handle loop
-        if let (Some(len), Some(steps)) = (len, wav.steps) {
-            // all in bytes
-            let pos = wav.pos(fs)?;
-            let start = onset.start * 2;
-            let len = (len * wav.pcm_len as f32 / steps as f32) as u64 & !1;
-            let end = start + len;
-            if pos > end || pos < start && pos + wav.pcm_len > end {
-                Self::fade_inner(
-                    &mut self.tail,
-                    &mut self.head,
-                    wav,
-                    fs,
-                )?;
-                // always loop over len/loop_div steps **after** onset
-                if reverse {
-                    wav.seek(end as i64, fs)?;
-                } else {
-                    wav.seek(start as i64, fs)?;
-                }
-            }
+    ) -> Result<(f32, f32), F::Error> {
+        if let Some(freeze) = self.freeze.as_mut() {
+            let (size, jitter) = self.scrub;
+            let tape = self.tape.next();
+            let speed = pitch * self.meta.rate * tape;
+            let buffer = &self.buffer[..=self.len];
+            let sample = freeze.read(buffer, speed, size, jitter, &mut self.rand);
+            return Ok((sample * tape, self.meta.pan));
         }
         // handle grain refill
-        if self.index as i64 >= GRAIN_LEN as i64 {
-            let seek_to = wav.pos(fs)? as i64 + GRAIN_LEN as i64 * 2;
-            self.fill(wav, fs)?;
-            wav.seek(seek_to, fs)?;
+        if self.index as i64 >= self.len as i64 {
+            Self::wrap_loop(&mut self.tail, false, span, &mut self.cache, onset, fs).await?;
+            let wav = &mut onset.wav;
+            let seek_to = wav.pos as i64 + self.len as i64 * 2;
+            self.fill(wav, fs).await?;
+            wav.seek(seek_to);
+            // wrap to [0, len)
+            self.index %= self.len as f32;
             if self.tail.state == FadeState::Primed {
                 self.tail.state = FadeState::Fading;
+                self.tail.meta = self.meta;
+                self.tail.index = self.index;
                 self.head.state = FadeState::None;
             }
-            // wrap to [0, GRAIN_LEN)
-            self.index %= GRAIN_LEN as f32;
+            self.meta = meta;
         } else if (self.index as i64) < 0 {
-            let seek_to = wav.pos(fs)? as i64 - GRAIN_LEN as i64 * 2;
-            wav.seek(seek_to, fs)?; // seek here so start of an onset is sought back from
-            self.fill(wav, fs)?;
-            wav.seek(seek_to, fs)?;
+            Self::wrap_loop(&mut self.head, true, span, &mut self.cache, onset, fs).await?;
+            let wav = &mut onset.wav;
+            let seek_to = wav.pos as i64 - self.len as i64 * 2;
+            wav.seek(seek_to); // seek here so start of an onset is sought back from
+            self.fill(wav, fs).await?;
+            wav.seek(seek_to);
+            // wrap to [0, len)
+            self.index = self.index.rem_euclid(self.len as f32);
             if self.head.state == FadeState::Primed {
                 self.head.state = FadeState::Fading;
+                self.head.meta = self.meta;
+                // edge of play at end of buffer
+                self.head.index = self.index + (self.head.len * 2) as f32 - self.len as f32;
                 self.tail.state = FadeState::None;
             }
-            // wrap to [0, GRAIN_LEN)
-            self.index = self.index.rem_euclid(GRAIN_LEN as f32);
+            self.meta = meta;
         }
-        // linear interpolation
-        let word_a = self.sample(self.index as usize) * (1. - self.index.fract());
-        let word_b = self.sample(self.index as usize + 1) * self.index.fract();
-        if reverse {
-            self.index -= speed;
-        } else {
-            self.index += speed;
+        let sample = match self.interpolation {
+            Interpolation::Linear => interpolate(&self.buffer[..=self.len], self.index),
+            Interpolation::Hermite => hermite(&self.buffer[..=self.len + 2], self.index),
+        };
+        let (sample, pan) = self.crossfade(sample);
+        // faded with speed, so no offset holds once stopped
+        let tape = self.tape.next();
+        let sign = if reverse { -1. } else { 1. };
+        self.index += sign * pitch * self.meta.rate * tape;
+        for fade in [&mut self.tail, &mut self.head] {
+            if fade.state == FadeState::Fading {
+                fade.index += sign * pitch * fade.meta.rate * tape;
+            }
+        }
+        Ok((sample * tape, pan))
+    }
+}
+
+/// onset displaced by a fresh hit, ringing out under its own fade
+struct Voice<F: Fs> {
+    onset: Option<active::Onset<F>>,
+    /// ring out fade level
+    level: Option<f32>,
+    /// gain multiplier of onset
+    velocity: f32,
+    svf: Svf,
+    grain: GrainReader,
+}
+
+#[maybe_async::maybe_async]
+impl<F: Fs> Voice<F> {
+    fn new() -> Self {
+        Self {
+            onset: None,
+            level: None,
+            velocity: 1.,
+            svf: Svf::new(),
+            grain: GrainReader::new(),
+        }
+    }
+
+    /// allocate voice in `slot` if `enabled`, else free it, closing its onset
+    /// if any; allocates, so call outside audio callback
+    async fn assign(
+        slot: &mut Option<alloc::boxed::Box<Self>>,
+        enabled: bool,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        match (enabled, slot.take()) {
+            (true, voice) => {
+                *slot = Some(voice.unwrap_or_else(|| alloc::boxed::Box::new(Self::new())))
+            }
+            (false, Some(voice)) => {
+                if let Some(onset) = voice.onset {
+                    fs.close(&onset.wav.file).await?;
+                }
+            }
+            (false, None) => (),
+        }
+        Ok(())
+    }
+}
+
+/// pool of voices a bank rings displaced onsets out in, so overlapping hits
+/// layer; quietest voice stolen once exhausted
+pub(crate) struct Voices<F: Fs> {
+    voices: alloc::boxed::Box<[Voice<F>]>,
+    /// onset of last event ended, ringing out its tail, if allocated
+    tail: Option<alloc::boxed::Box<Voice<F>>>,
+    /// whether ended events ring out in tail, else mute at once
+    tails: bool,
+}
+
+#[maybe_async::maybe_async]
+impl<F: Fs> Voices<F> {
+    /// allocates; `count` clamped to MAX_VOICES, monophonic at 0
+    fn new(count: usize) -> Self {
+        Self {
+            voices: (0..count.min(MAX_VOICES)).map(|_| Voice::new()).collect(),
+            tail: None,
+            tails: false,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.voices.is_empty()
+    }
+
+    pub fn tails(&self) -> bool {
+        self.tails && self.tail.is_some()
+    }
+
+    /// pool and tail alike
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut Voice<F>> {
+        self.voices.iter_mut().chain(self.tail.as_deref_mut())
+    }
+
+    /// ring `onset` of an ended event out at `velocity` as its tail, reading
+    /// on from where `grain` reads; any tail before closed
+    pub async fn tail(
+        &mut self,
+        onset: active::Onset<F>,
+        velocity: f32,
+        grain: &GrainReader,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        let Some(tail) = self.tail.as_deref_mut() else {
+            return fs.close(&onset.wav.file).await;
+        };
+        if let Some(old) = tail.onset.take() {
+            fs.close(&old.wav.file).await?;
+        }
+        tail.grain.resume(grain);
+        tail.onset = Some(onset);
+        tail.level = Some(1.);
+        tail.velocity = velocity;
+        Ok(())
+    }
+
+    /// ring `onset` out at `velocity` from the grain after that `grain` now
+    /// reads, in a free voice or the quietest one stolen
+    pub async fn ring(
+        &mut self,
+        onset: active::Onset<F>,
+        velocity: f32,
+        grain: &mut GrainReader,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        let level = |v: &Voice<F>| v.onset.as_ref().map(|_| v.level.unwrap_or(1.) * v.velocity);
+        let Some(voice) = self
+            .voices
+            .iter_mut()
+            .min_by(|a, b| level(a).unwrap_or(-1.).total_cmp(&level(b).unwrap_or(-1.)))
+        else {
+            return fs.close(&onset.wav.file).await;
+        };
+        if let Some(stolen) = voice.onset.take() {
+            fs.close(&stolen.wav.file).await?;
+        }
+        voice.grain.follow(grain);
+        voice.onset = Some(onset);
+        voice.level = Some(1.);
+        voice.velocity = velocity;
+        Ok(())
+    }
+
+    /// close onsets of voices faded out
+    async fn reap(&mut self, fs: &mut F) -> Result<(), F::Error> {
+        for voice in self.iter_mut() {
+            if voice.level.is_some_and(|v| v <= 0.) {
+                voice.level = None;
+                if let Some(onset) = voice.onset.take() {
+                    fs.close(&onset.wav.file).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// close onsets of every voice
+    async fn clear(&mut self, fs: &mut F) -> Result<(), F::Error> {
+        for voice in self.iter_mut() {
+            voice.level = None;
+            if let Some(onset) = voice.onset.take() {
+                fs.close(&onset.wav.file).await?;
+            }
         }
-        Ok(word_a + word_b)
+        Ok(())
+    }
+
+    fn open(&self) -> usize {
+        let voices = self.voices.iter().chain(self.tail.as_deref());
+        voices.filter(|v| v.onset.is_some()).count()
     }
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Kit<const PADS: usize> {
-    #[serde(with = "serde_arrays")]
+    #[serde(serialize_with = "crate::bd::seq", deserialize_with = "serde_arrays::deserialize")]
     pub onsets: [Option<passive::Onset>; PADS],
+    /// pads exempt from kit drift
+    #[serde(
+        serialize_with = "crate::bd::seq",
+        deserialize_with = "serde_arrays::deserialize",
+        default = "unlocked"
+    )]
+    pub locks: [bool; PADS],
+    /// further onsets of each pad, picked among with its first
+    #[serde(
+        serialize_with = "crate::bd::seq",
+        deserialize_with = "serde_arrays::deserialize",
+        default = "unlayered"
+    )]
+    pub layers: [Layers; PADS],
+}
+
+fn unlocked<const PADS: usize>() -> [bool; PADS] {
+    [false; PADS]
+}
+
+fn unlayered<const PADS: usize>() -> [Layers; PADS] {
+    core::array::from_fn(|_| Layers::default())
 }
 
 impl<const PADS: usize> Default for Kit<PADS> {
     fn default() -> Self {
         Self {
             onsets: core::array::from_fn(|_| None),
+            locks: [false; PADS],
+            layers: unlayered(),
         }
     }
 }
 
+/// how a pad with layers picks the onset of each hit
+#[derive(Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Select {
+    #[default]
+    RoundRobin,
+    Random,
+}
+
+/// onsets of a pad after its first
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Layers {
+    pub onsets: alloc::vec::Vec<passive::Onset>,
+    pub select: Select,
+}
+
+/// layer each pad of each kit picks next round-robin, counting pad's first
+/// onset as 0
+pub struct LayerCursor<const PADS: usize>([[usize; PADS]; PADS]);
+
+impl<const PADS: usize> Default for LayerCursor<PADS> {
+    fn default() -> Self {
+        Self([[0; PADS]; PADS])
+    }
+}
+
+impl<const PADS: usize> LayerCursor<PADS> {
+    /// cursor of pad `pad` in kit `kit`
+    pub(crate) fn get(&mut self, kit: u8, pad: u8) -> &mut usize {
+        &mut self.0[kit as usize][pad as usize]
+    }
+}
+
+#[maybe_async::maybe_async]
 impl<const PADS: usize> Kit<PADS> {
     pub(crate) fn generate_pan(index: impl Into<usize>) -> f32 {
         index.into() as f32 / PADS as f32 - 0.5
     }
 
-    pub(crate) fn onset_seek<F: FileHandler>(
-        &self,
-        to_close: Option<&F::File>,
-        index: u8,
-        pan: f32,
-        fs: &mut F,
-    ) -> Result<Option<active::Onset<F>>, Error<F::Error>> {
-        if let Some(source) = self.onsets[index as usize].as_ref() {
-            let mut onset = Self::onset_inner(source, to_close, index, pan, fs)?;
-            onset.wav.seek(source.start as i64 * 2, fs)?;
-            Ok(Some(onset))
-        } else {
-            Ok(None)
-        }
+    /// layer of pad `index` to sound next, 0 for its first onset; `next`
+    /// the pad's round-robin cursor
+    pub(crate) fn layer(&self, index: u8, next: &mut usize, rand: &mut impl Rand) -> usize {
+        let layers = &self.layers[index as usize];
+        let count = layers.onsets.len() + 1;
+        let layer = match layers.select {
+            Select::RoundRobin => *next % count,
+            Select::Random => rand.next_lim_usize(count),
+        };
+        *next = layer + 1;
+        layer
     }
 
-    fn onset_inner<F: FileHandler>(
-        source: &passive::Onset,
-        to_close: Option<&F::File>,
+    /// open onset at `layer` of pad `index`, if any, sought to its start;
+    /// closed again on failure
+    pub(crate) async fn onset_seek<F: Fs>(
+        &self,
         index: u8,
+        layer: usize,
         pan: f32,
         fs: &mut F,
-    ) -> Result<active::Onset<F>, Error<F::Error>> {
-        if let Some(file) = to_close {
-            fs.close(file)?;
-        }
-        let mut file = fs.open(&source.wav.path)?;
-        let re_err = |e| match e {
-            ReadExactError::UnexpectedEof => Error::DataNotFound,
-            ReadExactError::Other(e) => Error::Other(e),
+    ) -> Result<Option<active::Onset<F>>, Error<F::Error>> {
+        let first = self.onsets[index as usize].as_ref();
+        let source = match layer.checked_sub(1) {
+            Some(layer) => first.and(self.layers[index as usize].onsets.get(layer)),
+            None => first,
         };
-        let assert = |b: bool| if !b { Err(Error::BadFormat) } else { Ok(()) };
-        // parse wav looking for metadata and `data` subchunk
-        let mut pcm_start = 0;
-        let mut pcm_len = 0;
-        let mut sample_rate = 0;
-        let mut essential_chunks_parsed = 0;
-        while essential_chunks_parsed < 3 {
-            let mut id = [0u8; 4];
-            fs.read_exact(&mut file, &mut id).map_err(re_err)?;
-            if &id[..] == b"RIFF" {
-                fs.seek(&mut file, embedded_io::SeekFrom::Current(4))?;
-                let mut data = [0u8; 4];
-                fs.read_exact(&mut file, &mut data).map_err(re_err)?;
-                assert(&data[..] == b"WAVE")?;
-                essential_chunks_parsed += 1;
-            } else if &id[..] == b"fmt " {
-                let mut data32 = [0u8; 4];
-                let mut data16 = [0u8; 2];
-                fs.read_exact(&mut file, &mut data32).map_err(re_err)?;
-                assert(u32::from_le_bytes(data32) == 16)?; // `fmt ` chunk size
-                fs.read_exact(&mut file, &mut data16).map_err(re_err)?;
-                assert(u16::from_le_bytes(data16) == 1)?; // pcm integer format
-                fs.read_exact(&mut file, &mut data16).map_err(re_err)?;
-                assert(u16::from_le_bytes(data16) == 1)?; // 1 channel
-                fs.read_exact(&mut file, &mut data32).map_err(re_err)?;
-                sample_rate = u32::from_le_bytes(data32);
-                fs.seek(&mut file, embedded_io::SeekFrom::Current(6))?;
-                fs.read_exact(&mut file, &mut data16).map_err(re_err)?;
-                assert(u16::from_le_bytes(data16) == 16)?; // 16 bits/sample
-                essential_chunks_parsed += 1;
-            } else if &id[..] == b"data" {
-                let mut size = [0u8; 4];
-                fs.read_exact(&mut file, &mut size).map_err(re_err)?;
-                pcm_start = fs.stream_position(&mut file)?;
-                pcm_len = u32::from_le_bytes(size) as u64;
-                essential_chunks_parsed += 1;
-            } else {
-                let mut size = [0u8; 4];
-                fs.read_exact(&mut file, &mut size).map_err(re_err)?;
-                let chunk_len = u32::from_le_bytes(size) as i64;
-                fs.seek(&mut file, embedded_io::SeekFrom::Current(chunk_len))?;
+        let Some(source) = source else {
+            return Ok(None);
+        };
+        let mut file = fs.open(&source.wav.path).await?;
+        let (pcm_start, pcm_len, sample_rate) = match read_header(&mut file, fs).await {
+            Ok(header) => header,
+            Err(e) => {
+                let _ = fs.close(&file).await;
+                return Err(e);
             }
-        }
-        let wav = active::Wav {
+        };
+        let mut wav = active::Wav {
             steps: source.wav.steps,
             file,
             pcm_start,
             pcm_len,
             sample_rate,
+            id: prefetch::wav_id(&source.wav.path),
+            origin: source.start * 2,
+            pos: 0,
         };
-        Ok(active::Onset {
+        wav.seek(source.start as i64 * 2);
+        Ok(Some(active::Onset {
             index,
             pan,
             wav,
             start: source.start,
-        })
+            gain: source.gain,
+            pitch: source.pitch.map(|v| 2f32.powf(v / 12.)).unwrap_or(1.),
+            age: 0,
+        }))
+    }
+}
+
+/// parse wav header, returning pcm start and length in bytes and sample
+/// rate
+#[maybe_async::maybe_async]
+pub(crate) async fn read_header<F: Fs>(
+    file: &mut F::File,
+    fs: &mut F,
+) -> Result<(u64, u64, u32), Error<F::Error>> {
+    let re_err = |e| match e {
+        ReadExactError::UnexpectedEof => Error::DataNotFound,
+        ReadExactError::Other(e) => Error::Other(e),
+    };
+    let assert = |b: bool| if !b { Err(Error::BadFormat) } else { Ok(()) };
+    // parse wav looking for metadata and `data` subchunk
+    let mut pcm_start = 0;
+    let mut pcm_len = 0;
+    let mut sample_rate = 0;
+    let mut essential_chunks_parsed = 0;
+    while essential_chunks_parsed < 3 {
+        let mut id = [0u8; 4];
+        fs.read_exact(file, &mut id).await.map_err(re_err)?;
+        if &id[..] == b"RIFF" {
+            fs.seek(file, embedded_io::SeekFrom::Current(4)).await?;
+            let mut data = [0u8; 4];
+            fs.read_exact(file, &mut data).await.map_err(re_err)?;
+            assert(&data[..] == b"WAVE")?;
+            essential_chunks_parsed += 1;
+        } else if &id[..] == b"fmt " {
+            let mut data32 = [0u8; 4];
+            let mut data16 = [0u8; 2];
+            fs.read_exact(file, &mut data32).await.map_err(re_err)?;
+            assert(u32::from_le_bytes(data32) == 16)?; // `fmt ` chunk size
+            fs.read_exact(file, &mut data16).await.map_err(re_err)?;
+            assert(u16::from_le_bytes(data16) == 1)?; // pcm integer format
+            fs.read_exact(file, &mut data16).await.map_err(re_err)?;
+            assert(u16::from_le_bytes(data16) == 1)?; // 1 channel
+            fs.read_exact(file, &mut data32).await.map_err(re_err)?;
+            sample_rate = u32::from_le_bytes(data32);
+            fs.seek(file, embedded_io::SeekFrom::Current(6)).await?;
+            fs.read_exact(file, &mut data16).await.map_err(re_err)?;
+            assert(u16::from_le_bytes(data16) == 16)?; // 16 bits/sample
+            essential_chunks_parsed += 1;
+        } else if &id[..] == b"data" {
+            let mut size = [0u8; 4];
+            fs.read_exact(file, &mut size).await.map_err(re_err)?;
+            pcm_start = fs.stream_position(file).await?;
+            pcm_len = u32::from_le_bytes(size) as u64;
+            essential_chunks_parsed += 1;
+        } else {
+            let mut size = [0u8; 4];
+            fs.read_exact(file, &mut size).await.map_err(re_err)?;
+            let chunk_len = u32::from_le_bytes(size) as i64;
+            fs.seek(file, embedded_io::SeekFrom::Current(chunk_len))
+                .await?;
+        }
+    }
+    Ok((pcm_start, pcm_len, sample_rate))
+}
+
+/// runtime caps on phrase and sequence length, within compile-time capacity.
+/// soft caps: storage stays sized by `STEPS` and `PHRASES` whatever they are,
+/// so they bound how long phrases and sequences grow, not memory
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// longest phrase in steps
+    pub phrase_len: u16,
+    /// longest sequence in phrases
+    pub phrase_count: usize,
+}
+
+impl Limits {
+    /// clamped into `STEPS` steps and `PHRASES` phrases; never below one
+    pub fn within<const STEPS: usize, const PHRASES: usize>(self) -> Self {
+        Self {
+            phrase_len: (self.phrase_len as usize).clamp(1, STEPS) as u16,
+            phrase_count: self.phrase_count.clamp(1, PHRASES),
+        }
     }
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(from = "crate::compat::SavedBank")]
 pub struct Bank<const PADS: usize, const STEPS: usize> {
-    #[serde(with = "serde_arrays")]
+    #[serde(serialize_with = "crate::bd::seq")]
     pub kits: [Option<Kit<PADS>>; PADS],
-    #[serde(with = "serde_arrays")]
+    #[serde(serialize_with = "crate::bd::seq")]
     pub phrases: [Option<passive::Phrase<STEPS>>; PADS],
+    /// groove applied over phrase playback, if any
+    #[serde(default)]
+    pub groove: Option<passive::Groove>,
+    /// saved sequences
+    #[serde(default)]
+    pub pools: alloc::vec::Vec<passive::Pool>,
+    /// saved performance parameters
+    #[serde(default)]
+    pub scenes: alloc::vec::Vec<scene::Scene>,
 }
 
 impl<const PADS: usize, const STEPS: usize> Default for Bank<PADS, STEPS> {
@@ -352,30 +982,153 @@ impl<const PADS: usize, const STEPS: usize> Default for Bank<PADS, STEPS> {
         Self {
             kits: core::array::from_fn(|_| None),
             phrases: core::array::from_fn(|_| None),
+            groove: None,
+            pools: alloc::vec::Vec::new(),
+            scenes: alloc::vec::Vec::new(),
         }
     }
 }
 
+#[maybe_async::maybe_async]
 impl<const PADS: usize, const STEPS: usize> Bank<PADS, STEPS> {
-    /// find first non-None kit, if any, at `drift` indices from base `index`
+    /// copy kit `from` over kit `to`, emptying `to` if `from` is empty
+    pub fn copy_kit(&mut self, from: u8, to: u8) {
+        self.kits[to as usize] = self.kits[from as usize].clone();
+    }
+
+    /// swap onsets, layers and drift locks of pads `a` and `b` in kit
+    /// `index`, if any
+    pub fn swap_pads(&mut self, index: u8, a: u8, b: u8) {
+        if let Some(kit) = self.kits[index as usize].as_mut() {
+            kit.onsets.swap(a as usize, b as usize);
+            kit.locks.swap(a as usize, b as usize);
+            kit.layers.swap(a as usize, b as usize);
+        }
+    }
+
+    pub fn clear_kit(&mut self, index: u8) {
+        self.kits[index as usize] = None;
+    }
+
+    /// assign opening onsets of `rd` of `wav` over pads of kit `index` in
+    /// order, emptying pads past its last; count of pads assigned
+    pub fn spread(&mut self, index: u8, rd: &passive::Rd, wav: &passive::Wav) -> usize {
+        let kit = self.kits[index as usize].get_or_insert_default();
+        for (pad, (onset, layers)) in kit.onsets.iter_mut().zip(kit.layers.iter_mut()).enumerate() {
+            *onset = rd.onsets.get(pad).map(|&start| passive::Onset {
+                wav: wav.clone(),
+                start,
+                gain: 1.,
+                pitch: None,
+            });
+            layers.onsets.clear();
+        }
+        rd.onsets.len().min(PADS)
+    }
+
+    /// truncate phrases longer than `phrase_len` to their opening steps
+    pub fn truncate(&mut self, phrase_len: u16) {
+        for phrase in self.phrases.iter_mut().flatten() {
+            phrase.truncate(phrase_len);
+        }
+    }
+
+    /// peak magnitude of each onset from its start to end of wav, by kit and
+    /// pad
+    pub async fn peaks<F: Fs>(
+        &self,
+        fs: &mut F,
+    ) -> Result<[[Option<f32>; PADS]; PADS], Error<F::Error>> {
+        let mut peaks = [[None; PADS]; PADS];
+        for (kit, peaks) in self.kits.iter().zip(peaks.iter_mut()) {
+            let Some(kit) = kit else {
+                continue;
+            };
+            for (index, peak) in peaks.iter_mut().enumerate() {
+                if let Some(mut onset) = kit.onset_seek(index as u8, 0, 0., fs).await? {
+                    // close even if scan failed
+                    let max = Self::scan_peak(&mut onset.wav, fs).await;
+                    fs.close(&onset.wav.file).await?;
+                    *peak = Some(max? as f32 / i16::MAX as f32);
+                }
+            }
+        }
+        Ok(peaks)
+    }
+
+    /// largest absolute sample from position of `wav` to its end
+    async fn scan_peak<F: Fs>(
+        wav: &mut active::Wav<F>,
+        fs: &mut F,
+    ) -> Result<u16, Error<F::Error>> {
+        let re_err = |e| match e {
+            ReadExactError::UnexpectedEof => Error::DataNotFound,
+            ReadExactError::Other(e) => Error::Other(e),
+        };
+        let end = wav.pcm_start + wav.pcm_len;
+        let mut pos = wav.pcm_start + wav.pos;
+        fs.seek(&mut wav.file, embedded_io::SeekFrom::Start(pos))
+            .await?;
+        let mut buffer = [0u8; 512];
+        let mut max = 0u16;
+        while pos + 1 < end {
+            let len = ((end - pos) as usize).min(buffer.len()) & !1;
+            fs.read_exact(&mut wav.file, &mut buffer[..len])
+                .await
+                .map_err(re_err)?;
+            for bytes in buffer[..len].chunks_exact(2) {
+                let word = i16::from_le_bytes([bytes[0], bytes[1]]);
+                max = max.max(word.unsigned_abs());
+            }
+            pos += len as u64;
+        }
+        Ok(max)
+    }
+
+    /// trim each onset so its peak meets `target`
+    pub fn normalize(&mut self, peaks: &[[Option<f32>; PADS]; PADS], target: f32) {
+        for (kit, peaks) in self.kits.iter_mut().zip(peaks.iter()) {
+            let Some(kit) = kit else {
+                continue;
+            };
+            for (onset, peak) in kit.onsets.iter_mut().zip(peaks.iter()) {
+                if let (Some(onset), Some(peak)) = (onset, peak) {
+                    onset.gain = if *peak > 0. {
+                        (target / peak).min(MAX_TRIM)
+                    } else {
+                        1.
+                    };
+                }
+            }
+        }
+    }
+
+    /// find first non-None kit, if any, at `drift` indices from base `index`,
+    /// or at base if `pad` is locked there
     pub(crate) fn generate_kit(
         &self,
         mut index: u8,
-        drift: f32,
+        pad: u8,
+        drift: &mut Drift,
         rand: &mut impl Rand,
-    ) -> Option<&Kit<PADS>> {
+    ) -> Option<(u8, &Kit<PADS>)> {
         if self.kits.iter().all(|v| v.is_none()) {
             return None;
         }
-        let drift = drift * self.kits.len() as f32;
-        let mut drift = rand.next_lim_usize(drift as usize + 1)
-            + rand.next_bool(tinyrand::Probability::new(drift.fract() as f64)) as usize;
+        let locked = self.kits[index as usize]
+            .as_ref()
+            .is_some_and(|v| v.locks[pad as usize]);
+        let mut drift = if locked {
+            0
+        } else {
+            drift.generate(self.kits.len(), rand)
+        };
         loop {
             while self.kits[index as usize].is_none() {
                 index = (index + 1) % self.kits.len() as u8;
             }
             if drift == 0 {
-                return self.kits[index as usize].as_ref();
+                return self.kits[index as usize].as_ref().map(|v| (index, v));
             }
             drift -= 1;
             index += 1;
@@ -383,7 +1136,446 @@ impl<const PADS: usize, const STEPS: usize> Bank<PADS, STEPS> {
     }
 }
 
-pub struct Mod<T: Copy + core::ops::Mul> {
+/// distribution of drift offsets within the drift window
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum DriftMode {
+    /// jump anywhere within window
+    #[default]
+    Uniform,
+    /// step at most one index from last offset
+    Walk,
+    /// favor offsets near base index
+    Weighted,
+}
+
+#[derive(Default)]
+pub struct Drift {
+    /// window size as fraction of source length
+    pub amount: f32,
+    pub mode: DriftMode,
+    /// last offset, for random walk
+    walk: usize,
+}
+
+impl Drift {
+    /// generate offset in window of `amount * len` indices from base
+    pub(crate) fn generate(&mut self, len: usize, rand: &mut impl Rand) -> usize {
+        let drift = self.amount * len as f32;
+        match self.mode {
+            DriftMode::Uniform => {
+                rand.next_lim_usize(drift as usize + 1)
+                    + rand.next_bool(tinyrand::Probability::new(drift.fract() as f64)) as usize
+            }
+            DriftMode::Walk => {
+                self.walk = match rand.next_lim_usize(3) {
+                    0 => self.walk.saturating_sub(1),
+                    1 => self.walk,
+                    _ => self.walk + 1,
+                }
+                .min(drift.ceil() as usize);
+                self.walk
+            }
+            DriftMode::Weighted => {
+                // squared uniform skews toward zero
+                let r = rand.next_u32() as f32 / u32::MAX as f32;
+                (r * r * (drift + 1.)) as usize
+            }
+        }
+    }
+}
+
+/// max humanize delay in 1/256 steps
+const HUMANIZE_DELAY: f32 = 32.;
+/// max humanize gain deviation from unity
+const HUMANIZE_GAIN: f32 = 0.25;
+
+/// timing and level jitter over replayed phrase events
+#[derive(Default)]
+pub struct Humanize {
+    /// jitter depth in 0..=1
+    pub amount: f32,
+}
+
+impl Humanize {
+    /// generate late delay in 1/256 steps and velocity scale
+    pub(crate) fn generate(&self, rand: &mut impl Rand) -> (u16, f32) {
+        if self.amount <= 0. {
+            return (0, 1.);
+        }
+        let r = rand.next_u32() as f32 / u32::MAX as f32;
+        let delay = (r * self.amount * HUMANIZE_DELAY) as u16;
+        let r = rand.next_u32() as f32 / u32::MAX as f32;
+        (delay, 1. + (r * 2. - 1.) * self.amount * HUMANIZE_GAIN)
+    }
+}
+
+/// chance a generated trigger loops rather than holds
+const GENERATOR_LOOPS: f64 = 0.25;
+
+/// random hold and loop triggers on pads of the current kit, fired on the
+/// step grid independent of phrases
+#[derive(Copy, Clone, Default)]
+pub struct Generator {
+    /// chance per step of a trigger in 0..=1; off at 0
+    pub density: f32,
+}
+
+impl Generator {
+    /// roll for a trigger on a pad of `kit` holding an onset, if any
+    pub(crate) fn generate<const PADS: usize>(
+        &self,
+        kit: Option<&Kit<PADS>>,
+        rand: &mut impl Rand,
+    ) -> Option<passive::Event> {
+        if self.density <= 0. {
+            return None;
+        }
+        let kit = kit?;
+        if !rand.next_bool(Probability::new(self.density.min(1.) as f64)) {
+            return None;
+        }
+        let filled = kit.onsets.iter().filter(|v| v.is_some()).count();
+        if filled == 0 {
+            return None;
+        }
+        let nth = rand.next_lim_usize(filled);
+        let index = (0..PADS).filter(|v| kit.onsets[*v].is_some()).nth(nth)? as u8;
+        if rand.next_bool(Probability::new(GENERATOR_LOOPS)) {
+            let len = (1 << rand.next_lim_usize(4)) * passive::LOOP_STEP;
+            Some(passive::Event::Loop { index, len })
+        } else {
+            Some(passive::Event::Hold { index })
+        }
+    }
+}
+
+/// motion length when no phrase is running, in steps
+const MOTION_LEN: u16 = 16;
+/// steps per beat of launch quantization
+const BEAT_LEN: u16 = 4;
+/// steps per bar of launch quantization
+const BAR_LEN: u16 = 16;
+
+/// knob parameter a motion loop can drive
+#[derive(Copy, Clone, PartialEq)]
+pub enum MotionTarget {
+    Gain,
+    Width,
+    Pitch,
+    Roll,
+    KitDrift,
+    PhraseDrift,
+    Humanize,
+    Cutoff,
+    Resonance,
+}
+
+impl MotionTarget {
+    pub const COUNT: usize = 9;
+}
+
+/// per-step knob values replayed cyclically; NaN steps leave knob as is
+struct Motion<const STEPS: usize> {
+    values: [f32; STEPS],
+    len: u16,
+    /// steps written since recording began
+    recorded: u16,
+    /// live knob value while recording, if recording
+    live: Option<f32>,
+}
+
+impl<const STEPS: usize> Motion<STEPS> {
+    fn new(len: u16) -> Self {
+        Self {
+            values: [f32::NAN; STEPS],
+            len,
+            recorded: 0,
+            live: None,
+        }
+    }
+
+    /// value at `step`, recording live value over one loop length first
+    fn tick(&mut self, step: u16) -> Option<f32> {
+        let index = (step % self.len) as usize;
+        if let Some(live) = self.live {
+            if self.recorded < self.len {
+                self.values[index] = live;
+                self.recorded += 1;
+            } else {
+                self.live = None;
+            }
+        }
+        Some(self.values[index]).filter(|v| !v.is_nan())
+    }
+}
+
+/// accent rhythm over bank steps
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum AccentPattern {
+    #[default]
+    Off,
+    /// every nth step, from first
+    Every(u16),
+    /// steps set in `bits`, cycling over `len` steps
+    Mask { bits: u32, len: u8 },
+}
+
+impl AccentPattern {
+    fn accented(self, step: u16) -> bool {
+        match self {
+            AccentPattern::Off => false,
+            AccentPattern::Every(n) => n > 0 && step.is_multiple_of(n),
+            AccentPattern::Mask { bits, len } => {
+                len > 0 && bits & (1 << (step % len.min(32) as u16)) != 0
+            }
+        }
+    }
+}
+
+/// rhythmic gain and cutoff emphasis applied per step without editing phrases
+pub struct Accent {
+    pub pattern: AccentPattern,
+    /// gain multiplier on accented steps
+    pub gain: f32,
+    /// cutoff multiplier on accented steps
+    pub cutoff: f32,
+}
+
+impl Default for Accent {
+    fn default() -> Self {
+        Self {
+            pattern: AccentPattern::Off,
+            gain: 1.5,
+            cutoff: 1.,
+        }
+    }
+}
+
+/// musical loop divisions, including dotted and triplet ratios
+const DIVISIONS: [f32; 8] = [1., 4. / 3., 1.5, 2., 3., 4., 6., 8.];
+
+/// response of loop division to normalized control
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Curve {
+    /// 0 to 8 proportionally
+    #[default]
+    Linear,
+    /// 1 to 8, doubling per third of range
+    Exponential,
+    /// stepped through musical divisions
+    Musical,
+}
+
+impl Curve {
+    /// map control `abs` in 0..=1 to loop division
+    pub fn loop_div(self, abs: f32) -> f32 {
+        let abs = abs.clamp(0., 1.);
+        match self {
+            Curve::Linear => abs * 8.,
+            Curve::Exponential => 2f32.powf(abs * 3.),
+            Curve::Musical => {
+                DIVISIONS[((abs * DIVISIONS.len() as f32) as usize).min(DIVISIONS.len() - 1)]
+            }
+        }
+    }
+}
+
+/// behavior of input on release of last pad
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Release {
+    /// sync to clock at once
+    #[default]
+    Sync,
+    /// keep rolling until next event
+    Sustain,
+    /// fade out over release length, then sync
+    Decay,
+}
+
+/// grid live input launches on while the clock runs
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Launch {
+    /// at once, unquantized
+    Off,
+    /// on next step
+    #[default]
+    Step,
+    /// on next beat
+    Beat,
+    /// on next bar
+    Bar,
+}
+
+impl Launch {
+    /// whether the tick landing on `step` from song start launches input
+    fn due(self, step: u16) -> bool {
+        match self {
+            Launch::Off | Launch::Step => true,
+            Launch::Beat => step.is_multiple_of(BEAT_LEN),
+            Launch::Bar => step.is_multiple_of(BAR_LEN),
+        }
+    }
+}
+
+/// playback of onsets recorded at a rate other than output
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Resample {
+    /// convert rate by interpolation, keeping recorded pitch
+    #[default]
+    PreservePitch,
+    /// read frames one to one, shifting pitch by rate ratio like tape
+    Repitch,
+}
+
+/// how grains are read between frames at speeds other than unity
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Interpolation {
+    /// cheapest, if dull and aliasing at high speeds
+    #[default]
+    Linear,
+    /// 4-point hermite, cleaner at a few more multiplies a frame
+    Hermite,
+}
+
+/// how width spreads a bank across the stereo field
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum Stereo {
+    /// attenuate each channel by onset pan
+    #[default]
+    Pan,
+    /// pan onsets fully, then scale side against mid; 0.5 width is unity
+    MidSide,
+}
+
+/// scale side of stereo frame against mid; 0.5 `width` is unity, 0 mono
+fn mid_side(l: f32, r: f32, width: f32) -> (f32, f32) {
+    let mid = (l + r) * 0.5;
+    let side = (l - r) * width;
+    (mid + side, mid - side)
+}
+
+/// add stereo `l` and `r` to interleaved `frame` of any width; downmixed if
+/// mono, else repeated across channel pairs
+pub fn add_stereo<T: core::ops::AddAssign + From<f32>>(frame: &mut [T], l: f32, r: f32) {
+    if let [mono] = frame {
+        *mono += T::from((l + r) * 0.5);
+    } else {
+        for (channel, sample) in frame.iter_mut().enumerate() {
+            *sample += T::from(if channel % 2 == 0 { l } else { r });
+        }
+    }
+}
+
+/// lowest cutoff in hz, at normalized cutoff 0
+const CUTOFF_MIN: f32 = 20.;
+/// ratio of highest to lowest cutoff
+const CUTOFF_RANGE: f32 = 1000.;
+
+/// response of bank filter
+#[derive(Copy, Clone, Default, PartialEq)]
+pub enum FilterMode {
+    #[default]
+    Off,
+    LowPass,
+    HighPass,
+}
+
+/// state-variable filter over mono onset output, trapezoidal integrated
+struct Svf {
+    mode: FilterMode,
+    /// damping, from resonance
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+    /// integrator states
+    ic1: f32,
+    ic2: f32,
+}
+
+impl Svf {
+    fn new() -> Self {
+        Self {
+            mode: FilterMode::Off,
+            k: 2.,
+            a1: 0.,
+            a2: 0.,
+            a3: 0.,
+            ic1: 0.,
+            ic2: 0.,
+        }
+    }
+
+    /// set coefficients from normalized `cutoff` and `resonance` in 0..=1
+    fn tune(&mut self, mode: FilterMode, cutoff: f32, resonance: f32, sample_rate: u32) {
+        if mode != self.mode {
+            self.ic1 = 0.;
+            self.ic2 = 0.;
+        }
+        self.mode = mode;
+        let hz = CUTOFF_MIN * CUTOFF_RANGE.powf(cutoff.clamp(0., 1.));
+        let hz = hz.min(sample_rate as f32 * 0.45);
+        let g = (core::f32::consts::PI * hz / sample_rate as f32).tan();
+        self.k = 2. - 1.95 * resonance.clamp(0., 1.);
+        self.a1 = 1. / (1. + g * (g + self.k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+
+    fn process(&mut self, v0: f32) -> f32 {
+        if self.mode == FilterMode::Off {
+            return v0;
+        }
+        let v3 = v0 - self.ic2;
+        let v1 = self.a1 * self.ic1 + self.a2 * v3;
+        let v2 = self.ic2 + self.a2 * self.ic1 + self.a3 * v3;
+        self.ic1 = 2. * v1 - self.ic1;
+        self.ic2 = 2. * v2 - self.ic2;
+        match self.mode {
+            FilterMode::Off => v0,
+            FilterMode::LowPass => v2,
+            FilterMode::HighPass => v0 - self.k * v1 - v2,
+        }
+    }
+}
+
+/// level over time of each triggered onset
+#[derive(Copy, Clone, Default)]
+pub struct Envelope {
+    /// fade in length in seconds
+    pub attack: f32,
+    /// fade out length after attack in seconds; sustain if zero
+    pub release: f32,
+}
+
+impl Envelope {
+    /// level `age` frames after trigger
+    fn level(&self, age: u32, sample_rate: u32) -> f32 {
+        let attack = self.attack * sample_rate as f32;
+        let age = age as f32;
+        if age < attack {
+            age / attack
+        } else if self.release > 0. {
+            (1. - (age - attack) / (self.release * sample_rate as f32)).max(0.)
+        } else {
+            1.
+        }
+    }
+}
+
+/// last step of audible phrase
+#[derive(Copy, Clone)]
+pub struct Activity {
+    /// step index sans drift
+    pub step_index: u16,
+    pub len: u16,
+    /// whether step held an event
+    pub fired: bool,
+    /// whether step was substituted by drift
+    pub drifted: bool,
+}
+
+pub struct Mod<T: Copy + core::ops::Mul> {
     pub base: T,
     pub offset: T,
 }
@@ -398,130 +1590,844 @@ impl<T: Copy + core::ops::Mul> Mod<T> {
     }
 }
 
-pub struct BankHandler<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: FileHandler>
-{
+/// momentary micro-loop, read within while the event beneath it ticks on
+#[derive(Copy, Clone)]
+struct Stutter {
+    /// byte offset looped from, once caught at a step
+    start: Option<i64>,
+}
+
+pub struct BankHandler<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: Fs> {
     quant: bool,
     tempo: f32,
     ticks_per_step: u16,
+    /// output sample rate as of last read
+    sample_rate: u32,
+    /// frames read since last tick, for micro-timing
+    frames_since_tick: u32,
     pub loop_div: Mod<f32>,
+    /// control response of loop division base
+    roll_curve: Curve,
+    /// last loop division control in 0..=1
+    roll: f32,
+    /// fine multiplier of loop division, an octave either way
+    loop_fine: f32,
+    /// micro-loop held, if any
+    stutter: Option<Stutter>,
+    /// micro-loop length in steps
+    stutter_len: f32,
+    /// whether tape stopped, else spun back up to speed
+    tape_stop: bool,
+    /// tape stop and spin-up length in seconds
+    pub tape_len: f32,
+    /// whether playhead held, scrubbing a window about it
+    pub freeze: bool,
+    /// freeze window width in 0..=1 of a grain
+    pub freeze_size: f32,
+    /// randomness of freeze window edges in 0..=1
+    pub freeze_jitter: f32,
+    /// grain length in frames of every reader of bank
+    grain_len: usize,
+    /// gain, width and pitch as ramped toward
+    dezip: Dezip,
+    /// time constant in seconds gain, width and pitch ramp with; stepwise
+    /// at 0
+    pub smooth_len: f32,
 
     pub gain: f32,
+    /// delay send level
+    pub send: f32,
+    /// level of render, ahead of routing and sends
+    pub meter: Meter,
     pub width: f32,
+    pub stereo: Stereo,
     pub pitch: Mod<f32>,
+    pub resample: Resample,
+    pub interpolation: Interpolation,
+    /// steps by which held and looped onsets read ahead of clock, wrapping
+    /// within loops
+    pub slip: i16,
+
+    pub filter: FilterMode,
+    /// normalized, exponential over audible range
+    pub cutoff: Mod<f32>,
+    /// in 0..=1, self-oscillating near 1
+    pub resonance: Mod<f32>,
+    svf: Svf,
 
     pub bank: Bank<PADS, STEPS>,
     pub kit_index: u8,
-    pub kit_drift: f32,
-    pub phrase_drift: f32,
+    pub kit_drift: Drift,
+    layer_cursor: LayerCursor<PADS>,
+    pub phrase_drift: Drift,
+    pub humanize: Humanize,
+
+    motions: [Option<Motion<STEPS>>; MotionTarget::COUNT],
+    /// scene recalled toward, if recalling
+    recall: Option<Recall>,
+    /// steps since clock start, for motion and accent playback
+    step: u16,
+
+    pub accent: Accent,
+    /// gain and cutoff multipliers of current step
+    accent_level: (f32, f32),
+    /// velocity sensitivity; hits play at full gain at 0, at their velocity
+    /// at 1
+    pub sensitivity: f32,
+    /// pre-listen input buffered while quantized on the cue pair until it
+    /// fires
+    pub cue: bool,
+    pub generator: Generator,
+
+    pub envelope: Envelope,
 
+    pub release: Release,
+    pub launch: Launch,
+    /// release fade length in seconds
+    pub release_len: f32,
+    /// release fade level, if fading
+    decay: Option<f32>,
+    /// length in seconds an ended event's onset rings out over, through its
+    /// envelope; muted at once at 0 or without a tail assigned
+    pub tail_len: f32,
+    /// pad of audible onset last triggered, until taken
+    hit: Option<u8>,
+
+    limits: Limits,
     input: active::Input<F>,
     record: active::Record<STEPS, F>,
     sequence: active::Sequence<PHRASES, F>,
     grain: GrainReader,
+    /// displaced onsets ringing out, if polyphonic
+    voices: Voices<F>,
+    /// buffered input pre-listened, if cueing; allocated only while routed
+    preview: Option<alloc::boxed::Box<Voice<F>>>,
 }
 
-impl<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: FileHandler>
+#[maybe_async::maybe_async]
+impl<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: Fs>
     BankHandler<PADS, STEPS, PHRASES, F>
 {
-    fn new(ticks_per_step: u16) -> Self {
+    fn new(ticks_per_step: u16, limits: Limits) -> Self {
         Self {
             quant: false,
             tempo: 0.,
             ticks_per_step,
+            sample_rate: 0,
+            frames_since_tick: 0,
             loop_div: Mod::new(8., 1.),
+            roll_curve: Curve::Linear,
+            roll: 1.,
+            loop_fine: 1.,
+            stutter: None,
+            stutter_len: 0.5,
+            tape_stop: false,
+            tape_len: 0.5,
+            freeze: false,
+            freeze_size: 0.25,
+            freeze_jitter: 0.,
+            grain_len: MAX_GRAIN_LEN,
+            dezip: Dezip {
+                gain: Smooth::new(0.5),
+                width: Smooth::new(0.5),
+                pitch: Smooth::new(1.),
+                coeff: 0.,
+            },
+            smooth_len: 0.01,
 
             gain: 0.5,
+            send: 0.,
+            meter: Meter::default(),
             width: 0.5,
+            stereo: Stereo::Pan,
             pitch: Mod::new(1., 1.),
+            resample: Resample::PreservePitch,
+            interpolation: Interpolation::Linear,
+            slip: 0,
+
+            filter: FilterMode::Off,
+            cutoff: Mod::new(1., 1.),
+            resonance: Mod::new(0., 1.),
+            svf: Svf::new(),
 
             bank: Bank::default(),
             kit_index: 0,
-            kit_drift: 0.,
-            phrase_drift: 0.,
+            kit_drift: Drift::default(),
+            layer_cursor: LayerCursor::default(),
+            phrase_drift: Drift::default(),
+            humanize: Humanize::default(),
+
+            motions: core::array::from_fn(|_| None),
+            recall: None,
+            step: 0,
+
+            accent: Accent::default(),
+            accent_level: (1., 1.),
+            sensitivity: 0.,
+            cue: false,
+            generator: Generator::default(),
+
+            envelope: Envelope::default(),
+
+            release: Release::Sync,
+            launch: Launch::Step,
+            release_len: 0.5,
+            decay: None,
+            tail_len: 0.,
+            hit: None,
 
             input: active::Input::default(),
+            limits,
             record: active::Record::default(),
             sequence: active::Sequence::default(),
             grain: GrainReader::new(),
+            voices: Voices::new(0),
+            preview: None,
+        }
+    }
+
+    /// sweep filter from bipolar control in -1..=1: low-pass closing below
+    /// center, high-pass opening above, off at center
+    pub fn assign_filter_sweep(&mut self, bipolar: f32) {
+        if bipolar < -f32::EPSILON {
+            self.filter = FilterMode::LowPass;
+            self.cutoff.base = 1. + bipolar.max(-1.);
+        } else if bipolar > f32::EPSILON {
+            self.filter = FilterMode::HighPass;
+            self.cutoff.base = bipolar.min(1.);
+        } else {
+            self.filter = FilterMode::Off;
+        }
+    }
+
+    /// set loop division base from control `abs` in 0..=1
+    pub fn assign_roll(&mut self, abs: f32) {
+        self.roll = abs;
+        self.loop_div.base = self.roll_curve.loop_div(abs);
+    }
+
+    /// set fine multiplier of loop division from control `abs` in 0..=1, unity
+    /// at center
+    pub fn assign_loop_fine(&mut self, abs: f32) {
+        self.loop_fine = 2f32.powf(abs.clamp(0., 1.) * 2. - 1.);
+    }
+
+    /// ticks spanned by a loop `len` 1/256 steps long at division `div`
+    fn loop_ticks(len: u16, ticks_per_step: u16, div: f32) -> f32 {
+        len as f32 / passive::LOOP_STEP as f32 * ticks_per_step as f32 / div
+    }
+
+    /// set loop division control response, remapping last control
+    pub fn assign_roll_curve(&mut self, curve: Curve) {
+        self.roll_curve = curve;
+        self.loop_div.base = curve.loop_div(self.roll);
+    }
+
+    /// set knob `target` to `value`
+    pub fn assign(&mut self, target: MotionTarget, value: f32) {
+        match target {
+            MotionTarget::Gain => self.gain = value,
+            MotionTarget::Width => self.width = value,
+            MotionTarget::Pitch => self.pitch.base = value,
+            MotionTarget::Roll => self.assign_roll(value),
+            MotionTarget::KitDrift => self.kit_drift.amount = value,
+            MotionTarget::PhraseDrift => self.phrase_drift.amount = value,
+            MotionTarget::Humanize => self.humanize.amount = value,
+            MotionTarget::Cutoff => self.cutoff.base = value,
+            MotionTarget::Resonance => self.resonance.base = value,
+        }
+    }
+
+    /// set knob `target` to `value`, recording it into its motion loop from
+    /// this step; loop spans running phrase, if any
+    pub fn record_motion(&mut self, target: MotionTarget, value: f32) {
+        self.assign(target, value);
+        let len = self
+            .activity()
+            .map(|v| v.len)
+            .unwrap_or(MOTION_LEN)
+            .clamp(1, self.limits.phrase_len);
+        let motion = self.motions[target as usize].get_or_insert_with(|| Motion::new(len));
+        if motion.live.is_none() {
+            // overdub from this step
+            motion.recorded = 0;
         }
+        motion.live = Some(value);
+    }
+
+    /// stop recording all motion loops, keeping what was recorded
+    pub fn stop_motion_record(&mut self) {
+        for motion in self.motions.iter_mut().flatten() {
+            motion.live = None;
+        }
+    }
+
+    pub fn clear_motions(&mut self) {
+        self.motions = core::array::from_fn(|_| None);
     }
 
+    /// assign `onset` to pad of loaded kit, dropping its layers
     pub fn assign_onset(&mut self, pad_index: u8, onset: passive::Onset) {
-        self.bank.kits[self.kit_index as usize]
-            .get_or_insert_default()
-            .onsets[pad_index as usize] = Some(onset);
+        let kit = self.bank.kits[self.kit_index as usize].get_or_insert_default();
+        kit.onsets[pad_index as usize] = Some(onset);
+        kit.layers[pad_index as usize].onsets.clear();
+    }
+
+    /// layer `onset` onto pad of loaded kit, as its first if it has none
+    pub fn push_layer(&mut self, pad_index: u8, onset: passive::Onset) {
+        let kit = self.bank.kits[self.kit_index as usize].get_or_insert_default();
+        match kit.onsets[pad_index as usize] {
+            Some(_) => kit.layers[pad_index as usize].onsets.push(onset),
+            None => kit.onsets[pad_index as usize] = Some(onset),
+        }
+    }
+
+    /// set how pad of loaded kit picks among its layers, if any
+    pub fn assign_select(&mut self, pad_index: u8, select: Select) {
+        if let Some(kit) = self.bank.kits[self.kit_index as usize].as_mut() {
+            kit.layers[pad_index as usize].select = select;
+        }
+    }
+
+    /// transpose pad of loaded kit by `semitones`, if any; applies from next
+    /// trigger
+    pub fn assign_pad_pitch(&mut self, pad_index: u8, semitones: Option<f32>) {
+        if let Some(Some(onset)) = self.bank.kits[self.kit_index as usize]
+            .as_mut()
+            .map(|v| &mut v.onsets[pad_index as usize])
+        {
+            onset.pitch = semitones;
+        }
     }
 
-    pub fn force_event(
+    pub async fn force_event(
         &mut self,
         event: passive::Event,
         rand: &mut impl Rand,
         fs: &mut F,
     ) -> Result<(), Error<F::Error>> {
-        self.input.active.event.trans(
-            &event,
-            &self.bank,
-            self.kit_index,
-            self.kit_drift,
-            &mut self.grain,
-            rand,
-            fs,
-        )?;
+        self.decay = None;
+        self.input
+            .active
+            .event
+            .trans(
+                &event,
+                &self.bank,
+                self.kit_index,
+                &mut self.kit_drift,
+                &mut self.layer_cursor,
+                &mut self.grain,
+                Some(&mut self.voices),
+                self.input.active.velocity,
+                rand,
+                fs,
+            )
+            .await?;
+        self.input.active.velocity = 1.;
         Ok(())
     }
 
-    pub fn push_event(
+    pub async fn push_event(
+        &mut self,
+        event: passive::Event,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        self.push_hit(event, 1., rand, fs).await
+    }
+
+    /// push event hit at `velocity` in 0..=1, scaled per sensitivity
+    pub async fn push_hit(
         &mut self,
         event: passive::Event,
+        velocity: f32,
         rand: &mut impl Rand,
         fs: &mut F,
     ) -> Result<(), Error<F::Error>> {
-        if self.quant {
+        let gain = 1. - self.sensitivity.clamp(0., 1.) * (1. - velocity.clamp(0., 1.));
+        if self.buffered() {
             self.input.buffer.event = Some(event);
+            self.input.buffer.delay = self.step_fraction();
+            self.input.velocity = gain;
+            if self.cue {
+                self.cue_event(&event, gain, fs).await?;
+            }
         } else {
-            self.force_event(event, rand, fs)?;
+            self.force_event(event, rand, fs).await?;
+            self.input.active.velocity = gain;
+            if self.quant {
+                // recorded as pushed at next tick
+                self.input.free = Some((event, self.step_fraction()));
+            }
+        }
+        Ok(())
+    }
+
+    /// whether live input waits for a tick to launch
+    fn buffered(&self) -> bool {
+        self.quant && self.launch != Launch::Off
+    }
+
+    /// open onset of buffered `event`, if any, to pre-listen at `gain`,
+    /// closing any previous
+    async fn cue_event(
+        &mut self,
+        event: &passive::Event,
+        gain: f32,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        let Some(preview) = self.preview.as_deref_mut() else {
+            return Ok(());
+        };
+        if let Some(onset) = preview.onset.take() {
+            fs.close(&onset.wav.file).await?;
+        }
+        let (passive::Event::Hold { index } | passive::Event::Loop { index, .. }) = *event else {
+            return Ok(());
+        };
+        // undrifted, as drift is rolled only once fired
+        let Some(kit) = self.bank.kits[self.kit_index as usize].as_ref() else {
+            return Ok(());
+        };
+        let pan = Kit::<PADS>::generate_pan(index);
+        // first layer, as picking one would skip it on the hit
+        preview.onset = kit.onset_seek(index, 0, pan, fs).await?;
+        preview.velocity = gain;
+        preview.grain.restart(self.input.buffer.reverse);
+        Ok(())
+    }
+
+    /// release input per release policy
+    pub async fn push_release(
+        &mut self,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        match self.release {
+            Release::Sync => self.push_event(passive::Event::Sync, rand, fs).await?,
+            Release::Sustain => (),
+            Release::Decay => {
+                if !matches!(self.input.active.event, active::Event::Sync) {
+                    self.decay = Some(1.);
+                }
+            }
         }
         Ok(())
     }
 
+    /// set micro-loop length from control `abs` in 0..=1, halving from half a
+    /// step
+    pub fn assign_stutter(&mut self, abs: f32) {
+        let rolls = (abs.clamp(0., 1.) * (STUTTER_ROLLS - 1) as f32).round() as i32;
+        self.stutter_len = 0.5f32.powi(1 + rolls);
+    }
+
+    /// hold or release micro-loop, caught from next step; released, the event
+    /// beneath resumes where it has ticked to by next step
+    pub fn push_stutter(&mut self, down: bool) {
+        self.stutter = down.then_some(Stutter { start: None });
+    }
+
+    /// ramp read speed down to a stop, or back up to speed, over `tape_len`
+    pub fn push_tape_stop(&mut self, stop: bool) {
+        self.tape_stop = stop;
+    }
+
     pub fn push_reverse(&mut self, reverse: bool) {
-        if self.quant {
+        if self.buffered() {
             self.input.buffer.reverse = reverse;
         } else {
             self.input.active.reverse = reverse;
         }
     }
 
-    pub fn trim_record(&mut self, len: u16) {
-        self.record.trim(len);
+    /// begin a long take, saved by next trim as phrases chained over free
+    /// pads if longer than one
+    pub fn arm_record(&mut self) {
+        self.record.arm();
+    }
+
+    pub async fn trim_record(&mut self, len: u16, fs: &mut F) -> Result<(), Error<F::Error>> {
+        self.record.trim(len, self.limits.phrase_len, fs).await?;
+        Ok(())
+    }
+
+    /// last step of audible phrase, if any
+    pub fn activity(&self) -> Option<Activity> {
+        self.record
+            .activity()
+            .or_else(|| self.sequence.activity(&self.bank))
+    }
+
+    /// pad of audible onset triggered since last taken, if any, whether hit
+    /// live or fired by phrase playback
+    pub fn take_hit(&mut self) -> Option<u8> {
+        self.hit.take()
+    }
+
+    /// toggle drift lock of pad at `pad_index` in base kit, returning new
+    /// lock
+    pub fn toggle_pad_lock(&mut self, pad_index: u8) -> bool {
+        let kit = self.bank.kits[self.kit_index as usize].get_or_insert_default();
+        kit.locks[pad_index as usize] = !kit.locks[pad_index as usize];
+        kit.locks[pad_index as usize]
+    }
+
+    /// toggle drift lock of record step now playing, returning new lock, if
+    /// any
+    pub fn toggle_step_lock(&mut self) -> Option<bool> {
+        self.record.toggle_lock()
     }
 
-    pub fn take_record(&mut self, index: Option<u8>) {
-        if let Some(source) = self.record.take() {
-            if let Some(index) = index {
-                self.bank.phrases[index as usize] = Some(source);
-                self.sequence.clear();
-                self.sequence.push(index);
+    /// set playback quantization of trimmed record
+    pub fn assign_record_quantize(&mut self, quantize: passive::Quantize) {
+        self.record.assign_quantize(quantize);
+    }
+
+    /// set playback rate of trimmed record
+    pub fn assign_record_rate(&mut self, rate: passive::Rate) {
+        self.record.assign_rate(rate);
+    }
+
+    /// set playback quantization of phrase at pad `index`
+    pub fn assign_phrase_quantize(&mut self, index: u8, quantize: passive::Quantize) {
+        if let Some(phrase) = self.bank.phrases[index as usize].as_mut() {
+            phrase.quantize = quantize;
+        }
+    }
+
+    /// store trimmed record at pad `index`, if any, chaining further
+    /// segments of a long take over free pads after it; returns segments
+    /// dropped for want of free pads
+    pub async fn take_record(
+        &mut self,
+        index: Option<u8>,
+        fs: &mut F,
+    ) -> Result<usize, Error<F::Error>> {
+        let mut take = self.record.take(fs).await?.into_iter();
+        let (Some(index), Some(source)) = (index, take.next()) else {
+            return Ok(0);
+        };
+        self.bank.phrases[index as usize] = Some(source);
+        let mut link = index as usize;
+        let mut dropped = 0;
+        for source in take {
+            let free = (1..PADS)
+                .map(|v| (index as usize + v) % PADS)
+                .find(|v| self.bank.phrases[*v].is_none());
+            let Some(free) = free else {
+                dropped += 1;
+                continue;
+            };
+            if let Some(phrase) = self.bank.phrases[link].as_mut() {
+                phrase.follow = passive::Follow::Chain(free as u8);
             }
+            self.bank.phrases[free] = Some(source);
+            link = free;
+        }
+        self.sequence.clear(fs).await?;
+        self.sequence.push(index, self.limits.phrase_count);
+        Ok(dropped)
+    }
+
+    /// pads of phrases chained after phrase at pad `index`, in order
+    pub fn chain(&self, index: u8) -> impl Iterator<Item = u8> + '_ {
+        let mut link = index;
+        // a pad apiece at most, should links loop
+        (1..PADS).map_while(move |_| {
+            let phrase = self.bank.phrases.get(link as usize)?.as_ref()?;
+            let passive::Follow::Chain(next) = phrase.follow else {
+                return None;
+            };
+            self.bank.phrases.get(next as usize)?.as_ref()?;
+            link = next;
+            Some(next)
+        })
+    }
+
+    /// copy `len` step span of sequence now sounding to phrase at pad
+    /// `index`, returning whether any was sounding
+    pub fn capture_sequence(&mut self, index: u8, len: u16) -> bool {
+        let len = len.min(self.limits.phrase_len);
+        if let Some(phrase) = self.sequence.excerpt(&self.bank, len) {
+            self.bank.phrases[index as usize] = Some(phrase);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// set follow action of phrase at `pad_index`, if any; of its last
+    /// segment if chained
+    pub fn assign_follow(&mut self, pad_index: u8, follow: passive::Follow) {
+        let index = self.chain(pad_index).last().unwrap_or(pad_index);
+        if let Some(phrase) = self.bank.phrases[index as usize].as_mut() {
+            phrase.follow = follow;
         }
     }
 
-    pub fn clear_sequence(&mut self) {
-        self.sequence.clear();
+    /// replace bank, truncating phrases beyond runtime limits and rewinding
+    /// layers to each pad's first onset
+    pub fn assign_bank(&mut self, mut bank: Bank<PADS, STEPS>) {
+        bank.truncate(self.limits.phrase_len);
+        self.bank = bank;
+        self.layer_cursor = LayerCursor::default();
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub async fn clear_sequence(&mut self, fs: &mut F) -> Result<(), Error<F::Error>> {
+        self.sequence.clear(fs).await?;
+        Ok(())
     }
 
     pub fn push_sequence(&mut self, index: u8) {
-        self.sequence.push(index);
+        self.sequence.push(index, self.limits.phrase_count);
+    }
+
+    /// save sequence as pool `name`, replacing any of same name
+    pub fn save_pool(&mut self, name: alloc::string::String) {
+        let phrases = self.sequence.phrases().collect();
+        if let Some(pool) = self.bank.pools.iter_mut().find(|v| v.name == name) {
+            pool.phrases = phrases;
+        } else {
+            self.bank.pools.push(passive::Pool { name, phrases });
+        }
+    }
+
+    /// replace sequence with pool at `index`, if any
+    pub async fn recall_pool(&mut self, index: usize, fs: &mut F) -> Result<(), Error<F::Error>> {
+        if let Some(pool) = self.bank.pools.get(index) {
+            self.sequence.clear(fs).await?;
+            for &phrase in pool.phrases.iter() {
+                self.sequence.push(phrase, self.limits.phrase_count);
+            }
+        }
+        Ok(())
+    }
+
+    /// performance parameters as they stand
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            gain: self.gain,
+            speed: self.pitch.base,
+            pitch: self.pitch.offset,
+            roll: self.roll,
+            kit_drift: self.kit_drift.amount,
+            phrase_drift: self.phrase_drift.amount,
+            kit_index: self.kit_index,
+        }
+    }
+
+    /// set performance parameters to `snapshot`, keeping kit if beyond pads
+    pub fn apply_snapshot(&mut self, snapshot: &Snapshot) {
+        self.gain = snapshot.gain;
+        self.pitch.base = snapshot.speed;
+        self.pitch.offset = snapshot.pitch;
+        self.assign_roll(snapshot.roll);
+        self.kit_drift.amount = snapshot.kit_drift;
+        self.phrase_drift.amount = snapshot.phrase_drift;
+        if (snapshot.kit_index as usize) < PADS {
+            self.kit_index = snapshot.kit_index;
+        }
+    }
+
+    /// save performance parameters as scene `name`, replacing any of same
+    /// name
+    pub fn capture_scene(&mut self, name: alloc::string::String) {
+        let snapshot = self.snapshot();
+        if let Some(scene) = self.bank.scenes.iter_mut().find(|v| v.name == name) {
+            scene.snapshot = snapshot;
+        } else {
+            self.bank.scenes.push(scene::Scene { name, snapshot });
+        }
+    }
+
+    /// interpolate performance parameters toward scene `name` over `steps`,
+    /// at once at 0; whether bank saved any such scene. parameters written
+    /// meanwhile are overridden until recalled
+    pub fn recall_scene(&mut self, name: &str, steps: u16) -> bool {
+        let Some(scene) = self.bank.scenes.iter().find(|v| v.name == name) else {
+            return false;
+        };
+        let to = scene.snapshot;
+        if steps == 0 {
+            self.recall = None;
+            self.apply_snapshot(&to);
+        } else {
+            self.recall = Some(Recall::new(self.snapshot(), to, steps));
+        }
+        true
+    }
+
+    /// onset files held open by input, record, sequence and voices
+    pub fn open_onsets(&self) -> usize {
+        [
+            Some(&self.input.active),
+            self.record.active_phrase.as_ref().map(|v| &v.active),
+            self.sequence.active_phrase.as_ref().map(|v| &v.active),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|v| !matches!(v.event, active::Event::Sync))
+        .count()
+            + self.voices.open()
+            + self.preview.as_ref().is_some_and(|v| v.onset.is_some()) as usize
+    }
+
+    /// ring displaced onsets out in `count` voices, up to MAX_VOICES;
+    /// monophonic at 0, as by default. allocates, so call outside audio
+    /// callback
+    pub async fn assign_voices(&mut self, count: usize, fs: &mut F) -> Result<(), Error<F::Error>> {
+        self.voices.clear(fs).await?;
+        let tail = self.voices.tail.take();
+        self.voices = Voices::new(count);
+        self.voices.tail = tail;
+        Ok(())
+    }
+
+    /// allocate a voice to pre-listen buffered input in, or free it; none by
+    /// default, so nothing cues. allocates, so call outside audio callback
+    async fn assign_preview(&mut self, enabled: bool, fs: &mut F) -> Result<(), Error<F::Error>> {
+        Ok(Voice::assign(&mut self.preview, enabled, fs).await?)
+    }
+
+    /// allocate a voice for ended onsets to ring out in over `tail_len`, or
+    /// free it, muting them at once whatever `tail_len`; off by default.
+    /// allocates, so call outside audio callback
+    pub async fn assign_tail(&mut self, enabled: bool, fs: &mut F) -> Result<(), Error<F::Error>> {
+        Ok(Voice::assign(&mut self.voices.tail, enabled, fs).await?)
+    }
+
+    fn frames_per_step(&self) -> Option<u32> {
+        if self.tempo > 0. && self.sample_rate > 0 {
+            Some((self.sample_rate as f32 * 60. / (self.tempo * self.ticks_per_step as f32)) as u32)
+        } else {
+            None
+        }
+    }
+
+    /// position within current step in 1/256 steps
+    fn step_fraction(&self) -> u8 {
+        self.frames_per_step()
+            .map(|v| (self.frames_since_tick as u64 * 256 / v.max(1) as u64).min(255) as u8)
+            .unwrap_or_default()
+    }
+
+    /// frame after last tick at which event delayed by `delay` 1/256 steps fires
+    fn due_frame(&self, delay: u16) -> u32 {
+        self.frames_per_step()
+            .map(|v| (delay as u64 * v as u64 / 256) as u32)
+            .unwrap_or_default()
+    }
+
+    /// frames from now until earliest pending phrase event, if any
+    fn pending_due(&self) -> Option<u32> {
+        [
+            self.record.active_phrase.as_ref(),
+            self.sequence.active_phrase.as_ref(),
+        ]
+        .into_iter()
+        .filter_map(|v| v?.pending)
+        .filter(|(_, delay, _)| *delay < 256)
+        .map(|(_, delay, _)| self.due_frame(delay).saturating_sub(self.frames_since_tick))
+        .min()
+    }
+
+    async fn fire_pending(
+        &mut self,
+        rand: &mut impl Rand,
+        fs: &mut F,
+    ) -> Result<(), Error<F::Error>> {
+        let live_reverse = self.input.active.reverse;
+        let audible = self.audible();
+        let due = [
+            self.record.active_phrase.as_ref(),
+            self.sequence.active_phrase.as_ref(),
+        ]
+        .map(|v| {
+            v.and_then(|v| v.pending).is_some_and(|(_, delay, _)| {
+                delay < 256 && self.due_frame(delay) <= self.frames_since_tick
+            })
+        });
+        for (phrase, due) in [
+            self.record.active_phrase.as_mut(),
+            self.sequence.active_phrase.as_mut(),
+        ]
+        .into_iter()
+        .zip(due)
+        .zip([1, 2])
+        {
+            if let ((Some(phrase), true), index) = (phrase, due) {
+                phrase
+                    .fire(
+                        live_reverse,
+                        self.ticks_per_step,
+                        &self.bank,
+                        self.kit_index,
+                        &mut self.kit_drift,
+                        &mut self.layer_cursor,
+                        &mut self.grain,
+                        (audible == Some(index)).then_some(&mut self.voices),
+                        rand,
+                        fs,
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// read active event, firing pending phrase events at their micro-timing
+    async fn read_attenuated<T: core::ops::AddAssign + From<f32>>(
+        &mut self,
+        rand: &mut impl Rand,
+        fs: &mut F,
+        mut buffer: &mut [T],
+        channels: usize,
+        sample_rate: u32,
+    ) -> Result<(), Error<F::Error>> {
+        self.sample_rate = sample_rate;
+        while let Some(due) = self
+            .pending_due()
+            .filter(|v| (*v as usize) < buffer.len() / channels)
+        {
+            let (head, tail) = buffer.split_at_mut(due as usize * channels);
+            self.read_active(fs, head, channels, sample_rate).await?;
+            self.frames_since_tick += due;
+            self.fire_pending(rand, fs).await?;
+            buffer = tail;
+        }
+        self.read_active(fs, buffer, channels, sample_rate).await?;
+        self.frames_since_tick += (buffer.len() / channels) as u32;
+        if self.decay.is_some_and(|v| v <= 0.) {
+            // release faded out, so no tail left to ring
+            self.voices.tails = false;
+            self.force_event(passive::Event::Sync, rand, fs).await?;
+        }
+        Ok(())
     }
 
-    fn read_attenuated<T: core::ops::AddAssign + From<f32>>(
+    async fn read_active<T: core::ops::AddAssign + From<f32>>(
         &mut self,
         fs: &mut F,
         buffer: &mut [T],
         channels: usize,
         sample_rate: u32,
     ) -> Result<(), F::Error> {
+        self.dezip.gain.target = self.gain;
+        self.dezip.width.target = self.width;
+        self.dezip.pitch.target = self.pitch.net();
+        self.dezip.coeff = (-1. / (self.smooth_len * sample_rate as f32)).exp();
         let reverse = self.reverse();
+        let velocity = actives_mut!(self)
+            .into_iter()
+            .flatten()
+            .find(|v| !matches!(v.event, active::Event::Sync))
+            .map(|v| v.velocity)
+            .unwrap_or(1.);
         let event = if let Some(event) = actives_mut!(self)
             .into_iter()
             .find_map(|v| v.and_then(|v| v.non_sync()))
@@ -531,102 +2437,345 @@ impl<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: FileHandler
             &mut active::Event::Sync
         };
 
-        let (len, onset) = match event {
+        let div = self.loop_div.net() * self.loop_fine;
+        let (span, onset) = match event {
             active::Event::Sync => (None, None),
             active::Event::Hold { onset, .. } => (None, Some(onset)),
-            active::Event::Loop { onset, len, .. } => (Some(*len as f32 * self.ticks_per_step as f32 / self.loop_div.net()), Some(onset)),
+            active::Event::Loop { onset, len, .. } => {
+                let len = Self::loop_ticks(*len, self.ticks_per_step, div);
+                (Some((onset.start as i64 * 2, len)), Some(onset))
+            }
+        };
+        // micro-loop overrides any loop beneath once caught
+        let span = match self.stutter {
+            Some(Stutter { start: Some(start) }) => {
+                Some((start, self.stutter_len * self.ticks_per_step as f32))
+            }
+            _ => span,
         };
-        let speed = if let Some(ref onset) = onset {
-            self.pitch.net() * onset.wav.sample_rate as f32 / sample_rate as f32
+        // onsets triggered since last read are read from age 0
+        if let Some(onset) = onset.as_ref().filter(|v| v.age == 0) {
+            self.hit = Some(onset.index);
+        }
+        let decay_step = 1. / (self.release_len * sample_rate as f32).max(1.);
+        let tail_step = 1. / (self.tail_len * sample_rate as f32).max(1.);
+        self.voices.tails = self.tail_len > 0.;
+        let tape_step = 1. / (self.tape_len * sample_rate as f32).max(1.);
+        self.grain.tape.step = if self.tape_stop {
+            -tape_step
         } else {
-            self.pitch.net()
+            tape_step
         };
+        let scrub = (self.freeze_size * self.grain_len as f32, self.freeze_jitter);
+        self.grain.assign_len(self.grain_len);
+        self.grain.interpolation = self.interpolation;
+        for voice in self.voices.iter_mut() {
+            voice.grain.assign_len(self.grain_len);
+            voice.grain.interpolation = self.interpolation;
+        }
+        self.grain.freeze(self.freeze.then_some(scrub));
+        self.svf.tune(
+            self.filter,
+            self.cutoff.net() * self.accent_level.1,
+            self.resonance.net(),
+            sample_rate,
+        );
         Self::read_grain::<T>(
-            self.gain,
-            self.width,
-            speed,
+            velocity * self.accent_level.0,
+            self.dezip,
+            self.stereo,
+            self.resample,
             reverse,
-            len,
+            span,
             onset,
+            &mut self.decay,
+            decay_step,
+            self.envelope,
+            sample_rate,
+            &mut self.svf,
             &mut self.grain,
             fs,
             buffer,
             channels,
         )
+        .await?;
+        // tail rings out over its own length
+        let voices = self.voices.voices.iter_mut().map(|v| (v, decay_step));
+        let tail = self.voices.tail.as_deref_mut().map(|v| (v, tail_step));
+        for (voice, decay_step) in voices.chain(tail) {
+            let Some(onset) = voice.onset.as_mut() else {
+                continue;
+            };
+            voice.svf.tune(
+                self.filter,
+                self.cutoff.net() * self.accent_level.1,
+                self.resonance.net(),
+                sample_rate,
+            );
+            Self::read_grain::<T>(
+                voice.velocity * self.accent_level.0,
+                self.dezip,
+                self.stereo,
+                self.resample,
+                reverse,
+                None,
+                Some(onset),
+                &mut voice.level,
+                decay_step,
+                self.envelope,
+                sample_rate,
+                &mut voice.svf,
+                &mut voice.grain,
+                fs,
+                buffer,
+                channels,
+            )
+            .await?;
+        }
+        self.dezip.skip(buffer.len() / channels);
+        self.voices.reap(fs).await
+    }
+
+    /// read buffered input pre-listened, if any, closing it once fired, the
+    /// clock stops or cueing ends
+    async fn read_cue<T: core::ops::AddAssign + From<f32>>(
+        &mut self,
+        fs: &mut F,
+        buffer: &mut [T],
+        channels: usize,
+        sample_rate: u32,
+    ) -> Result<(), F::Error> {
+        let Some(preview) = self.preview.as_deref_mut().filter(|v| v.onset.is_some()) else {
+            return Ok(());
+        };
+        if !self.cue || !self.quant || self.input.buffer.event.is_none() {
+            if let Some(onset) = preview.onset.take() {
+                fs.close(&onset.wav.file).await?;
+            }
+            return Ok(());
+        }
+        preview.grain.assign_len(self.grain_len);
+        preview.grain.interpolation = self.interpolation;
+        preview.svf.tune(
+            self.filter,
+            self.cutoff.net(),
+            self.resonance.net(),
+            sample_rate,
+        );
+        Self::read_grain::<T>(
+            preview.velocity,
+            self.dezip,
+            self.stereo,
+            self.resample,
+            self.input.buffer.reverse,
+            None,
+            preview.onset.as_mut(),
+            &mut preview.level,
+            0.,
+            self.envelope,
+            sample_rate,
+            &mut preview.svf,
+            &mut preview.grain,
+            fs,
+            buffer,
+            channels,
+        )
+        .await
+    }
+
+    /// grain read speed of `onset` at unity bank pitch
+    fn rate(resample: Resample, onset: &active::Onset<F>, sample_rate: u32) -> f32 {
+        match resample {
+            Resample::PreservePitch => {
+                onset.pitch * onset.wav.sample_rate as f32 / sample_rate as f32
+            }
+            Resample::Repitch => onset.pitch,
+        }
     }
 
     /// associated method to appease borrow rules
     #[allow(clippy::too_many_arguments)]
-    fn read_grain<T: core::ops::AddAssign + From<f32>>(
-        gain: f32,
-        width: f32,
-        speed: f32,
+    async fn read_grain<T: core::ops::AddAssign + From<f32>>(
+        velocity: f32,
+        mut dezip: Dezip,
+        stereo: Stereo,
+        resample: Resample,
         reverse: bool,
-        len: Option<f32>,
+        span: Option<Span>,
         onset: Option<&mut active::Onset<F>>,
+        decay: &mut Option<f32>,
+        decay_step: f32,
+        envelope: Envelope,
+        sample_rate: u32,
+        svf: &mut Svf,
         grain: &mut GrainReader,
         fs: &mut F,
         buffer: &mut [T],
         channels: usize,
     ) -> Result<(), F::Error> {
-        // FIXME: support alternative channel counts?
-        assert!(channels == 2, "currently only stereo output is supported");
         // FIXME: play tails of sound with no onset active
-        // requires maintainance of onset data with GrainReader.tail!head for sample
-        // rate and pan (both of which should also be accounted for when fading
-        // between samples anyhow)
         if let Some(onset) = onset {
-            for i in 0..buffer.len() / channels {
-                let sample = grain.read_interpolated(speed, reverse, len, onset, fs)?;
-                let l = sample * (1. + width * ((onset.pan - 0.5).abs() - 1.)) * gain;
-                let r = sample * (1. + width * ((onset.pan + 0.5).abs() - 1.)) * gain;
-                buffer[i * channels] += T::from(l);
-                buffer[i * channels + 1] += T::from(r);
+            let meta = Meta {
+                rate: Self::rate(resample, onset, sample_rate),
+                pan: onset.pan,
+            };
+            for frame in buffer.chunks_exact_mut(channels) {
+                let (gain, width, pitch) = dezip.next();
+                let gain = gain * velocity;
+                let level = if let Some(level) = decay.as_mut() {
+                    *level = (*level - decay_step).max(0.);
+                    *level
+                } else {
+                    1.
+                };
+                let level = level * envelope.level(onset.age, sample_rate);
+                onset.age = onset.age.saturating_add(1);
+                let (sample, pan) = grain
+                    .read_interpolated(pitch, meta, reverse, span, onset, fs)
+                    .await?;
+                let sample = svf.process(sample * onset.gain * level);
+                let (l, r) = match stereo {
+                    Stereo::Pan => (
+                        sample * (1. + width * ((pan - 0.5).abs() - 1.)) * gain,
+                        sample * (1. + width * ((pan + 0.5).abs() - 1.)) * gain,
+                    ),
+                    Stereo::MidSide => mid_side(
+                        sample * (pan - 0.5).abs() * gain,
+                        sample * (pan + 0.5).abs() * gain,
+                        width,
+                    ),
+                };
+                add_stereo(frame, l, r);
             }
         }
         Ok(())
     }
 
-    fn tick(&mut self, rand: &mut impl Rand, fs: &mut F) -> Result<(), Error<F::Error>> {
+    async fn tick(&mut self, rand: &mut impl Rand, fs: &mut F) -> Result<(), Error<F::Error>> {
         self.quant = true;
-        let input_event = self.input.tick(
-            self.ticks_per_step,
-            &self.bank,
-            self.kit_index,
-            self.kit_drift,
-            &mut self.grain,
-            rand,
-            fs,
-        )?;
-        let record_event = self.record.tick(
-            self.input.active.reverse,
-            self.ticks_per_step,
-            &self.bank,
-            self.kit_index,
-            self.kit_drift,
-            self.phrase_drift,
-            &mut self.grain,
-            rand,
-            fs,
-        )?;
-        let sequence_event = self.sequence.tick(
-            self.input.active.reverse,
-            self.ticks_per_step,
-            &self.bank,
-            self.kit_index,
-            self.kit_drift,
-            self.phrase_drift,
-            &mut self.grain,
-            rand,
-            fs,
-        )?;
-        let event = input_event.or(record_event).or(sequence_event);
-        self.record.push(passive::Step {
-            event,
-            reverse: self.reverse(),
-        });
-        if event.is_none() {
-            // sync audible active, if any, with clock (with crossfade)
+        let step = self.step;
+        self.step = self.step.wrapping_add(1);
+        self.accent_level = if self.accent.pattern.accented(step) {
+            (self.accent.gain, self.accent.cutoff)
+        } else {
+            (1., 1.)
+        };
+        // replay motion loops
+        for target in [
+            MotionTarget::Gain,
+            MotionTarget::Width,
+            MotionTarget::Pitch,
+            MotionTarget::Roll,
+            MotionTarget::KitDrift,
+            MotionTarget::PhraseDrift,
+            MotionTarget::Humanize,
+            MotionTarget::Cutoff,
+            MotionTarget::Resonance,
+        ] {
+            if let Some(value) = self.motions[target as usize].as_mut().and_then(|v| v.tick(step)) {
+                self.assign(target, value);
+            }
+        }
+        // interpolate toward recalled scene
+        if let Some(recall) = self.recall.as_mut() {
+            match recall.tick() {
+                Some(snapshot) => self.apply_snapshot(&snapshot),
+                None => self.recall = None,
+            }
+        }
+        // generated trigger fires on this step unless input is buffered
+        let mut launch = self.launch.due(step);
+        if self.input.buffer.event.is_none() {
+            let kit = self.bank.kits[self.kit_index as usize].as_ref();
+            self.input.buffer.event = self.generator.generate(kit, rand);
+            launch |= self.input.buffer.event.is_some();
+        }
+        let input_event = self
+            .input
+            .tick(
+                launch,
+                self.ticks_per_step,
+                &self.bank,
+                self.kit_index,
+                &mut self.kit_drift,
+                &mut self.layer_cursor,
+                &mut self.grain,
+                Some(&mut self.voices),
+                rand,
+                fs,
+            )
+            .await?;
+        if input_event.is_some() {
+            // fresh input cancels release fade
+            self.decay = None;
+        }
+        // phrases ring out onsets only if they were audible
+        let audible = self.audible();
+        let record_event = self
+            .record
+            .tick(
+                self.input.active.reverse,
+                self.ticks_per_step,
+                &self.bank,
+                self.kit_index,
+                &mut self.kit_drift,
+                &mut self.layer_cursor,
+                &mut self.phrase_drift,
+                &self.humanize,
+                &mut self.grain,
+                (audible == Some(1)).then_some(&mut self.voices),
+                rand,
+                fs,
+            )
+            .await?;
+        let audible = self.audible();
+        let sequence_event = self
+            .sequence
+            .tick(
+                self.input.active.reverse,
+                self.ticks_per_step,
+                &self.bank,
+                self.kit_index,
+                &mut self.kit_drift,
+                &mut self.layer_cursor,
+                &mut self.phrase_drift,
+                &self.humanize,
+                &mut self.grain,
+                (audible == Some(2)).then_some(&mut self.voices),
+                rand,
+                fs,
+            )
+            .await?;
+        let reverse = self.reverse();
+        let event = if let Some((event, delay)) = input_event {
+            match delay {
+                Some(delay) => self.record.push_late(event, delay, reverse),
+                // launched on this step
+                None => self.record.push(passive::Step {
+                    event: Some(event),
+                    reverse,
+                    delay: 0,
+                    lock: false,
+                }),
+            }
+            Some(event)
+        } else {
+            let step = record_event.or(sequence_event);
+            self.record.push(passive::Step {
+                event: step.map(|v| v.0),
+                reverse,
+                delay: step.map(|v| v.1).unwrap_or_default(),
+                lock: false,
+            });
+            step.map(|v| v.0)
+        };
+        self.frames_since_tick = 0;
+        let slip = self.slip as f32 * self.ticks_per_step as f32;
+        let caught = self.stutter.is_some_and(|v| v.start.is_some());
+        if (event.is_none() && !caught) || (event.is_some() && self.slip != 0) {
+            // sync audible active, if any, with clock (with crossfade); fresh
+            // events too if slipped from their start. caught micro-loops read
+            // on undisturbed
             if let Some(event) = actives_mut!(self)
                 .into_iter()
                 .find_map(|v| v.and_then(|v| v.non_sync()))
@@ -636,47 +2785,147 @@ impl<const PADS: usize, const STEPS: usize, const PHRASES: usize, F: FileHandler
                     active::Event::Hold { onset, tick } => {
                         let wav = &mut onset.wav;
                         if let Some(steps) = wav.steps {
-                            self.grain.fade(Some(wav), fs)?;
-                            let offset =
-                                (wav.pcm_len as f32 / steps as f32 * *tick as f32) as i64 & !1;
-                            wav.seek(onset.start as i64 * 2 + offset, fs)?;
+                            self.grain.fade(Some(wav), fs).await?;
+                            let offset = (wav.pcm_len as f32 / steps as f32 * (*tick as f32 + slip))
+                                as i64
+                                & !1;
+                            wav.seek(onset.start as i64 * 2 + offset);
                         }
                     }
                     active::Event::Loop { onset, tick, len } => {
                         let wav = &mut onset.wav;
                         if let Some(steps) = wav.steps {
-                            self.grain.fade(Some(wav), fs)?;
+                            self.grain.fade(Some(wav), fs).await?;
+                            let div = self.loop_div.net() * self.loop_fine;
+                            let ticks = Self::loop_ticks(*len, self.ticks_per_step, div);
                             let offset = (wav.pcm_len as f32 / steps as f32
-                                * (*tick as f32).rem_euclid(
-                                    *len as f32 * self.ticks_per_step as f32 / self.loop_div.net(),
-                                )) as i64
+                                * (*tick as f32 + slip).rem_euclid(ticks))
+                                as i64
                                 & !1;
-                            wav.seek(onset.start as i64 * 2 + offset, fs)?;
+                            wav.seek(onset.start as i64 * 2 + offset);
                         }
                     }
                 }
             }
         }
+        // catch micro-loop where audible onset now reads, anew on fresh events
+        let stutter = self.stutter.as_mut();
+        if let Some(stutter) = stutter.filter(|v| v.start.is_none() || event.is_some()) {
+            stutter.start = actives_mut!(self)
+                .into_iter()
+                .find_map(|v| v.and_then(|v| v.non_sync()))
+                .and_then(|v| match v {
+                    active::Event::Sync => None,
+                    active::Event::Hold { onset, .. } | active::Event::Loop { onset, .. } => {
+                        Some(onset.wav.pos as i64)
+                    }
+                });
+        }
+        // read ahead of audible onset, if any, so grain reads until next tick
+        // hit cache
+        if let Some(active::Event::Hold { onset, .. } | active::Event::Loop { onset, .. }) =
+            actives_mut!(self)
+                .into_iter()
+                .find_map(|v| v.and_then(|v| v.non_sync()))
+        {
+            self.grain
+                .cache
+                .prefetch(&mut onset.wav, reverse, fs)
+                .await?;
+        }
+        for voice in self.voices.iter_mut() {
+            if let Some(onset) = voice.onset.as_mut() {
+                voice
+                    .grain
+                    .cache
+                    .prefetch(&mut onset.wav, reverse, fs)
+                    .await?;
+            }
+        }
         Ok(())
     }
 
     fn stop(&mut self) {
         self.quant = false;
+        self.step = 0;
+        self.accent_level = (1., 1.);
+    }
+
+    /// next tick plays step `step` from song start, running phrases too
+    fn locate(&mut self, step: u32) {
+        self.step = step as u16;
+        self.record.locate(step);
+        self.sequence.locate(&self.bank, step);
+    }
+
+    /// index of audible active among input, record and sequence, if any
+    fn audible(&self) -> Option<usize> {
+        [
+            Some(&self.input.active),
+            self.record.active_phrase.as_ref().map(|v| &v.active),
+            self.sequence.active_phrase.as_ref().map(|v| &v.active),
+        ]
+        .into_iter()
+        .position(|v| v.is_some_and(|v| !matches!(v.event, active::Event::Sync)))
     }
 
+    /// direction heard: live reverse overrides the absolute direction of the
+    /// audible phrase step, if any
     fn reverse(&self) -> bool {
-        self.input.active.reverse
-            ^ self
-                .record
-                .active_phrase
-                .as_ref()
-                .map(|v| v.active.reverse)
-                .or(self
-                    .sequence
-                    .active_phrase
-                    .as_ref()
-                    .map(|v| v.active.reverse))
-                .unwrap_or_default()
+        let phrase = match self.audible() {
+            Some(1) => self.record.active_phrase.as_ref(),
+            Some(2) => self.sequence.active_phrase.as_ref(),
+            _ => None,
+        };
+        self.input.active.reverse || phrase.is_some_and(|v| v.active.reverse)
+    }
+}
+
+/// master fade length in ms
+pub const MASTER_FADE_LEN: u32 = 20;
+
+/// linear master gain ramp, de-clicking stream start and stop
+struct MasterFade {
+    level: f32,
+    target: f32,
+}
+
+impl MasterFade {
+    /// silent, fading in
+    fn new() -> Self {
+        Self {
+            level: 0.,
+            target: 1.,
+        }
+    }
+
+    fn apply(&mut self, buffer: &mut [f32], channels: usize, sample_rate: u32) {
+        if self.level == self.target {
+            if self.level == 0. {
+                buffer.fill(0.);
+            }
+            return;
+        }
+        let delta = 1000. / (MASTER_FADE_LEN * sample_rate) as f32;
+        for frame in buffer.chunks_mut(channels) {
+            self.level = if self.level < self.target {
+                (self.level + delta).min(self.target)
+            } else {
+                (self.level - delta).max(self.target)
+            };
+            frame.iter_mut().for_each(|v| *v *= self.level);
+        }
+    }
+}
+
+/// equal-power gain of bank `index` at crossfade `x` in 0..=1; banks past the
+/// second are unaffected
+fn crossfade(x: f32, index: usize) -> f32 {
+    let angle = x.clamp(0., 1.) * core::f32::consts::FRAC_PI_2;
+    match index {
+        0 => angle.cos(),
+        1 => angle.sin(),
+        _ => 1.,
     }
 }
 
@@ -686,58 +2935,355 @@ pub struct SystemHandler<
     const STEPS: usize,
     const PHRASES: usize,
     R: Rand,
-    F: FileHandler,
+    F: Fs,
 > {
     pub banks: [BankHandler<PADS, STEPS, PHRASES, F>; BANKS],
     pub rand: R,
     pub fs: F,
+    /// master mid/side width, 0.5 unity; bypassed if none
+    pub width: Option<f32>,
+    /// output pair per bank, counted from 0; every pair if none or beyond
+    /// output channels
+    pub routes: [Option<usize>; BANKS],
+    pub delay: Delay,
+    /// equal-power mix from first bank alone at 0 to second alone at 1;
+    /// bypassed if none
+    pub crossfade: Option<f32>,
+    /// master gain into soft clip, unity 1
+    pub gain: f32,
+    /// ahead of soft clip
+    pub dc: DcBlocker,
+    pub clipper: Clipper,
+    /// metered after clipping
+    pub loudness: Loudness,
+    /// metered after clipping
+    pub meter: Meter,
+    pub rehearsal: Rehearsal,
+    pub click: Click,
+    /// output pair counted from 0 cueing banks pre-listen on, over whatever
+    /// else it carries; off if none or beyond output channels
+    cue: Option<usize>,
+    fade: MasterFade,
 }
 
+#[maybe_async::maybe_async]
 impl<
         const BANKS: usize,
         const PADS: usize,
         const STEPS: usize,
         const PHRASES: usize,
         R: Rand,
-        F: FileHandler,
+        F: Fs,
     > SystemHandler<BANKS, PADS, STEPS, PHRASES, R, F>
 {
-    pub fn new(ticks_per_step: u16, rand: R, fs: F) -> Self {
+    /// phrases and sequences capped by `limits`, clamped into capacity
+    pub fn new(ticks_per_step: u16, rand: R, fs: F, limits: Limits) -> Self {
+        let limits = limits.within::<STEPS, PHRASES>();
         Self {
-            banks: core::array::from_fn(|_| BankHandler::new(ticks_per_step)),
+            banks: core::array::from_fn(|_| BankHandler::new(ticks_per_step, limits)),
             rand,
             fs,
+            width: None,
+            routes: [None; BANKS],
+            delay: Delay::new(),
+            crossfade: None,
+            gain: 1.,
+            dc: DcBlocker::default(),
+            clipper: Clipper::default(),
+            loudness: Loudness::new(),
+            meter: Meter::default(),
+            rehearsal: Rehearsal::new(ticks_per_step),
+            click: Click::new(ticks_per_step),
+            cue: None,
+            fade: MasterFade::new(),
+        }
+    }
+
+    /// apply master width and fade to rendered `buffer`; call last on each
+    /// output buffer
+    pub fn master(&mut self, buffer: &mut [f32], channels: usize, sample_rate: u32) {
+        if let Some(width) = self.width {
+            // each channel pair; mono left be
+            for pair in buffer.chunks_exact_mut(channels).flat_map(|v| v.chunks_exact_mut(2)) {
+                (pair[0], pair[1]) = mid_side(pair[0], pair[1], width);
+            }
         }
+        self.fade.apply(buffer, channels, sample_rate);
+    }
+
+    /// fade master out to silence, e.g. ahead of stream teardown
+    pub fn fade_out(&mut self) {
+        self.fade.target = 0.;
+    }
+
+    pub fn fade_in(&mut self) {
+        self.fade.target = 1.;
+    }
+
+    /// whether master fully faded out
+    pub fn faded_out(&self) -> bool {
+        self.fade.level == 0. && self.fade.target == 0.
     }
 
-    pub fn read_all<T: core::ops::AddAssign + From<f32>>(
+    /// sum banks into their routed pairs of `buffer`, metering each, mix delay
+    /// return of their sends into every pair, capture for or mix in rehearsal
+    /// loop, apply master gain, block dc, soft clip and meter the lot, then
+    /// overwrite click pair, if any, with click and mix pre-listen into cue
+    /// pair, if any
+    pub async fn read_all(
         &mut self,
-        buffer: &mut [T],
+        buffer: &mut [f32],
+        channels: usize,
+        sample_rate: u32,
+    ) -> Result<(), Error<F::Error>> {
+        self.read_stems(buffer, channels, sample_rate, |_, _| ())
+            .await
+    }
+
+    /// as `read_all`, passing each chunk of bank render by bank index, then of
+    /// delay return as none, to `stem`, e.g. for capturing them separately
+    pub async fn read_stems(
+        &mut self,
+        buffer: &mut [f32],
         channels: usize,
         sample_rate: u32,
+        mut stem: impl FnMut(Option<usize>, &[f32]),
     ) -> Result<(), Error<F::Error>> {
+        for chunk in buffer.chunks_mut(Delay::chunk_len(channels)) {
+            self.delay.send[..chunk.len()].fill(0.);
+            for (index, bank) in self.banks.iter_mut().enumerate() {
+                let dry = &mut self.delay.dry[..chunk.len()];
+                dry.fill(0.);
+                bank.read_attenuated(&mut self.rand, &mut self.fs, dry, channels, sample_rate)
+                    .await?;
+                if let Some(x) = self.crossfade {
+                    let gain = crossfade(x, index);
+                    for sample in dry.iter_mut() {
+                        *sample *= gain;
+                    }
+                }
+                let route = self.routes[index].filter(|v| (v + 1) * 2 <= channels);
+                let sends = self.delay.send.iter_mut();
+                let samples = chunk.iter_mut().zip(sends).zip(dry.iter()).enumerate();
+                for (sample, ((out, send), dry)) in samples {
+                    if route.is_none_or(|v| sample % channels / 2 == v) {
+                        *out += *dry;
+                    }
+                    *send += *dry * bank.send;
+                }
+                bank.meter.process(dry, channels, sample_rate);
+                stem(Some(index), dry);
+            }
+            self.delay.process(chunk.len(), channels, sample_rate);
+            let ret = &self.delay.dry[..chunk.len()];
+            for (out, ret) in chunk.iter_mut().zip(ret.iter()) {
+                *out += *ret;
+            }
+            stem(None, ret);
+            self.rehearsal.process(chunk, channels);
+            // also catches anything summed into buffer beforehand
+            for sample in chunk.iter_mut() {
+                *sample *= self.gain;
+            }
+            self.dc.process(chunk, channels, sample_rate);
+            self.clipper.process(chunk);
+            self.loudness.process(chunk, channels, sample_rate);
+            self.meter.process(chunk, channels, sample_rate);
+            self.click.process(chunk, channels, sample_rate);
+            // pre-listen read regardless, so it closes once done
+            let cued = &mut self.delay.dry[..chunk.len()];
+            cued.fill(0.);
+            for bank in self.banks.iter_mut() {
+                bank.read_cue(&mut self.fs, cued, channels, sample_rate)
+                    .await?;
+            }
+            if let Some(cue) = self.cue.filter(|v| (v + 1) * 2 <= channels) {
+                for (frame, cued) in chunk
+                    .chunks_exact_mut(channels)
+                    .zip(cued.chunks_exact(channels))
+                {
+                    frame[cue * 2] += cued[cue * 2];
+                    frame[cue * 2 + 1] += cued[cue * 2 + 1];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn tick(&mut self) -> Result<(), Error<F::Error>> {
+        for bank in self.banks.iter_mut() {
+            bank.tick(&mut self.rand, &mut self.fs).await?;
+        }
+        self.rehearsal.tick();
+        self.click.tick();
+        Ok(())
+    }
+
+    /// save performance parameters of every bank as scene `name` in its bank
+    pub fn capture_scene(&mut self, name: &str) {
+        for bank in self.banks.iter_mut() {
+            bank.capture_scene(name.into());
+        }
+    }
+
+    /// interpolate every bank saving scene `name` toward it over `steps`, at
+    /// once at 0; count of banks recalling
+    pub fn recall_scene(&mut self, name: &str, steps: u16) -> usize {
+        self.banks
+            .iter_mut()
+            .map(|v| v.recall_scene(name, steps) as usize)
+            .sum()
+    }
+
+    /// onset files held open across banks; a file handler should count as
+    /// many open between calls
+    pub fn open_onsets(&self) -> usize {
+        self.banks.iter().map(|v| v.open_onsets()).sum()
+    }
+
+    /// cache `blocks` of `BLOCK_LEN` bytes per bank ahead of its
+    /// playhead, up to `MAX_PREFETCH`; allocates, so call outside audio
+    /// callback
+    pub fn assign_prefetch(&mut self, blocks: usize) {
+        for bank in self.banks.iter_mut() {
+            bank.grain.cache.resize(blocks);
+        }
+    }
+
+    /// set grain length of every bank from control `abs` in 0..=1, halving
+    /// from MAX_GRAIN_LEN at 1 to an eighth of it at 0; shorter grains answer
+    /// seeks sooner but crossfade more often
+    pub fn assign_texture(&mut self, abs: f32) {
+        let halvings = ((1. - abs.clamp(0., 1.)) * 3.).round() as u32;
+        for bank in self.banks.iter_mut() {
+            bank.grain_len = MAX_GRAIN_LEN >> halvings;
+        }
+    }
+
+    /// hold recently triggered onsets in `bytes` of ram per bank, least
+    /// recently used evicted first, each in at most a quarter of it; off at 0,
+    /// as by default. allocates, so call outside audio callback
+    pub fn assign_sample_cache(&mut self, bytes: usize) {
+        for bank in self.banks.iter_mut() {
+            bank.grain.cache.samples = SampleCache::new(bytes);
+        }
+    }
+
+    /// capture up to `len` frames of master output for rehearsal loops; off at
+    /// 0, as by default. allocates, so call outside audio callback
+    pub fn assign_rehearsal(&mut self, len: usize) {
+        self.rehearsal.resize(len);
+    }
+
+    /// ring onsets displaced by fresh hits out in `count` voices per bank, up
+    /// to MAX_VOICES; monophonic at 0, as by default. allocates, so call
+    /// outside audio callback
+    pub async fn assign_voices(&mut self, count: usize) -> Result<(), Error<F::Error>> {
         for bank in self.banks.iter_mut() {
-            bank.read_attenuated(&mut self.fs, buffer, channels, sample_rate)?;
+            bank.assign_voices(count, &mut self.fs).await?;
         }
         Ok(())
     }
 
-    pub fn tick(&mut self) -> Result<(), Error<F::Error>> {
+    /// route pre-listen of cueing banks to output pair `cue` counted from 0,
+    /// over whatever else it carries, allocating a preview voice per bank; off
+    /// and freed if none, as by default. allocates, so call outside audio
+    /// callback
+    pub async fn assign_cue(&mut self, cue: Option<usize>) -> Result<(), Error<F::Error>> {
         for bank in self.banks.iter_mut() {
-            bank.tick(&mut self.rand, &mut self.fs)?;
+            bank.assign_preview(cue.is_some(), &mut self.fs).await?;
         }
+        self.cue = cue;
         Ok(())
     }
 
+    /// allocate a voice per bank for ended onsets to ring out in over its
+    /// `tail_len`, or free them; off by default. allocates, so call outside
+    /// audio callback
+    pub async fn assign_tails(&mut self, enabled: bool) -> Result<(), Error<F::Error>> {
+        for bank in self.banks.iter_mut() {
+            bank.assign_tail(enabled, &mut self.fs).await?;
+        }
+        Ok(())
+    }
+
+    /// grain reads across banks that missed prefetch and waited on storage
+    pub fn prefetch_misses(&self) -> usize {
+        self.banks.iter().map(|v| v.grain.cache.misses).sum()
+    }
+
     pub fn stop(&mut self) {
         for bank in self.banks.iter_mut() {
             bank.stop();
         }
+        self.rehearsal.stop();
+        self.click.stop();
+    }
+
+    /// jump to `step` steps from song start, as on a song position pointer,
+    /// so the next tick plays it
+    pub fn locate(&mut self, step: u32) {
+        for bank in self.banks.iter_mut() {
+            bank.locate(step);
+        }
+        self.rehearsal.locate(step);
+        self.click.locate(step);
     }
 
     pub fn assign_tempo(&mut self, tempo: f32) {
         for bank in self.banks.iter_mut() {
             bank.tempo = tempo;
         }
+        self.delay.tempo = tempo;
+    }
+}
+
+#[cfg(all(test, feature = "sync"))]
+mod tests {
+    use super::*;
+
+    /// no files at all; nothing ticked here opens any
+    struct NoFs;
+
+    impl embedded_io::ErrorType for NoFs {
+        type Error = core::convert::Infallible;
+    }
+
+    impl crate::FileHandler for NoFs {
+        type File = ();
+
+        fn open(&mut self, _: &str) -> Result<(), Self::Error> {
+            unreachable!()
+        }
+
+        fn try_clone(&mut self, _: &()) -> Result<(), Self::Error> {
+            unreachable!()
+        }
+
+        fn close(&mut self, _: &()) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn read(&mut self, _: &mut (), _: &mut [u8]) -> Result<usize, Self::Error> {
+            unreachable!()
+        }
+
+        fn seek(&mut self, _: &mut (), _: embedded_io::SeekFrom) -> Result<u64, Self::Error> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn trim_empty_record_then_tick() {
+        let limits = Limits {
+            phrase_len: 16,
+            phrase_count: 4,
+        };
+        let mut system = SystemHandler::<1, 8, 16, 4, _, _>::new(6, Wyrand::seed(0), NoFs, limits);
+        system.banks[0].trim_record(4, &mut system.fs).unwrap();
+        for _ in 0..24 {
+            system.tick().unwrap();
+        }
+        assert!(system.banks[0].activity().is_none());
     }
 }
@@ -3,13 +3,19 @@
 #[cfg(not(feature = "std"))]
 #[allow(unused_imports)]
 use micromath::F32Ext;
+use crate::{pads, Error, Fs};
 use tinyrand::Rand;
 
 extern crate alloc;
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// onsets of a wav in its samples; parsed from json rd with `from_json`
+#[derive(Clone, serde::Serialize)]
 pub struct Rd {
     pub steps: Option<u16>,
+    /// sample rate onsets are counted at, written so other tools needn't
+    /// assume one; the wav's own if none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate: Option<u32>,
     pub onsets: alloc::vec::Vec<u64>,
 }
 
@@ -17,23 +23,147 @@ impl Default for Rd {
     fn default() -> Self {
         Self {
             steps: None,
+            rate: None,
             onsets: alloc::vec![0],
         }
     }
 }
 
+/// units of onsets in a json rd
+#[derive(Copy, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Units {
+    /// samples at the rd's `rate`, else the wav's own
+    #[default]
+    Samples,
+    /// seconds from the start of the wav
+    Seconds,
+}
+
+/// json rd as written, e.g. by third-party tools
+#[derive(serde::Deserialize)]
+struct RdFile {
+    steps: Option<u16>,
+    #[serde(default)]
+    units: Units,
+    #[serde(default)]
+    rate: Option<u32>,
+    onsets: alloc::vec::Vec<f64>,
+}
+
+#[maybe_async::maybe_async]
+impl Rd {
+    /// parse json rd, converting onsets to samples of a wav at `rate`
+    pub fn from_json(bytes: &[u8], rate: u32) -> Result<Self, serde_json::Error> {
+        let file: RdFile = serde_json::from_slice(bytes)?;
+        let scale = match file.units {
+            Units::Samples => rate as f64 / file.rate.unwrap_or(rate) as f64,
+            Units::Seconds => rate as f64,
+        };
+        // round to nearest, negative onsets saturating to 0
+        let mut onsets: alloc::vec::Vec<u64> = file
+            .onsets
+            .iter()
+            .map(|v| (v * scale + 0.5) as u64)
+            .collect();
+        onsets.sort_unstable();
+        onsets.dedup();
+        if onsets.is_empty() {
+            onsets.push(0);
+        }
+        Ok(Self {
+            steps: file.steps,
+            rate: Some(rate),
+            onsets,
+        })
+    }
+
+    /// insert onset at sample `start` in order, returning its index
+    pub fn insert(&mut self, start: u64) -> usize {
+        let index = self.onsets.partition_point(|&v| v < start);
+        if self.onsets.get(index) != Some(&start) {
+            self.onsets.insert(index, start);
+        }
+        index
+    }
+
+    /// remove onset at `index` unless it is the last left, returning its
+    /// start
+    pub fn remove(&mut self, index: usize) -> Option<u64> {
+        (self.onsets.len() > 1 && index < self.onsets.len()).then(|| self.onsets.remove(index))
+    }
+
+    /// move onset at `index` by `delta` samples, short of its neighbours,
+    /// returning its new start
+    pub fn nudge(&mut self, index: usize, delta: i64) -> Option<u64> {
+        let min = index
+            .checked_sub(1)
+            .and_then(|v| self.onsets.get(v))
+            .map_or(0, |v| v + 1);
+        let max = self
+            .onsets
+            .get(index + 1)
+            .map_or(u64::MAX, |v| v.saturating_sub(1));
+        let onset = self.onsets.get_mut(index)?;
+        *onset = onset.saturating_add_signed(delta).max(min).min(max);
+        Some(*onset)
+    }
+
+    /// write as json to `path`, replacing any existing rd
+    pub async fn save<F: Fs>(&self, path: &str, fs: &mut F) -> Result<(), Error<F::Error>> {
+        let bytes = serde_json::to_vec(self).map_err(|_| Error::BadFormat)?;
+        let mut file = fs.create(path).await?;
+        let written = match fs.write_all(&mut file, &bytes).await {
+            Ok(()) => fs.flush(&mut file).await,
+            Err(e) => Err(e),
+        };
+        fs.close(&file).await?;
+        written
+    }
+}
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Wav {
     pub steps: Option<u16>,
     pub path: alloc::string::String,
 }
 
+#[maybe_async::maybe_async]
+impl Wav {
+    async fn header<F: Fs>(&self, fs: &mut F) -> Result<(u64, u64, u32), Error<F::Error>> {
+        let mut file = fs.open(&self.path).await?;
+        let header = pads::read_header(&mut file, fs).await;
+        fs.close(&file).await?;
+        header
+    }
+
+    /// pcm length in samples, read from the wav header
+    pub async fn samples<F: Fs>(&self, fs: &mut F) -> Result<u64, Error<F::Error>> {
+        Ok(self.header(fs).await?.1 / 2)
+    }
+
+    /// sample rate, read from the wav header
+    pub async fn rate<F: Fs>(&self, fs: &mut F) -> Result<u32, Error<F::Error>> {
+        Ok(self.header(fs).await?.2)
+    }
+}
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Onset {
     pub wav: Wav,
     pub start: u64,
+    /// loudness trim multiplier
+    #[serde(default = "unity")]
+    pub gain: f32,
+    /// fixed transposition in semitones, if any
+    #[serde(default)]
+    pub pitch: Option<f32>,
 }
 
+/// loop length of a step, loops being counted in 1/256 steps
+pub const LOOP_STEP: u16 = 256;
+
+/// pad event; loop `len` in 1/256 steps, so odd and fractional loops hold
 #[derive(Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Event {
     Sync,
@@ -41,30 +171,252 @@ pub enum Event {
     Loop { index: u8, len: u16 },
 }
 
+/// named sequence of phrase pad indices
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Pool {
+    pub name: alloc::string::String,
+    pub phrases: alloc::vec::Vec<u8>,
+}
+
+/// playback quantization of recorded micro-timing
+#[derive(Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Quantize {
+    #[default]
+    Off,
+    Step,
+    HalfStep,
+}
+
+impl Quantize {
+    /// snap delay in 1/256 steps; 256 fires on the next step
+    pub(crate) fn snap(self, delay: u8) -> u16 {
+        match self {
+            Quantize::Off => delay as u16,
+            Quantize::Step => {
+                if delay < 128 {
+                    0
+                } else {
+                    256
+                }
+            }
+            Quantize::HalfStep => (delay as u16 + 64) / 128 * 128,
+        }
+    }
+}
+
+/// phrase playback rate against the clock
+#[derive(Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Rate {
+    /// halftime, each step held over two clock steps
+    Half,
+    #[default]
+    Normal,
+    /// double time, two steps a clock step, the second halfway through
+    Double,
+}
+
+impl Rate {
+    /// delay in 1/256 clock steps of an event `delay` 1/256 phrase steps late;
+    /// `second` whether the later of a double time pair. 256 fires on the next
+    /// clock step
+    pub(crate) fn place(self, delay: u16, second: bool) -> u16 {
+        match self {
+            Rate::Half => (delay * 2).min(256),
+            Rate::Normal => delay,
+            Rate::Double => delay / 2 + 128 * second as u16,
+        }
+    }
+}
+
+/// per-step timing and velocity offsets applied over phrase playback
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Groove {
+    pub steps: alloc::vec::Vec<GrooveStep>,
+}
+
+#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GrooveStep {
+    /// lateness of event after step in 1/256 steps
+    #[serde(default)]
+    pub delay: u8,
+    /// gain multiplier of event
+    #[serde(default = "unity")]
+    pub velocity: f32,
+}
+
+fn unity() -> f32 {
+    1.
+}
+
+impl Groove {
+    /// groove step at phrase `step_index`, wrapping, if any
+    pub(crate) fn step(&self, step_index: u16) -> Option<GrooveStep> {
+        if self.steps.is_empty() {
+            None
+        } else {
+            Some(self.steps[step_index as usize % self.steps.len()])
+        }
+    }
+}
+
 #[derive(Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub(crate) struct Step {
     pub event: Option<Event>,
+    /// absolute direction, as heard when recorded
     pub reverse: bool,
+    /// micro-timing of event after step in 1/256 steps
+    #[serde(default)]
+    pub delay: u8,
+    /// exempt from phrase drift
+    #[serde(default)]
+    pub lock: bool,
+}
+
+/// what a sequence does once a phrase completes
+#[derive(Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Follow {
+    /// next sequenced phrase, subject to drift
+    #[default]
+    Next,
+    /// random sequenced phrase
+    Random,
+    /// phrase at pad index, if any; next otherwise
+    Jump(u8),
+    /// stop bank until sequence next changes
+    Stop,
+    /// continue from start of phrase at pad index, split from the same long
+    /// take; next otherwise
+    Chain(u8),
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(from = "SavedPhrase")]
 pub struct Phrase<const STEPS: usize> {
-    #[serde(with = "serde_arrays")]
+    #[serde(serialize_with = "crate::bd::seq")]
     pub(crate) steps: [Step; STEPS],
     pub(crate) len: u16,
+    #[serde(default)]
+    pub quantize: Quantize,
+    #[serde(default)]
+    pub follow: Follow,
+    #[serde(default)]
+    pub rate: Rate,
+}
+
+/// phrase as saved, of whatever capacity it was saved with
+#[derive(serde::Deserialize)]
+pub(crate) struct SavedPhrase {
+    steps: alloc::vec::Vec<Step>,
+    len: u16,
+    #[serde(default)]
+    quantize: Quantize,
+    #[serde(default)]
+    follow: Follow,
+    #[serde(default)]
+    rate: Rate,
+}
+
+impl SavedPhrase {
+    /// steps in phrase as saved
+    pub(crate) fn len(&self) -> usize {
+        self.steps.len().min(self.len as usize)
+    }
+
+    /// scale loop lengths saved in whole steps to 1/256 steps
+    pub(crate) fn scale_loops(&mut self) {
+        for step in self.steps.iter_mut() {
+            if let Some(Event::Loop { len, .. }) = &mut step.event {
+                *len = len.saturating_mul(LOOP_STEP);
+            }
+        }
+    }
+}
+
+impl<const STEPS: usize> From<SavedPhrase> for Phrase<STEPS> {
+    /// keep opening steps of phrases saved longer than capacity
+    fn from(saved: SavedPhrase) -> Self {
+        let active = &saved.steps[saved.steps.len().saturating_sub(saved.len as usize)..];
+        let len = active.len().min(STEPS);
+        let mut steps = [Step::default(); STEPS];
+        steps[STEPS - len..].copy_from_slice(&active[..len]);
+        Self {
+            steps,
+            len: len as u16,
+            quantize: saved.quantize,
+            follow: saved.follow,
+            rate: saved.rate,
+        }
+    }
 }
 
 impl<const STEPS: usize> Phrase<STEPS> {
+    /// source index of step `offset` steps after `step_index`
+    fn index(&self, step_index: u16, offset: usize) -> usize {
+        STEPS - self.len as usize + (step_index as usize + offset) % self.len as usize
+    }
+
+    /// locked steps neither drift nor get drifted into; returns step and
+    /// whether it was substituted by drift
     pub(crate) fn generate_step(
         &self,
         step_index: u16,
-        phrase_drift: f32,
+        phrase_drift: &mut pads::Drift,
         rand: &mut impl Rand,
-    ) -> Step {
-        let drift = phrase_drift * self.len as f32;
-        let drift = rand.next_lim_usize(drift as usize + 1)
-            + rand.next_bool(tinyrand::Probability::new(drift.fract() as f64)) as usize;
-        let index = STEPS - self.len as usize + (step_index as usize + drift) % self.len as usize;
-        self.steps[index]
+    ) -> (Step, bool) {
+        let base_index = self.index(step_index, 0);
+        let base = self.steps[base_index];
+        if base.lock {
+            return (base, false);
+        }
+        let drift = phrase_drift.generate(self.len as usize, rand);
+        let index = self.index(step_index, drift);
+        let step = self.steps[index];
+        if step.lock {
+            (base, false)
+        } else {
+            (step, index != base_index)
+        }
+    }
+
+    /// phrase of the `len` step span holding `step_index`, opening with the
+    /// event sounding at its start
+    pub(crate) fn excerpt(&self, step_index: u16, len: u16) -> Self {
+        let len = len.clamp(1, self.len);
+        let start = step_index % self.len / len * len;
+        let mut steps = [Step::default(); STEPS];
+        let first = STEPS - len as usize;
+        for (offset, step) in steps[first..].iter_mut().enumerate() {
+            *step = self.steps[self.index(start, offset)];
+        }
+        if steps[first].event.is_none() {
+            // carry event from before span
+            steps[first].event = (1..self.len as usize)
+                .map(|back| self.steps[self.index(start, self.len as usize - back)])
+                .find_map(|v| v.event);
+        }
+        Self {
+            steps,
+            len,
+            quantize: self.quantize,
+            follow: Follow::Next,
+            rate: self.rate,
+        }
+    }
+
+    /// keep only opening `len` steps, if longer
+    pub(crate) fn truncate(&mut self, len: u16) {
+        if self.len > len {
+            let first = STEPS - self.len as usize;
+            self.steps
+                .copy_within(first..first + len as usize, STEPS - len as usize);
+            self.len = len;
+        }
+    }
+
+    /// toggle drift lock of step at `step_index`, returning new lock
+    pub(crate) fn toggle_lock(&mut self, step_index: u16) -> bool {
+        let step = &mut self.steps[self.index(step_index, 0)];
+        step.lock = !step.lock;
+        step.lock
     }
 }
@@ -0,0 +1,159 @@
+//! read-ahead cache of pcm blocks, so grain reads at audio rate hit memory
+//! rather than stalling on storage
+
+extern crate alloc;
+
+use crate::{active, samples::SampleCache, Fs};
+use embedded_io::SeekFrom;
+
+/// cached block length in bytes; one sd card sector
+pub const BLOCK_LEN: usize = 512;
+/// blocks cached per bank unless assigned otherwise; ~4 ticks of mono 48 khz
+/// at 120 bpm
+pub const DEFAULT_PREFETCH: usize = 16;
+/// most blocks cached per bank
+pub const MAX_PREFETCH: usize = 256;
+
+/// identify wav at `path` across triggers, so retriggered slices of one wav
+/// keep its blocks; fnv-1a
+pub(crate) fn wav_id(path: &str) -> u64 {
+    path.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// direct-mapped cache of the wav a bank plays, each block in slot of its
+/// index modulo slot count, filled ahead of the playhead on tick
+pub(crate) struct Prefetch {
+    blocks: alloc::boxed::Box<[[u8; BLOCK_LEN]]>,
+    /// block index held by each slot, if any
+    tags: alloc::boxed::Box<[Option<u64>]>,
+    /// wav whose blocks are held
+    id: u64,
+    /// blocks read at audio rate for lack of prefetch
+    pub misses: usize,
+    /// recent onsets in ram, read before storage
+    pub samples: SampleCache,
+}
+
+#[maybe_async::maybe_async]
+impl Prefetch {
+    /// allocates; `len` in blocks, clamped to 1..=MAX_PREFETCH
+    pub fn new(len: usize) -> Self {
+        let len = len.clamp(1, MAX_PREFETCH);
+        Self {
+            blocks: alloc::vec![[0; BLOCK_LEN]; len].into_boxed_slice(),
+            tags: alloc::vec![None; len].into_boxed_slice(),
+            id: 0,
+            misses: 0,
+            samples: SampleCache::new(0),
+        }
+    }
+
+    /// reallocate for `len` blocks, dropping those held
+    pub fn resize(&mut self, len: usize) {
+        let len = len.clamp(1, MAX_PREFETCH);
+        self.blocks = alloc::vec![[0; BLOCK_LEN]; len].into_boxed_slice();
+        self.tags = alloc::vec![None; len].into_boxed_slice();
+    }
+
+    fn slot(&self, index: u64) -> usize {
+        (index % self.tags.len() as u64) as usize
+    }
+
+    /// read block `index` of `wav` into its slot unless held, from sample
+    /// cache if there, dropping blocks of any other wav first; slot, and
+    /// whether read from storage
+    async fn load<F: Fs>(
+        &mut self,
+        index: u64,
+        wav: &mut active::Wav<F>,
+        fs: &mut F,
+    ) -> Result<(usize, bool), F::Error> {
+        if self.id != wav.id {
+            self.tags.fill(None);
+            self.id = wav.id;
+        }
+        let slot = self.slot(index);
+        if self.tags[slot] == Some(index) {
+            return Ok((slot, false));
+        }
+        // untag first, so a failed read leaves no stale block
+        self.tags[slot] = None;
+        let page = self.samples.page(index, wav);
+        if let Some(page) = page.filter(|v| self.samples.filled(*v)) {
+            self.blocks[slot].copy_from_slice(self.samples.read(page));
+            self.tags[slot] = Some(index);
+            return Ok((slot, false));
+        }
+        let start = index * BLOCK_LEN as u64;
+        let len = (wav.pcm_len.saturating_sub(start) as usize).min(BLOCK_LEN);
+        fs.seek(&mut wav.file, SeekFrom::Start(wav.pcm_start + start))
+            .await?;
+        let block = &mut self.blocks[slot];
+        let mut filled = 0;
+        while filled < len {
+            let n = fs.read(&mut wav.file, &mut block[filled..len]).await?;
+            if n == 0 {
+                // pcm shorter than header claims; pad with silence
+                break;
+            }
+            filled += n;
+        }
+        block[filled..].fill(0);
+        if let Some(page) = page {
+            self.samples.fill(page, block);
+        }
+        self.tags[slot] = Some(index);
+        Ok((slot, true))
+    }
+
+    /// read from playhead of `wav` up to end of its block or pcm, advancing
+    /// playhead; 0 at end of pcm
+    pub async fn read<F: Fs>(
+        &mut self,
+        wav: &mut active::Wav<F>,
+        bytes: &mut [u8],
+        fs: &mut F,
+    ) -> Result<usize, F::Error> {
+        if wav.pos >= wav.pcm_len {
+            return Ok(0);
+        }
+        let index = wav.pos / BLOCK_LEN as u64;
+        let (slot, stored) = self.load(index, wav, fs).await?;
+        if stored {
+            self.misses += 1;
+        }
+        let offset = (wav.pos % BLOCK_LEN as u64) as usize;
+        let len = bytes
+            .len()
+            .min(BLOCK_LEN - offset)
+            .min((wav.pcm_len - wav.pos) as usize);
+        bytes[..len].copy_from_slice(&self.blocks[slot][offset..offset + len]);
+        wav.pos += len as u64;
+        Ok(len)
+    }
+
+    /// load as many blocks of `wav` as fit from a block behind its playhead,
+    /// where crossfades read back from, on in direction of play, wrapping at
+    /// its ends as grain reads do
+    pub async fn prefetch<F: Fs>(
+        &mut self,
+        wav: &mut active::Wav<F>,
+        reverse: bool,
+        fs: &mut F,
+    ) -> Result<(), F::Error> {
+        let count = wav.pcm_len.div_ceil(BLOCK_LEN as u64) as i64;
+        let head = (wav.pos / BLOCK_LEN as u64) as i64;
+        // farthest first, so nearer blocks win slots they share once wrapped
+        for step in (0..(self.tags.len() as i64).min(count)).rev() {
+            let index = if reverse {
+                head + 1 - step
+            } else {
+                head - 1 + step
+            };
+            self.load(index.rem_euclid(count) as u64, wav, fs).await?;
+        }
+        Ok(())
+    }
+}
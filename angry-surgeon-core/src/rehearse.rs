@@ -0,0 +1,180 @@
+//! rehearsal loop: the last bars of master output captured to ram, looped
+//! slower without repitching to practice transitions against
+
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use micromath::F32Ext;
+
+/// steps per bar
+const BAR_LEN: u32 = 16;
+/// most bars looped
+pub const MAX_REHEARSAL_BARS: usize = 16;
+/// stretch grain length in frames; grains overlap by half
+const GRAIN_LEN: usize = 2048;
+
+/// overlap-add read of the loop region at unity rate, restarted at the
+/// stretched playhead each grain
+#[derive(Copy, Clone, Default)]
+struct Grain {
+    /// region offset in frames
+    start: f32,
+    /// frames into grain
+    phase: usize,
+}
+
+pub struct Rehearsal {
+    /// bars looped, up to `MAX_REHEARSAL_BARS`
+    pub bars: u16,
+    /// loop speed; below 1 slows without repitching
+    pub speed: f32,
+    ticks_per_bar: u32,
+    ticks: u32,
+    /// captured stereo mix of every output pair
+    ring: alloc::boxed::Box<[[f32; 2]]>,
+    /// frames captured since cleared
+    written: u64,
+    /// frames captured at each of latest bar lines, oldest first
+    marks: [Option<u64>; MAX_REHEARSAL_BARS + 1],
+    /// looped ring region as start and length in frames, if looping
+    region: Option<(u64, u64)>,
+    /// stretched playhead into region in frames
+    pos: f32,
+    grains: [Grain; 2],
+}
+
+impl Rehearsal {
+    pub(crate) fn new(ticks_per_step: u16) -> Self {
+        Self {
+            bars: 4,
+            speed: 0.5,
+            ticks_per_bar: ticks_per_step as u32 * BAR_LEN,
+            ticks: 0,
+            ring: alloc::boxed::Box::new([]),
+            written: 0,
+            marks: [None; MAX_REHEARSAL_BARS + 1],
+            region: None,
+            pos: 0.,
+            grains: [Grain::default(); 2],
+        }
+    }
+
+    /// capture up to `len` frames; allocates, so call outside audio callback.
+    /// off at 0, as by default
+    pub(crate) fn resize(&mut self, len: usize) {
+        self.ring = alloc::vec![[0.; 2]; len].into_boxed_slice();
+        self.release();
+    }
+
+    /// mark bar lines while capturing
+    pub(crate) fn tick(&mut self) {
+        if self.ticks.is_multiple_of(self.ticks_per_bar) && self.region.is_none() {
+            self.marks.rotate_left(1);
+            self.marks[MAX_REHEARSAL_BARS] = Some(self.written);
+        }
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    /// realign bar lines with clock restart
+    pub(crate) fn stop(&mut self) {
+        self.ticks = 0;
+    }
+
+    /// realign bar lines with clock jump to `ticks`
+    pub(crate) fn locate(&mut self, ticks: u32) {
+        self.ticks = ticks;
+    }
+
+    /// loop last `bars` whole bars captured, between bar lines; whether
+    /// captured long enough to
+    pub fn loop_last(&mut self) -> bool {
+        let bars = (self.bars as usize).clamp(1, MAX_REHEARSAL_BARS);
+        let (Some(start), Some(end)) = (
+            self.marks[MAX_REHEARSAL_BARS - bars],
+            self.marks[MAX_REHEARSAL_BARS],
+        ) else {
+            return false;
+        };
+        let len = end - start;
+        if len == 0 || self.written - start > self.ring.len() as u64 {
+            return false;
+        }
+        self.region = Some((start, len));
+        self.pos = 0.;
+        // second grain halfway through the tail leading into loop start
+        self.grains = [
+            Grain {
+                start: 0.,
+                phase: 0,
+            },
+            Grain {
+                start: (len as f32 - (GRAIN_LEN / 2) as f32).rem_euclid(len as f32),
+                phase: GRAIN_LEN / 2,
+            },
+        ];
+        true
+    }
+
+    /// stop looping, capturing afresh
+    pub fn release(&mut self) {
+        self.region = None;
+        self.written = 0;
+        self.marks = [None; MAX_REHEARSAL_BARS + 1];
+    }
+
+    pub fn looping(&self) -> bool {
+        self.region.is_some()
+    }
+
+    /// capture interleaved `buffer` of `channels`, or mix loop into every
+    /// pair of it while looping
+    pub(crate) fn process(&mut self, buffer: &mut [f32], channels: usize) {
+        if self.ring.is_empty() {
+            return;
+        }
+        for frame in buffer.chunks_exact_mut(channels) {
+            if let Some((start, len)) = self.region {
+                let [l, r] = self.next(start, len);
+                for pair in frame.chunks_exact_mut(2) {
+                    pair[0] += l;
+                    pair[1] += r;
+                }
+            } else {
+                let mut mix = [0.; 2];
+                for pair in frame.chunks_exact(2) {
+                    mix[0] += pair[0];
+                    mix[1] += pair[1];
+                }
+                let len = self.ring.len() as u64;
+                self.ring[(self.written % len) as usize] = mix;
+                self.written += 1;
+            }
+        }
+    }
+
+    /// next stretched frame of region at `start` of `len` frames
+    fn next(&mut self, start: u64, len: u64) -> [f32; 2] {
+        let ring_len = self.ring.len() as u64;
+        let mut out = [0.; 2];
+        for grain in self.grains.iter_mut() {
+            if grain.phase >= GRAIN_LEN {
+                grain.phase = 0;
+                grain.start = self.pos;
+            }
+            // hann windows at half overlap sum to unity
+            let window = 0.5
+                - 0.5 * f32::cos(core::f32::consts::TAU * grain.phase as f32 / GRAIN_LEN as f32);
+            let offset = (grain.start + grain.phase as f32).rem_euclid(len as f32);
+            let (a, fract) = (offset as u64, offset.fract());
+            let frame_a = self.ring[((start + a % len) % ring_len) as usize];
+            let frame_b = self.ring[((start + (a + 1) % len) % ring_len) as usize];
+            for (out, (a, b)) in out.iter_mut().zip(frame_a.iter().zip(frame_b.iter())) {
+                *out += (a + (b - a) * fract) * window;
+            }
+            grain.phase += 1;
+        }
+        self.pos = (self.pos + self.speed.max(0.)).rem_euclid(len as f32);
+        out
+    }
+}
@@ -0,0 +1,154 @@
+//! lru cache of recently triggered onsets held in ram, so hammered pads stop
+//! rereading the same sectors
+
+extern crate alloc;
+
+use crate::{active, prefetch::BLOCK_LEN, Fs};
+
+/// onsets held at once
+const MAX_SAMPLES: usize = 32;
+/// inverse share of cache one onset may hold; longer onsets keep their heads
+const MAX_SHARE: u64 = 4;
+/// end of page chain
+const NONE: u32 = u32::MAX;
+
+#[derive(Copy, Clone)]
+struct Entry {
+    /// of wav path
+    id: u64,
+    /// block of onset start, first held
+    first: u64,
+    /// blocks held
+    len: u64,
+    /// first page of chain
+    head: u32,
+    /// clock at last use
+    used: u32,
+}
+
+/// pool of block-sized pages, chained per onset and claimed at first read, so
+/// caching never allocates; pages fill as their blocks are first read
+pub(crate) struct SampleCache {
+    pages: alloc::boxed::Box<[[u8; BLOCK_LEN]]>,
+    /// next page in chain of each page, whether of an onset or free
+    next: alloc::boxed::Box<[u32]>,
+    /// whether each page read from storage since claimed
+    filled: alloc::boxed::Box<[bool]>,
+    free: u32,
+    free_len: u64,
+    entries: [Option<Entry>; MAX_SAMPLES],
+    clock: u32,
+}
+
+impl SampleCache {
+    /// allocates; off at `bytes` below a block
+    pub fn new(bytes: usize) -> Self {
+        let len = (bytes / BLOCK_LEN).min(NONE as usize);
+        Self {
+            pages: alloc::vec![[0; BLOCK_LEN]; len].into_boxed_slice(),
+            next: (1..=len as u32)
+                .map(|v| if v < len as u32 { v } else { NONE })
+                .collect(),
+            filled: alloc::vec![false; len].into_boxed_slice(),
+            free: if len > 0 { 0 } else { NONE },
+            free_len: len as u64,
+            entries: [None; MAX_SAMPLES],
+            clock: 0,
+        }
+    }
+
+    /// page holding block `index` of onset playing `wav`, claiming pages for
+    /// the onset, least recently used evicted first, if it holds none; none
+    /// if off or block outside those held
+    pub fn page<F: Fs>(&mut self, index: u64, wav: &active::Wav<F>) -> Option<u32> {
+        let first = wav.origin / BLOCK_LEN as u64;
+        let slot = match self
+            .entries
+            .iter()
+            .position(|v| v.is_some_and(|v| v.id == wav.id && v.first == first))
+        {
+            Some(slot) => slot,
+            None => self.claim(first, wav)?,
+        };
+        self.clock = self.clock.wrapping_add(1);
+        let entry = self.entries[slot].as_mut()?;
+        entry.used = self.clock;
+        if index < entry.first || index >= entry.first + entry.len {
+            return None;
+        }
+        let mut page = entry.head;
+        for _ in entry.first..index {
+            page = self.next[page as usize];
+        }
+        Some(page)
+    }
+
+    /// whether `page` read from storage yet
+    pub fn filled(&self, page: u32) -> bool {
+        self.filled[page as usize]
+    }
+
+    pub fn read(&self, page: u32) -> &[u8; BLOCK_LEN] {
+        &self.pages[page as usize]
+    }
+
+    pub fn fill(&mut self, page: u32, block: &[u8; BLOCK_LEN]) {
+        self.pages[page as usize].copy_from_slice(block);
+        self.filled[page as usize] = true;
+    }
+
+    /// chain pages for blocks from `first` to end of `wav`, up to its share,
+    /// returning entry slot
+    fn claim<F: Fs>(&mut self, first: u64, wav: &active::Wav<F>) -> Option<usize> {
+        let len = (wav.pcm_len.div_ceil(BLOCK_LEN as u64))
+            .saturating_sub(first)
+            .min(self.pages.len() as u64 / MAX_SHARE);
+        if len == 0 {
+            return None;
+        }
+        while self.free_len < len || self.entries.iter().all(|v| v.is_some()) {
+            let lru = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| Some((i, v.as_ref()?.used)))
+                .min_by_key(|(_, used)| *used)?
+                .0;
+            self.evict(lru);
+        }
+        // unlink first `len` free pages, marking them unread
+        let head = self.free;
+        let mut tail = head;
+        self.filled[tail as usize] = false;
+        for _ in 1..len {
+            tail = self.next[tail as usize];
+            self.filled[tail as usize] = false;
+        }
+        self.free = self.next[tail as usize];
+        self.next[tail as usize] = NONE;
+        self.free_len -= len;
+        let slot = self.entries.iter().position(|v| v.is_none())?;
+        self.entries[slot] = Some(Entry {
+            id: wav.id,
+            first,
+            len,
+            head,
+            used: self.clock,
+        });
+        Some(slot)
+    }
+
+    /// return pages of entry at `slot` to free list
+    fn evict(&mut self, slot: usize) {
+        let Some(entry) = self.entries[slot].take() else {
+            return;
+        };
+        let mut tail = entry.head;
+        for _ in 1..entry.len {
+            tail = self.next[tail as usize];
+        }
+        self.next[tail as usize] = self.free;
+        self.free = entry.head;
+        self.free_len += entry.len;
+    }
+}
@@ -0,0 +1,76 @@
+//! named snapshots of performance parameters, recalled by interpolating toward
+//! them over a span of steps
+
+extern crate alloc;
+
+/// performance parameters of one bank
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub gain: f32,
+    /// pitch base, as set by the speed control
+    pub speed: f32,
+    /// pitch offset, as bent
+    pub pitch: f32,
+    /// loop division control in 0..=1
+    pub roll: f32,
+    pub kit_drift: f32,
+    pub phrase_drift: f32,
+    pub kit_index: u8,
+}
+
+impl Snapshot {
+    /// `t` of the way from `self` to `to`, kit switching halfway
+    pub fn lerp(&self, to: &Self, t: f32) -> Self {
+        let t = t.clamp(0., 1.);
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        Self {
+            gain: lerp(self.gain, to.gain),
+            speed: lerp(self.speed, to.speed),
+            pitch: lerp(self.pitch, to.pitch),
+            roll: lerp(self.roll, to.roll),
+            kit_drift: lerp(self.kit_drift, to.kit_drift),
+            phrase_drift: lerp(self.phrase_drift, to.phrase_drift),
+            kit_index: if t < 0.5 {
+                self.kit_index
+            } else {
+                to.kit_index
+            },
+        }
+    }
+}
+
+/// snapshot of a bank, named alike across banks captured together
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Scene {
+    pub name: alloc::string::String,
+    pub snapshot: Snapshot,
+}
+
+/// recall underway, from parameters as they stood to a scene's
+pub(crate) struct Recall {
+    from: Snapshot,
+    to: Snapshot,
+    /// steps interpolated so far
+    step: u16,
+    len: u16,
+}
+
+impl Recall {
+    pub(crate) fn new(from: Snapshot, to: Snapshot, len: u16) -> Self {
+        Self {
+            from,
+            to,
+            step: 0,
+            len,
+        }
+    }
+
+    /// parameters due this step, none once recalled
+    pub(crate) fn tick(&mut self) -> Option<Snapshot> {
+        if self.step >= self.len {
+            return None;
+        }
+        self.step += 1;
+        Some(self.from.lerp(&self.to, self.step as f32 / self.len as f32))
+    }
+}
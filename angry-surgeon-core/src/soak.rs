@@ -0,0 +1,61 @@
+//! unattended random play for long-running stability tests, exercising the
+//! onset open, clone and close paths
+
+use crate::{
+    pads::SystemHandler,
+    passive::{Event, LOOP_STEP},
+    Error, Fs,
+};
+use tinyrand::{Probability, Rand};
+
+/// random events and phrase changes pushed into every bank each step
+#[derive(Copy, Clone, PartialEq)]
+pub struct Soak {
+    /// chance per bank per step of a random action, in 0..=1
+    pub intensity: f32,
+}
+
+#[maybe_async::maybe_async]
+impl Soak {
+    /// roll for one step of random play over banks of `system`
+    pub async fn step<
+        const BANKS: usize,
+        const PADS: usize,
+        const STEPS: usize,
+        const PHRASES: usize,
+        R: Rand,
+        F: Fs,
+    >(
+        &self,
+        system: &mut SystemHandler<BANKS, PADS, STEPS, PHRASES, R, F>,
+    ) -> Result<(), Error<F::Error>> {
+        let rand = &mut system.rand;
+        let fs = &mut system.fs;
+        let chance = Probability::new(self.intensity.clamp(0., 1.) as f64);
+        for bank in system.banks.iter_mut() {
+            if !rand.next_bool(chance) {
+                continue;
+            }
+            let index = rand.next_lim_usize(PADS) as u8;
+            match rand.next_lim_usize(5) {
+                0 => bank.push_event(Event::Hold { index }, rand, fs).await?,
+                1 => {
+                    let len = (1 << rand.next_lim_usize(4)) * LOOP_STEP;
+                    bank.push_event(Event::Loop { index, len }, rand, fs)
+                        .await?
+                }
+                2 => bank.push_event(Event::Sync, rand, fs).await?,
+                3 => bank.push_reverse(rand.next_bool(Probability::new(0.5))),
+                _ => {
+                    // grow sequence by phrase at pad, if any; restart it otherwise
+                    if bank.bank.phrases[index as usize].is_some() {
+                        bank.push_sequence(index);
+                    } else {
+                        bank.clear_sequence(fs).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use angry_surgeon_core::{Limits, MotionTarget};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
 /// in hz
 pub const SAMPLE_RATE: u32 = 48000;
 pub const STEP_DIV: u16 = 4;
@@ -5,10 +8,23 @@ pub const BANK_COUNT: usize = 2;
 pub const PAD_COUNT: usize = 8;
 pub const MAX_PHRASE_LEN: usize = 2usize.pow(PAD_COUNT as u32 - 1);
 pub const MAX_PHRASE_COUNT: usize = 64;
+/// runtime phrase caps; soft, as storage is sized by the maxima above
+pub const PHRASE_LIMITS: Limits = Limits {
+    phrase_len: MAX_PHRASE_LEN as u16,
+    phrase_count: MAX_PHRASE_COUNT,
+};
+/// bytes of heap per bank holding recently triggered onsets, sparing the sd
+/// card rereads of hammered pads
+pub const SAMPLE_CACHE_LEN: usize = 32 * 1024;
 
 /// pulses per quarter
 pub const PPQ: u16 = 2;
 
+/// codec word size
+pub const BIT_DEPTH: angry_surgeon_core::BitDepth = angry_surgeon_core::BitDepth::TwentyFour;
+/// whether output is tpdf dithered before quantizing
+pub const DITHER: bool = true;
+
 #[repr(u8)]
 #[derive(Copy, Clone)]
 pub enum Bank {
@@ -30,3 +46,134 @@ pub type SystemHandler = angry_surgeon_core::SystemHandler<
     crate::fs::FileHandler,
     tinyrand::Wyrand,
 >;
+
+/// unset parameter sentinel
+const UNSET: u32 = f32::NAN.to_bits();
+
+/// f32 parameter mailbox shared lock-free between tasks; writes coalesce until
+/// taken
+pub struct Param(AtomicU32);
+
+impl Param {
+    const fn new() -> Self {
+        Self(AtomicU32::new(UNSET))
+    }
+
+    pub fn write(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// latest value written since last take, if any
+    fn take(&self) -> Option<f32> {
+        let value = f32::from_bits(self.0.swap(UNSET, Ordering::Relaxed));
+        (!value.is_nan()).then_some(value)
+    }
+}
+
+/// per-bank continuous parameters written by controls outside the system lock
+pub struct BankParams {
+    pub gain: Param,
+    pub width: Param,
+    pub speed: Param,
+    pub speed_offset: Param,
+    pub roll: Param,
+    pub loop_div_offset: Param,
+    pub kit_drift: Param,
+    pub phrase_drift: Param,
+    /// bipolar; low-pass below center, high-pass above
+    pub filter_sweep: Param,
+    pub resonance: Param,
+    /// whether knob writes record into motion loops
+    pub motion: AtomicBool,
+}
+
+impl BankParams {
+    const fn new() -> Self {
+        Self {
+            gain: Param::new(),
+            width: Param::new(),
+            speed: Param::new(),
+            speed_offset: Param::new(),
+            roll: Param::new(),
+            loop_div_offset: Param::new(),
+            kit_drift: Param::new(),
+            phrase_drift: Param::new(),
+            filter_sweep: Param::new(),
+            resonance: Param::new(),
+            motion: AtomicBool::new(false),
+        }
+    }
+}
+
+pub static PARAMS: [BankParams; BANK_COUNT] = [BankParams::new(), BankParams::new()];
+
+/// grain length from shortest at 0 to longest at 1
+pub static TEXTURE: Param = Param::new();
+
+/// live input passed straight to output while mangler is muted
+pub struct Dry {
+    pub enabled: AtomicBool,
+    pub level: Param,
+}
+
+impl Dry {
+    /// level taken since last call, if any
+    pub fn take_level(&self) -> Option<f32> {
+        self.level.take()
+    }
+}
+
+pub static DRY: Dry = Dry {
+    enabled: AtomicBool::new(false),
+    level: Param::new(),
+};
+
+/// samples of live input held, kept to a second of sram
+const CAPTURE_LEN: usize = SAMPLE_RATE as usize;
+
+/// live input capture, served by the file handler at `fs::CAPTURE_PATH`
+pub static CAPTURE: angry_surgeon_core::Capture<CAPTURE_LEN> =
+    angry_surgeon_core::Capture::new(SAMPLE_RATE);
+
+/// output buffers not handed to dma in time
+pub static UNDERRUNS: AtomicU32 = AtomicU32::new(0);
+
+/// apply parameters written since last call; only audio render holds system
+/// lock for this
+pub fn apply_params(system: &mut SystemHandler) {
+    for (params, bank) in PARAMS.iter().zip(system.banks.iter_mut()) {
+        let motion = params.motion.load(Ordering::Relaxed);
+        for (param, target) in [
+            (&params.gain, MotionTarget::Gain),
+            (&params.width, MotionTarget::Width),
+            (&params.speed, MotionTarget::Pitch),
+            (&params.roll, MotionTarget::Roll),
+            (&params.kit_drift, MotionTarget::KitDrift),
+            (&params.phrase_drift, MotionTarget::PhraseDrift),
+            (&params.resonance, MotionTarget::Resonance),
+        ] {
+            if let Some(v) = param.take() {
+                if motion {
+                    bank.record_motion(target, v);
+                } else {
+                    bank.assign(target, v);
+                }
+            }
+        }
+        if !motion {
+            bank.stop_motion_record();
+        }
+        if let Some(v) = params.speed_offset.take() {
+            bank.speed.offset = v;
+        }
+        if let Some(v) = params.loop_div_offset.take() {
+            bank.loop_div.offset = v;
+        }
+        if let Some(v) = params.filter_sweep.take() {
+            bank.assign_filter_sweep(v);
+        }
+    }
+    if let Some(v) = TEXTURE.take() {
+        system.assign_texture(v);
+    }
+}
@@ -1,4 +1,6 @@
-use angry_surgeon_core::FileHandler as _;
+use alloc::{format, string::String, vec::Vec};
+use angry_surgeon_core::{DirEntry, FileHandler as _, CAPTURE_NAME};
+use core::cell::Cell;
 use embedded_io::{ErrorType, Seek, Write};
 use embedded_sdmmc::{BlockDevice, File, LfnBuffer, RawDirectory, RawFile, VolumeManager};
 
@@ -6,6 +8,8 @@ pub const MAX_DIRS: usize = 3; // root always open, 2 more for file search
 pub const MAX_FILES: usize = 5; // one for bd, 2 * 2 for active wavs
 pub const MAX_VOLUMES: usize = 1;
 const READER_LEN: usize = 512;
+/// path live input capture is served at, as onsets are referenced from banks
+pub const CAPTURE_PATH: &str = "onsets/live.wav";
 
 pub type FileHandler = SdmmcFileHandler<
     crate::hal::sdmmc::SdmmcBlockDevice<
@@ -13,9 +17,15 @@ pub type FileHandler = SdmmcFileHandler<
     >,
 >;
 
+/// file on card, or live input capture at byte position
+pub enum SdFile {
+    Raw(RawFile),
+    Capture(Cell<u64>),
+}
+
 pub struct BufReader<'a> {
     fs: &'a mut FileHandler,
-    file: RawFile,
+    file: &'a SdFile,
     buffer: [u8; READER_LEN],
     index: usize,
     rem: usize,
@@ -24,9 +34,12 @@ pub struct BufReader<'a> {
 impl<'a> BufReader<'a> {
     pub fn new(
         fs: &'a mut FileHandler,
-        file: RawFile,
+        file: &'a SdFile,
     ) -> Result<Self, <FileHandler as ErrorType>::Error> {
-        let rem = fs.vol_mgr.file_length(file)? as usize;
+        let rem = match file {
+            SdFile::Raw(file) => fs.vol_mgr.file_length(*file)? as usize,
+            SdFile::Capture(_) => crate::audio::CAPTURE.file_len() as usize,
+        };
         Ok(Self {
             fs,
             file,
@@ -46,7 +59,7 @@ impl<'a> BufReader<'a> {
             // refill buffer
             let mut slice = &mut self.buffer[..];
             while !slice.is_empty() {
-                match self.fs.read(&self.file, slice) {
+                match self.fs.read(self.file, slice) {
                     Ok(n) => {
                         if n == 0 {
                             break; // reached EOF, read partially filled buffer
@@ -84,6 +97,8 @@ impl embedded_sdmmc::TimeSource for TimeSource {
 pub struct SdmmcFileHandler<D: BlockDevice> {
     vol_mgr: VolumeManager<D, TimeSource, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
     root: RawDirectory,
+    /// handles opened less those closed
+    open_files: usize,
 }
 
 impl<D: BlockDevice> SdmmcFileHandler<D> {
@@ -92,18 +107,74 @@ impl<D: BlockDevice> SdmmcFileHandler<D> {
     ) -> Result<Self, embedded_sdmmc::Error<D::Error>> {
         let vol = vol_mgr.open_raw_volume(embedded_sdmmc::VolumeIdx(0))?;
         let root = vol_mgr.open_root_dir(vol)?;
-        Ok(Self { vol_mgr, root })
+        Ok(Self {
+            vol_mgr,
+            root,
+            open_files: 0,
+        })
     }
-}
 
-impl<D: BlockDevice> embedded_io::ErrorType for SdmmcFileHandler<D> {
-    type Error = embedded_sdmmc::Error<D::Error>;
+    /// handles opened and not yet closed
+    pub fn open_files(&self) -> usize {
+        self.open_files
+    }
 }
 
-impl<D: BlockDevice> angry_surgeon_core::FileHandler for SdmmcFileHandler<D> {
-    type File = embedded_sdmmc::RawFile;
+impl<D: BlockDevice> SdmmcFileHandler<D> {
+    /// open `name` in root by 8.3 name, creating or truncating it for write
+    pub fn create_short(&mut self, name: &str) -> Result<SdFile, embedded_sdmmc::Error<D::Error>> {
+        let file = self.vol_mgr.open_file_in_dir(
+            self.root,
+            name,
+            embedded_sdmmc::Mode::ReadWriteCreateOrTruncate,
+        )?;
+        self.open_files += 1;
+        Ok(SdFile::Raw(file))
+    }
 
-    fn open(&mut self, path: &str) -> Result<Self::File, Self::Error> {
+    /// open `name` in root by 8.3 name for read
+    pub fn open_short(&mut self, name: &str) -> Result<SdFile, embedded_sdmmc::Error<D::Error>> {
+        let mode = embedded_sdmmc::Mode::ReadOnly;
+        let file = self.vol_mgr.open_file_in_dir(self.root, name, mode)?;
+        self.open_files += 1;
+        Ok(SdFile::Raw(file))
+    }
+
+    /// open directory at long-name `path` from root
+    fn open_dir_path(
+        &mut self,
+        path: &str,
+    ) -> Result<RawDirectory, embedded_sdmmc::Error<D::Error>> {
+        let mut dir = self.root;
+        let mut bytes = [0u8; 255];
+        let mut lfn_buffer = LfnBuffer::new(&mut bytes);
+
+        for node in path.split_terminator('/') {
+            let mut sfn = None;
+            self.vol_mgr
+                .iterate_dir_lfn(dir, &mut lfn_buffer, |entry, lfn| {
+                    if lfn == Some(node) && entry.attributes.is_directory() {
+                        sfn = Some(entry.name.clone());
+                    }
+                })?;
+            let new = match sfn {
+                Some(sfn) => self.vol_mgr.open_dir(dir, sfn),
+                None => Err(embedded_sdmmc::Error::NotFound),
+            };
+            if dir != self.root {
+                self.vol_mgr.close_dir(dir)?;
+            }
+            dir = new?;
+        }
+        Ok(dir)
+    }
+
+    /// open file at long-name `path` from root in `mode`
+    fn open_path(
+        &mut self,
+        path: &str,
+        mode: embedded_sdmmc::Mode,
+    ) -> Result<RawFile, embedded_sdmmc::Error<D::Error>> {
         let mut rem = path.split_terminator('/').count();
         let chain = path.split_terminator('/');
 
@@ -122,12 +193,11 @@ impl<D: BlockDevice> angry_surgeon_core::FileHandler for SdmmcFileHandler<D> {
                 })?;
             if let Some(sfn) = sfn {
                 if rem == 0 {
-                    let file =
-                        self.vol_mgr
-                            .open_file_in_dir(dir, sfn, embedded_sdmmc::Mode::ReadOnly)?;
+                    let file = self.vol_mgr.open_file_in_dir(dir, sfn, mode)?;
                     if dir != self.root {
                         self.vol_mgr.close_dir(dir)?;
                     }
+                    self.open_files += 1;
                     return Ok(file);
                 } else {
                     let new = self.vol_mgr.open_dir(dir, sfn)?;
@@ -136,34 +206,139 @@ impl<D: BlockDevice> angry_surgeon_core::FileHandler for SdmmcFileHandler<D> {
                     }
                     dir = new;
                 }
+            } else if rem == 0 && mode != embedded_sdmmc::Mode::ReadOnly {
+                // create by short name
+                let file = self.vol_mgr.open_file_in_dir(dir, node, mode)?;
+                if dir != self.root {
+                    self.vol_mgr.close_dir(dir)?;
+                }
+                self.open_files += 1;
+                return Ok(file);
             } else {
                 return Err(embedded_sdmmc::Error::NotFound);
             }
         }
         Err(embedded_sdmmc::Error::NotFound)
     }
+}
+
+impl<D: BlockDevice> embedded_io::ErrorType for SdmmcFileHandler<D> {
+    type Error = embedded_sdmmc::Error<D::Error>;
+}
+
+impl<D: BlockDevice> angry_surgeon_core::FileHandler for SdmmcFileHandler<D> {
+    type File = SdFile;
+
+    fn open(&mut self, path: &str) -> Result<Self::File, Self::Error> {
+        if path == CAPTURE_PATH {
+            self.open_files += 1;
+            return Ok(SdFile::Capture(Cell::new(0)));
+        }
+        let mode = embedded_sdmmc::Mode::ReadOnly;
+        Ok(SdFile::Raw(self.open_path(path, mode)?))
+    }
 
     fn try_clone(&mut self, file: &Self::File) -> Result<Self::File, Self::Error> {
-        Ok(*file)
+        Ok(match file {
+            SdFile::Raw(file) => SdFile::Raw(*file),
+            SdFile::Capture(pos) => SdFile::Capture(pos.clone()),
+        })
+    }
+
+    /// new files take the 8.3 name of their last path node
+    fn create(&mut self, path: &str) -> Result<Self::File, angry_surgeon_core::Error<Self::Error>> {
+        if path == CAPTURE_PATH {
+            return Err(angry_surgeon_core::Error::Unsupported);
+        }
+        let mode = embedded_sdmmc::Mode::ReadWriteCreateOrTruncate;
+        Ok(SdFile::Raw(self.open_path(path, mode)?))
+    }
+
+    fn read_dir(
+        &mut self,
+        path: &str,
+    ) -> Result<Vec<DirEntry>, angry_surgeon_core::Error<Self::Error>> {
+        let dir = self.open_dir_path(path)?;
+        let mut entries = Vec::new();
+        let mut bytes = [0u8; 255];
+        let mut lfn_buffer = LfnBuffer::new(&mut bytes);
+        let listed = self
+            .vol_mgr
+            .iterate_dir_lfn(dir, &mut lfn_buffer, |entry, lfn| {
+                entries.push(DirEntry {
+                    name: lfn.map(String::from).unwrap_or_else(|| format!("{}", entry.name)),
+                    is_dir: entry.attributes.is_directory(),
+                });
+            });
+        if dir != self.root {
+            self.vol_mgr.close_dir(dir)?;
+        }
+        listed?;
+        if CAPTURE_PATH.strip_suffix(CAPTURE_NAME) == Some(&format!("{}/", path)) {
+            entries.push(DirEntry {
+                name: CAPTURE_NAME.into(),
+                is_dir: false,
+            });
+        }
+        Ok(entries)
     }
 
     fn close(&mut self, file: &Self::File) -> Result<(), Self::Error> {
-        self.vol_mgr.close_file(*file)
+        if let SdFile::Raw(file) = file {
+            self.vol_mgr.close_file(*file)?;
+        }
+        self.open_files -= 1;
+        Ok(())
     }
 
     fn read(&mut self, file: &Self::File, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        self.vol_mgr.read(*file, buf)
+        match file {
+            SdFile::Raw(file) => self.vol_mgr.read(*file, buf),
+            SdFile::Capture(pos) => {
+                let read = crate::audio::CAPTURE.read_at(pos.get(), buf);
+                pos.set(pos.get() + read as u64);
+                Ok(read)
+            }
+        }
     }
 
-    fn write(&mut self, file: &Self::File, buf: &[u8]) -> Result<usize, Self::Error> {
+    fn write(
+        &mut self,
+        file: &Self::File,
+        buf: &[u8],
+    ) -> Result<usize, angry_surgeon_core::Error<Self::Error>> {
+        let SdFile::Raw(file) = file else {
+            return Err(angry_surgeon_core::Error::Unsupported);
+        };
         let mut file = file.to_file(&self.vol_mgr);
         let res =
             <File<D, TimeSource, MAX_DIRS, MAX_FILES, MAX_VOLUMES> as Write>::write(&mut file, buf);
         file.to_raw_file(); // don't close on drop
-        res
+        Ok(res?)
+    }
+
+    fn flush(&mut self, file: &Self::File) -> Result<(), angry_surgeon_core::Error<Self::Error>> {
+        match file {
+            SdFile::Raw(file) => Ok(self.vol_mgr.flush_file(*file)?),
+            SdFile::Capture(_) => Ok(()),
+        }
     }
 
     fn seek(&mut self, file: &Self::File, pos: embedded_io::SeekFrom) -> Result<u64, Self::Error> {
+        let file = match file {
+            SdFile::Raw(file) => file,
+            SdFile::Capture(at) => {
+                let to = match pos {
+                    embedded_io::SeekFrom::Start(v) => Some(v),
+                    embedded_io::SeekFrom::End(v) => {
+                        crate::audio::CAPTURE.file_len().checked_add_signed(v)
+                    }
+                    embedded_io::SeekFrom::Current(v) => at.get().checked_add_signed(v),
+                };
+                at.set(to.ok_or(embedded_sdmmc::Error::InvalidOffset)?);
+                return Ok(at.get());
+            }
+        };
         let mut file = file.to_file(&self.vol_mgr);
         let res =
             <File<D, TimeSource, MAX_DIRS, MAX_FILES, MAX_VOLUMES> as Seek>::seek(&mut file, pos);
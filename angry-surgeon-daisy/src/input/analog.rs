@@ -1,3 +1,4 @@
+use micromath::F32Ext;
 use stm32h7xx_hal::adc::{Adc, Disabled, Enabled};
 
 pub const CHANNEL_COUNT: usize = 11;
@@ -90,12 +91,194 @@ impl Pots {
     }
 }
 
+/// pot calibration file in sd root, as 8.3 name
+pub const CALIBRATION_PATH: &str = "pots.cfg";
+/// full scale of 12 bit conversion
+const FULL_SCALE: u16 = (1 << 12) - 1;
+/// minimum swept range accepted from calibration
+const MIN_RANGE: u16 = FULL_SCALE / 2;
+
+/// two-point scaling of one pot, with dead zones at both ends
+#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Calibration {
+    pub min: u16,
+    pub max: u16,
+    /// dead zone width at each end, in raw units
+    #[serde(default)]
+    pub dead: u16,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            min: 0,
+            max: FULL_SCALE,
+            dead: 0,
+        }
+    }
+}
+
+impl Calibration {
+    /// scale raw `sample` to 0..=1, reaching both ends within dead zones
+    pub fn scale(&self, sample: u16) -> f32 {
+        let lo = self.min.saturating_add(self.dead);
+        let hi = self.max.saturating_sub(self.dead).max(lo.saturating_add(1));
+        (sample.saturating_sub(lo) as f32 / (hi - lo) as f32).min(1.)
+    }
+}
+
+/// thumbstick axis response around its resting center
+#[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Response {
+    /// resting raw value
+    pub center: u16,
+    /// dead zone around center as fraction of half range
+    pub detent: f32,
+    /// exponent of deflection past detent; above 1 gives finer control near
+    /// center
+    pub curve: f32,
+    /// fraction of remaining distance covered per conversion when springing
+    /// back toward center
+    pub smoothing: f32,
+}
+
+impl Default for Response {
+    fn default() -> Self {
+        Self {
+            center: FULL_SCALE / 2,
+            detent: 0.08,
+            curve: 1.5,
+            smoothing: 0.2,
+        }
+    }
+}
+
+impl Response {
+    /// deflection of raw `sample` in -1..=1, zero within detent
+    fn deflection(&self, sample: u16) -> f32 {
+        let half = if sample < self.center {
+            self.center
+        } else {
+            FULL_SCALE - self.center
+        }
+        .max(1);
+        let d = ((sample as f32 - self.center as f32) / half as f32).clamp(-1., 1.);
+        if d.abs() <= self.detent {
+            0.
+        } else {
+            let past = (d.abs() - self.detent) / (1. - self.detent).max(f32::EPSILON);
+            past.powf(self.curve).copysign(d)
+        }
+    }
+}
+
+/// calibrations of tempo pot, per-bank pots and thumbstick axes, as stored
+/// on sd
+#[derive(Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Calibrations {
+    pub tempo: Calibration,
+    pub pots: [[Calibration; 3]; 2],
+    #[serde(default)]
+    pub thumbs: [[Response; 2]; 2],
+}
+
+/// thumbstick axis state
+#[derive(Default)]
+pub struct Thumb {
+    last: u16,
+    /// smoothed deflection
+    value: f32,
+}
+
+impl Thumb {
+    /// push raw `sample`, returning new deflection in -1..=1 if changed
+    pub fn push(&mut self, sample: u16, response: &Response) -> Option<f32> {
+        let target = response.deflection(sample);
+        let value = if target.abs() < self.value.abs() {
+            // spring back smoothly rather than snap
+            let value = self.value + (target - self.value) * response.smoothing;
+            if (value - target).abs() < 1e-3 {
+                target
+            } else {
+                value
+            }
+        } else {
+            target
+        };
+        self.last = sample;
+        if value != self.value {
+            self.value = value;
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// running extremes of a calibration sweep
+#[derive(Copy, Clone)]
+pub struct Sweep {
+    min: u16,
+    max: u16,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            min: u16::MAX,
+            max: 0,
+        }
+    }
+}
+
+impl Sweep {
+    pub fn push(&mut self, sample: u16) {
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+    }
+
+    /// replace ends of `calibration` if sweep covered enough range
+    fn apply(&self, calibration: &mut Calibration) {
+        if self.max.saturating_sub(self.min) >= MIN_RANGE {
+            calibration.min = self.min;
+            calibration.max = self.max;
+        }
+    }
+}
+
+/// sweeps of tempo pot and per-bank pots while calibrating
+#[derive(Default)]
+pub struct Sweeps {
+    pub tempo: Sweep,
+    pub pots: [[Sweep; 3]; 2],
+}
+
+impl Sweeps {
+    /// apply sweeps, taking resting `thumbs` as their centers
+    pub fn apply(&self, calibrations: &mut Calibrations, thumbs: &[[Thumb; 2]; 2]) {
+        self.tempo.apply(&mut calibrations.tempo);
+        for (thumbs, responses) in thumbs.iter().zip(calibrations.thumbs.iter_mut()) {
+            for (thumb, response) in thumbs.iter().zip(responses.iter_mut()) {
+                response.center = thumb.last;
+            }
+        }
+        for (sweeps, calibrations) in self.pots.iter().zip(calibrations.pots.iter_mut()) {
+            for (sweep, calibration) in sweeps.iter().zip(calibrations.iter_mut()) {
+                sweep.apply(calibration);
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct AdcData {
     pub mult: f32,
     pub tempo: u16,
     pub pots: [Pots; 2],
-    pub thumbs: [[u16; 2]; 2],
+    pub thumbs: [[Thumb; 2]; 2],
+    pub calibrations: Calibrations,
+    /// sweeps in progress while both shifts held, if calibrating
+    pub sweeps: Option<Sweeps>,
 }
 
 /// read initial vref for conversion factor (only available via adc3)
@@ -149,6 +332,23 @@ pub fn init_data(
     }
 }
 
+pub type Transfer = crate::hal::dma::Transfer<
+    crate::hal::dma::dma::Stream1<crate::hal::stm32::DMA1>,
+    Adc<crate::hal::pac::ADC1, Enabled>,
+    crate::hal::dma::PeripheralToMemory,
+    &'static mut [u16],
+    crate::hal::dma::DBTransfer,
+>;
+
+/// restart one shot transfer and conversion of sequence
+pub fn restart(transfer: &mut Transfer) {
+    transfer.start(|adc| {
+        adc.inner_mut()
+            .cr
+            .modify(|_, w| w.adstart().start_conversion())
+    });
+}
+
 /// start hardcoded adc seqeunce (friendship ENDED with genericism)
 pub fn start_seq(adc: &mut Adc<crate::hal::pac::ADC1, Enabled>) {
     let regs = adc.inner_mut();
@@ -13,11 +13,17 @@ pub enum Source {
 pub struct Blink<const P: char, const N: u8> {
     output: crate::hal::gpio::Pin<P, N, crate::hal::gpio::Output>,
     last: Instant<u32, 1, 1_000_000>,
+    /// whether blinks keep dark, e.g. while idle
+    pub dark: bool,
 }
 
 impl<const P: char, const N: u8> Blink<P, N> {
     pub fn new(output: Pin<P, N, Output>, now: Instant<u32, 1, 1_000_000>) -> Self {
-        Self { output, last: now }
+        Self {
+            output,
+            last: now,
+            dark: false,
+        }
     }
 
     pub async fn tick(
@@ -25,7 +31,7 @@ impl<const P: char, const N: u8> Blink<P, N> {
         period: Duration<u32, 1, 1_000_000>,
         sustain: Duration<u32, 1, 1_000_000>,
     ) {
-        if self.output.is_set_low() {
+        if self.output.is_set_low() && !self.dark {
             self.output.set_high();
             crate::Mono::delay_until(self.last + sustain).await;
         } else {
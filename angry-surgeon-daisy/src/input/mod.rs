@@ -1,4 +1,5 @@
-use angry_surgeon_core::{Event, FileHandler as _};
+use angry_surgeon_core::{Event, FileHandler as _, LOOP_STEP};
+use core::sync::atomic::Ordering;
 use embedded_io::ErrorType;
 
 use crate::{
@@ -39,7 +40,10 @@ impl BankHandler {
         }
     }
 
-    fn reverse_up(&mut self, system: &mut SystemHandler) {
+    fn reverse_up(
+        &mut self,
+        system: &mut SystemHandler,
+    ) -> Result<(), <FileHandler as ErrorType>::Error> {
         match self.state {
             BankState::Mangle => {
                 self.reverse = false;
@@ -48,10 +52,12 @@ impl BankHandler {
             BankState::BakeRecord => {
                 // exit record
                 self.state = BankState::Mangle;
-                system.banks[usize::from(self.bank)].take_record(self.downs.first().copied());
+                system.banks[usize::from(self.bank)]
+                    .take_record(self.downs.first().copied(), &mut system.fs)?;
             }
             _ => (),
         }
+        Ok(())
     }
 
     fn reverse_down(
@@ -97,18 +103,22 @@ impl BankHandler {
         &mut self,
         system: &mut SystemHandler,
     ) -> Result<(), <FileHandler as ErrorType>::Error> {
-        if self.state == BankState::Mangle {
+        if self.state == BankState::LoadKit && self.shift {
+            // clear loaded kit
+            let bank = &mut system.banks[usize::from(self.bank)];
+            bank.bank.clear_kit(bank.kit_index as u8);
+        } else if self.state == BankState::LoadKit {
+            // clear knob motion loops recorded while kit held
+            system.banks[usize::from(self.bank)].clear_motions();
+        } else if self.state == BankState::Mangle {
             if self.shift {
                 // init build pool
                 self.state = BankState::BuildPool { cleared: false };
             } else {
                 self.hold = !self.hold;
                 if !self.hold && self.downs.is_empty() {
-                    system.banks[usize::from(self.bank)].push_event(
-                        &mut system.fs,
-                        &mut system.rand,
-                        Event::Sync,
-                    )?;
+                    system.banks[usize::from(self.bank)]
+                        .push_release(&mut system.fs, &mut system.rand)?;
                 }
             }
         }
@@ -116,6 +126,9 @@ impl BankHandler {
     }
 
     fn kit_up(&mut self) {
+        audio::PARAMS[usize::from(self.bank)]
+            .motion
+            .store(false, Ordering::Relaxed);
         if self.state == BankState::LoadKit {
             // exit load kit
             self.state = BankState::Mangle;
@@ -124,8 +137,11 @@ impl BankHandler {
 
     fn kit_down(&mut self) {
         if self.state == BankState::Mangle && !self.shift {
-            // init load kit
+            // init load kit; knob moves while held record motion loops
             self.state = BankState::LoadKit;
+            audio::PARAMS[usize::from(self.bank)]
+                .motion
+                .store(true, Ordering::Relaxed);
         }
         // bank save hanled in InputHandler
     }
@@ -163,6 +179,17 @@ impl BankHandler {
     ) -> Result<(), <FileHandler as ErrorType>::Error> {
         match &mut self.state {
             BankState::Mangle => self.pad_input(system)?,
+            BankState::LoadKit if self.downs.len() == 2 => {
+                let bank = &mut system.banks[usize::from(self.bank)];
+                if self.shift {
+                    // swap pads of loaded kit
+                    bank.bank
+                        .swap_pads(bank.kit_index as u8, self.downs[0], self.downs[1]);
+                } else {
+                    // copy kit of first pad to second
+                    bank.bank.copy_kit(self.downs[0], self.downs[1]);
+                }
+            }
             BankState::LoadKit => {
                 system.banks[usize::from(self.bank)].kit_index = self.downs[0] as usize;
             }
@@ -211,16 +238,13 @@ impl BankHandler {
                 )?;
             }
         } else {
-            // init sync
-            system.banks[usize::from(self.bank)].push_event(
-                &mut system.fs,
-                &mut system.rand,
-                Event::Sync,
-            )?;
+            // init release
+            system.banks[usize::from(self.bank)].push_release(&mut system.fs, &mut system.rand)?;
         }
         Ok(())
     }
 
+    /// loop length in 1/256 steps of held chord, each pad past `index` a bit
     fn binary_offset(&self, index: u8) -> u16 {
         self.downs
             .iter()
@@ -230,6 +254,7 @@ impl BankHandler {
                     .unwrap_or(v + audio::PAD_COUNT as u8 - 1 - index)
             })
             .fold(0u16, |acc, v| acc | (1 << v))
+            * LOOP_STEP
     }
 }
 
@@ -253,24 +278,22 @@ impl InputHandler {
         bank: audio::Bank,
         system: &mut SystemHandler,
     ) -> Result<(), <FileHandler as ErrorType>::Error> {
-        let bd = system.banks[usize::from(bank)].bank.clone();
-        if let Ok(bytes) = serde_json::to_vec(&bd) {
-            let mut index = 0;
-            let file = loop {
-                match system.fs.open(&alloc::format!("banks/banks{}.bd", index)) {
-                    Err(embedded_sdmmc::Error::FileAlreadyExists) => index += 1,
-                    Err(_) => panic!(),
-                    Ok(file) => break file,
+        let mut index = 0;
+        let path = loop {
+            let path = alloc::format!("banks/bank{}.bd", index);
+            match system.fs.open(&path) {
+                Ok(file) => {
+                    system.fs.close(&file)?;
+                    index += 1;
                 }
-            };
-            let mut slice = &bytes[..];
-            while !slice.is_empty() {
-                let n = system.fs.write(&file, slice)?;
-                slice = &slice[n..];
+                Err(embedded_sdmmc::Error::NotFound) => break path,
+                Err(e) => return Err(e),
             }
-            system.fs.close(&file)?;
+        };
+        match system.banks[usize::from(bank)].bank.save(&path, &mut system.fs) {
+            Err(angry_surgeon_core::Error::Other(e)) => Err(e),
+            _ => Ok(()),
         }
-        Ok(())
     }
 
     pub fn touch_up(
@@ -289,7 +312,7 @@ impl InputHandler {
             my_bank.downs.retain(|&i| i != index);
             my_bank.pad_up(system)?;
         } else if index == touch::pads::REVERSE {
-            my_bank.reverse_up(system);
+            my_bank.reverse_up(system)?;
         } else if index == touch::pads::HOLD {
             my_bank.hold_up(system);
         } else if index == touch::pads::KIT {
@@ -310,6 +333,12 @@ impl InputHandler {
         };
         if index == touch::pads::SHIFT {
             my_bank.shift = true;
+        } else if index == touch::pads::REVERSE && self.bank_a.shift && self.bank_b.shift {
+            // toggle dry throughput, muting mangler
+            audio::DRY.enabled.fetch_xor(true, Ordering::Relaxed);
+        } else if index == touch::pads::HOLD && self.bank_a.shift && self.bank_b.shift {
+            // click out for loopback round trip, blinked on user led
+            crate::latency::request();
         } else if index == touch::pads::KIT {
             if my_bank.shift {
                 self.save_bank(bank, system)?;
@@ -0,0 +1,87 @@
+//! loopback round-trip latency probe: a click written to output on request and
+//! counted in frames until captured on input, reported as user led blink codes
+
+use crate::audio;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// captured sample magnitude recognized as click
+const THRESHOLD: f32 = 0.25;
+/// frames after which unreturned click is abandoned
+const TIMEOUT: u32 = audio::SAMPLE_RATE;
+/// click length in frames
+const CLICK_LEN: usize = 8;
+/// in ms
+const LONG_LEN: u32 = 450;
+/// in ms
+const SHORT_LEN: u32 = 150;
+/// in ms
+const FLICKER_LEN: u32 = 50;
+const FLICKER_COUNT: u32 = 10;
+/// in ms
+pub const PAUSE_LEN: u32 = 800;
+
+/// click asked for, not yet written
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// write a click with the next output buffer
+pub fn request() {
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+#[derive(Copy, Clone)]
+pub enum Outcome {
+    /// frames from click written to captured, buffering included
+    RoundTrip(u32),
+    /// no click captured; check loopback
+    Lost,
+}
+
+impl Outcome {
+    /// blink count and length of each, in ms, to show in turn: tens of ms as
+    /// long blinks, then ones as short; rapid flicker if lost
+    pub fn code(self) -> [(u32, u32); 2] {
+        match self {
+            Outcome::RoundTrip(frames) => {
+                let ms = (frames * 1000 + audio::SAMPLE_RATE / 2) / audio::SAMPLE_RATE;
+                [(ms / 10, LONG_LEN), (ms % 10, SHORT_LEN)]
+            }
+            Outcome::Lost => [(FLICKER_COUNT, FLICKER_LEN), (0, 0)],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Probe {
+    /// frames since click written, if awaiting capture
+    elapsed: Option<u32>,
+}
+
+impl Probe {
+    /// overwrite head of interleaved `buffer` of `channels` with a full-scale
+    /// click if requested and none awaiting capture
+    pub fn send(&mut self, buffer: &mut [f32], channels: usize) {
+        if self.elapsed.is_some() || !REQUESTED.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        for sample in buffer.iter_mut().take(CLICK_LEN * channels) {
+            *sample = 1.;
+        }
+        self.elapsed = Some(0);
+    }
+
+    /// scan interleaved `input` of `channels`, captured over the buffer since
+    /// last call, for click; outcome once found or abandoned
+    pub fn capture(&mut self, input: &[f32], channels: usize) -> Option<Outcome> {
+        let elapsed = self.elapsed.as_mut()?;
+        let outcome = match input.iter().position(|v| v.abs() > THRESHOLD) {
+            Some(index) => Outcome::RoundTrip(*elapsed + (index / channels) as u32),
+            None if *elapsed > TIMEOUT => Outcome::Lost,
+            None => {
+                *elapsed += (input.len() / channels) as u32;
+                return None;
+            }
+        };
+        self.elapsed = None;
+        Some(outcome)
+    }
+}
@@ -7,6 +7,10 @@ use stm32h7xx_hal as hal;
 mod audio;
 mod fs;
 mod input;
+mod latency;
+mod power;
+mod selftest;
+mod soak;
 
 rtic_monotonics::systick_monotonic!(Mono, 1_000_000); // us resolution
 
@@ -16,7 +20,7 @@ static HEAP: embedded_alloc::LlffHeap = embedded_alloc::LlffHeap::empty();
 #[rtic::app(device = hal::stm32, peripherals = true, dispatchers = [SPI1, SPI2])]
 mod app {
     use crate::*;
-    use angry_surgeon_core::{FileHandler, GRAIN_LEN};
+    use angry_surgeon_core::{FileHandler, MAX_GRAIN_LEN};
     use embedded_hal::delay::DelayNs;
     use hal::prelude::*;
     use hal::traits::i2s::FullDuplex;
@@ -27,7 +31,7 @@ mod app {
     use stm32h7xx_hal::gpio::ExtiPin;
     use tinyrand::Seeded;
 
-    const DMA_BUFFER_LEN: usize = GRAIN_LEN * 2;
+    const DMA_BUFFER_LEN: usize = MAX_GRAIN_LEN * 2;
 
     #[unsafe(link_section = ".sram1_bss")]
     static TX_BUFFER0: grounded::uninit::GroundedArrayCell<u32, DMA_BUFFER_LEN> =
@@ -36,6 +40,12 @@ mod app {
     static TX_BUFFER1: grounded::uninit::GroundedArrayCell<u32, DMA_BUFFER_LEN> =
         grounded::uninit::GroundedArrayCell::uninit();
     #[unsafe(link_section = ".sram1_bss")]
+    static RX_BUFFER0: grounded::uninit::GroundedArrayCell<u32, DMA_BUFFER_LEN> =
+        grounded::uninit::GroundedArrayCell::uninit();
+    #[unsafe(link_section = ".sram1_bss")]
+    static RX_BUFFER1: grounded::uninit::GroundedArrayCell<u32, DMA_BUFFER_LEN> =
+        grounded::uninit::GroundedArrayCell::uninit();
+    #[unsafe(link_section = ".sram1_bss")]
     static ADC_BUFFER: grounded::uninit::GroundedArrayCell<u16, { input::analog::CHANNEL_COUNT }> =
         grounded::uninit::GroundedArrayCell::uninit();
 
@@ -60,6 +70,7 @@ mod app {
     struct Local {
         shift_tx: [rtic_sync::signal::SignalWriter<'static, bool>; 2],
         shift_rx: [rtic_sync::signal::SignalReader<'static, bool>; 2],
+        wake_tx: rtic_sync::signal::SignalWriter<'static, ()>,
 
         clock_in_signal: hal::gpio::PG10<hal::gpio::Input>,
         last_clock_in: Option<rtic_monotonics::fugit::Instant<u32, 1, 1_000_000>>,
@@ -69,13 +80,7 @@ mod app {
         mpr121_a: input::touch::Mpr121Data<hal::gpio::gpiob::PB6<hal::gpio::Input>>,
         mpr121_b: input::touch::Mpr121Data<hal::gpio::gpiob::PB7<hal::gpio::Input>>,
 
-        adc1_transfer: hal::dma::Transfer<
-            hal::dma::dma::Stream1<hal::stm32::DMA1>,
-            hal::adc::Adc<hal::pac::ADC1, hal::adc::Enabled>,
-            hal::dma::PeripheralToMemory,
-            &'static mut [u16],
-            hal::dma::DBTransfer,
-        >,
+        adc1_transfer: input::analog::Transfer,
         adc_data: input::analog::AdcData,
 
         sai1_transfer: hal::dma::Transfer<
@@ -85,6 +90,17 @@ mod app {
             &'static mut [u32],
             hal::dma::DBTransfer,
         >,
+        sai1_rx_transfer: hal::dma::Transfer<
+            hal::dma::dma::Stream2<hal::stm32::DMA1>,
+            hal::sai::dma::ChannelB<hal::stm32::SAI1>,
+            hal::dma::PeripheralToMemory,
+            &'static mut [u32],
+            hal::dma::DBTransfer,
+        >,
+        /// dry throughput level
+        dry_level: f32,
+        dither: angry_surgeon_core::Dither,
+        probe: latency::Probe,
     }
 
     #[init]
@@ -92,7 +108,16 @@ mod app {
         // init allocator
         {
             use core::mem::MaybeUninit;
-            const HEAP_SIZE: usize = 65535;
+            // plus stereo f32 delay ring and its send scratch, and per bank
+            // prefetch blocks and sample cache with slack for their tables
+            const HEAP_SIZE: usize = 65535
+                + angry_surgeon_core::MAX_DELAY_LEN * 8
+                + 16 * 1024
+                + audio::BANK_COUNT
+                    * (angry_surgeon_core::DEFAULT_PREFETCH * angry_surgeon_core::BLOCK_LEN
+                        + audio::SAMPLE_CACHE_LEN)
+                    * 17
+                    / 16;
             static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
             #[allow(static_mut_refs)]
             unsafe {
@@ -129,7 +154,8 @@ mod app {
         let gpioe = cx.device.GPIOE.split(ccdr.peripheral.GPIOE);
         let gpiog = cx.device.GPIOG.split(ccdr.peripheral.GPIOG);
 
-        let led = gpioc.pc7.into_push_pull_output();
+        let mut led = gpioc.pc7.into_push_pull_output();
+        let mut report = selftest::Report::default();
         let dma1_streams = hal::dma::dma::StreamsTuple::new(cx.device.DMA1, ccdr.peripheral.DMA1);
 
         // -------------------------------------------------------------------------
@@ -171,8 +197,11 @@ mod app {
                 .SDMMC1
                 .sdmmc(sdmmc_pins, ccdr.peripheral.SDMMC1, &ccdr.clocks);
         while sdmmc.init(24.MHz()).is_err() {
-            Mono.delay_ms(1000);
+            // nothing runs without sd; blink its code until inserted
+            report.record(selftest::Check::Sd, false);
+            report.blink(&mut led, &mut Mono);
         }
+        report.record(selftest::Check::Sd, true);
         let vol_mgr = embedded_sdmmc::VolumeManager::new_with_limits(
             sdmmc.sdmmc_block_device(),
             fs::TimeSource,
@@ -212,8 +241,8 @@ mod app {
             &mut cx.device.SYSCFG,
             &mut cx.device.EXTI,
         );
-        mpr121.init(mpr121_a.addr).unwrap();
-        mpr121.init(mpr121_b.addr).unwrap();
+        report.record(selftest::Check::TouchA, mpr121.init(mpr121_a.addr).is_ok());
+        report.record(selftest::Check::TouchB, mpr121.init(mpr121_b.addr).is_ok());
 
         unsafe {
             hal::pac::NVIC::unmask(hal::pac::interrupt::EXTI9_5);
@@ -228,7 +257,7 @@ mod app {
             ccdr.peripheral.ADC3,
             &ccdr.clocks,
         );
-        let adc_data = input::analog::init_data(adc3, &mut cx.device.ADC3_COMMON);
+        let mut adc_data = input::analog::init_data(adc3, &mut cx.device.ADC3_COMMON);
         let adc1 = hal::adc::Adc::adc1(
             cx.device.ADC1,
             4.MHz(),
@@ -246,7 +275,7 @@ mod app {
             .priority(hal::dma::config::Priority::VeryHigh)
             .memory_increment(true)
             .transfer_complete_interrupt(true);
-        let mut adc1_transfer: hal::dma::Transfer<_, _, hal::dma::PeripheralToMemory, _, _> =
+        let mut adc1_transfer: input::analog::Transfer =
             hal::dma::Transfer::init(dma1_streams.1, adc1, adc_buffer, None, config);
 
         unsafe {
@@ -275,24 +304,52 @@ mod app {
 
         // -------------------------------------------------------------------------
         // --- SYSTEM HANDLER INIT
-        let mut system =
-            audio::SystemHandler::new(fs, tinyrand::Wyrand::seed(0xf2aa), audio::STEP_DIV, 8.);
+        let mut system = audio::SystemHandler::new(
+            fs,
+            tinyrand::Wyrand::seed(0xf2aa),
+            audio::STEP_DIV,
+            8.,
+            audio::PHRASE_LIMITS,
+        );
+        system.assign_sample_cache(audio::SAMPLE_CACHE_LEN);
         // init for testing
         {
             system.assign_tempo(192.);
-            let bd_file = system.fs.open("banks/bank0.bd").unwrap();
-            let mut reader = crate::fs::BufReader::new(&mut system.fs, bd_file).unwrap();
+            // first bank listed in ./banks, if any
+            let path = angry_surgeon_core::Browser::open("banks", "bd", &mut system.fs)
+                .ok()
+                .and_then(|v| v.paths().next());
+            if let Some(bd_file) = path.and_then(|v| system.fs.open(&v).ok()) {
+                let mut reader = crate::fs::BufReader::new(&mut system.fs, &bd_file).unwrap();
+                let mut bytes = alloc::vec::Vec::new();
+                while let Ok(Some(c)) = reader.next() {
+                    bytes.push(c);
+                }
+                if let Ok(saved) = angry_surgeon_core::SavedBank::from_bd(&bytes) {
+                    // fit banks saved with other capacities, e.g. on linux
+                    let (bd, compat) =
+                        saved.into_bank::<{ audio::PAD_COUNT }, { audio::MAX_PHRASE_LEN }>();
+                    report.record(selftest::Check::BankCompat, compat.is_clean());
+                    system.banks[1].assign_bank(bd);
+                }
+                let _ = system.fs.close(&bd_file);
+            }
+        }
+        // load pot calibration, if any
+        let mut calibrated = false;
+        if let Ok(file) = system.fs.open_short(input::analog::CALIBRATION_PATH) {
+            let mut reader = crate::fs::BufReader::new(&mut system.fs, &file).unwrap();
             let mut bytes = alloc::vec::Vec::new();
             while let Ok(Some(c)) = reader.next() {
                 bytes.push(c);
             }
-            if let Ok(bd) = serde_json::from_slice::<
-                angry_surgeon_core::Bank<{ audio::PAD_COUNT }, { audio::MAX_PHRASE_LEN }>,
-            >(&bytes)
-            {
-                system.banks[1].bank = bd;
+            if let Ok(calibrations) = serde_json::from_slice(&bytes) {
+                adc_data.calibrations = calibrations;
+                calibrated = true;
             }
+            let _ = system.fs.close(&file);
         }
+        report.record(selftest::Check::Calibration, calibrated);
         let input_handler = input::InputHandler::new();
 
         // -------------------------------------------------------------------------
@@ -302,20 +359,30 @@ mod app {
             gpioe.pe5.into_alternate(),
             gpioe.pe4.into_alternate(),
             gpioe.pe6.into_alternate(),
-            Option::<hal::gpio::Pin<'E', 3, hal::gpio::Alternate<6>>>::None,
+            Some(gpioe.pe3.into_alternate()),
         );
         let sai1_tx_config = hal::sai::I2SChanConfig::new(stm32h7xx_hal::sai::I2SDir::Tx)
             .set_clock_strobe(stm32h7xx_hal::sai::I2SClockStrobe::Falling)
             .set_frame_sync_active_high(true)
             .set_protocol(stm32h7xx_hal::sai::I2SProtocol::MSB)
             .set_frame_size(Some(64));
+        // codec input on block b, clocked by block a
+        let sai1_rx_config = hal::sai::I2SChanConfig::new(stm32h7xx_hal::sai::I2SDir::Rx)
+            .set_sync_type(stm32h7xx_hal::sai::I2SSync::Internal)
+            .set_clock_strobe(stm32h7xx_hal::sai::I2SClockStrobe::Rising)
+            .set_frame_sync_active_high(true)
+            .set_protocol(stm32h7xx_hal::sai::I2SProtocol::MSB)
+            .set_frame_size(Some(64));
         let mut sai1 = cx.device.SAI1.i2s_ch_a(
             sai1_pins,
             48.kHz(),
-            hal::sai::I2SDataSize::BITS_16,
+            match audio::BIT_DEPTH {
+                angry_surgeon_core::BitDepth::Sixteen => hal::sai::I2SDataSize::BITS_16,
+                angry_surgeon_core::BitDepth::TwentyFour => hal::sai::I2SDataSize::BITS_24,
+            },
             sai1_rec,
             &ccdr.clocks,
-            hal::sai::I2sUsers::new(sai1_tx_config),
+            hal::sai::I2sUsers::new(sai1_tx_config).add_slave(sai1_rx_config),
         );
 
         let tx_buffer0: &mut [u32] = unsafe {
@@ -343,22 +410,57 @@ mod app {
                 dma_config,
             );
 
+        let rx_buffer0: &mut [u32] = unsafe {
+            RX_BUFFER0.initialize_all_copied(0);
+            let (ptr, len) = RX_BUFFER0.get_ptr_len();
+            core::slice::from_raw_parts_mut(ptr, len)
+        };
+        let rx_buffer1: &mut [u32] = unsafe {
+            RX_BUFFER1.initialize_all_copied(0);
+            let (ptr, len) = RX_BUFFER1.get_ptr_len();
+            core::slice::from_raw_parts_mut(ptr, len)
+        };
+        // polled from audio_out, so no interrupt of its own
+        let rx_dma_config = hal::dma::dma::DmaConfig::default()
+            .priority(hal::dma::config::Priority::VeryHigh)
+            .memory_increment(true)
+            .circular_buffer(true)
+            .double_buffer(true);
+        let mut sai1_rx_transfer: hal::dma::Transfer<_, _, hal::dma::PeripheralToMemory, _, _> =
+            hal::dma::Transfer::init(
+                dma1_streams.2,
+                unsafe { hal::pac::Peripherals::steal().SAI1.dma_ch_b() },
+                rx_buffer0,
+                Some(rx_buffer1),
+                rx_dma_config,
+            );
+
         unsafe {
             hal::pac::NVIC::unmask(hal::pac::Interrupt::DMA1_STR0);
         };
 
+        sai1_rx_transfer.start(|_| {});
+        let mut codec_ok = false;
         sai1_transfer.start(|_| {
+            sai1.enable_dma(hal::sai::SaiChannel::ChannelB);
             sai1.enable_dma(hal::sai::SaiChannel::ChannelA);
             sai1.enable();
-            sai1.try_send(0, 0).unwrap();
+            codec_ok = sai1.try_send(0, 0).is_ok();
         });
+        report.record(selftest::Check::Codec, codec_ok);
         cx.core.SCB.enable_icache();
 
+        // -------------------------------------------------------------------------
+        // --- SELF TEST
+        report.blink(&mut led, &mut Mono);
+
         let (tempo_tx, tempo_rx) = rtic_sync::make_signal!(f32);
         let (shift_a_tx, shift_a_rx) = rtic_sync::make_signal!(bool);
         let (shift_b_tx, shift_b_rx) = rtic_sync::make_signal!(bool);
+        let (wake_tx, wake_rx) = rtic_sync::make_signal!(());
 
         clock_out::spawn(tempo_rx, clock_out, tempo_led).unwrap();
+        idle_watch::spawn(wake_rx).unwrap();
 
         (
             Shared {
@@ -369,6 +471,7 @@ mod app {
             Local {
                 shift_tx: [shift_a_tx, shift_b_tx],
                 shift_rx: [shift_a_rx, shift_b_rx],
+                wake_tx,
 
                 clock_in_signal,
                 last_clock_in: None,
@@ -382,6 +485,10 @@ mod app {
                 adc_data,
 
                 sai1_transfer,
+                sai1_rx_transfer,
+                dry_level: 1.,
+                dither: angry_surgeon_core::Dither::new(audio::BIT_DEPTH, audio::DITHER),
+                probe: latency::Probe::default(),
             },
         )
     }
@@ -400,8 +507,10 @@ mod app {
 
         let mut clock_out = input::clock::Blink::new(clock_out, last_step);
         let mut tempo_led = input::clock::Blink::new(tempo_led, last_step);
+        let mut marks = soak::Marks::default();
 
         loop {
+            tempo_led.dark = power::idle();
             match select4(
                 tempo_led.tick(
                     beat_dur,
@@ -424,7 +533,15 @@ mod app {
                 Either4::Third(()) => {
                     last_step +=
                         MicrosDurationU32::micros(beat_dur.to_micros() / audio::STEP_DIV as u32);
-                    cx.shared.system.lock(|system| system.tick().unwrap());
+                    cx.shared.system.lock(|system| {
+                        system.tick().unwrap();
+                        // every handle opened is held by an onset or closed
+                        #[cfg(feature = "handle-check")]
+                        assert_eq!(system.fs.open_files(), system.open_onsets());
+                        if let Some(soak) = soak::SOAK {
+                            marks.step(soak, system);
+                        }
+                    });
                 }
                 Either4::Fourth(tempo) => {
                     beat_dur = MicrosDurationU32::micros((60_000_000. / tempo) as u32);
@@ -434,6 +551,61 @@ mod app {
         }
     }
 
+    #[task(priority = 1)]
+    async fn idle_watch(
+        _cx: idle_watch::Context,
+        mut wake_rx: rtic_sync::signal::SignalReader<'static, ()>,
+    ) {
+        use embassy_futures::select::*;
+
+        let Some(idle_after) = power::IDLE_AFTER else {
+            return;
+        };
+        let idle_after = MicrosDurationU32::millis(idle_after);
+        let mut last_active = Mono::now();
+        loop {
+            let poll = if power::idle() {
+                power::SLOW_POLL
+            } else {
+                power::CHECK
+            };
+            // touch cuts wait short, waking at once
+            let _ = select(Mono::delay(MicrosDurationU32::millis(poll)), wake_rx.wait()).await;
+            let now = Mono::now();
+            if power::take_activity() {
+                last_active = now;
+                if power::idle() {
+                    power::assign_idle(false);
+                }
+            } else if !power::idle()
+                && now
+                    .checked_duration_since(last_active)
+                    .is_some_and(|v| v > idle_after)
+            {
+                power::assign_idle(true);
+            }
+            // poll pots once per wait while idle, resuming them on wake
+            if power::take_adc_stopped() {
+                power::request_poll();
+                rtic::pend(hal::pac::Interrupt::DMA1_STR1);
+            }
+        }
+    }
+
+    /// blink round trip of latency probe on user led
+    #[task(shared = [led], priority = 1)]
+    async fn report_latency(mut cx: report_latency::Context, outcome: latency::Outcome) {
+        for (count, len) in outcome.code() {
+            for _ in 0..count {
+                cx.shared.led.lock(|led| led.set_high());
+                Mono::delay(MicrosDurationU32::millis(len)).await;
+                cx.shared.led.lock(|led| led.set_low());
+                Mono::delay(MicrosDurationU32::millis(len)).await;
+            }
+            Mono::delay(MicrosDurationU32::millis(latency::PAUSE_LEN)).await;
+        }
+    }
+
     #[task(binds = EXTI15_10, shared = [tempo_tx], local = [clock_in_signal, last_clock_in], priority = 3)]
     fn clock_in(mut cx: clock_in::Context) {
         cx.local.clock_in_signal.clear_interrupt_pending_bit();
@@ -463,8 +635,11 @@ mod app {
         }
     }
 
-    #[task(binds = EXTI9_5, shared = [system], local = [shift_tx, input_handler, mpr121, mpr121_a, mpr121_b], priority = 3)]
+    #[task(binds = EXTI9_5, shared = [system], local = [shift_tx, wake_tx, input_handler, mpr121, mpr121_a, mpr121_b], priority = 3)]
     fn mpr121(mut cx: mpr121::Context) {
+        // touch wakes from idle at once
+        power::note_activity();
+        cx.local.wake_tx.write(());
         loop {
             match (
                 cx.local.mpr121_a.irq.is_low(),
@@ -548,6 +723,11 @@ mod app {
     #[task(binds = DMA1_STR1, shared = [tempo_tx, system], local = [shift_rx, adc1_transfer, adc_data], priority = 3)]
     fn adc_in(mut cx: adc_in::Context) {
         let transfer = cx.local.adc1_transfer;
+        // pended by idle_watch to poll stopped sequence, not by a transfer
+        if power::take_poll() {
+            input::analog::restart(transfer);
+            return;
+        }
         let adc_data = cx.local.adc_data;
 
         for i in 0..audio::BANK_COUNT {
@@ -555,63 +735,102 @@ mod app {
                 adc_data.pots[i].shift(shift);
             }
         }
+        // sweep pot extremes while both shifts held, saving on release
+        if adc_data.pots.iter().all(|v| v.shift) {
+            adc_data.sweeps.get_or_insert_default();
+        } else if let Some(sweeps) = adc_data.sweeps.take() {
+            sweeps.apply(&mut adc_data.calibrations, &adc_data.thumbs);
+            if let Ok(bytes) = serde_json::to_vec(&adc_data.calibrations) {
+                cx.shared.system.lock(|system| {
+                    if let Ok(file) = system.fs.create_short(input::analog::CALIBRATION_PATH) {
+                        let _ = system.fs.write(&file, &bytes);
+                        let _ = system.fs.close(&file);
+                    }
+                });
+            }
+        }
 
+        let dry = audio::DRY.enabled.load(core::sync::atomic::Ordering::Relaxed);
         let _ = transfer.next_transfer_with(|buffer, _current, _incomplete| {
             for (index, sample) in buffer.iter().enumerate() {
                 use input::analog::channels::*;
 
-                let abs = *sample as f32 * adc_data.mult;
-
                 macro_rules! pots {
                     ($bank:ident,$base:expr) => {
                         let index = index - $base as usize;
-                        if adc_data.pots[usize::from(audio::Bank::$bank)].maybe_set(index, *sample)
+                        let abs = adc_data.calibrations.pots[usize::from(audio::Bank::$bank)][index]
+                            .scale(*sample);
+                        let last = adc_data.pots[usize::from(audio::Bank::$bank)].last(index as u8);
+                        if let Some(sweeps) = adc_data.sweeps.as_mut() {
+                            sweeps.pots[usize::from(audio::Bank::$bank)][index].push(*sample);
+                        } else if adc_data.pots[usize::from(audio::Bank::$bank)]
+                            .maybe_set(index, *sample)
                         {
-                            cx.shared.system.lock(|system| {
-                                let bank = &mut system.banks[usize::from(audio::Bank::$bank)];
-                                match (index, adc_data.pots[usize::from(audio::Bank::$bank)].shift)
+                            if last.abs_diff(*sample) > power::KNOB_WAKE {
+                                power::note_activity();
+                            }
+                            let params = &audio::PARAMS[usize::from(audio::Bank::$bank)];
+                            match (index, adc_data.pots[usize::from(audio::Bank::$bank)].shift) {
+                                (0, false)
+                                    if dry && matches!(audio::Bank::$bank, audio::Bank::A) =>
                                 {
-                                    (0, false) => bank.gain = abs * 2.,
-                                    (0, true) => bank.width = abs,
-                                    (1, false) => bank.speed.base = abs * 2.,
-                                    (1, true) => bank.loop_div.base = (abs * 8.).round(),
-                                    (2, false) => bank.kit_drift = abs,
-                                    (2, true) => bank.phrase_drift = abs,
-                                    _ => unreachable!(),
+                                    // first gain pot sets dry level while throughput on
+                                    audio::DRY.level.write(abs * 2.)
                                 }
-                            });
+                                (0, false) => params.gain.write(abs * 2.),
+                                (0, true) => params.width.write(abs),
+                                (1, false) => params.speed.write(abs * 2.),
+                                (1, true) => params.roll.write(abs),
+                                (2, false) => params.kit_drift.write(abs),
+                                (2, true) => params.phrase_drift.write(abs),
+                                _ => unreachable!(),
+                            }
                         }
                     };
                 }
 
                 macro_rules! thumb {
-                    ($bank:ident,$base:expr,$x_abs:expr) => {
+                    ($bank:ident,$base:expr,$x_sign:expr) => {
                         let index = index - $base as usize;
-                        let last = &mut adc_data.thumbs[usize::from(audio::Bank::$bank)][index];
-                        if *sample != *last {
-                            *last = *sample;
-                            cx.shared.system.lock(|system| {
-                                let bank = &mut system.banks[usize::from(audio::Bank::$bank)];
-                                match index {
-                                    0 => bank.speed.offset = $x_abs * 2.,
-                                    1 => bank.loop_div.offset = abs * 2.,
-                                    _ => unreachable!(),
-                                }
-                            });
+                        let response =
+                            &adc_data.calibrations.thumbs[usize::from(audio::Bank::$bank)][index];
+                        let thumb = &mut adc_data.thumbs[usize::from(audio::Bank::$bank)][index];
+                        if let Some(deflection) = thumb.push(*sample, response) {
+                            power::note_activity();
+                            let params = &audio::PARAMS[usize::from(audio::Bank::$bank)];
+                            match (index, adc_data.pots[usize::from(audio::Bank::$bank)].shift) {
+                                (0, false) => params.speed_offset.write(1. + $x_sign * deflection),
+                                (1, false) => params.loop_div_offset.write(1. + deflection),
+                                // shifted thumb sweeps filter
+                                (0, true) => params.filter_sweep.write($x_sign * deflection),
+                                (1, true) => params.resonance.write(deflection.abs()),
+                                _ => unreachable!(),
+                            }
                         }
                     };
                 }
 
                 match index as u8 {
                     TEMPO => {
-                        if cx
+                        if let Some(sweeps) = adc_data.sweeps.as_mut() {
+                            sweeps.tempo.push(*sample);
+                        } else if adc_data.pots.iter().any(|v| v.shift) && *sample != adc_data.tempo
+                        {
+                            // shifted tempo pot trades grain smoothness for latency
+                            adc_data.tempo = *sample;
+                            let abs = adc_data.calibrations.tempo.scale(*sample);
+                            audio::TEXTURE.write(abs);
+                        } else if cx
                             .shared
                             .tempo_tx
                             .lock(|tempo_tx| matches!(tempo_tx.0, input::clock::Source::Internal))
                             && *sample != adc_data.tempo
                         {
+                            if adc_data.tempo.abs_diff(*sample) > power::KNOB_WAKE {
+                                power::note_activity();
+                            }
                             adc_data.tempo = *sample;
-                            let tempo = abs * 270. + 30.;
+                            let tempo = adc_data.calibrations.tempo.scale(*sample) * 270. + 30.;
                             cx.shared.tempo_tx.lock(|tempo_tx| {
                                 if tempo_tx.0 == input::clock::Source::Internal {
                                     tempo_tx.1.write(tempo);
@@ -623,45 +842,88 @@ mod app {
                         pots!(A, *POTS_A.start());
                     }
                     i if THUMB_A.contains(&i) => {
-                        thumb!(A, *THUMB_A.start(), 1. - abs);
+                        thumb!(A, *THUMB_A.start(), -1.);
                     }
                     i if POTS_B.contains(&i) => {
                         pots!(B, *POTS_B.start());
                     }
                     i if THUMB_B.contains(&i) => {
-                        thumb!(B, *THUMB_B.start(), abs);
+                        thumb!(B, *THUMB_B.start(), 1.);
                     }
                     _ => unreachable!(),
                 }
             }
             (buffer, ())
         });
-        transfer.start(|adc| {
-            adc.inner_mut()
-                .cr
-                .modify(|_, w| w.adstart().start_conversion())
-        });
+        if power::idle() {
+            // left stopped for idle_watch to poll slowly
+            power::stop_adc();
+        } else {
+            input::analog::restart(transfer);
+        }
     }
 
-    #[task(binds = DMA1_STR0, shared = [led, system], local = [sai1_transfer], priority = 3)]
+    #[task(binds = DMA1_STR0, shared = [led, system], local = [sai1_transfer, sai1_rx_transfer, dry_level, dither, probe], priority = 3)]
     fn audio_out(mut cx: audio_out::Context) {
         let transfer = cx.local.sai1_transfer;
 
         let mut f32_buffer = [0f32; DMA_BUFFER_LEN];
+        if let Some(level) = audio::DRY.take_level() {
+            *cx.local.dry_level = level;
+        }
+        let dry = audio::DRY.enabled.load(core::sync::atomic::Ordering::Relaxed);
+        let dry_level = *cx.local.dry_level;
+        let dither = cx.local.dither;
+        // codec words are right aligned in u32
+        let shift = 32 - audio::BIT_DEPTH.bits();
+        let mask = u32::MAX >> shift;
+        let full_scale = (mask >> 1) as f32;
+        // drain input regardless, so throughput resumes without stale audio
+        // and capture keeps rolling
+        let mut in_buffer = [0f32; DMA_BUFFER_LEN];
+        let _ = unsafe {
+            cx.local.sai1_rx_transfer.next_dbm_transfer_with(|buffer, _current| {
+                for i in 0..DMA_BUFFER_LEN {
+                    // sign extend codec word
+                    let word = ((buffer[i] << shift) as i32 >> shift) as f32;
+                    in_buffer[i] = word / full_scale;
+                }
+            })
+        };
+        audio::CAPTURE.push(&in_buffer, 2);
+        if let Some(outcome) = cx.local.probe.capture(&in_buffer, 2) {
+            // dropped if last report still blinking
+            let _ = report_latency::spawn(outcome);
+        }
+        if dry {
+            for (out, sample) in f32_buffer.iter_mut().zip(in_buffer) {
+                *out = sample * dry_level;
+            }
+        }
         cx.shared.system.lock(|system| {
-            let _ = system.read_all::<{ audio::SAMPLE_RATE as u16 }, _>(&mut f32_buffer, 2);
+            audio::apply_params(system);
+            if !dry {
+                let _ = system.read_all::<{ audio::SAMPLE_RATE as u16 }, _>(&mut f32_buffer, 2);
+            }
+            // master width, then fade in from silence after sai start
+            system.master(&mut f32_buffer, 2, audio::SAMPLE_RATE);
         });
+        cx.local.probe.send(&mut f32_buffer, 2);
+        if dry || f32_buffer.iter().any(|v| v.abs() > power::SILENCE) {
+            power::note_activity();
+        }
         unsafe {
             if transfer
                 .next_dbm_transfer_with(|buffer, _current| {
                     for i in 0..DMA_BUFFER_LEN {
                         core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
-                        buffer[i] = (f32_buffer[i] * i16::MAX as f32) as i16 as u16 as u32;
+                        buffer[i] = dither.quantize(f32_buffer[i]) as u32 & mask;
                         core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
                     }
                 })
                 .is_err()
             {
+                audio::UNDERRUNS.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
                 cx.shared.led.lock(|led| led.set_high());
             };
         }
@@ -0,0 +1,71 @@
+//! idle power saving for battery builds: once nothing has sounded and nothing
+//! been touched or turned for a while, pots are polled slowly, the tempo led
+//! goes dark and peripherals only driven with the core awake are clock gated
+//! while it sleeps. a touch wakes everything at once
+
+use crate::hal;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// ms without activity before idling, if ever
+pub const IDLE_AFTER: Option<u32> = Some(5 * 60 * 1000);
+/// ms between pot polls while idle
+pub const SLOW_POLL: u32 = 100;
+/// ms between idle checks while awake
+pub const CHECK: u32 = 1000;
+/// output level under which audio counts as silence
+pub const SILENCE: f32 = 1e-4;
+/// raw pot travel counting as activity, clear of conversion noise
+pub const KNOB_WAKE: u16 = 32;
+
+/// activity noted since last taken
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static IDLE: AtomicBool = AtomicBool::new(false);
+/// whether adc sequence left stopped for a slow poll to restart
+static ADC_STOPPED: AtomicBool = AtomicBool::new(false);
+/// whether adc interrupt pended to restart stopped sequence
+static POLL: AtomicBool = AtomicBool::new(false);
+
+/// note audio out or control input, keeping awake
+pub fn note_activity() {
+    ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// whether activity noted since last call
+pub fn take_activity() -> bool {
+    ACTIVE.swap(false, Ordering::Relaxed)
+}
+
+pub fn idle() -> bool {
+    IDLE.load(Ordering::Relaxed)
+}
+
+/// enter or leave idle, gating sd and touch i2c clocks while the core sleeps;
+/// both are polled from tasks, so only ever driven with it awake
+pub fn assign_idle(idle: bool) {
+    IDLE.store(idle, Ordering::Relaxed);
+    // safety: lone writer of these low-power enable bits
+    let rcc = unsafe { &*hal::pac::RCC::ptr() };
+    rcc.ahb3lpenr.modify(|_, w| w.sdmmc1lpen().bit(!idle));
+    rcc.apb1llpenr.modify(|_, w| w.i2c1lpen().bit(!idle));
+}
+
+/// note adc sequence left stopped after its transfer while idle
+pub fn stop_adc() {
+    ADC_STOPPED.store(true, Ordering::Relaxed);
+}
+
+/// whether adc sequence stopped since last call, so due a restart
+pub fn take_adc_stopped() -> bool {
+    ADC_STOPPED.swap(false, Ordering::Relaxed)
+}
+
+/// note adc interrupt about to be pended to restart stopped sequence
+pub fn request_poll() {
+    POLL.store(true, Ordering::Relaxed);
+}
+
+/// whether adc interrupt pended to restart stopped sequence, rather than by a
+/// finished transfer
+pub fn take_poll() -> bool {
+    POLL.swap(false, Ordering::Relaxed)
+}
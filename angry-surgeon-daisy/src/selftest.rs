@@ -0,0 +1,72 @@
+//! boot diagnostics of peripheral health, reported as user led blink codes
+
+use crate::hal;
+use embedded_hal::delay::DelayNs;
+
+/// in ms
+const BLINK_LEN: u32 = 150;
+/// in ms
+const PAUSE_LEN: u32 = 800;
+
+pub type Led = hal::gpio::PC7<hal::gpio::Output<hal::gpio::PushPull>>;
+
+/// checked peripheral; value is its blink count
+#[derive(Copy, Clone)]
+#[repr(u8)]
+pub enum Check {
+    /// sd card mount
+    Sd = 1,
+    /// mpr121 a ack
+    TouchA = 2,
+    /// mpr121 b ack
+    TouchB = 3,
+    /// codec sai start
+    Codec = 4,
+    /// pot calibration loaded from sd
+    Calibration = 5,
+    /// bank loaded without dropping or truncating anything saved
+    BankCompat = 6,
+}
+
+#[derive(Default)]
+pub struct Report {
+    /// bit per failed check
+    failed: u8,
+}
+
+impl Report {
+    pub fn record(&mut self, check: Check, ok: bool) {
+        if ok {
+            self.failed &= !(1 << check as u8);
+        } else {
+            self.failed |= 1 << check as u8;
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// blink count of each failed check, pausing between; one long blink if
+    /// all passed
+    pub fn blink(&self, led: &mut Led, delay: &mut impl DelayNs) {
+        if self.passed() {
+            led.set_high();
+            delay.delay_ms(PAUSE_LEN);
+            led.set_low();
+            return;
+        }
+        for code in 1..8u8 {
+            if self.failed & 1 << code == 0 {
+                continue;
+            }
+            for _ in 0..code {
+                led.set_high();
+                delay.delay_ms(BLINK_LEN);
+                led.set_low();
+                delay.delay_ms(BLINK_LEN);
+            }
+            delay.delay_ms(PAUSE_LEN);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! soak test marks: heap, underrun and file handle high-water marks, rewritten
+//! to sd so the last survive a crash
+
+use crate::audio;
+use angry_surgeon_core::FileHandler as _;
+use core::sync::atomic::Ordering;
+
+/// random play driven by the step clock, if any
+pub const SOAK: Option<angry_surgeon_core::Soak> = None;
+/// steps between log rewrites; ~1 minute at 120 bpm
+const LOG_STEPS: u32 = 480;
+const LOG_PATH: &str = "SOAK.LOG";
+
+#[derive(Default)]
+pub struct Marks {
+    steps: u32,
+    heap: usize,
+    files: usize,
+}
+
+impl Marks {
+    /// step random play, then note marks, logging every `LOG_STEPS`
+    pub fn step(&mut self, soak: angry_surgeon_core::Soak, system: &mut audio::SystemHandler) {
+        let _ = soak.step(system);
+        self.steps += 1;
+        self.heap = self.heap.max(crate::HEAP.used());
+        self.files = self.files.max(system.fs.open_files());
+        if self.steps % LOG_STEPS == 0 {
+            let line = alloc::format!(
+                "{} steps: peak heap {} b, {} underruns, {} open files (peak {})\n",
+                self.steps,
+                self.heap,
+                audio::UNDERRUNS.load(Ordering::Relaxed),
+                system.fs.open_files(),
+                self.files,
+            );
+            if let Ok(file) = system.fs.create_short(LOG_PATH) {
+                let _ = system.fs.write(&file, line.as_bytes());
+                let _ = system.fs.close(&file);
+            }
+        }
+    }
+}
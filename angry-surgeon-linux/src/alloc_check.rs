@@ -0,0 +1,72 @@
+//! allocation counting over the audio callback, enabled by the
+//! `assert-no-alloc` feature
+
+#[cfg(feature = "assert-no-alloc")]
+mod checked {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    thread_local! {
+        static GUARDED: Cell<bool> = const { Cell::new(false) };
+    }
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    struct Checked;
+
+    impl Checked {
+        fn count() {
+            if GUARDED.with(Cell::get) {
+                COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    unsafe impl GlobalAlloc for Checked {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            Self::count();
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            Self::count();
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            Self::count();
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOC: Checked = Checked;
+
+    pub fn guard<R>(f: impl FnOnce() -> R) -> (R, usize) {
+        let before = COUNT.load(Ordering::Relaxed);
+        GUARDED.with(|v| v.set(true));
+        let ret = f();
+        GUARDED.with(|v| v.set(false));
+        (ret, COUNT.load(Ordering::Relaxed) - before)
+    }
+}
+
+/// run `f`, returning allocations made on this thread meanwhile; always 0
+/// unless checking is enabled
+#[cfg(feature = "assert-no-alloc")]
+pub fn guard<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    checked::guard(f)
+}
+
+/// run `f`, returning allocations made on this thread meanwhile; always 0
+/// unless checking is enabled
+#[cfg(not(feature = "assert-no-alloc"))]
+pub fn guard<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    (f(), 0)
+}
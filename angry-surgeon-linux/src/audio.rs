@@ -1,12 +1,17 @@
-use angry_surgeon_core::{Event, Onset};
+use crate::mapping::Pressure;
+use crate::record::Recorder;
+use angry_surgeon_core::{
+    AccentPattern, ClockOffset, Curve, DriftMode, Event, FilterMode, Follow, Groove, Launch,
+    Limits, MotionTarget, Onset, Quantize, Rate, Rd, Release, Resample, Select, Stereo, Wav,
+};
 use color_eyre::Result;
-use cpal::{FromSample, SizedSample};
 use std::{
     io::{Read, Seek},
-    sync::mpsc::Receiver,
+    sync::mpsc::{Receiver, Sender, SyncSender},
 };
 use tinyrand::Seeded;
 
+/// output rate asked of devices; the engine follows whatever is granted
 pub const SAMPLE_RATE: u32 = 48000;
 pub const PPQ: u16 = 24;
 pub const TICKS_PER_STEP: u16 = 4;
@@ -15,6 +20,23 @@ pub const BANK_COUNT: usize = 2;
 pub const PAD_COUNT: usize = 8;
 pub const MAX_PHRASE_COUNT: usize = 128;
 pub const MAX_PHRASE_LEN: usize = 2usize.pow(PAD_COUNT as u32 - 1);
+/// runtime phrase caps; soft, as storage is sized by the maxima above
+pub const PHRASE_LIMITS: Limits = Limits {
+    phrase_len: MAX_PHRASE_LEN as u16,
+    phrase_count: MAX_PHRASE_COUNT,
+};
+/// preferred output channels; others mapped from stereo
+pub const CHANNEL_COUNT: u16 = 2;
+/// samples reserved for stem render buffer, so recording needn't grow it
+pub const SCRATCH_LEN: usize = 16384;
+/// seconds of master output held for rehearsal; 16 bars down to 60 bpm
+const REHEARSAL_SECS: usize = 64;
+/// onset peak level targeted by bank normalization
+const TRIM_TARGET: f32 = 0.9;
+/// pitch raise at full pressure in semitones
+const PRESSURE_SEMITONES: f32 = 2.;
+/// loop division doublings at full pressure
+const PRESSURE_ROLLS: f32 = 3.;
 
 #[derive(Copy, Clone)]
 pub enum Bank {
@@ -22,38 +44,126 @@ pub enum Bank {
     B,
 }
 
+pub const SOURCE_COUNT: usize = 4;
+
+/// record bus source
+#[derive(Copy, Clone)]
+pub enum Source {
+    Master,
+    Oneshot,
+    Bank(Bank),
+}
+
+impl Source {
+    pub fn index(self) -> usize {
+        match self {
+            Source::Master => 0,
+            Source::Oneshot => 1,
+            Source::Bank(bank) => 2 + bank as u8 as usize,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Master => "master",
+            Source::Oneshot => "oneshot",
+            Source::Bank(Bank::A) => "a",
+            Source::Bank(Bank::B) => "b",
+        }
+    }
+}
+
 pub enum Cmd {
     LoadOneshot(std::fs::File),
     StopOneshot,
-    AssignGainOneshot(f32),
+
+    StartRecord(Source, Recorder),
+    StopRecord(Source),
+    ProbeLatency(std::sync::Arc<crate::latency::Probe>),
 
     Tick,
     Stop,
+    /// jump to steps from song start
+    Locate(u32),
+    /// offset clock ticks by ms, delaying if positive
+    AssignClockOffset(f32),
+    /// fade master out ahead of stream teardown
+    FadeOut,
     AssignTempo(f32),
-    OffsetPitch(f32),
+    /// restart integrated loudness
+    ResetLoudness,
+    /// loop last bars of master output, or release loop
+    Rehearse(bool),
+    AssignRehearsalSpeed(f32),
+    /// pre-listen quantized hits on cue pair, or stop
+    Cue(bool),
+    /// sound metronome click, or silence it
+    Click(bool),
+    /// one step of random play
+    Soak(angry_surgeon_core::Soak),
+    /// save performance parameters of every bank as named scene at index
+    CaptureScene(usize, String),
+    /// interpolate banks toward named scene at index over steps
+    RecallScene(usize, String, u16),
     Bank(Bank, BankCmd),
 }
 
 pub enum BankCmd {
-    AssignGain(f32),
-    AssignWidth(f32),
-    AssignPitch(f32),
-    AssignRoll(f32),
-    AssignKitDrift(f32),
-    AssignPhraseDrift(f32),
-
-    SaveBank(std::fs::File),
+    AssignRollCurve(Curve),
+    AssignDriftMode(DriftMode),
+    AssignLaunch(Launch),
+    AssignGroove(Option<Box<Groove>>),
+    ClearMotions,
+    AssignAccent(AccentPattern),
+    AssignResample(Resample),
+    AssignPadPitch(u8, Option<f32>),
+    AssignFilterMode(FilterMode),
+    AssignStereo(Stereo),
+    /// hold or release playhead
+    AssignFreeze(bool),
+    AssignFollow(u8, Follow),
+
+    SaveBank(String),
     LoadBank(Box<angry_surgeon_core::Bank<PAD_COUNT, MAX_PHRASE_LEN>>),
+    Normalize(Box<[[Option<f32>; PAD_COUNT]; PAD_COUNT]>),
     LoadKit(u8),
+    /// copy kit over another
+    CopyKit(u8, u8),
+    /// swap two pads of loaded kit
+    SwapPads(u8, u8),
+    /// empty loaded kit
+    ClearKit,
     AssignOnset(u8, Box<Onset>),
+    /// layer onset onto pad of loaded kit
+    PushLayer(u8, Box<Onset>),
+    /// assign opening onsets of rd over pads of loaded kit
+    Spread(Box<Rd>, Box<Wav>),
+    AssignSelect(u8, Select),
 
+    /// begin a long take, chained over free pads once taken
+    ArmRecord,
     ForceEvent(Event),
     PushEvent(Event),
+    /// push pad hit at velocity in 0..=1
+    PushHit(Event, f32),
     PushReverse(bool),
+    /// hold or release micro-loop
+    PushStutter(bool),
+    /// ramp to a stop, or back up to speed
+    PushTapeStop(bool),
+    PushRelease,
+    AssignRelease(Release, f32),
     TrimRecord(u16),
+    AssignRecordQuantize(Quantize),
+    AssignRecordRate(Rate),
+    TogglePadLock(u8),
+    ToggleStepLock,
     TakeRecord(Option<u8>),
+    CaptureSequence(u8, u16),
     ClearSequence,
     PushSequence(u8),
+    SavePool(String),
+    RecallPool(usize),
 }
 
 pub struct Oneshot<const LEN: usize> {
@@ -163,9 +273,7 @@ impl<const LEN: usize> Oneshot<LEN> {
         buffer: &mut [T],
         channels: usize,
     ) -> Result<(), std::io::Error> {
-        // TODO: support other channel counts?
-        assert!(channels == 2);
-        for i in 0..buffer.len() / channels {
+        for frame in buffer.chunks_exact_mut(channels) {
             // update buffer if necessary
             self.fill()?;
             if self.rem == 0 {
@@ -177,8 +285,66 @@ impl<const LEN: usize> Oneshot<LEN> {
             self.index += 1;
             self.rem -= 2;
 
-            buffer[i * 2] += T::from(word);
-            buffer[i * 2 + 1] += T::from(word);
+            angry_surgeon_core::add_stereo(frame, word, word);
+        }
+        Ok(())
+    }
+}
+
+/// recording timeline marker, formatted only once written out
+#[derive(Copy, Clone)]
+pub struct Mark {
+    what: &'static str,
+    /// none for marks across banks, e.g. scenes
+    bank: Option<Bank>,
+    index: Option<u8>,
+}
+
+impl Mark {
+    fn new(what: &'static str, bank: Bank, index: Option<u8>) -> Self {
+        Self {
+            what,
+            bank: Some(bank),
+            index,
+        }
+    }
+
+    /// mark of scene at `index`, unnumbered past `u8::MAX`
+    fn scene(what: &'static str, index: usize) -> Self {
+        Self {
+            what,
+            bank: None,
+            index: u8::try_from(index).ok(),
+        }
+    }
+
+    /// formatted length in bytes
+    pub(crate) fn len(&self) -> u32 {
+        struct Count(u32);
+        impl core::fmt::Write for Count {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                self.0 += s.len() as u32;
+                Ok(())
+            }
+        }
+        let mut count = Count(0);
+        let _ = core::fmt::write(&mut count, format_args!("{}", self));
+        count.0
+    }
+}
+
+impl core::fmt::Display for Mark {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.what)?;
+        match (self.bank, self.index) {
+            (Some(bank), index) => {
+                write!(f, " {}", Source::Bank(bank).name())?;
+                if let Some(index) = index {
+                    write!(f, "{}", index)?;
+                }
+            }
+            (None, Some(index)) => write!(f, " {}", index)?,
+            (None, None) => (),
         }
         Ok(())
     }
@@ -193,75 +359,571 @@ pub struct SystemHandler {
         tinyrand::Wyrand,
         crate::fs::LinuxFileHandler,
     >,
-    oneshot: Oneshot<{ angry_surgeon_core::GRAIN_LEN * 2 }>,
+    oneshot: Oneshot<{ angry_surgeon_core::MAX_GRAIN_LEN * 2 }>,
+    recorders: [Option<Recorder>; SOURCE_COUNT],
+    /// per-source render buffer while recording stems
+    scratch: Vec<f32>,
+    /// output rate granted by the device
+    sample_rate: u32,
+    /// clock ticks since start or locate, so recordings mark steps
+    ticks: u32,
+    /// latency probe awaiting click, if any
+    probe: Option<std::sync::Arc<crate::latency::Probe>>,
+    /// external clock ticks held back or fired ahead of time
+    offset: ClockOffset,
+    /// dry throughput level
+    dry_level: f32,
+    /// pitch bend offset of second bank
+    bend: f32,
+    /// pitch offset by pressure per bank
+    pitch_pressure: [f32; BANK_COUNT],
+    params: std::sync::Arc<crate::params::Params>,
     cmd_rx: Receiver<Cmd>,
+    tui_tx: Sender<crate::tui::Cmd>,
+    /// clock and pad hits out, if midi output requested
+    midi_tx: Option<SyncSender<crate::midi_out::Msg>>,
 }
 
 impl SystemHandler {
-    pub fn new(cmd_rx: Receiver<Cmd>) -> Result<Self> {
+    pub fn new(
+        params: std::sync::Arc<crate::params::Params>,
+        cmd_rx: Receiver<Cmd>,
+        tui_tx: Sender<crate::tui::Cmd>,
+        midi_tx: Option<SyncSender<crate::midi_out::Msg>>,
+        output: crate::output::Output,
+    ) -> Result<Self> {
+        let mut system = angry_surgeon_core::SystemHandler::new(
+            TICKS_PER_STEP,
+            tinyrand::Wyrand::seed(0xf2aa),
+            crate::fs::LinuxFileHandler {},
+            PHRASE_LIMITS,
+        );
+        system.routes = output.routes;
+        system.click.route = output.click;
+        system.click.enabled = output.click.is_some();
+        if let Some(target) = output.lufs_target {
+            system.loudness.target = target;
+        }
+        if let Some(bars) = output.rehearse_bars {
+            system.rehearsal.bars = bars;
+        }
+        let sample_rate = params
+            .sample_rate
+            .load(std::sync::atomic::Ordering::Relaxed);
+        system.assign_rehearsal(REHEARSAL_SECS * sample_rate as usize);
+        system.assign_voices(output.voices)?;
+        // tail length is a live control, so its voice is held from the start
+        system.assign_tails(true)?;
+        system.assign_cue(output.cue)?;
+        for bank in system.banks.iter_mut() {
+            if let Some(ms) = output.smoothing {
+                bank.smooth_len = ms / 1000.;
+            }
+            bank.interpolation = output.interpolation;
+        }
+        let mut offset = ClockOffset::default();
+        offset.assign(output.clock_offset, sample_rate);
         Ok(Self {
-            system: angry_surgeon_core::SystemHandler::new(
-                TICKS_PER_STEP,
-                tinyrand::Wyrand::seed(0xf2aa),
-                crate::fs::LinuxFileHandler {},
-            ),
+            system,
             oneshot: Oneshot::new(),
+            recorders: core::array::from_fn(|_| None),
+            scratch: Vec::with_capacity(SCRATCH_LEN),
+            sample_rate,
+            ticks: 0,
+            probe: None,
+            offset,
+            dry_level: 1.,
+            bend: 1.,
+            pitch_pressure: [1.; BANK_COUNT],
+            params,
             cmd_rx,
+            tui_tx,
+            midi_tx,
         })
     }
 
-    pub fn tick<T>(&mut self, buffer: &mut [T], channels: usize) -> Result<()>
-    where
-        T: SizedSample + FromSample<f32>,
-    {
+    pub fn tick(&mut self, buffer: &mut [f32], channels: usize) -> Result<()> {
+        self.apply_params();
         while let Ok(cmd) = self.cmd_rx.try_recv() {
             match cmd {
                 Cmd::LoadOneshot(file) => self.oneshot.load(Some(file))?,
                 Cmd::StopOneshot => self.oneshot.load(None)?,
-                Cmd::AssignGainOneshot(v) => self.oneshot.gain = v,
 
-                Cmd::Tick => self.system.tick()?,
-                Cmd::Stop => self.system.stop(),
+                // writers close takes out on their own threads
+                Cmd::StartRecord(source, recorder) => {
+                    if let Some(recorder) = self.recorders[source.index()].replace(recorder) {
+                        recorder.finish(&self.tui_tx);
+                    }
+                }
+                Cmd::StopRecord(source) => {
+                    if let Some(recorder) = self.recorders[source.index()].take() {
+                        recorder.finish(&self.tui_tx);
+                    }
+                }
+                Cmd::ProbeLatency(probe) => self.probe = Some(probe),
+
+                Cmd::Tick => {
+                    if self.offset.push() {
+                        self.step()?;
+                    }
+                }
+                Cmd::Stop => self.stop(),
+                Cmd::Locate(step) => self.locate(step),
+                Cmd::AssignClockOffset(v) => self.offset.assign(v, self.sample_rate),
+                Cmd::FadeOut => self.system.fade_out(),
                 Cmd::AssignTempo(v) => self.system.assign_tempo(v),
-                Cmd::OffsetPitch(v) => {
-                    // only affects second bank
-                    self.system.banks[1].pitch.offset = v;
+                Cmd::ResetLoudness => self.system.loudness.reset(),
+                Cmd::Rehearse(true) => {
+                    let looping = self.system.rehearsal.loop_last();
+                    let _ = self.tui_tx.send(crate::tui::Cmd::Rehearse(looping));
+                }
+                Cmd::Rehearse(false) => {
+                    self.system.rehearsal.release();
+                    let _ = self.tui_tx.send(crate::tui::Cmd::Rehearse(false));
+                }
+                Cmd::AssignRehearsalSpeed(v) => self.system.rehearsal.speed = v,
+                Cmd::Cue(cue) => {
+                    for bank_h in self.system.banks.iter_mut() {
+                        bank_h.cue = cue;
+                    }
+                }
+                Cmd::Click(click) => self.system.click.enabled = click,
+                Cmd::Soak(soak) => soak.step(&mut self.system)?,
+                Cmd::CaptureScene(index, name) => {
+                    self.system.capture_scene(&name);
+                    Self::mark(&mut self.recorders, Mark::scene("capture scene", index));
+                }
+                Cmd::RecallScene(index, name, steps) => {
+                    self.system.recall_scene(&name, steps);
+                    Self::mark(&mut self.recorders, Mark::scene("recall scene", index));
                 }
                 Cmd::Bank(bank, cmd) => {
                     let bank_h = &mut self.system.banks[bank as u8 as usize];
                     match cmd {
-                        BankCmd::AssignGain(v) => bank_h.gain = v,
-                        BankCmd::AssignWidth(v) => bank_h.width = v,
-                        BankCmd::AssignPitch(v) => bank_h.pitch.base = v,
-                        BankCmd::AssignRoll(v) => bank_h.loop_div.base = v,
-                        BankCmd::AssignKitDrift(v) => bank_h.kit_drift = v,
-                        BankCmd::AssignPhraseDrift(v) => bank_h.phrase_drift = v,
-
-                        BankCmd::SaveBank(file) => {
-                            serde_json::to_writer_pretty(file, &bank_h.bank)?;
+                        BankCmd::AssignRollCurve(v) => bank_h.assign_roll_curve(v),
+                        BankCmd::AssignGroove(v) => bank_h.bank.groove = v.map(|v| *v),
+                        BankCmd::ClearMotions => bank_h.clear_motions(),
+                        BankCmd::AssignAccent(v) => bank_h.accent.pattern = v,
+                        BankCmd::AssignResample(v) => bank_h.resample = v,
+                        BankCmd::AssignPadPitch(index, v) => bank_h.assign_pad_pitch(index, v),
+                        BankCmd::AssignFilterMode(v) => bank_h.filter = v,
+                        BankCmd::AssignStereo(v) => bank_h.stereo = v,
+                        BankCmd::AssignFreeze(v) => bank_h.freeze = v,
+                        BankCmd::AssignFollow(index, v) => bank_h.assign_follow(index, v),
+                        BankCmd::AssignDriftMode(v) => {
+                            bank_h.kit_drift.mode = v;
+                            bank_h.phrase_drift.mode = v;
+                        }
+                        BankCmd::AssignLaunch(v) => bank_h.launch = v,
+
+                        // encoded here, written out by the tui off the audio thread
+                        BankCmd::SaveBank(path) => {
+                            let bytes = bank_h.bank.to_bd();
+                            let _ = self.tui_tx.send(crate::tui::Cmd::SaveBank(path, bytes));
                         }
-                        BankCmd::LoadBank(bank) => bank_h.bank = *bank,
+                        BankCmd::LoadBank(loaded) => {
+                            bank_h.assign_bank(*loaded);
+                            Self::mark(&mut self.recorders, Mark::new("load bank", bank, None));
+                        }
+                        BankCmd::Normalize(peaks) => bank_h.bank.normalize(&peaks, TRIM_TARGET),
                         BankCmd::LoadKit(index) => bank_h.kit_index = index,
+                        BankCmd::CopyKit(from, to) => bank_h.bank.copy_kit(from, to),
+                        BankCmd::SwapPads(a, b) => bank_h.bank.swap_pads(bank_h.kit_index, a, b),
+                        BankCmd::ClearKit => bank_h.bank.clear_kit(bank_h.kit_index),
                         BankCmd::AssignOnset(index, onset) => bank_h.assign_onset(index, *onset),
+                        BankCmd::PushLayer(index, onset) => bank_h.push_layer(index, *onset),
+                        BankCmd::Spread(rd, wav) => {
+                            bank_h.bank.spread(bank_h.kit_index, &rd, &wav);
+                        }
+                        BankCmd::AssignSelect(index, v) => bank_h.assign_select(index, v),
                         BankCmd::ForceEvent(event) => {
                             bank_h.force_event(event, &mut self.system.rand, &mut self.system.fs)?
                         }
                         BankCmd::PushEvent(event) => {
                             bank_h.push_event(event, &mut self.system.rand, &mut self.system.fs)?
                         }
+                        BankCmd::PushHit(event, velocity) => bank_h.push_hit(
+                            event,
+                            velocity,
+                            &mut self.system.rand,
+                            &mut self.system.fs,
+                        )?,
                         BankCmd::PushReverse(reverse) => bank_h.push_reverse(reverse),
-                        BankCmd::TrimRecord(len) => bank_h.trim_record(len),
-                        BankCmd::TakeRecord(index) => bank_h.take_record(index),
-                        BankCmd::ClearSequence => bank_h.clear_sequence(),
+                        BankCmd::PushStutter(down) => bank_h.push_stutter(down),
+                        BankCmd::PushTapeStop(stop) => bank_h.push_tape_stop(stop),
+                        BankCmd::PushRelease => {
+                            bank_h.push_release(&mut self.system.rand, &mut self.system.fs)?
+                        }
+                        BankCmd::AssignRelease(release, len) => {
+                            bank_h.release = release;
+                            bank_h.release_len = len;
+                        }
+                        BankCmd::ArmRecord => bank_h.arm_record(),
+                        BankCmd::TrimRecord(len) => {
+                            bank_h.trim_record(len, &mut self.system.fs)?
+                        }
+                        BankCmd::AssignRecordQuantize(quantize) => {
+                            bank_h.assign_record_quantize(quantize)
+                        }
+                        BankCmd::AssignRecordRate(rate) => bank_h.assign_record_rate(rate),
+                        BankCmd::TogglePadLock(index) => {
+                            bank_h.toggle_pad_lock(index);
+                        }
+                        BankCmd::ToggleStepLock => {
+                            bank_h.toggle_step_lock();
+                        }
+                        BankCmd::TakeRecord(index) => {
+                            let dropped = bank_h.take_record(index, &mut self.system.fs)?;
+                            if let Some(index) = index {
+                                Self::mark(
+                                    &mut self.recorders,
+                                    Mark::new("take phrase", bank, Some(index)),
+                                );
+                                let mut linked = 0;
+                                for pad in bank_h.chain(index) {
+                                    linked += 1;
+                                    let _ = self.tui_tx.send(crate::tui::Cmd::Bank(
+                                        bank,
+                                        crate::tui::BankCmd::Chain(pad),
+                                    ));
+                                }
+                                if linked > 0 || dropped > 0 {
+                                    let chained = crate::tui::Cmd::Chained { linked, dropped };
+                                    let _ = self.tui_tx.send(chained);
+                                }
+                            }
+                        }
+                        BankCmd::CaptureSequence(index, len) => {
+                            if bank_h.capture_sequence(index, len) {
+                                Self::mark(
+                                    &mut self.recorders,
+                                    Mark::new("capture phrase", bank, Some(index)),
+                                );
+                                let _ = self.tui_tx.send(crate::tui::Cmd::Bank(
+                                    bank,
+                                    crate::tui::BankCmd::CaptureSequence(index, len),
+                                ));
+                            }
+                        }
+                        BankCmd::ClearSequence => bank_h.clear_sequence(&mut self.system.fs)?,
                         BankCmd::PushSequence(index) => bank_h.push_sequence(index),
+                        BankCmd::SavePool(name) => bank_h.save_pool(name),
+                        BankCmd::RecallPool(index) => {
+                            bank_h.recall_pool(index, &mut self.system.fs)?
+                        }
+                    }
+                }
+            }
+        }
+        // render up to each offset tick falling due this buffer, then past it
+        let mut buffer = buffer;
+        while let Some(frames) = self.offset.next(buffer.len() / channels) {
+            let (head, tail) = buffer.split_at_mut(frames * channels);
+            self.render(head, channels)?;
+            self.step()?;
+            buffer = tail;
+        }
+        self.offset.advance(buffer.len() / channels);
+        self.render(buffer, channels)?;
+        Ok(())
+    }
+
+    /// as `tick`, also stepping at each of `steps`, frame offsets into
+    /// `buffer` in order, e.g. as followed from a host transport
+    pub fn tick_at(&mut self, buffer: &mut [f32], channels: usize, steps: &[usize]) -> Result<()> {
+        let mut buffer = buffer;
+        let mut done = 0;
+        for &step in steps {
+            let (head, tail) = buffer.split_at_mut((step - done) * channels);
+            if !head.is_empty() {
+                self.tick(head, channels)?;
+            }
+            self.step()?;
+            buffer = tail;
+            done = step;
+        }
+        self.tick(buffer, channels)
+    }
+
+    /// stop clock, echoed to midi output
+    pub fn stop(&mut self) {
+        self.ticks = 0;
+        self.offset.stop();
+        self.system.stop();
+        self.send_midi(crate::midi_out::Msg::Stop);
+    }
+
+    /// jump to `step` from song start
+    pub fn locate(&mut self, step: u32) {
+        // ticks held back belong before the jump
+        self.offset.stop();
+        self.ticks = step.wrapping_mul(TICKS_PER_STEP as u32);
+        self.system.locate(step);
+    }
+
+    pub fn assign_tempo(&mut self, tempo: f32) {
+        self.system.assign_tempo(tempo);
+    }
+
+    /// clear sequences of every bank, as on a stop from outside
+    pub fn clear_sequences(&mut self) -> Result<()> {
+        for bank_h in self.system.banks.iter_mut() {
+            bank_h.clear_sequence(&mut self.system.fs)?;
+        }
+        Ok(())
+    }
+
+    /// one clock tick of every bank, echoed to midi output
+    fn step(&mut self) -> Result<()> {
+        if self.ticks.is_multiple_of(TICKS_PER_STEP as u32) {
+            for recorder in self.recorders.iter_mut().flatten() {
+                recorder.step();
+            }
+        }
+        self.ticks = self.ticks.wrapping_add(1);
+        self.system.tick()?;
+        self.send_midi(crate::midi_out::Msg::Tick);
+        // report phrase activity for heatmap
+        for (params, bank_h) in self.params.banks.iter().zip(self.system.banks.iter()) {
+            if let Some(activity) = bank_h.activity() {
+                params.activity.write(activity);
+            }
+        }
+        Ok(())
+    }
+
+    /// mix interleaved `buffer` of `channels` from every source
+    fn render(&mut self, buffer: &mut [f32], channels: usize) -> Result<()> {
+        buffer.fill(0.);
+        if self.params.dry.enabled.load(std::sync::atomic::Ordering::Relaxed) {
+            // mangler muted, input passed straight through
+            self.params.dry.read(buffer, channels, self.dry_level);
+        } else if self.recorders.iter().all(|v| v.is_none()) {
+            self.oneshot.read_attenuated(buffer, channels)?;
+            self.system.read_all(buffer, channels, self.sample_rate)?;
+        } else {
+            self.read_recorded(buffer, channels)?;
+        }
+        if self.midi_tx.is_some() {
+            for bank in 0..BANK_COUNT {
+                if let Some(pad) = self.system.banks[bank].take_hit() {
+                    self.send_midi(crate::midi_out::Msg::Hit(bank, pad));
+                }
+            }
+        }
+        // master width, then de-click stream start and teardown
+        self.system.master(buffer, channels, self.sample_rate);
+        if let Some(probe) = self.probe.take() {
+            // full-scale click at head of buffer
+            for sample in buffer
+                .iter_mut()
+                .take(crate::latency::CLICK_LEN * channels)
+            {
+                *sample = 1.;
+            }
+            probe.send();
+        }
+        // every handle opened is held by an onset or closed
+        crate::fs::assert_open(self.system.open_onsets());
+        self.params.prefetch_misses.store(
+            self.system.prefetch_misses(),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        self.params.loudness.write(&self.system.loudness);
+        // report levels once a meter window completes
+        if let Some(master) = self.system.meter.take() {
+            let banks = core::array::from_fn(|i| self.system.banks[i].meter.take());
+            let banks = banks.map(Option::unwrap_or_default);
+            let _ = self.tui_tx.send(crate::tui::Cmd::Meter(banks, master));
+        }
+        Ok(())
+    }
+
+    /// queue `msg` for midi output, if any, dropping it if the queue is full
+    fn send_midi(&mut self, msg: crate::midi_out::Msg) {
+        if let Some(midi_tx) = self.midi_tx.as_ref() {
+            let _ = midi_tx.try_send(msg);
+        }
+    }
+
+    /// apply continuous parameters written since last callback
+    fn apply_params(&mut self) {
+        if let Some(v) = self.params.gain_oneshot.take() {
+            self.oneshot.gain = v;
+        }
+        if let Some(v) = self.params.dry.level.take() {
+            self.dry_level = v;
+        }
+        if let Some(v) = self.params.master_width.take() {
+            self.system.width = Some(v);
+        }
+        if let Some(v) = self.params.master_gain.take() {
+            self.system.gain = v;
+        }
+        if let Some(v) = self.params.crossfade.take() {
+            self.system.crossfade = Some(v);
+        }
+        if let Some(v) = self.params.texture.take() {
+            self.system.assign_texture(v);
+        }
+        if let Some(v) = self.params.delay_feedback.take() {
+            self.system.delay.feedback = v;
+        }
+        if let Some(v) = self.params.delay_division.take() {
+            self.system.delay.division = v;
+        }
+        if let Some(v) = self.params.drive.take() {
+            self.system.clipper.drive = v;
+        }
+        if let Some(v) = self.params.ceiling.take() {
+            self.system.clipper.ceiling = v;
+        }
+        if let Some(v) = self.params.pitch_offset.take() {
+            // only affects second bank
+            self.bend = v;
+            self.system.banks[1].pitch.offset = v * self.pitch_pressure[1];
+        }
+        for (index, (params, bank_h)) in self
+            .params
+            .banks
+            .iter()
+            .zip(self.system.banks.iter_mut())
+            .enumerate()
+        {
+            let motion = params.motion.load(std::sync::atomic::Ordering::Relaxed);
+            for (param, target) in [
+                (&params.gain, MotionTarget::Gain),
+                (&params.width, MotionTarget::Width),
+                (&params.pitch, MotionTarget::Pitch),
+                (&params.roll, MotionTarget::Roll),
+                (&params.kit_drift, MotionTarget::KitDrift),
+                (&params.phrase_drift, MotionTarget::PhraseDrift),
+                (&params.humanize, MotionTarget::Humanize),
+                (&params.cutoff, MotionTarget::Cutoff),
+                (&params.resonance, MotionTarget::Resonance),
+            ] {
+                if let Some(v) = param.take() {
+                    if motion {
+                        bank_h.record_motion(target, v);
+                    } else {
+                        bank_h.assign(target, v);
                     }
                 }
             }
+            if !motion {
+                bank_h.stop_motion_record();
+            }
+            if let Some(v) = params.accent.take() {
+                bank_h.accent.gain = v;
+            }
+            if let Some(v) = params.attack.take() {
+                bank_h.envelope.attack = v;
+            }
+            if let Some(v) = params.release.take() {
+                bank_h.envelope.release = v;
+            }
+            if let Some(v) = params.send.take() {
+                bank_h.send = v;
+            }
+            if let Some(v) = params.sensitivity.take() {
+                bank_h.sensitivity = v;
+            }
+            if let Some(v) = params.slip.take() {
+                bank_h.slip = v as i16;
+            }
+            if let Some(v) = params.density.take() {
+                bank_h.generator.density = v;
+            }
+            if let Some(v) = params.loop_fine.take() {
+                bank_h.assign_loop_fine(v);
+            }
+            if let Some(v) = params.stutter.take() {
+                bank_h.assign_stutter(v);
+            }
+            if let Some(v) = params.tape_len.take() {
+                bank_h.tape_len = v;
+            }
+            if let Some(v) = params.freeze_size.take() {
+                bank_h.freeze_size = v;
+            }
+            if let Some(v) = params.freeze_jitter.take() {
+                bank_h.freeze_jitter = v;
+            }
+            if let Some(v) = params.tail_len.take() {
+                bank_h.tail_len = v;
+            }
+            if let Some(v) = params.pressure[Pressure::Pitch as usize].take() {
+                self.pitch_pressure[index] = 2f32.powf(v * PRESSURE_SEMITONES / 12.);
+                let bend = if index == 1 { self.bend } else { 1. };
+                bank_h.pitch.offset = bend * self.pitch_pressure[index];
+            }
+            if let Some(v) = params.pressure[Pressure::Filter as usize].take() {
+                bank_h.cutoff.offset = 1. + v;
+            }
+            if let Some(v) = params.pressure[Pressure::LoopDiv as usize].take() {
+                bank_h.loop_div.offset = 2f32.powi((v * PRESSURE_ROLLS) as i32);
+            }
         }
-        buffer.fill(T::EQUILIBRIUM);
-        let f32_buffer: &mut [f32] = unsafe { core::mem::transmute(buffer) };
-        self.oneshot.read_attenuated(f32_buffer, channels)?;
-        self.system.read_all(f32_buffer, channels, SAMPLE_RATE)?;
+    }
+
+    /// drop a timeline marker into every running recording
+    fn mark(recorders: &mut [Option<Recorder>; SOURCE_COUNT], mark: Mark) {
+        for recorder in recorders.iter_mut().flatten() {
+            recorder.mark(mark);
+        }
+    }
+
+    /// push `buffer` to the recording of `source`, if any, finishing it early
+    /// if its writer failed or fell behind rather than fail the tick
+    fn record(
+        recorders: &mut [Option<Recorder>; SOURCE_COUNT],
+        source: Source,
+        buffer: &[f32],
+        tui_tx: &Sender<crate::tui::Cmd>,
+    ) {
+        let recorder = &mut recorders[source.index()];
+        if recorder.as_mut().is_some_and(|v| !v.write(buffer)) {
+            if let Some(recorder) = recorder.take() {
+                recorder.finish(tui_tx);
+            }
+        }
+    }
+
+    /// render each source separately so armed sources can be captured as stems
+    fn read_recorded(&mut self, buffer: &mut [f32], channels: usize) -> Result<()> {
+        self.scratch.resize(buffer.len(), 0.);
+        self.scratch.fill(0.);
+        self.oneshot.read_attenuated(&mut self.scratch, channels)?;
+        Self::record(
+            &mut self.recorders,
+            Source::Oneshot,
+            &self.scratch,
+            &self.tui_tx,
+        );
+        for (w, r) in buffer.iter_mut().zip(self.scratch.iter()) {
+            *w += *r;
+        }
+        // bank stems dry; delay return heard only in master
+        let (recorders, tui_tx) = (&mut self.recorders, &self.tui_tx);
+        self.system
+            .read_stems(buffer, channels, self.sample_rate, |bank, chunk| {
+                let source = match bank {
+                    Some(0) => Source::Bank(Bank::A),
+                    Some(_) => Source::Bank(Bank::B),
+                    None => return,
+                };
+                Self::record(recorders, source, chunk, tui_tx);
+            })?;
+        Self::record(&mut self.recorders, Source::Master, buffer, &self.tui_tx);
         Ok(())
     }
 }
+
+impl Drop for SystemHandler {
+    fn drop(&mut self) {
+        // off the audio thread by now, so wait for takes to reach disk
+        for recorder in self.recorders.iter_mut().filter_map(|v| v.take()) {
+            let _ = recorder.finish(&self.tui_tx).join();
+        }
+    }
+}
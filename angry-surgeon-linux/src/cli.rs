@@ -0,0 +1,140 @@
+//! command line: devices picked by flag, prompted for only where left out, so
+//! launch scripts run unattended. flags configuring other modules declared
+//! here too for help and validation, though each module parses its own
+
+use color_eyre::Result;
+use std::io::Write;
+
+#[derive(clap::Parser)]
+#[command(about = "sample mangler for midi controllers", version)]
+pub struct Cli {
+    /// audio host, by name or listed index
+    #[arg(long)]
+    pub host: Option<String>,
+    /// audio output device, by name or listed index
+    #[arg(long)]
+    pub output: Option<String>,
+    /// midi input port, by name or listed index
+    #[arg(long)]
+    pub midi_in: Option<String>,
+    /// output sample rate in hz [default: 48000 where offered, else the
+    /// device's highest]
+    #[arg(long)]
+    pub sample_rate: Option<u32>,
+    /// output buffer size in frames; device default if left out
+    #[arg(long)]
+    pub buffer_size: Option<u32>,
+    #[command(flatten)]
+    _others: Others,
+}
+
+/// flags parsed by the modules they configure, see their `from_args`
+#[derive(clap::Args)]
+#[allow(dead_code)]
+struct Others {
+    /// native jack backend following transport, instead of a cpal host
+    #[arg(long)]
+    jack: bool,
+    /// send clock and pad notes to a midi output port
+    #[arg(long)]
+    midi_out: bool,
+    /// serve osc control on this udp port
+    #[arg(long, value_name = "PORT")]
+    osc: Option<u16>,
+    /// controller mapping file [default: ./mapping.json if there]
+    #[arg(long, value_name = "PATH")]
+    mapping: Option<String>,
+    /// depth of recordings and 32-bit integer devices, 16 or 24
+    #[arg(long, value_name = "BITS")]
+    bits: Option<u8>,
+    /// truncate integer output rather than dither
+    #[arg(long)]
+    no_dither: bool,
+    /// output pair of bank a, from 1 [default: every pair]
+    #[arg(long, value_name = "PAIR")]
+    route_a: Option<usize>,
+    /// output pair of bank b, from 1 [default: every pair]
+    #[arg(long, value_name = "PAIR")]
+    route_b: Option<usize>,
+    /// output pair carrying the metronome click, from 1, starting it on
+    /// [default: mixed into every pair, off]
+    #[arg(long, value_name = "PAIR")]
+    click: Option<usize>,
+    /// output pair carrying pre-listen, from 1
+    #[arg(long, value_name = "PAIR")]
+    cue: Option<usize>,
+    /// integrated loudness to monitor against in lufs
+    #[arg(long, value_name = "LUFS", allow_negative_numbers = true)]
+    lufs_target: Option<f32>,
+    /// rehearsal loop length in bars
+    #[arg(long, value_name = "BARS")]
+    rehearse_bars: Option<usize>,
+    /// voices per bank displaced onsets ring out in, monophonic at 0 [default: 4]
+    #[arg(long, value_name = "COUNT")]
+    voices: Option<usize>,
+    /// ms external clock ticks are delayed by, ahead of time if negative
+    #[arg(long, value_name = "MS", allow_negative_numbers = true)]
+    clock_offset: Option<f32>,
+    /// ms gain, width and speed changes ramp over, stepwise at 0 [default: 10]
+    #[arg(long, value_name = "MS")]
+    smoothing: Option<f32>,
+    /// grain interpolation, linear or hermite [default: hermite]
+    #[arg(long, value_name = "MODE")]
+    interpolation: Option<String>,
+    /// SCHED_FIFO priority of the render thread, 1 to 99
+    #[arg(long, value_name = "PRIORITY")]
+    priority: Option<usize>,
+    /// cpu to pin the render thread to
+    #[arg(long, value_name = "CPU")]
+    cpu: Option<usize>,
+    /// list only raw hardware devices, bypassing software mixing
+    #[arg(long)]
+    raw: bool,
+    /// clock random play unattended at this intensity, 0 to 1
+    #[arg(long, value_name = "INTENSITY")]
+    soak: Option<f32>,
+}
+
+/// index of the `what` picked from `names`: matched by `flag` if given, by
+/// exact name, listed index, then sole partial name; else the only one on
+/// offer, else prompted for
+pub fn select(what: &str, names: &[String], flag: Option<&str>) -> Result<usize> {
+    if let Some(flag) = flag {
+        let lower = flag.to_lowercase();
+        let mut partial = names
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.to_lowercase().contains(&lower))
+            .map(|(i, _)| i);
+        let msg = format!("no single {} matches {}", what, flag);
+        let index = names
+            .iter()
+            .position(|v| v == flag)
+            .or(flag.parse().ok().filter(|v| *v < names.len()))
+            .or(partial.next().filter(|_| partial.next().is_none()))
+            .ok_or(color_eyre::Report::msg(msg))?;
+        println!("\nselected {}: {}", what, names[index]);
+        return Ok(index);
+    }
+    match names.len() {
+        0 => Err(color_eyre::Report::msg(format!("no {} found", what))),
+        1 => {
+            println!("\nselected only available {}: {}", what, names[0]);
+            Ok(0)
+        }
+        _ => {
+            println!("\navailable {}s:", what);
+            for (i, name) in names.iter().enumerate() {
+                println!("{}: {}", i, name);
+            }
+            print!("select an {}: ", what);
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            let msg = format!("invalid {} selected", what);
+            Some(input.trim().parse::<usize>()?)
+                .filter(|v| *v < names.len())
+                .ok_or(color_eyre::Report::msg(msg))
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! synthesized demo content: test wavs, their rds and tags, a groove and a bank
+
+use crate::audio::{MAX_PHRASE_LEN, PAD_COUNT, SAMPLE_RATE};
+use angry_surgeon_core::{Bank, Groove, GrooveStep, Onset, Rd, Tags, Wav};
+use color_eyre::Result;
+use std::{f32::consts::TAU, io::Write, path::Path};
+
+/// seconds per onset segment; two steps at tempo 120
+const SEGMENT_LEN: f32 = 0.25;
+/// steps per onset segment
+const SEGMENT_STEPS: u16 = 2;
+/// onset segments per wav
+const SEGMENT_COUNT: usize = PAD_COUNT;
+
+/// write demo content under `root`
+pub fn generate(root: &Path) -> Result<()> {
+    // bank paths relative to root, which angry-surgeon runs from
+    let onsets = Path::new("onsets/demo");
+    std::fs::create_dir_all(root.join(onsets))?;
+    std::fs::create_dir_all(root.join("banks"))?;
+    std::fs::create_dir_all(root.join("grooves"))?;
+    std::fs::create_dir_all(root.join("oneshots/1"))?;
+
+    let segment = (SEGMENT_LEN * SAMPLE_RATE as f32) as usize;
+    // name and type tag
+    let wavs: [(&str, &str, Vec<f32>); 3] = [
+        ("beeps", "tone", segments(segment, beeps)),
+        ("clicks", "drums", segments(segment, |_, t| click(t))),
+        ("sweep", "fx", sweep(segment * SEGMENT_COUNT)),
+    ];
+    let mut bank = Bank::<PAD_COUNT, MAX_PHRASE_LEN>::default();
+    for ((name, kind, samples), kit) in wavs.iter().zip(bank.kits.iter_mut()) {
+        let path = onsets.join(name).with_extension("wav");
+        write_wav(&root.join(&path), samples, SAMPLE_RATE)?;
+        let rd = Rd {
+            steps: Some(SEGMENT_STEPS * SEGMENT_COUNT as u16),
+            rate: Some(SAMPLE_RATE),
+            onsets: (0..SEGMENT_COUNT).map(|i| (i * segment) as u64).collect(),
+        };
+        serde_json::to_writer_pretty(
+            std::fs::File::create(root.join(path.with_extension("rd")))?,
+            &rd,
+        )?;
+        let tags = Tags {
+            genre: Some("demo".to_string()),
+            bpm: Some(crate::input::bpm(
+                rd.steps.unwrap(),
+                samples.len() as u64,
+                SAMPLE_RATE,
+            )),
+            kind: Some(kind.to_string()),
+            ..Default::default()
+        };
+        serde_json::to_writer_pretty(
+            std::fs::File::create(root.join(Tags::sidecar(path.to_str().unwrap())))?,
+            &tags,
+        )?;
+        // one kit per wav, one pad per segment
+        let kit = kit.get_or_insert_default();
+        for (onset, &start) in kit.onsets.iter_mut().zip(rd.onsets.iter()) {
+            *onset = Some(Onset {
+                wav: Wav {
+                    steps: rd.steps,
+                    path: path.to_str().unwrap().to_string(),
+                },
+                start,
+                gain: 1.,
+                pitch: None,
+            });
+        }
+    }
+    serde_json::to_writer_pretty(
+        std::fs::File::create(root.join("banks/demo.bd"))?,
+        &bank,
+    )?;
+    write_wav(
+        &root.join("oneshots/1/click.wav"),
+        &wavs[1].2[..segment],
+        SAMPLE_RATE,
+    )?;
+    // offbeat sixteenths pushed late, accented downbeats
+    let groove = Groove {
+        steps: (0..4)
+            .map(|i| GrooveStep {
+                delay: if i % 2 == 1 { 64 } else { 0 },
+                velocity: if i == 0 { 1. } else { 0.8 },
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(
+        std::fs::File::create(root.join("grooves/swing.json"))?,
+        &groove,
+    )?;
+    println!("wrote demo content to {}", root.display());
+    Ok(())
+}
+
+/// concatenate `SEGMENT_COUNT` segments of `len` frames from `f(index, secs)`
+fn segments(len: usize, f: impl Fn(usize, f32) -> f32) -> Vec<f32> {
+    (0..SEGMENT_COUNT * len)
+        .map(|i| f(i / len, (i % len) as f32 / SAMPLE_RATE as f32))
+        .collect()
+}
+
+/// `index + 1` short beeps, so each pad announces itself
+fn beeps(index: usize, t: f32) -> f32 {
+    const BEEP_LEN: f32 = 0.02;
+    let beep = (t / (BEEP_LEN * 1.5)) as usize;
+    if beep <= index && t % (BEEP_LEN * 1.5) < BEEP_LEN {
+        0.5 * (TAU * 880. * t).sin()
+    } else {
+        0.
+    }
+}
+
+/// decaying 2kHz blip
+fn click(t: f32) -> f32 {
+    (TAU * 2000. * t).sin() * (-t * 400.).exp()
+}
+
+/// exponential sine sweep from 50Hz to 10kHz over `len` frames
+fn sweep(len: usize) -> Vec<f32> {
+    let secs = len as f32 / SAMPLE_RATE as f32;
+    let ratio = (10000f32 / 50.).ln();
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            0.5 * (TAU * 50. * secs / ratio * ((t / secs * ratio).exp() - 1.)).sin()
+        })
+        .collect()
+}
+
+/// write 16-bit mono pcm wav at `rate`
+pub fn write_wav(path: &Path, samples: &[f32], rate: u32) -> Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let data_len = samples.len() as u32 * 2;
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36 + data_len).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?; // chunk size
+    file.write_all(&1u16.to_le_bytes())?; // pcm integer format
+    file.write_all(&1u16.to_le_bytes())?; // 1 channel
+    file.write_all(&rate.to_le_bytes())?;
+    file.write_all(&(rate * 2).to_le_bytes())?; // byte rate
+    file.write_all(&2u16.to_le_bytes())?; // block align
+    file.write_all(&16u16.to_le_bytes())?; // 16 bits/sample
+    file.write_all(b"data")?;
+    file.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        let word = (sample.clamp(-1., 1.) * i16::MAX as f32) as i16;
+        file.write_all(&word.to_le_bytes())?;
+    }
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! dry throughput: live input passed straight to output while mangler is muted
+
+use crate::params::Param;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+/// ring capacity in samples
+const RING_LEN: usize = 16384;
+/// ring holds stereo frames, mapped onto output channels on read
+const RING_CHANNELS: usize = 2;
+
+/// lock-free input to output sample ring, written only by the input callback
+/// and read only by the output callback
+pub struct Throughput {
+    pub enabled: AtomicBool,
+    pub level: Param,
+    samples: Box<[AtomicU32]>,
+    /// samples written
+    head: AtomicUsize,
+    /// samples read
+    tail: AtomicUsize,
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Throughput {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            level: Param::new(),
+            samples: (0..RING_LEN).map(|_| AtomicU32::new(0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// push interleaved input of `channels` as stereo; drops frames on
+    /// overflow
+    pub fn push(&self, data: &[f32], channels: usize) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let tail = self.tail.load(Ordering::Acquire);
+        let mut head = self.head.load(Ordering::Relaxed);
+        for frame in data.chunks_exact(channels) {
+            if head - tail + RING_CHANNELS > RING_LEN {
+                break;
+            }
+            for c in 0..RING_CHANNELS {
+                let sample = frame[c.min(channels - 1)];
+                self.samples[head % RING_LEN].store(sample.to_bits(), Ordering::Relaxed);
+                head += 1;
+            }
+        }
+        self.head.store(head, Ordering::Release);
+    }
+
+    /// read into interleaved output `buffer` of `channels` scaled by `level`,
+    /// zero-filling underrun; skips stale backlog to keep latency within one
+    /// buffer
+    pub fn read(&self, buffer: &mut [f32], channels: usize, level: f32) {
+        let head = self.head.load(Ordering::Acquire);
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        let len = buffer.len() / channels * RING_CHANNELS;
+        if head - tail > len * 2 {
+            tail = head - len;
+        }
+        for frame in buffer.chunks_exact_mut(channels) {
+            frame.fill(0.);
+            if tail + RING_CHANNELS <= head {
+                let [l, r] = [tail, tail + 1].map(|v| {
+                    f32::from_bits(self.samples[v % RING_LEN].load(Ordering::Relaxed)) * level
+                });
+                tail += RING_CHANNELS;
+                angry_surgeon_core::add_stereo(frame, l, r);
+            }
+        }
+        self.tail.store(tail, Ordering::Release);
+    }
+}
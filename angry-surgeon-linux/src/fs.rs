@@ -1,31 +1,157 @@
+use angry_surgeon_core::{Capture, DirEntry, Error, CAPTURE_NAME};
 use color_eyre::eyre::Result;
-use embedded_io::{Read, Seek};
+use embedded_io::{Read, Seek, Write};
 use embedded_io_adapters::std::FromStd;
 
+/// directory live input capture is listed in, beside onsets on disk
+const CAPTURE_DIR: &str = "onsets";
+/// samples of live input held, 8 s at preferred rate
+const CAPTURE_LEN: usize = 8 * crate::audio::SAMPLE_RATE as usize;
+
+/// live input capture, served as `CAPTURE_NAME` in `CAPTURE_DIR`
+pub static CAPTURE: Capture<CAPTURE_LEN> = Capture::new(crate::audio::SAMPLE_RATE);
+
 pub struct LinuxFileHandler {}
 
+pub enum LinuxFile {
+    Disk(FromStd<std::fs::File>),
+    /// live input capture, at byte position
+    Capture(u64),
+}
+
+/// whether `path` names live input capture
+fn is_capture(path: &str) -> bool {
+    let path = std::path::Path::new(path);
+    path.parent().is_some_and(|v| v.ends_with(CAPTURE_DIR))
+        && path.file_name().is_some_and(|v| v == CAPTURE_NAME)
+}
+
+/// handle accounting, enabled by the `handle-check` feature: handles opened
+/// less those closed, per thread
+#[cfg(feature = "handle-check")]
+mod check {
+    use std::cell::Cell;
+
+    thread_local! {
+        static OPEN: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub fn count(delta: isize) {
+        OPEN.with(|v| {
+            let open = v.get().checked_add_signed(delta);
+            v.set(open.expect("handle closed more often than opened"));
+        });
+    }
+
+    pub fn assert_open(expected: usize) {
+        let open = OPEN.with(Cell::get);
+        assert_eq!(open, expected, "{} handles open on this thread, {} expected", open, expected);
+    }
+}
+
+#[cfg(feature = "handle-check")]
+use check::count;
+
+#[cfg(not(feature = "handle-check"))]
+fn count(_delta: isize) {}
+
+/// assert handles opened on this thread less those closed equal `expected`;
+/// a no-op unless checking is enabled
+#[cfg(feature = "handle-check")]
+pub fn assert_open(expected: usize) {
+    check::assert_open(expected)
+}
+
+/// assert handles opened on this thread less those closed equal `expected`;
+/// a no-op unless checking is enabled
+#[cfg(not(feature = "handle-check"))]
+pub fn assert_open(_expected: usize) {}
+
 impl embedded_io::ErrorType for LinuxFileHandler {
     type Error = <FromStd<std::fs::File> as embedded_io::ErrorType>::Error;
 }
 
 impl angry_surgeon_core::FileHandler for LinuxFileHandler {
-    type File = FromStd<std::fs::File>;
+    type File = LinuxFile;
 
     fn open(&mut self, path: &str) -> Result<Self::File, Self::Error> {
-        Ok(FromStd::new(std::fs::File::open(path)?))
+        let file = match is_capture(path) {
+            true => LinuxFile::Capture(0),
+            false => LinuxFile::Disk(FromStd::new(std::fs::File::open(path)?)),
+        };
+        count(1);
+        Ok(file)
     }
 
     fn try_clone(&mut self, file: &Self::File) -> Result<Self::File, Self::Error> {
-        Ok(FromStd::new(file.inner().try_clone()?))
+        let file = match file {
+            LinuxFile::Disk(file) => LinuxFile::Disk(FromStd::new(file.inner().try_clone()?)),
+            LinuxFile::Capture(pos) => LinuxFile::Capture(*pos),
+        };
+        count(1);
+        Ok(file)
+    }
+
+    fn create(&mut self, path: &str) -> Result<Self::File, Error<Self::Error>> {
+        if is_capture(path) {
+            return Err(Error::Unsupported);
+        }
+        let file = LinuxFile::Disk(FromStd::new(std::fs::File::create(path)?));
+        count(1);
+        Ok(file)
     }
 
-    /// std::fs::File automatically closed when dropped, so impl not needed
+    fn read_dir(&mut self, path: &str) -> Result<Vec<DirEntry>, Error<Self::Error>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)?.filter_map(|v| v.ok()) {
+            // skip names not representable as &str
+            if let Some(name) = entry.file_name().to_str() {
+                entries.push(DirEntry {
+                    name: name.into(),
+                    is_dir: entry.metadata()?.is_dir(),
+                });
+            }
+        }
+        if is_capture(&format!("{}/{}", path, CAPTURE_NAME))
+            && !entries.iter().any(|v| v.name == CAPTURE_NAME)
+        {
+            entries.push(DirEntry {
+                name: CAPTURE_NAME.into(),
+                is_dir: false,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// std::fs::File automatically closed when dropped, so only accounted
     fn close(&mut self, _file: &Self::File) -> Result<(), Self::Error> {
+        count(-1);
         Ok(())
     }
 
     fn read(&mut self, file: &mut Self::File, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        file.read(buf)
+        match file {
+            LinuxFile::Disk(file) => file.read(buf),
+            LinuxFile::Capture(pos) => {
+                let read = CAPTURE.read_at(*pos, buf);
+                *pos += read as u64;
+                Ok(read)
+            }
+        }
+    }
+
+    fn write(&mut self, file: &mut Self::File, buf: &[u8]) -> Result<usize, Error<Self::Error>> {
+        match file {
+            LinuxFile::Disk(file) => Ok(file.write(buf)?),
+            LinuxFile::Capture(_) => Err(Error::Unsupported),
+        }
+    }
+
+    fn flush(&mut self, file: &mut Self::File) -> Result<(), Error<Self::Error>> {
+        match file {
+            LinuxFile::Disk(file) => Ok(file.flush()?),
+            LinuxFile::Capture(_) => Ok(()),
+        }
     }
 
     fn seek(
@@ -33,6 +159,17 @@ impl angry_surgeon_core::FileHandler for LinuxFileHandler {
         file: &mut Self::File,
         pos: embedded_io::SeekFrom,
     ) -> Result<u64, Self::Error> {
-        file.seek(pos)
+        match file {
+            LinuxFile::Disk(file) => file.seek(pos),
+            LinuxFile::Capture(at) => {
+                let to = match pos {
+                    embedded_io::SeekFrom::Start(v) => Some(v),
+                    embedded_io::SeekFrom::End(v) => CAPTURE.file_len().checked_add_signed(v),
+                    embedded_io::SeekFrom::Current(v) => at.checked_add_signed(v),
+                };
+                *at = to.ok_or(std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+                Ok(*at)
+            }
+        }
     }
 }
@@ -1,12 +1,25 @@
-use crate::{audio, tui};
+use crate::{
+    audio,
+    mapping::{Kind, Mapping},
+    params::{BankParams, Params},
+    scene::{self, Scene},
+    tui,
+};
 use audio::{Bank, MAX_PHRASE_LEN, PAD_COUNT, PPQ, TICKS_PER_STEP};
 
-use angry_surgeon_core::{Event, Onset, Wav};
+use angry_surgeon_core::{
+    AccentPattern, Browser, Collision, DriftMode, Event, FilterMode, Follow, Launch, Onset,
+    Quantize, Rate, Release, Resample, Select, Stereo, Tags, Wav, LOOP_STEP,
+};
 use color_eyre::Result;
 use midly::{live::LiveEvent, MidiMessage};
 use std::{
-    path::{Path, PathBuf},
-    sync::mpsc::{Receiver, Sender},
+    path::Path,
+    sync::{
+        atomic::Ordering,
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
 };
 
 macro_rules! audio_bank_cmd {
@@ -47,54 +60,14 @@ macro_rules! inc {
     };
 }
 
-macro_rules! paths {
-    ($parent:expr,$iter:expr,$ext:expr) => {{
-        let mut paths: Vec<Box<Path>> = Vec::new();
-        if let Some(parent) = $parent {
-            if !parent.to_str().unwrap().is_empty() {
-                paths.push(parent.to_path_buf().into_boxed_path())
-            }
-        }
-        for entry in $iter.filter_map(|v| v.ok()) {
-            let path = entry.path();
-            if entry.metadata()?.is_dir()
-                || path.extension().is_some_and(|v| v.to_str() == Some($ext))
-            {
-                paths.push(path.into_boxed_path());
-            }
-        }
-        paths.sort();
-        paths
-    }};
-}
-
-macro_rules! to_fs {
-    ($parent:expr,$names:expr,$index:expr) => {{
-        let mut strings = [const { String::new() }; tui::FILE_COUNT];
-        if !$names.is_empty() {
-            for i in 0..tui::FILE_COUNT {
-                let index = ($index as isize + i as isize - tui::FILE_COUNT as isize / 2)
-                    .rem_euclid($names.len() as isize) as usize;
-                if $parent == Some($names[index].as_ref()) {
-                    strings[i] = "..".to_string();
-                } else {
-                    strings[i] = $names[index]
-                        .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string();
-                }
-            }
-        }
-        strings
-    }};
-    ($name:expr) => {
-        $name.file_name().unwrap().to_str().unwrap().to_string()
-    };
-}
-
-mod keys {
+pub(crate) mod keys {
+    pub const FREEZE_A: u8 = 41;
+    pub const TAPE_A: u8 = 42;
+    pub const STUTTER_A: u8 = 43;
+    pub const STEREO_A: u8 = 44;
+    pub const RESAMPLE_A: u8 = 45;
+    pub const ACCENT_A: u8 = 46;
+    pub const MOTION_A: u8 = 47;
     pub const KIT_A: u8 = 48;
     pub const HOLD_A: u8 = 49;
     pub const REVERSE_A: u8 = 50;
@@ -106,24 +79,150 @@ mod keys {
     pub const REVERSE_B: u8 = 69;
     pub const HOLD_B: u8 = 70;
     pub const KIT_B: u8 = 71;
+    pub const MOTION_B: u8 = 73;
+    pub const ACCENT_B: u8 = 74;
+    pub const RESAMPLE_B: u8 = 76;
+    pub const STEREO_B: u8 = 77;
+    pub const STUTTER_B: u8 = 78;
+    pub const TAPE_B: u8 = 79;
+    pub const FREEZE_B: u8 = 80;
 
     pub const OPEN: u8 = 72;
+    pub const DRY: u8 = 75;
 }
 
-mod ctrl {
+pub(crate) mod ctrl {
     pub const GAIN_ONESHOT: u8 = 83;
+    pub const DRY_LEVEL: u8 = 84;
+    pub const MASTER_WIDTH: u8 = 85;
+    pub const DELAY_FEEDBACK: u8 = 86;
+    pub const DELAY_DIVISION: u8 = 87;
+    pub const DRIVE: u8 = 88;
+    pub const CEILING: u8 = 89;
+    pub const MORPH: u8 = 90;
+    pub const MASTER_GAIN: u8 = 91;
+    pub const SCENE_FADE: u8 = 92;
+    pub const CROSSFADE: u8 = 93;
+    pub const TEXTURE: u8 = 94;
 
     pub const GAIN_A: u8 = 102;
     pub const SPEED_A: u8 = 103;
     pub const DRIFT_A: u8 = 28;
+    pub const DRIFT_MODE_A: u8 = 30;
+    pub const RELEASE_A: u8 = 32;
+    pub const HUMANIZE_A: u8 = 34;
+    pub const ACCENT_A: u8 = 36;
+    pub const ENVELOPE_A: u8 = 38;
+    pub const FILTER_A: u8 = 40;
+    pub const FILTER_MODE_A: u8 = 42;
+    pub const SEND_A: u8 = 44;
+    pub const FOLLOW_A: u8 = 46;
+    pub const SLIP_A: u8 = 48;
+    pub const VELOCITY_A: u8 = 50;
+    pub const DENSITY_A: u8 = 52;
+    pub const LAUNCH_A: u8 = 54;
+    pub const LOOP_FINE_A: u8 = 56;
+    pub const STUTTER_A: u8 = 58;
+    pub const TAPE_A: u8 = 60;
+    pub const FREEZE_SIZE_A: u8 = 62;
+    pub const FREEZE_JITTER_A: u8 = 64;
+    pub const TAIL_A: u8 = 66;
 
     pub const GAIN_B: u8 = 105;
     pub const SPEED_B: u8 = 106;
     pub const DRIFT_B: u8 = 29;
+    pub const DRIFT_MODE_B: u8 = 31;
+    pub const RELEASE_B: u8 = 33;
+    pub const HUMANIZE_B: u8 = 35;
+    pub const ACCENT_B: u8 = 37;
+    pub const ENVELOPE_B: u8 = 39;
+    pub const FILTER_B: u8 = 41;
+    pub const FILTER_MODE_B: u8 = 43;
+    pub const SEND_B: u8 = 45;
+    pub const FOLLOW_B: u8 = 47;
+    pub const SLIP_B: u8 = 49;
+    pub const VELOCITY_B: u8 = 51;
+    pub const DENSITY_B: u8 = 53;
+    pub const LAUNCH_B: u8 = 55;
+    pub const LOOP_FINE_B: u8 = 57;
+    pub const STUTTER_B: u8 = 59;
+    pub const TAPE_B: u8 = 61;
+    pub const FREEZE_SIZE_B: u8 = 63;
+    pub const FREEZE_JITTER_B: u8 = 65;
+    pub const TAIL_B: u8 = 67;
+}
+
+/// steps per bar of sequence capture
+const BAR_LEN: u16 = 16;
+/// delay times in beats, selected across knob range
+const DELAY_DIVISIONS: [f32; 6] = [0.25, 1. / 3., 0.5, 2. / 3., 0.75, 1.];
+/// onset nudge in samples with shift b held
+const NUDGE_COARSE: i64 = 512;
+/// onset nudge in samples with shift a held
+const NUDGE_FINE: i64 = 16;
+/// midi clocks per song position pointer unit
+const SPP_CLOCKS: u32 = 6;
+/// steps recalled scenes interpolate over until set
+const SCENE_FADE: u16 = 16;
+
+/// tempo at which `steps` steps span `samples` samples at `rate`, as the core
+/// paces steps
+pub fn bpm(steps: u16, samples: u64, rate: u32) -> f32 {
+    rate as f32 * 60. * steps as f32 / (samples as f32 * TICKS_PER_STEP as f32)
+}
+
+/// entry names around browser index, as the tui lists them
+fn listing(browser: &Browser) -> [String; tui::FILE_COUNT] {
+    let mut names = browser.window(tui::FILE_COUNT).map(String::from);
+    core::array::from_fn(|_| names.next().unwrap_or_default())
+}
+
+/// whether performance lock refuses gesture, logging so if it does
+fn refuse(locked: bool, tui_tx: &mut Sender<tui::Cmd>) -> Result<bool> {
+    if locked {
+        tui_tx.send(tui::Cmd::Log("locked".to_string()))?;
+    }
+    Ok(locked)
+}
+
+/// first ./banks/bank{n}.bd not taken
+fn free_bank_path() -> Result<String> {
+    let mut index = 0;
+    while std::fs::exists(format!("banks/bank{}.bd", index))? {
+        index += 1;
+    }
+    Ok(format!("banks/bank{}.bd", index))
+}
+
+/// bank file at `path`, fit to capacity
+fn read_bank(path: &str) -> Result<angry_surgeon_core::Bank<PAD_COUNT, MAX_PHRASE_LEN>> {
+    let saved = angry_surgeon_core::SavedBank::from_bd(&std::fs::read(path)?)?;
+    Ok(saved.into_bank::<PAD_COUNT, MAX_PHRASE_LEN>().0)
 }
 
 pub enum Cmd {
     Deafen(bool),
+    /// capture current parameters into scene slot
+    StoreScene(scene::Slot),
+    /// interpolate parameters from scene x at 0 to scene y at 1
+    Morph(f32),
+    /// save performance parameters of both banks as a fresh named scene
+    CaptureScene,
+    /// recall named scene after that last recalled
+    RecallScene,
+    /// filter file browser listed by next tag value, then none
+    CycleTag,
+    /// mark bank browsed to for merging, or merge marked one with it
+    MergeBd,
+    /// save each kit of bank browsed to as a bank of its own
+    SplitBd,
+    /// engage/release performance lock, refusing save, load, clear and
+    /// assign gestures
+    Lock(bool),
+    /// start midi learn, or skip control now learning
+    Learn,
+    /// end midi learn, saving mapping learned
+    EndLearn,
 }
 
 #[derive(PartialEq)]
@@ -132,6 +231,7 @@ enum BankState {
     LoadKit,
     TrimRecord,
     BuildSequence { cleared: bool },
+    EditAccent,
 }
 
 enum Preshift {
@@ -215,11 +315,41 @@ struct BankHandler {
     gain: Knob,
     speed: Knob,
     drift: Knob,
+    accent: Knob,
+    envelope: Knob,
+    filter: Knob,
 
     downs: Vec<u8>,
+    /// velocity of last pad hit
+    velocity: u8,
     shift: bool,
     reverse: bool,
     hold: bool,
+    /// playback quantization of record being baked
+    quantize: Quantize,
+    /// playback rate of record being baked
+    rate: Rate,
+    /// layer pick last assigned to held pads
+    select: Select,
+    drift_mode: DriftMode,
+    release: Release,
+    launch: Launch,
+    filter_mode: FilterMode,
+    resample: Resample,
+    stereo: Stereo,
+    freeze: bool,
+    /// last pad transposition sent while loading kit, if any
+    transpose: Option<f32>,
+    /// count of pools saved in bank
+    pools: usize,
+    /// accent every nth step, if any
+    accent_every: Option<u16>,
+    /// last follow action assigned, by pad
+    follow: Option<(u8, Follow)>,
+    /// user accent steps, one per pad
+    accent_mask: u32,
+    /// refuse save and clear gestures
+    locked: bool,
 
     state: BankState,
 }
@@ -232,11 +362,31 @@ impl BankHandler {
             gain: Knob::new(),
             speed: Knob::new(),
             drift: Knob::new(),
+            accent: Knob::new(),
+            envelope: Knob::new(),
+            filter: Knob::new(),
 
             downs: Vec::new(),
+            velocity: 127,
             shift: false,
             reverse: false,
             hold: false,
+            quantize: Quantize::Off,
+            rate: Rate::Normal,
+            select: Select::RoundRobin,
+            drift_mode: DriftMode::Uniform,
+            release: Release::Sync,
+            launch: Launch::Step,
+            filter_mode: FilterMode::Off,
+            resample: Resample::PreservePitch,
+            stereo: Stereo::Pan,
+            freeze: false,
+            transpose: None,
+            pools: 0,
+            accent_every: None,
+            follow: None,
+            accent_mask: 0,
+            locked: false,
 
             state: BankState::Mangle,
         }
@@ -247,40 +397,463 @@ impl BankHandler {
         self.gain.preshift = Preshift::Primed;
         self.speed.preshift = Preshift::Primed;
         self.drift.preshift = Preshift::Primed;
+        self.accent.preshift = Preshift::Primed;
+        self.envelope.preshift = Preshift::Primed;
+        self.filter.preshift = Preshift::Primed;
     }
 
-    fn gain(&mut self, value: u8, audio_tx: &mut Sender<audio::Cmd>) -> Result<()> {
+    fn gain(&mut self, value: u8, params: &BankParams) {
         if self.gain.maybe_set(value, self.shift) {
-            let cmd = if self.shift {
-                audio_bank_cmd!(self.bank, AssignWidth, value as f32 / 127.)
+            if self.shift {
+                params.width.write(value as f32 / 127.);
             } else {
-                audio_bank_cmd!(self.bank, AssignGain, value as f32 / 127.)
-            };
-            audio_tx.send(cmd)?;
+                params.gain.write(value as f32 / 127.);
+            }
+        }
+    }
+
+    fn pitch(
+        &mut self,
+        value: u8,
+        params: &BankParams,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        if self.state == BankState::LoadKit && !self.shift && !self.downs.is_empty() {
+            // transpose held pads of loaded kit within an octave either way
+            let semitones = (value as f32 / 127. * 24.).round() - 12.;
+            if self.transpose != Some(semitones) {
+                self.transpose = Some(semitones);
+                for &index in self.downs.iter() {
+                    let pitch = (semitones != 0.).then_some(semitones);
+                    audio_tx.send(audio_bank_cmd!(self.bank, AssignPadPitch, index, pitch))?;
+                }
+                tui_tx.send(tui::Cmd::Log(format!("transposed pads {:+} st", semitones)))?;
+            }
+        } else if self.speed.maybe_set(value, self.shift) {
+            if self.shift {
+                params.roll.write(value as f32 / 127.);
+            } else {
+                params.pitch.write(value as f32 / 127. * 2.);
+            }
         }
         Ok(())
     }
 
-    fn pitch(&mut self, value: u8, audio_tx: &mut Sender<audio::Cmd>) -> Result<()> {
+    fn drift(&mut self, value: u8, params: &BankParams) {
         if self.speed.maybe_set(value, self.shift) {
-            let cmd = if self.shift {
-                audio_bank_cmd!(self.bank, AssignRoll, value as f32 / 127. * 8.)
+            if self.shift {
+                params.phrase_drift.write(value as f32 / 127.);
             } else {
-                audio_bank_cmd!(self.bank, AssignPitch, value as f32 / 127. * 2.)
+                params.kit_drift.write(value as f32 / 127.);
+            }
+        }
+    }
+
+    /// onset attack up to half a second; release up to two seconds with shift
+    fn envelope(&mut self, value: u8, params: &BankParams) {
+        if self.envelope.maybe_set(value, self.shift) {
+            if self.shift {
+                params.release.write(value as f32 / 127. * 2.);
+            } else {
+                params.attack.write(value as f32 / 127. * 0.5);
+            }
+        }
+    }
+
+    /// filter cutoff; resonance with shift
+    fn filter(&mut self, value: u8, params: &BankParams) {
+        if self.filter.maybe_set(value, self.shift) {
+            if self.shift {
+                params.resonance.write(value as f32 / 127.);
+            } else {
+                params.cutoff.write(value as f32 / 127.);
+            }
+        }
+    }
+
+    fn filter_mode(
+        &mut self,
+        value: u8,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        // split knob range into thirds
+        let mode = match value {
+            0..=42 => FilterMode::Off,
+            43..=85 => FilterMode::LowPass,
+            _ => FilterMode::HighPass,
+        };
+        if mode != self.filter_mode {
+            self.filter_mode = mode;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignFilterMode, mode))?;
+            let name = match mode {
+                FilterMode::Off => "off",
+                FilterMode::LowPass => "low-pass",
+                FilterMode::HighPass => "high-pass",
             };
-            audio_tx.send(cmd)?;
+            tui_tx.send(tui::Cmd::Log(format!(
+                "filter {}: {}",
+                audio::Source::Bank(self.bank).name(),
+                name
+            )))?;
         }
         Ok(())
     }
 
-    fn drift(&mut self, value: u8, audio_tx: &mut Sender<audio::Cmd>) -> Result<()> {
-        if self.speed.maybe_set(value, self.shift) {
-            let cmd = if self.shift {
-                audio_bank_cmd!(self.bank, AssignPhraseDrift, value as f32 / 127.)
+    /// slip in whole steps across a bar
+    fn slip(&mut self, value: u8, params: &BankParams) {
+        params.slip.write((value as u16 * BAR_LEN / 128) as f32);
+    }
+
+    fn send(&mut self, value: u8, params: &BankParams) {
+        params.send.write(value as f32 / 127.);
+    }
+
+    fn humanize(&mut self, value: u8, params: &BankParams) {
+        params.humanize.write(value as f32 / 127.);
+    }
+
+    fn sensitivity(&mut self, value: u8, params: &BankParams) {
+        params.sensitivity.write(value as f32 / 127.);
+    }
+
+    /// random trigger chance per step; off at knob minimum
+    fn density(&mut self, value: u8, params: &BankParams) {
+        params.density.write(value as f32 / 127.);
+    }
+
+    /// fine loop division an octave either way, unity at center
+    fn loop_fine(&mut self, value: u8, params: &BankParams) {
+        params.loop_fine.write(value as f32 / 127.);
+    }
+
+    /// micro-loop length, halving from half a step at knob minimum
+    fn stutter(&mut self, value: u8, params: &BankParams) {
+        params.stutter.write(value as f32 / 127.);
+    }
+
+    /// tape stop and spin-up length up to two seconds
+    fn tape_len(&mut self, value: u8, params: &BankParams) {
+        params.tape_len.write(value as f32 / 127. * 2.);
+    }
+
+    /// freeze window width, from a sliver to a whole grain
+    fn freeze_size(&mut self, value: u8, params: &BankParams) {
+        params.freeze_size.write(value as f32 / 127.);
+    }
+
+    fn freeze_jitter(&mut self, value: u8, params: &BankParams) {
+        params.freeze_jitter.write(value as f32 / 127.);
+    }
+
+    /// ring ended events out for up to two seconds, muting at once at 0
+    fn tail_len(&mut self, value: u8, params: &BankParams) {
+        params.tail_len.write(value as f32 / 127. * 2.);
+    }
+
+    /// follow action of first pad held while building sequence, by quarter of
+    /// knob range; jumps to second pad held, else repeats
+    fn follow(
+        &mut self,
+        value: u8,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        let (BankState::BuildSequence { .. }, Some(&index)) = (&self.state, self.downs.first())
+        else {
+            return Ok(());
+        };
+        let follow = match value {
+            0..=31 => Follow::Next,
+            32..=63 => Follow::Random,
+            64..=95 => Follow::Stop,
+            _ => Follow::Jump(self.downs.get(1).copied().unwrap_or(index)),
+        };
+        if self.follow != Some((index, follow)) {
+            self.follow = Some((index, follow));
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignFollow, index, follow))?;
+            let name = match follow {
+                Follow::Next => "next".to_string(),
+                Follow::Random => "random".to_string(),
+                Follow::Stop => "stop".to_string(),
+                Follow::Jump(v) => format!("jump to {}", v),
+                Follow::Chain(v) => format!("chain to {}", v),
+            };
+            tui_tx.send(tui::Cmd::Log(format!(
+                "follow {}{}: {}",
+                audio::Source::Bank(self.bank).name(),
+                index,
+                name
+            )))?;
+        }
+        Ok(())
+    }
+
+    /// accent every nth step, off at bottom of range; accent gain with shift
+    fn accent(
+        &mut self,
+        value: u8,
+        params: &BankParams,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        if self.accent.maybe_set(value, self.shift) {
+            if self.shift {
+                params.accent.write(1. + value as f32 / 127. * 2.);
             } else {
-                audio_bank_cmd!(self.bank, AssignKitDrift, value as f32 / 127.)
+                let every = (value > 0).then(|| 1 + value as u16 * 15 / 127);
+                if every != self.accent_every {
+                    self.accent_every = every;
+                    let pattern = every.map(AccentPattern::Every).unwrap_or_default();
+                    audio_tx.send(audio_bank_cmd!(self.bank, AssignAccent, pattern))?;
+                    tui_tx.send(tui::Cmd::Log(match every {
+                        Some(n) => format!(
+                            "accent {}: every {}",
+                            audio::Source::Bank(self.bank).name(),
+                            n
+                        ),
+                        None => format!("accent {}: off", audio::Source::Bank(self.bank).name()),
+                    }))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn accent_up(&mut self) {
+        if self.state == BankState::EditAccent {
+            // exit edit accent
+            self.state = BankState::Mangle;
+        }
+    }
+
+    fn accent_down(
+        &mut self,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        if self.state == BankState::Mangle {
+            if self.shift {
+                // clear user pattern
+                self.accent_mask = 0;
+                self.accent_every = None;
+                audio_tx.send(audio_bank_cmd!(self.bank, AssignAccent, AccentPattern::Off))?;
+                tui_tx.send(tui::Cmd::Log(format!(
+                    "accent {}: off",
+                    audio::Source::Bank(self.bank).name()
+                )))?;
+            } else {
+                // init edit accent; pads toggle accented steps
+                self.state = BankState::EditAccent;
+            }
+        }
+        Ok(())
+    }
+
+    /// toggle between rate conversion and repitching of mismatched onsets
+    fn resample(
+        &mut self,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        self.resample = match self.resample {
+            Resample::PreservePitch => Resample::Repitch,
+            Resample::Repitch => Resample::PreservePitch,
+        };
+        audio_tx.send(audio_bank_cmd!(self.bank, AssignResample, self.resample))?;
+        let name = match self.resample {
+            Resample::PreservePitch => "preserve pitch",
+            Resample::Repitch => "repitch",
+        };
+        tui_tx.send(tui::Cmd::Log(format!(
+            "resample {}: {}",
+            audio::Source::Bank(self.bank).name(),
+            name
+        )))?;
+        Ok(())
+    }
+
+    fn stereo(
+        &mut self,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        self.stereo = match self.stereo {
+            Stereo::Pan => Stereo::MidSide,
+            Stereo::MidSide => Stereo::Pan,
+        };
+        audio_tx.send(audio_bank_cmd!(self.bank, AssignStereo, self.stereo))?;
+        let name = match self.stereo {
+            Stereo::Pan => "pan",
+            Stereo::MidSide => "mid/side",
+        };
+        tui_tx.send(tui::Cmd::Log(format!(
+            "width {}: {}",
+            audio::Source::Bank(self.bank).name(),
+            name
+        )))?;
+        Ok(())
+    }
+
+    /// hold playhead as texture of the window about it, or release it
+    fn freeze(
+        &mut self,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        self.freeze = !self.freeze;
+        audio_tx.send(audio_bank_cmd!(self.bank, AssignFreeze, self.freeze))?;
+        tui_tx.send(tui::Cmd::Log(format!(
+            "freeze {}: {}",
+            audio::Source::Bank(self.bank).name(),
+            if self.freeze { "on" } else { "off" }
+        )))?;
+        Ok(())
+    }
+
+    /// record knob motion while held; clear motions with shift
+    fn motion(
+        &mut self,
+        down: bool,
+        params: &BankParams,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        if down && self.shift {
+            if refuse(self.locked, tui_tx)? {
+                return Ok(());
+            }
+            audio_tx.send(audio_bank_cmd!(self.bank, ClearMotions))?;
+            tui_tx.send(tui::Cmd::Log(format!(
+                "cleared motions {}",
+                audio::Source::Bank(self.bank).name()
+            )))?;
+        } else {
+            params.motion.store(down, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn modes(&self) -> scene::Modes {
+        scene::Modes {
+            filter: self.filter_mode,
+            drift: self.drift_mode,
+            stereo: self.stereo,
+            resample: self.resample,
+        }
+    }
+
+    /// switch to discrete settings of a scene, sending only those changed
+    fn assign_modes(
+        &mut self,
+        modes: scene::Modes,
+        audio_tx: &mut Sender<audio::Cmd>,
+    ) -> Result<()> {
+        if modes.filter != self.filter_mode {
+            self.filter_mode = modes.filter;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignFilterMode, modes.filter))?;
+        }
+        if modes.drift != self.drift_mode {
+            self.drift_mode = modes.drift;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignDriftMode, modes.drift))?;
+        }
+        if modes.stereo != self.stereo {
+            self.stereo = modes.stereo;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignStereo, modes.stereo))?;
+        }
+        if modes.resample != self.resample {
+            self.resample = modes.resample;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignResample, modes.resample))?;
+        }
+        Ok(())
+    }
+
+    fn drift_mode(
+        &mut self,
+        value: u8,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        // split knob range into thirds
+        let mode = match value {
+            0..=42 => DriftMode::Uniform,
+            43..=85 => DriftMode::Walk,
+            _ => DriftMode::Weighted,
+        };
+        if mode != self.drift_mode {
+            self.drift_mode = mode;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignDriftMode, mode))?;
+            let name = match mode {
+                DriftMode::Uniform => "uniform",
+                DriftMode::Walk => "walk",
+                DriftMode::Weighted => "weighted",
+            };
+            tui_tx.send(tui::Cmd::Log(format!(
+                "drift {}: {}",
+                audio::Source::Bank(self.bank).name(),
+                name
+            )))?;
+        }
+        Ok(())
+    }
+
+    fn launch(
+        &mut self,
+        value: u8,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        // split knob range into quarters
+        let launch = match value {
+            0..=31 => Launch::Off,
+            32..=63 => Launch::Step,
+            64..=95 => Launch::Beat,
+            _ => Launch::Bar,
+        };
+        if launch != self.launch {
+            self.launch = launch;
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignLaunch, launch))?;
+            let name = match launch {
+                Launch::Off => "free",
+                Launch::Step => "step",
+                Launch::Beat => "beat",
+                Launch::Bar => "bar",
             };
-            audio_tx.send(cmd)?;
+            tui_tx.send(tui::Cmd::Log(format!(
+                "launch {}: {}",
+                audio::Source::Bank(self.bank).name(),
+                name
+            )))?;
+        }
+        Ok(())
+    }
+
+    fn release(
+        &mut self,
+        value: u8,
+        audio_tx: &mut Sender<audio::Cmd>,
+        tui_tx: &mut Sender<tui::Cmd>,
+    ) -> Result<()> {
+        // split knob range into thirds, last third scaling decay length
+        let (release, len) = match value {
+            0..=42 => (Release::Sync, 0.),
+            43..=85 => (Release::Sustain, 0.),
+            _ => (Release::Decay, 0.05 + (value - 86) as f32 / 41. * 1.95),
+        };
+        audio_tx.send(audio_bank_cmd!(self.bank, AssignRelease, release, len))?;
+        if release != self.release {
+            self.release = release;
+            let name = match release {
+                Release::Sync => "sync",
+                Release::Sustain => "sustain",
+                Release::Decay => "decay",
+            };
+            tui_tx.send(tui::Cmd::Log(format!(
+                "release {}: {}",
+                audio::Source::Bank(self.bank).name(),
+                name
+            )))?;
         }
         Ok(())
     }
@@ -320,6 +893,8 @@ impl BankHandler {
                 // init record
                 self.state = BankState::TrimRecord;
                 self.hold = false;
+                self.quantize = Quantize::Off;
+                self.rate = Rate::Normal;
                 if self.downs.is_empty() {
                     audio_tx.send(audio_bank_cmd!(self.bank, PushEvent, Event::Sync))?;
                 }
@@ -338,6 +913,20 @@ impl BankHandler {
                 self.reverse = true;
                 audio_tx.send(audio_bank_cmd!(self.bank, PushReverse, true))?;
             }
+        } else if self.state == BankState::LoadKit && !self.downs.is_empty() {
+            // cycle how held pads pick among their layers
+            self.select = match self.select {
+                Select::RoundRobin => Select::Random,
+                Select::Random => Select::RoundRobin,
+            };
+            for &index in self.downs.iter() {
+                audio_tx.send(audio_bank_cmd!(self.bank, AssignSelect, index, self.select))?;
+            }
+            let name = match self.select {
+                Select::RoundRobin => "round-robin",
+                Select::Random => "random",
+            };
+            tui_tx.send(tui::Cmd::Log(format!("layers {}", name)))?;
         }
         Ok(())
     }
@@ -349,11 +938,12 @@ impl BankHandler {
     ) -> Result<()> {
         if let BankState::BuildSequence { cleared } = self.state {
             // exit build sequence
-            if !cleared {
+            if !cleared && !refuse(self.locked, tui_tx)? {
                 audio_tx.send(audio_bank_cmd!(self.bank, ClearSequence))?;
                 tui_tx.send(tui_bank_cmd!(self.bank, ClearSequence))?;
             }
             self.state = BankState::Mangle;
+            self.follow = None;
             tui_tx.send(tui_bank_cmd!(self.bank, Mangle))?;
         }
         Ok(())
@@ -364,7 +954,19 @@ impl BankHandler {
         audio_tx: &mut Sender<audio::Cmd>,
         tui_tx: &mut Sender<tui::Cmd>,
     ) -> Result<()> {
-        if self.state == BankState::Mangle {
+        if self.state == BankState::TrimRecord {
+            // lock step now playing against drift
+            audio_tx.send(audio_bank_cmd!(self.bank, ToggleStepLock))?;
+            tui_tx.send(tui::Cmd::Log("toggled step lock".to_string()))?;
+        } else if self.state == BankState::LoadKit && self.shift {
+            // clear loaded kit
+            if refuse(self.locked, tui_tx)? {
+                return Ok(());
+            }
+            audio_tx.send(audio_bank_cmd!(self.bank, ClearKit))?;
+            tui_tx.send(tui_bank_cmd!(self.bank, ClearKit))?;
+            tui_tx.send(tui::Cmd::Log("cleared kit".to_string()))?;
+        } else if self.state == BankState::Mangle {
             if self.shift {
                 // init build sequence
                 self.state = BankState::BuildSequence { cleared: false };
@@ -372,7 +974,7 @@ impl BankHandler {
             } else {
                 self.hold = !self.hold;
                 if !self.hold && self.downs.is_empty() {
-                    audio_tx.send(audio_bank_cmd!(self.bank, PushEvent, Event::Sync))?;
+                    audio_tx.send(audio_bank_cmd!(self.bank, PushRelease))?;
                 }
             }
         }
@@ -383,6 +985,7 @@ impl BankHandler {
         if self.state == BankState::LoadKit {
             // exit load kit
             self.state = BankState::Mangle;
+            self.transpose = None;
             tui_tx.send(tui_bank_cmd!(self.bank, Mangle))?;
         }
         Ok(())
@@ -396,21 +999,52 @@ impl BankHandler {
         if self.state == BankState::Mangle {
             if self.shift {
                 // save bank
-                let mut index = 0;
-                while std::fs::exists(format!("banks/bank{}.bd", index))? {
-                    index += 1;
+                if refuse(self.locked, tui_tx)? {
+                    return Ok(());
                 }
-                audio_tx.send(audio_bank_cmd!(
-                    self.bank,
-                    SaveBank,
-                    std::fs::File::create_new(format!("banks/bank{}.bd", index))?
-                ))?;
-                tui_tx.send(tui::Cmd::Log(format!("saved to ./banks/bank{}.bd!", index)))?;
+                let path = free_bank_path()?;
+                audio_tx.send(audio_bank_cmd!(self.bank, SaveBank, path))?;
             } else {
                 // init load kit
                 self.state = BankState::LoadKit;
                 tui_tx.send(tui_bank_cmd!(self.bank, LoadKit, None))?;
             }
+        } else if let BankState::BuildSequence { .. } = self.state {
+            // save sequence as pool
+            if refuse(self.locked, tui_tx)? {
+                return Ok(());
+            }
+            let name = format!("pool{}", self.pools);
+            self.pools += 1;
+            audio_tx.send(audio_bank_cmd!(self.bank, SavePool, name.clone()))?;
+            tui_tx.send(tui_bank_cmd!(self.bank, SavePool))?;
+            tui_tx.send(tui::Cmd::Log(format!(
+                "saved {} to bank {}",
+                name,
+                audio::Source::Bank(self.bank).name()
+            )))?;
+        } else if self.state == BankState::TrimRecord && self.shift {
+            // cycle playback rate
+            self.rate = match self.rate {
+                Rate::Normal => Rate::Double,
+                Rate::Double => Rate::Half,
+                Rate::Half => Rate::Normal,
+            };
+            audio_tx.send(audio_bank_cmd!(self.bank, AssignRecordRate, self.rate))?;
+            tui_tx.send(tui_bank_cmd!(self.bank, RateRecord, self.rate))?;
+        } else if self.state == BankState::TrimRecord {
+            // cycle playback quantization
+            self.quantize = match self.quantize {
+                Quantize::Off => Quantize::Step,
+                Quantize::Step => Quantize::HalfStep,
+                Quantize::HalfStep => Quantize::Off,
+            };
+            audio_tx.send(audio_bank_cmd!(
+                self.bank,
+                AssignRecordQuantize,
+                self.quantize
+            ))?;
+            tui_tx.send(tui_bank_cmd!(self.bank, QuantizeRecord, self.quantize))?;
         }
         Ok(())
     }
@@ -421,11 +1055,7 @@ impl BankHandler {
         tui_tx: &mut Sender<tui::Cmd>,
     ) -> Result<()> {
         match self.state {
-            BankState::Mangle => {
-                if !self.hold {
-                    self.pad_input(audio_tx)?;
-                }
-            }
+            BankState::Mangle if !self.hold => self.pad_input(audio_tx)?,
             BankState::TrimRecord => {
                 let len = if self.downs.len() > 1 {
                     self.binary_offset(self.downs[0])
@@ -452,6 +1082,33 @@ impl BankHandler {
     ) -> Result<()> {
         match &mut self.state {
             BankState::Mangle => self.pad_input(audio_tx)?,
+            BankState::LoadKit if self.shift && self.downs.len() == 2 => {
+                // swap pads of loaded kit, undoing lock toggled by first
+                let (a, b) = (self.downs[0], self.downs[1]);
+                audio_tx.send(audio_bank_cmd!(self.bank, TogglePadLock, a))?;
+                if refuse(self.locked, tui_tx)? {
+                    return Ok(());
+                }
+                audio_tx.send(audio_bank_cmd!(self.bank, SwapPads, a, b))?;
+                tui_tx.send(tui_bank_cmd!(self.bank, SwapPads, a, b))?;
+                tui_tx.send(tui::Cmd::Log(format!("swapped pads {} and {}", a, b)))?;
+            }
+            BankState::LoadKit if self.downs.len() == 2 => {
+                // copy kit of first pad to second
+                let (from, to) = (self.downs[0], self.downs[1]);
+                if refuse(self.locked, tui_tx)? {
+                    return Ok(());
+                }
+                audio_tx.send(audio_bank_cmd!(self.bank, CopyKit, from, to))?;
+                tui_tx.send(tui_bank_cmd!(self.bank, CopyKit, from, to))?;
+                tui_tx.send(tui::Cmd::Log(format!("copied kit {} to {}", from, to)))?;
+            }
+            BankState::LoadKit if self.shift => {
+                // lock pad in loaded kit against drift
+                let index = *self.downs.last().unwrap();
+                audio_tx.send(audio_bank_cmd!(self.bank, TogglePadLock, index))?;
+                tui_tx.send(tui::Cmd::Log(format!("toggled pad {} lock", index)))?;
+            }
             BankState::LoadKit => {
                 audio_tx.send(audio_bank_cmd!(self.bank, LoadKit, self.downs[0]))?;
                 tui_tx.send(tui_bank_cmd!(
@@ -474,6 +1131,29 @@ impl BankHandler {
                     len
                 ))?;
             }
+            BankState::BuildSequence { .. } if self.shift => {
+                // capture sounding step, or bar if more pads down, to first pad
+                let len = if self.downs.len() > 1 { BAR_LEN } else { 1 };
+                audio_tx.send(audio_bank_cmd!(self.bank, CaptureSequence, self.downs[0], len))?;
+            }
+            BankState::EditAccent => {
+                let index = *self.downs.last().unwrap();
+                self.accent_mask ^= 1 << index;
+                self.accent_every = None;
+                let pattern = AccentPattern::Mask {
+                    bits: self.accent_mask,
+                    len: PAD_COUNT as u8,
+                };
+                audio_tx.send(audio_bank_cmd!(self.bank, AssignAccent, pattern))?;
+                let steps = (0..PAD_COUNT)
+                    .map(|i| if self.accent_mask & 1 << i != 0 { 'x' } else { '.' })
+                    .collect::<String>();
+                tui_tx.send(tui::Cmd::Log(format!(
+                    "accent {}: {}",
+                    audio::Source::Bank(self.bank).name(),
+                    steps
+                )))?;
+            }
             BankState::BuildSequence { cleared } => {
                 if !*cleared {
                     *cleared = true;
@@ -491,26 +1171,34 @@ impl BankHandler {
     }
 
     fn pad_input(&mut self, audio_tx: &mut Sender<audio::Cmd>) -> Result<()> {
+        let velocity = self.velocity as f32 / 127.;
         if let Some(&index) = self.downs.first() {
             if self.downs.len() > 1 {
                 // init loop start
                 let len = self.binary_offset(index);
                 audio_tx.send(audio_bank_cmd!(
                     self.bank,
-                    PushEvent,
-                    Event::Loop { index, len }
+                    PushHit,
+                    Event::Loop { index, len },
+                    velocity
                 ))?;
             } else {
                 // init loop stop | jump
-                audio_tx.send(audio_bank_cmd!(self.bank, PushEvent, Event::Hold { index }))?;
+                audio_tx.send(audio_bank_cmd!(
+                    self.bank,
+                    PushHit,
+                    Event::Hold { index },
+                    velocity
+                ))?;
             }
         } else {
-            // init sync
-            audio_tx.send(audio_bank_cmd!(self.bank, PushEvent, Event::Sync))?;
+            // init release
+            audio_tx.send(audio_bank_cmd!(self.bank, PushRelease))?;
         }
         Ok(())
     }
 
+    /// loop length in 1/256 steps of held chord, each pad past `index` a bit
     fn binary_offset(&self, index: u8) -> u16 {
         self.downs
             .iter()
@@ -520,15 +1208,10 @@ impl BankHandler {
                     .unwrap_or(v + PAD_COUNT as u8 - 1 - index)
             })
             .fold(0u16, |acc, v| acc | (1 << v))
+            * LOOP_STEP
     }
 }
 
-struct Context {
-    dir: Box<Path>,
-    paths: Vec<Box<Path>>,
-    file_index: usize,
-}
-
 enum GlobalState {
     Yield,
     LoadBd {
@@ -538,6 +1221,8 @@ enum GlobalState {
     LoadOnset {
         rd: angry_surgeon_core::Rd,
         onset_index: usize,
+        /// whether rd differs from the one on disk
+        edited: bool,
     },
 }
 
@@ -545,15 +1230,36 @@ pub struct InputHandler {
     bank_a: BankHandler,
     bank_b: BankHandler,
 
-    bd_cx: Option<Context>,
-    rd_cx: Option<Context>,
+    bd_browser: Option<Browser>,
+    rd_browser: Option<Browser>,
     banks_maybe_focus: Option<audio::Bank>,
+    /// bank file marked to merge into another, if any
+    merge_from: Option<String>,
 
     deafen: bool,
+    /// refuse save, load, clear and assign gestures
+    locked: bool,
+    mapping: Mapping,
+    /// control now learning, if learning
+    learn: Option<usize>,
+    /// kind and number last learned, ignored until another comes, as knobs
+    /// send runs of cc
+    learned: Option<(Kind, u8)>,
+    /// scenes x and y, if stored
+    scenes: [Option<Scene>; 2],
+    /// scene whose discrete settings were last switched to, if any
+    morph_slot: Option<usize>,
+    /// names of scenes saved into banks, as captured or loaded
+    named_scenes: Vec<String>,
+    /// named scene last recalled, if any
+    recalled: Option<usize>,
+    /// steps recalled scenes interpolate over
+    scene_fade: u16,
     clock: u16,
     last_step: Option<std::time::Instant>,
     state: GlobalState,
 
+    params: Arc<Params>,
     audio_tx: Sender<audio::Cmd>,
     tui_tx: Sender<tui::Cmd>,
     cmd_rx: Receiver<Cmd>,
@@ -561,23 +1267,36 @@ pub struct InputHandler {
 
 impl InputHandler {
     pub fn new(
+        params: Arc<Params>,
         audio_tx: Sender<audio::Cmd>,
         tui_tx: Sender<tui::Cmd>,
         cmd_rx: Receiver<Cmd>,
+        mapping: Mapping,
     ) -> Self {
         Self {
             bank_a: BankHandler::new(Bank::A),
             bank_b: BankHandler::new(Bank::B),
 
-            bd_cx: None,
-            rd_cx: None,
+            bd_browser: None,
+            rd_browser: None,
             banks_maybe_focus: None,
+            merge_from: None,
 
             deafen: false,
+            locked: false,
+            mapping,
+            learn: None,
+            learned: None,
+            scenes: [None, None],
+            morph_slot: None,
+            named_scenes: Vec::new(),
+            recalled: None,
+            scene_fade: SCENE_FADE,
             clock: 0,
             last_step: None,
             state: GlobalState::Yield,
 
+            params,
             audio_tx,
             tui_tx,
             cmd_rx,
@@ -585,42 +1304,135 @@ impl InputHandler {
     }
 
     pub fn push_midi(&mut self, message: &[u8]) -> Result<()> {
+        // whether midi learn started on this message, so it learns none of it
+        let mut woke = false;
         match self.cmd_rx.try_recv() {
             Ok(cmd) => match cmd {
                 Cmd::Deafen(deafen) => self.deafen = deafen,
+                Cmd::StoreScene(slot) => self.store_scene(slot)?,
+                Cmd::Morph(t) => self.morph(t)?,
+                Cmd::CaptureScene => self.capture_scene()?,
+                Cmd::RecallScene => {
+                    let index = self.recalled.map_or(0, |v| v + 1);
+                    self.recall_scene(index % self.named_scenes.len().max(1))?
+                }
+                Cmd::CycleTag => self.cycle_tag()?,
+                Cmd::MergeBd => self.merge_bd()?,
+                Cmd::SplitBd => self.split_bd()?,
+                Cmd::Lock(locked) => self.lock(locked)?,
+                Cmd::Learn => woke = self.learn()?,
+                Cmd::EndLearn => self.end_learn()?,
             },
             Err(std::sync::mpsc::TryRecvError::Empty) => (),
             Err(e) => Err(e)?,
         }
         if !self.deafen {
             match LiveEvent::parse(message)? {
-                LiveEvent::Midi { message, .. } => {
+                LiveEvent::Midi { message, .. } if self.learn.is_some() && !woke => {
+                    self.learn_message(message)?
+                }
+                LiveEvent::Midi { .. } if self.learn.is_some() => (),
+                LiveEvent::Midi { channel, message } => {
                     match message {
                         MidiMessage::NoteOff { key, .. } => self.note_off(key.as_int())?,
-                        MidiMessage::NoteOn { key, .. } => self.note_on(key.as_int())?,
+                        MidiMessage::NoteOn { key, vel } if vel == 0 => {
+                            self.note_off(key.as_int())?
+                        }
+                        MidiMessage::NoteOn { key, vel } => {
+                            self.note_on(key.as_int(), vel.as_int())?
+                        }
                         MidiMessage::Controller { controller, value } => {
                             self.controller(controller.as_int(), value.as_int())?
                         }
+                        MidiMessage::ProgramChange { program } => {
+                            self.program_change(channel.as_int(), program.as_int())?
+                        }
                         MidiMessage::PitchBend { bend } => {
                             // only affects second bank
-                            self.audio_tx
-                                .send(audio::Cmd::OffsetPitch(1. - bend.as_f32()))?;
+                            self.params.pitch_offset.write(1. - bend.as_f32());
+                        }
+                        MidiMessage::ChannelAftertouch { vel } => {
+                            self.channel_pressure(vel.as_int())
+                        }
+                        MidiMessage::Aftertouch { key, vel } => {
+                            self.key_pressure(key.as_int(), vel.as_int())
                         }
-                        _ => (),
                     }
                 }
                 LiveEvent::Realtime(midly::live::SystemRealtime::TimingClock) => {
                     self.timing_clock()?
                 }
                 LiveEvent::Realtime(midly::live::SystemRealtime::Stop) => self.stop()?,
+                LiveEvent::Realtime(midly::live::SystemRealtime::Start) => self.locate(0)?,
+                LiveEvent::Realtime(midly::live::SystemRealtime::Continue) => self.resume(),
+                LiveEvent::Common(midly::live::SystemCommon::SongPosition(position)) => {
+                    self.locate(position.as_int() as u32)?
+                }
                 _ => (),
             }
         }
         Ok(())
     }
 
+    /// apply channel pressure to every bank with pads held
+    fn channel_pressure(&self, value: u8) {
+        if !self.bank_a.downs.is_empty() {
+            self.press(Bank::A, value);
+        }
+        if !self.bank_b.downs.is_empty() {
+            self.press(Bank::B, value);
+        }
+    }
+
+    /// apply per-note pressure to bank of pad at `key`, if any
+    fn key_pressure(&self, key: u8, value: u8) {
+        match self.mapping.resolve(Kind::Key, key) {
+            Some(key) if keys::BANK_A.contains(&key) => self.press(Bank::A, value),
+            Some(key) if keys::BANK_B.contains(&key) => self.press(Bank::B, value),
+            _ => (),
+        }
+    }
+
+    /// drive parameter pressure is routed to in `bank`, if any
+    fn press(&self, bank: Bank, value: u8) {
+        if let Some(pressure) = self.mapping.pressure(bank) {
+            self.params.bank(bank).pressure[pressure as usize].write(value as f32 / 127.);
+        }
+    }
+
     fn note_off(&mut self, key: u8) -> Result<()> {
+        let Some(key) = self.mapping.resolve(Kind::Key, key) else {
+            return Ok(());
+        };
         match key {
+            keys::STUTTER_A => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::A, PushStutter, false))?;
+            }
+            keys::STUTTER_B => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::B, PushStutter, false))?;
+            }
+            keys::TAPE_A => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::A, PushTapeStop, false))?;
+            }
+            keys::TAPE_B => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::B, PushTapeStop, false))?;
+            }
+            keys::MOTION_A => self.bank_a.motion(
+                false,
+                self.params.bank(Bank::A),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
+            keys::MOTION_B => self.bank_b.motion(
+                false,
+                self.params.bank(Bank::B),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
             keys::SHIFT_A => {
                 self.bank_a.shift(false);
                 // unfocus for load bd
@@ -666,6 +1478,8 @@ impl InputHandler {
                     self.bank_a.kit_up(&mut self.tui_tx)?;
                 }
             }
+            keys::ACCENT_A => self.bank_a.accent_up(),
+            keys::ACCENT_B => self.bank_b.accent_up(),
             keys::KIT_B => {
                 if let GlobalState::Yield = self.state {
                     self.bank_b.kit_up(&mut self.tui_tx)?;
@@ -674,6 +1488,9 @@ impl InputHandler {
             _ if keys::BANK_A.contains(&key) => {
                 let index = keys::BANK_A.start + PAD_COUNT as u8 - 1 - key; // flipped
                 self.bank_a.downs.retain(|&v| v != index);
+                if self.bank_a.downs.is_empty() {
+                    self.press(Bank::A, 0);
+                }
                 match self.state {
                     GlobalState::Yield => {
                         self.bank_a.pad_up(&mut self.audio_tx, &mut self.tui_tx)?
@@ -695,6 +1512,9 @@ impl InputHandler {
             _ if keys::BANK_B.contains(&key) => {
                 let index = key - keys::BANK_B.start;
                 self.bank_b.downs.retain(|&v| v != index);
+                if self.bank_b.downs.is_empty() {
+                    self.press(Bank::B, 0);
+                }
                 match self.state {
                     GlobalState::Yield => {
                         self.bank_b.pad_up(&mut self.audio_tx, &mut self.tui_tx)?
@@ -718,9 +1538,57 @@ impl InputHandler {
         Ok(())
     }
 
-    fn note_on(&mut self, key: u8) -> Result<()> {
+    fn note_on(&mut self, key: u8, velocity: u8) -> Result<()> {
+        let Some(key) = self.mapping.resolve(Kind::Key, key) else {
+            return Ok(());
+        };
         match key {
             keys::OPEN => self.open()?,
+            keys::RESAMPLE_A => self
+                .bank_a
+                .resample(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::RESAMPLE_B => self
+                .bank_b
+                .resample(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::STEREO_A => self.bank_a.stereo(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::FREEZE_A => self.bank_a.freeze(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::FREEZE_B => self.bank_b.freeze(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::STEREO_B => self.bank_b.stereo(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::DRY => {
+                let dry = !self.params.dry.enabled.fetch_xor(true, Ordering::Relaxed);
+                self.tui_tx.send(tui::Cmd::Log(format!(
+                    "dry throughput {}",
+                    if dry { "on; mangler muted" } else { "off" }
+                )))?;
+            }
+            keys::STUTTER_A => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::A, PushStutter, true))?;
+            }
+            keys::STUTTER_B => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::B, PushStutter, true))?;
+            }
+            keys::TAPE_A => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::A, PushTapeStop, true))?;
+            }
+            keys::TAPE_B => {
+                self.audio_tx
+                    .send(audio_bank_cmd!(Bank::B, PushTapeStop, true))?;
+            }
+            keys::MOTION_A => self.bank_a.motion(
+                true,
+                self.params.bank(Bank::A),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
+            keys::MOTION_B => self.bank_b.motion(
+                true,
+                self.params.bank(Bank::B),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
             keys::SHIFT_A => {
                 self.bank_a.shift(true);
                 self.banks_maybe_focus = Some(Bank::A);
@@ -745,6 +1613,8 @@ impl InputHandler {
                 if let GlobalState::Yield = self.state {
                     self.bank_b
                         .reverse_down(&mut self.audio_tx, &mut self.tui_tx)?;
+                } else if let Some(delta) = self.nudge_len() {
+                    self.nudge_onset(-delta)?;
                 } else {
                     self.decrement()?;
                 }
@@ -753,22 +1623,40 @@ impl InputHandler {
                 if let GlobalState::Yield = self.state {
                     self.bank_a
                         .hold_down(&mut self.audio_tx, &mut self.tui_tx)?;
+                } else {
+                    self.save_rd()?;
                 }
             }
             keys::HOLD_B => {
                 if let GlobalState::Yield = self.state {
                     self.bank_b
                         .hold_down(&mut self.audio_tx, &mut self.tui_tx)?;
+                } else if self.bank_b.shift {
+                    self.remove_onset()?;
+                } else {
+                    self.split_onset()?;
                 }
             }
             keys::KIT_A => {
                 if let GlobalState::Yield = self.state {
                     self.bank_a.kit_down(&mut self.audio_tx, &mut self.tui_tx)?;
+                } else if self.bank_a.shift {
+                    self.spread(Bank::A)?;
+                } else if self.bank_b.shift {
+                    self.spread(Bank::B)?;
                 }
             }
+            keys::ACCENT_A => self
+                .bank_a
+                .accent_down(&mut self.audio_tx, &mut self.tui_tx)?,
+            keys::ACCENT_B => self
+                .bank_b
+                .accent_down(&mut self.audio_tx, &mut self.tui_tx)?,
             keys::KIT_B => {
                 if let GlobalState::Yield = self.state {
                     self.bank_b.kit_down(&mut self.audio_tx, &mut self.tui_tx)?;
+                } else if let Some(delta) = self.nudge_len() {
+                    self.nudge_onset(delta)?;
                 } else {
                     self.increment()?;
                 }
@@ -777,28 +1665,34 @@ impl InputHandler {
                 let index = keys::BANK_A.start + PAD_COUNT as u8 - 1 - key; // flipped
                                                                             // let index = PAD_COUNT as u8 - (key - keys::BANK_A.start); // flipped
                 self.bank_a.downs.push(index);
+                self.bank_a.velocity = velocity;
                 match &mut self.state {
                     GlobalState::Yield => {
                         self.bank_a.pad_down(&mut self.audio_tx, &mut self.tui_tx)?
                     }
-                    GlobalState::LoadOnset { rd, onset_index } => {
-                        let cx = self.rd_cx.as_ref().unwrap();
-                        let path = &cx.paths[cx.file_index];
-                        if std::fs::exists(path)? {
+                    GlobalState::LoadOnset {
+                        rd, onset_index, ..
+                    } => {
+                        let path = self.rd_browser.as_ref().and_then(Browser::path);
+                        if let Some(path) = path.filter(|v| Path::new(v).exists()) {
                             // assign onset to pad
                             let onset = Onset {
                                 wav: Wav {
                                     steps: rd.steps,
-                                    path: path.to_str().unwrap().to_string(),
+                                    path,
                                 },
                                 start: rd.onsets[*onset_index],
+                                gain: 1.,
+                                pitch: None,
                             };
-                            self.audio_tx.send(audio_bank_cmd!(
-                                Bank::A,
-                                AssignOnset,
-                                index,
-                                Box::new(onset)
-                            ))?;
+                            // layered onto pad with shift
+                            let onset = Box::new(onset);
+                            let cmd = if self.bank_a.shift {
+                                audio::BankCmd::PushLayer(index, onset)
+                            } else {
+                                audio::BankCmd::AssignOnset(index, onset)
+                            };
+                            self.audio_tx.send(audio::Cmd::Bank(Bank::A, cmd))?;
                             self.audio_tx.send(audio_bank_cmd!(
                                 Bank::A,
                                 ForceEvent,
@@ -820,28 +1714,34 @@ impl InputHandler {
             _ if keys::BANK_B.contains(&key) => {
                 let index = key - keys::BANK_B.start;
                 self.bank_b.downs.push(index);
+                self.bank_b.velocity = velocity;
                 match &mut self.state {
                     GlobalState::Yield => {
                         self.bank_b.pad_down(&mut self.audio_tx, &mut self.tui_tx)?
                     }
-                    GlobalState::LoadOnset { rd, onset_index } => {
-                        let cx = self.rd_cx.as_ref().unwrap();
-                        let path = &cx.paths[cx.file_index].with_extension("wav");
-                        if std::fs::exists(path)? {
+                    GlobalState::LoadOnset {
+                        rd, onset_index, ..
+                    } => {
+                        let path = self.rd_browser.as_ref().and_then(Browser::path);
+                        if let Some(path) = path.filter(|v| Path::new(v).exists()) {
                             // assign onset to pad
                             let onset = Onset {
                                 wav: Wav {
                                     steps: rd.steps,
-                                    path: path.to_str().unwrap().to_string(),
+                                    path,
                                 },
                                 start: rd.onsets[*onset_index],
+                                gain: 1.,
+                                pitch: None,
                             };
-                            self.audio_tx.send(audio_bank_cmd!(
-                                Bank::B,
-                                AssignOnset,
-                                index,
-                                Box::new(onset)
-                            ))?;
+                            // layered onto pad with shift
+                            let onset = Box::new(onset);
+                            let cmd = if self.bank_b.shift {
+                                audio::BankCmd::PushLayer(index, onset)
+                            } else {
+                                audio::BankCmd::AssignOnset(index, onset)
+                            };
+                            self.audio_tx.send(audio::Cmd::Bank(Bank::B, cmd))?;
                             self.audio_tx.send(audio_bank_cmd!(
                                 Bank::B,
                                 ForceEvent,
@@ -866,34 +1766,484 @@ impl InputHandler {
     }
 
     fn controller(&mut self, controller: u8, value: u8) -> Result<()> {
+        let Some(controller) = self.mapping.resolve(Kind::Ctrl, controller) else {
+            return Ok(());
+        };
         match controller {
             ctrl::GAIN_ONESHOT => {
-                self.audio_tx
-                    .send(audio::Cmd::AssignGainOneshot(value as f32 / 127.))?;
+                self.params.gain_oneshot.write(value as f32 / 127.);
+            }
+            ctrl::DRY_LEVEL => {
+                self.params.dry.level.write(value as f32 / 127. * 2.);
+            }
+            ctrl::MASTER_WIDTH => {
+                self.params.master_width.write(value as f32 / 127.);
+            }
+            ctrl::MASTER_GAIN => {
+                // unity at center
+                self.params.master_gain.write(value as f32 / 127. * 2.);
+            }
+            ctrl::CROSSFADE => {
+                self.params.crossfade.write(value as f32 / 127.);
+            }
+            ctrl::TEXTURE => {
+                // smoother grains for later seeks
+                self.params.texture.write(value as f32 / 127.);
+            }
+            ctrl::DELAY_FEEDBACK => {
+                // short of runaway
+                self.params.delay_feedback.write(value as f32 / 127. * 0.95);
+            }
+            ctrl::DELAY_DIVISION => {
+                let index = value as usize * DELAY_DIVISIONS.len() / 128;
+                self.params.delay_division.write(DELAY_DIVISIONS[index]);
+            }
+            ctrl::DRIVE => {
+                self.params.drive.write(1. + value as f32 / 127. * 3.);
+            }
+            ctrl::CEILING => {
+                self.params.ceiling.write(0.25 + value as f32 / 127. * 0.75);
+            }
+            ctrl::MORPH => self.morph(value as f32 / 127.)?,
+            ctrl::SCENE_FADE => {
+                // up to four bars
+                self.scene_fade = value as u16 / 2;
+                self.tui_tx.send(tui::Cmd::Log(format!(
+                    "scene fade {} steps",
+                    self.scene_fade
+                )))?;
+            }
+            ctrl::SLIP_A => {
+                self.bank_a.slip(value, self.params.bank(Bank::A));
+            }
+            ctrl::SLIP_B => {
+                self.bank_b.slip(value, self.params.bank(Bank::B));
+            }
+            ctrl::VELOCITY_A => {
+                self.bank_a.sensitivity(value, self.params.bank(Bank::A));
+            }
+            ctrl::VELOCITY_B => {
+                self.bank_b.sensitivity(value, self.params.bank(Bank::B));
+            }
+            ctrl::DENSITY_A => {
+                self.bank_a.density(value, self.params.bank(Bank::A));
+            }
+            ctrl::DENSITY_B => {
+                self.bank_b.density(value, self.params.bank(Bank::B));
+            }
+            ctrl::LOOP_FINE_A => {
+                self.bank_a.loop_fine(value, self.params.bank(Bank::A));
+            }
+            ctrl::LOOP_FINE_B => {
+                self.bank_b.loop_fine(value, self.params.bank(Bank::B));
+            }
+            ctrl::STUTTER_A => {
+                self.bank_a.stutter(value, self.params.bank(Bank::A));
+            }
+            ctrl::STUTTER_B => {
+                self.bank_b.stutter(value, self.params.bank(Bank::B));
+            }
+            ctrl::TAPE_A => {
+                self.bank_a.tape_len(value, self.params.bank(Bank::A));
+            }
+            ctrl::TAPE_B => {
+                self.bank_b.tape_len(value, self.params.bank(Bank::B));
+            }
+            ctrl::FREEZE_SIZE_A => {
+                self.bank_a.freeze_size(value, self.params.bank(Bank::A));
+            }
+            ctrl::FREEZE_SIZE_B => {
+                self.bank_b.freeze_size(value, self.params.bank(Bank::B));
+            }
+            ctrl::FREEZE_JITTER_A => {
+                self.bank_a.freeze_jitter(value, self.params.bank(Bank::A));
+            }
+            ctrl::FREEZE_JITTER_B => {
+                self.bank_b.freeze_jitter(value, self.params.bank(Bank::B));
+            }
+            ctrl::TAIL_A => {
+                self.bank_a.tail_len(value, self.params.bank(Bank::A));
+            }
+            ctrl::TAIL_B => {
+                self.bank_b.tail_len(value, self.params.bank(Bank::B));
+            }
+            ctrl::SEND_A => {
+                self.bank_a.send(value, self.params.bank(Bank::A));
+            }
+            ctrl::SEND_B => {
+                self.bank_b.send(value, self.params.bank(Bank::B));
             }
             ctrl::GAIN_A => {
-                self.bank_a.gain(value, &mut self.audio_tx)?;
+                self.bank_a.gain(value, self.params.bank(Bank::A));
             }
             ctrl::GAIN_B => {
-                self.bank_b.gain(value, &mut self.audio_tx)?;
+                self.bank_b.gain(value, self.params.bank(Bank::B));
             }
             ctrl::SPEED_A => {
-                self.bank_a.pitch(value, &mut self.audio_tx)?;
+                self.bank_a.pitch(
+                    value,
+                    self.params.bank(Bank::A),
+                    &mut self.audio_tx,
+                    &mut self.tui_tx,
+                )?;
             }
             ctrl::SPEED_B => {
-                self.bank_b.pitch(value, &mut self.audio_tx)?;
+                self.bank_b.pitch(
+                    value,
+                    self.params.bank(Bank::B),
+                    &mut self.audio_tx,
+                    &mut self.tui_tx,
+                )?;
             }
             ctrl::DRIFT_A => {
-                self.bank_a.drift(value, &mut self.audio_tx)?;
+                self.bank_a.drift(value, self.params.bank(Bank::A));
             }
             ctrl::DRIFT_B => {
-                self.bank_b.drift(value, &mut self.audio_tx)?;
+                self.bank_b.drift(value, self.params.bank(Bank::B));
+            }
+            ctrl::RELEASE_A => {
+                self.bank_a
+                    .release(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::RELEASE_B => {
+                self.bank_b
+                    .release(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::HUMANIZE_A => {
+                self.bank_a.humanize(value, self.params.bank(Bank::A));
+            }
+            ctrl::HUMANIZE_B => {
+                self.bank_b.humanize(value, self.params.bank(Bank::B));
+            }
+            ctrl::FILTER_A => {
+                self.bank_a.filter(value, self.params.bank(Bank::A));
+            }
+            ctrl::FILTER_B => {
+                self.bank_b.filter(value, self.params.bank(Bank::B));
+            }
+            ctrl::FILTER_MODE_A => {
+                self.bank_a
+                    .filter_mode(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::FILTER_MODE_B => {
+                self.bank_b
+                    .filter_mode(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::ENVELOPE_A => {
+                self.bank_a.envelope(value, self.params.bank(Bank::A));
+            }
+            ctrl::ENVELOPE_B => {
+                self.bank_b.envelope(value, self.params.bank(Bank::B));
+            }
+            ctrl::FOLLOW_A => self
+                .bank_a
+                .follow(value, &mut self.audio_tx, &mut self.tui_tx)?,
+            ctrl::FOLLOW_B => self
+                .bank_b
+                .follow(value, &mut self.audio_tx, &mut self.tui_tx)?,
+            ctrl::ACCENT_A => self.bank_a.accent(
+                value,
+                self.params.bank(Bank::A),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
+            ctrl::ACCENT_B => self.bank_b.accent(
+                value,
+                self.params.bank(Bank::B),
+                &mut self.audio_tx,
+                &mut self.tui_tx,
+            )?,
+            ctrl::DRIFT_MODE_A => {
+                self.bank_a
+                    .drift_mode(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::DRIFT_MODE_B => {
+                self.bank_b
+                    .drift_mode(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::LAUNCH_A => {
+                self.bank_a
+                    .launch(value, &mut self.audio_tx, &mut self.tui_tx)?;
+            }
+            ctrl::LAUNCH_B => {
+                self.bank_b
+                    .launch(value, &mut self.audio_tx, &mut self.tui_tx)?;
             }
             _ => (),
         }
         Ok(())
     }
 
+    /// filter browser now shown by tag value after its current filter among
+    /// those of its directory, then none
+    fn cycle_tag(&mut self) -> Result<()> {
+        let fs = &mut crate::fs::LinuxFileHandler {};
+        let browser = match self.state {
+            GlobalState::LoadBd { .. } => self.bd_browser.as_mut().unwrap(),
+            GlobalState::LoadRd => self.rd_browser.as_mut().unwrap(),
+            _ => {
+                self.tui_tx
+                    .send(tui::Cmd::Log("browse to filter by tag".to_string()))?;
+                return Ok(());
+            }
+        };
+        let tags = browser.tags(fs)?;
+        let filter = match browser.filter() {
+            Some(filter) => tags
+                .iter()
+                .position(|v| v == filter)
+                .and_then(|v| tags.get(v + 1)),
+            None => tags.first(),
+        }
+        .cloned();
+        self.tui_tx.send(tui::Cmd::Log(match filter.as_ref() {
+            Some(filter) => format!("tag {}", filter),
+            None => "tag none".to_string(),
+        }))?;
+        browser.assign_filter(filter, fs)?;
+        self.tui_tx.send(match self.state {
+            GlobalState::LoadBd { .. } => tui::Cmd::LoadBd(listing(browser)),
+            _ => tui::Cmd::LoadRd(listing(browser)),
+        })?;
+        Ok(())
+    }
+
+    /// mark bank file browsed to, or merge marked one into it as a new file,
+    /// moving colliding kits and phrases to free slots
+    fn merge_bd(&mut self) -> Result<()> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let path = match self.state {
+            GlobalState::LoadBd { .. } => self.bd_browser.as_ref().and_then(Browser::path),
+            _ => None,
+        };
+        let Some(path) = path else {
+            self.tui_tx
+                .send(tui::Cmd::Log("browse to a bank to merge".to_string()))?;
+            return Ok(());
+        };
+        let Some(from) = self.merge_from.take().filter(|v| *v != path) else {
+            self.tui_tx
+                .send(tui::Cmd::Log(format!("merge {} into...", path)))?;
+            self.merge_from = Some(path);
+            return Ok(());
+        };
+        let mut bank = read_bank(&path)?;
+        let merged = bank.merge(read_bank(&from)?, Collision::Relocate);
+        let saved = free_bank_path()?;
+        bank.save(&saved, &mut crate::fs::LinuxFileHandler {})?;
+        self.tui_tx
+            .send(tui::Cmd::Log(format!("merged to ./{} ({})", saved, merged)))?;
+        self.relist_bd()
+    }
+
+    /// save each kit of bank file browsed to as a new bank file
+    fn split_bd(&mut self) -> Result<()> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let path = match self.state {
+            GlobalState::LoadBd { .. } => self.bd_browser.as_ref().and_then(Browser::path),
+            _ => None,
+        };
+        let Some(path) = path else {
+            self.tui_tx
+                .send(tui::Cmd::Log("browse to a bank to split".to_string()))?;
+            return Ok(());
+        };
+        let banks = read_bank(&path)?.split();
+        for bank in banks.iter() {
+            bank.save(&free_bank_path()?, &mut crate::fs::LinuxFileHandler {})?;
+        }
+        self.tui_tx.send(tui::Cmd::Log(format!(
+            "split {} into {} banks",
+            path,
+            banks.len()
+        )))?;
+        self.relist_bd()
+    }
+
+    /// engage/release performance lock, leaving any browser so none of its
+    /// load or assign gestures stay reachable
+    fn lock(&mut self, locked: bool) -> Result<()> {
+        self.locked = locked;
+        self.bank_a.locked = locked;
+        self.bank_b.locked = locked;
+        if locked && !matches!(self.state, GlobalState::Yield) {
+            self.state = GlobalState::Yield;
+            self.tui_tx.send(tui::Cmd::Yield)?;
+        }
+        self.tui_tx.send(tui::Cmd::Log(format!(
+            "performance lock {}",
+            if locked { "on" } else { "off" }
+        )))?;
+        Ok(())
+    }
+
+    /// start midi learn at first control, or skip control now learning;
+    /// whether started
+    fn learn(&mut self) -> Result<bool> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(false);
+        }
+        let started = self.learn.is_none();
+        if started {
+            self.tui_tx.send(tui::Cmd::Learn(true))?;
+        }
+        self.learn = Some(self.learn.map(|v| v + 1).unwrap_or(0));
+        self.prompt_learn()?;
+        Ok(started)
+    }
+
+    /// bind control now learning to key pressed or knob turned in `message`,
+    /// moving on to next control
+    fn learn_message(&mut self, message: MidiMessage) -> Result<()> {
+        let Some(index) = self.learn else {
+            return Ok(());
+        };
+        let Some((_, kind)) = self.mapping.control(index) else {
+            return Ok(());
+        };
+        let number = match (kind, message) {
+            (Kind::Key, MidiMessage::NoteOn { key, vel }) if vel > 0 => key.as_int(),
+            (Kind::Ctrl, MidiMessage::Controller { controller, .. }) => controller.as_int(),
+            _ => return Ok(()),
+        };
+        if self.learned == Some((kind, number)) {
+            return Ok(());
+        }
+        self.mapping.bind(index, number);
+        self.learned = Some((kind, number));
+        self.learn = Some(index + 1);
+        self.prompt_learn()
+    }
+
+    /// log control now learning, ending learn past last
+    fn prompt_learn(&mut self) -> Result<()> {
+        match self.learn.and_then(|v| self.mapping.control(v)) {
+            Some((name, kind)) => self.tui_tx.send(tui::Cmd::Log(format!(
+                "learn {}: {}",
+                name,
+                match kind {
+                    Kind::Key => "press a key",
+                    Kind::Ctrl => "turn a knob",
+                }
+            )))?,
+            None => self.end_learn()?,
+        }
+        Ok(())
+    }
+
+    /// end midi learn, saving mapping learned
+    fn end_learn(&mut self) -> Result<()> {
+        if self.learn.take().is_none() {
+            return Ok(());
+        }
+        self.learned = None;
+        self.tui_tx.send(tui::Cmd::Learn(false))?;
+        let msg = match self.mapping.save() {
+            Ok(()) => format!("mapping saved to ./{}", self.mapping.path()),
+            Err(e) => e.to_string(),
+        };
+        self.tui_tx.send(tui::Cmd::Log(msg))?;
+        Ok(())
+    }
+
+    /// relist bank browser after writing banks
+    fn relist_bd(&mut self) -> Result<()> {
+        if let Some(browser) = self.bd_browser.as_mut() {
+            browser.refresh(&mut crate::fs::LinuxFileHandler {})?;
+            self.tui_tx.send(tui::Cmd::LoadBd(listing(browser)))?;
+        }
+        Ok(())
+    }
+
+    fn store_scene(&mut self, slot: scene::Slot) -> Result<()> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let modes = [self.bank_a.modes(), self.bank_b.modes()];
+        self.scenes[slot as usize] = Some(Scene::capture(&self.params, modes));
+        // reapply discrete settings on next morph
+        self.morph_slot = None;
+        self.tui_tx
+            .send(tui::Cmd::Log(format!("stored scene {}", slot.name())))?;
+        Ok(())
+    }
+
+    /// interpolate parameters `t` of the way from scene x to scene y
+    fn morph(&mut self, t: f32) -> Result<()> {
+        let [Some(x), Some(y)] = &self.scenes else {
+            self.tui_tx
+                .send(tui::Cmd::Log("store scenes x and y to morph".to_string()))?;
+            return Ok(());
+        };
+        Scene::morph(x, y, t, &self.params);
+        let slot = (t >= 0.5) as usize;
+        if self.morph_slot != Some(slot) {
+            let modes = if slot == 0 { x.modes } else { y.modes };
+            self.bank_a.assign_modes(modes[0], &mut self.audio_tx)?;
+            self.bank_b.assign_modes(modes[1], &mut self.audio_tx)?;
+            self.morph_slot = Some(slot);
+        }
+        Ok(())
+    }
+
+    /// save performance parameters of both banks as a fresh named scene
+    fn capture_scene(&mut self) -> Result<()> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let name = (self.named_scenes.len()..)
+            .map(|v| format!("scene{}", v))
+            .find(|v| !self.named_scenes.contains(v))
+            .unwrap();
+        let index = self.named_scenes.len();
+        self.audio_tx
+            .send(audio::Cmd::CaptureScene(index, name.clone()))?;
+        self.tui_tx
+            .send(tui::Cmd::Log(format!("captured {}", name)))?;
+        self.named_scenes.push(name);
+        Ok(())
+    }
+
+    /// interpolate both banks toward named scene at `index`, if any
+    fn recall_scene(&mut self, index: usize) -> Result<()> {
+        let Some(name) = self.named_scenes.get(index) else {
+            self.tui_tx
+                .send(tui::Cmd::Log("no scene to recall".to_string()))?;
+            return Ok(());
+        };
+        self.audio_tx.send(audio::Cmd::RecallScene(
+            index,
+            name.clone(),
+            self.scene_fade,
+        ))?;
+        self.tui_tx.send(tui::Cmd::Log(format!(
+            "recall {} over {} steps",
+            name, self.scene_fade
+        )))?;
+        self.recalled = Some(index);
+        Ok(())
+    }
+
+    /// recall pool `program` into bank of `channel`, or named scene
+    /// `program` on the third
+    fn program_change(&mut self, channel: u8, program: u8) -> Result<()> {
+        let bank = match channel {
+            0 => Bank::A,
+            1 => Bank::B,
+            2 => return self.recall_scene(program as usize),
+            _ => return Ok(()),
+        };
+        self.audio_tx
+            .send(audio_bank_cmd!(bank, RecallPool, program as usize))?;
+        self.tui_tx
+            .send(tui_bank_cmd!(bank, RecallPool, program as usize))?;
+        Ok(())
+    }
+
     fn timing_clock(&mut self) -> Result<()> {
         // affect both banks
         if self.clock == 0 {
@@ -924,200 +2274,199 @@ impl InputHandler {
         Ok(())
     }
 
+    /// jump to `position` in 16ths from song start, as on start or a song
+    /// position pointer; next clock ticks there
+    fn locate(&mut self, position: u32) -> Result<()> {
+        // affect both banks
+        self.resume();
+        let ticks = position * SPP_CLOCKS / (PPQ / TICKS_PER_STEP) as u32;
+        self.audio_tx.send(audio::Cmd::Locate(ticks))?;
+        Ok(())
+    }
+
+    /// pick clock back up on the next one, as on continue
+    fn resume(&mut self) {
+        self.clock = 0;
+        self.last_step = None;
+    }
+
     fn open(&mut self) -> Result<()> {
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let fs = &mut crate::fs::LinuxFileHandler {};
         match &self.state {
             GlobalState::Yield => {
                 if let Some(bank) = self.banks_maybe_focus.take() {
                     // trans load bd
-                    if let Some(cx) = &mut self.bd_cx {
-                        // recall dir
-                        let paths = paths!(cx.dir.parent(), std::fs::read_dir(&cx.dir)?, "bd");
-                        self.tui_tx.send(tui::Cmd::LoadBd(to_fs!(
-                            cx.dir.parent(),
-                            paths,
-                            cx.file_index
-                        )))?;
-                        cx.paths = paths;
-                        self.state = GlobalState::LoadBd { bank };
-                    } else if let Ok(dir) = std::fs::read_dir("banks") {
-                        // open ./banks
-                        let paths = paths!(Some(Path::new("")), dir, "bd");
-                        self.tui_tx.send(tui::Cmd::LoadBd(to_fs!(
-                            Some(Path::new("")),
-                            paths,
-                            0
-                        )))?;
-                        self.bd_cx = Some(Context {
-                            dir: PathBuf::from("banks").into_boxed_path(),
-                            file_index: 0,
-                            paths,
-                        });
-                        self.state = GlobalState::LoadBd { bank };
-                    } else {
-                        self.tui_tx
-                            .send(tui::Cmd::Log("no ./banks found".to_string()))?;
+                    match Self::browse(&mut self.bd_browser, "banks", "bd", fs) {
+                        Ok(browser) => {
+                            self.tui_tx.send(tui::Cmd::LoadBd(listing(browser)))?;
+                            self.state = GlobalState::LoadBd { bank };
+                        }
+                        Err(_) => {
+                            self.tui_tx
+                                .send(tui::Cmd::Log("no ./banks found".to_string()))?;
+                        }
                     }
                 } else {
                     // trans load rd
-                    if let Some(cx) = &mut self.rd_cx {
-                        // recall dir
-                        let paths = paths!(cx.dir.parent(), std::fs::read_dir(&cx.dir)?, "wav");
-                        self.tui_tx.send(tui::Cmd::LoadRd(to_fs!(
-                            cx.dir.parent(),
-                            paths,
-                            cx.file_index
-                        )))?;
-                        cx.paths = paths;
-                        self.state = GlobalState::LoadRd;
-                    } else if let Ok(dir) = std::fs::read_dir("onsets") {
-                        // open ./onsets
-                        let paths = paths!(Some(Path::new("")), dir, "wav");
-                        self.tui_tx.send(tui::Cmd::LoadRd(to_fs!(
-                            Some(Path::new("")),
-                            paths,
-                            0
-                        )))?;
-                        self.rd_cx = Some(Context {
-                            dir: PathBuf::from("onsets").into_boxed_path(),
-                            file_index: 0,
-                            paths,
-                        });
-                        self.state = GlobalState::LoadRd;
-                    } else {
-                        self.tui_tx
-                            .send(tui::Cmd::Log("no ./onsets found".to_string()))?;
+                    match Self::browse(&mut self.rd_browser, "onsets", "wav", fs) {
+                        Ok(browser) => {
+                            self.tui_tx.send(tui::Cmd::LoadRd(listing(browser)))?;
+                            self.state = GlobalState::LoadRd;
+                        }
+                        Err(_) => {
+                            self.tui_tx
+                                .send(tui::Cmd::Log("no ./onsets found".to_string()))?;
+                        }
                     }
                 }
             }
             GlobalState::LoadBd { bank } => {
-                let cx = self.bd_cx.as_ref().unwrap();
-                let path = &cx.paths[cx.file_index];
-                if let Ok(entry) = std::fs::metadata(path) {
-                    if entry.is_dir() {
-                        // open dir
-                        let paths = paths!(path.parent(), std::fs::read_dir(path)?, "bd");
-                        self.tui_tx
-                            .send(tui::Cmd::LoadBd(to_fs!(path.parent(), paths, 0)))?;
-                        self.bd_cx = Some(Context {
-                            dir: path.clone(),
-                            paths,
-                            file_index: 0,
-                        });
-                    } else if entry.is_file()
-                        && path.extension().is_some_and(|v| v.to_str() == Some("bd"))
-                    {
-                        // load bd
-                        let bytes = std::fs::read(path)?;
-                        if let Ok(bd) = serde_json::from_slice::<
-                            angry_surgeon_core::Bank<PAD_COUNT, MAX_PHRASE_LEN>,
-                        >(&bytes)
-                        {
-                            self.tui_tx.send(tui_bank_cmd!(
-                                *bank,
-                                LoadBank,
-                                tui::Bank::from_audio(&bd)
-                            ))?;
-                            self.audio_tx
-                                .send(audio_bank_cmd!(*bank, LoadBank, Box::new(bd)))?;
-                            self.tui_tx.send(tui::Cmd::Log(std::format!(
-                                "load {}!",
-                                cx.paths[cx.file_index].to_str().unwrap_or_default()
-                            )))?;
-                        } else {
-                            self.tui_tx.send(tui::Cmd::Log("bad .bd".to_string()))?;
-                        }
-                    }
-                } else {
-                    self.tui_tx
-                        .send(tui::Cmd::Log("bad fs entry".to_string()))?;
+                let bank = *bank;
+                let browser = self.bd_browser.as_mut().unwrap();
+                match browser.select(fs) {
+                    Ok(Some(path)) => self.load_bd(bank, &path)?,
+                    Ok(None) => self.tui_tx.send(tui::Cmd::LoadBd(listing(browser)))?,
+                    Err(e) => self.tui_tx.send(tui::Cmd::Log(e.to_string()))?,
                 }
             }
             GlobalState::LoadRd => {
-                let cx = self.rd_cx.as_ref().unwrap();
-                let path = &cx.paths[cx.file_index];
-                if let Ok(entry) = std::fs::metadata(path) {
-                    if entry.is_dir() {
-                        // open dir
-                        let paths = paths!(path.parent(), std::fs::read_dir(path)?, "wav");
-                        self.tui_tx
-                            .send(tui::Cmd::LoadRd(to_fs!(path.parent(), paths, 0)))?;
-                        self.rd_cx = Some(Context {
-                            dir: path.clone(),
-                            paths,
-                            file_index: 0,
-                        });
-                    } else if entry.is_file()
-                        && path.extension().is_some_and(|v| v.to_str() == Some("wav"))
-                    {
-                        // load rd or default (loop file)
-                        if let Ok(bytes) = std::fs::read(path.with_extension("rd")) {
-                            if let Ok(rd) = serde_json::from_slice::<angry_surgeon_core::Rd>(&bytes)
-                            {
-                                self.tui_tx.send(tui::Cmd::LoadOnset {
-                                    name: to_fs!(path),
-                                    index: 0,
-                                    count: rd.onsets.len(),
-                                })?;
-                                self.state = GlobalState::LoadOnset { rd, onset_index: 0 };
-                            } else {
-                                self.tui_tx.send(tui::Cmd::Log("bad .rd".to_string()))?;
-                            }
-                        } else {
-                            let rd = angry_surgeon_core::Rd::default();
-                            self.tui_tx.send(tui::Cmd::LoadOnset {
-                                name: to_fs!(path),
-                                index: 0,
-                                count: rd.onsets.len(),
-                            })?;
-                            self.state = GlobalState::LoadOnset { rd, onset_index: 0 };
-                        };
-                    }
-                } else {
-                    self.tui_tx
-                        .send(tui::Cmd::Log("bad fs entry".to_string()))?;
+                let browser = self.rd_browser.as_mut().unwrap();
+                match browser.select(fs) {
+                    Ok(Some(path)) => self.load_rd(&path)?,
+                    Ok(None) => self.tui_tx.send(tui::Cmd::LoadRd(listing(browser)))?,
+                    Err(e) => self.tui_tx.send(tui::Cmd::Log(e.to_string()))?,
                 }
             }
-            GlobalState::LoadOnset { .. } => {
-                let cx = self.rd_cx.as_ref().unwrap();
-                self.tui_tx.send(tui::Cmd::LoadRd(to_fs!(
-                    cx.dir.parent(),
-                    cx.paths,
-                    cx.file_index
-                )))?;
+            GlobalState::LoadOnset { edited, .. } => {
+                if *edited {
+                    self.tui_tx
+                        .send(tui::Cmd::Log("discarded unsaved .rd edits".to_string()))?;
+                }
+                let browser = self.rd_browser.as_ref().unwrap();
+                self.tui_tx.send(tui::Cmd::LoadRd(listing(browser)))?;
                 self.state = GlobalState::LoadRd;
             }
         }
         Ok(())
     }
 
+    /// relist directory browsed last, if any, or open `root`; forgotten if
+    /// gone
+    fn browse<'a>(
+        browser: &'a mut Option<Browser>,
+        root: &str,
+        ext: &str,
+        fs: &mut crate::fs::LinuxFileHandler,
+    ) -> Result<&'a Browser, angry_surgeon_core::Error<std::io::Error>> {
+        let listed = match browser.as_mut() {
+            Some(browser) => browser.refresh(fs),
+            None => Browser::open(root, ext, fs).map(|v| *browser = Some(v)),
+        };
+        if let Err(e) = listed {
+            *browser = None;
+            return Err(e);
+        }
+        Ok(browser.as_ref().unwrap())
+    }
+
+    fn load_bd(&mut self, bank: Bank, path: &str) -> Result<()> {
+        let bytes = std::fs::read(path)?;
+        match angry_surgeon_core::SavedBank::from_bd(&bytes) {
+            Ok(saved) => {
+                // fit banks saved with other capacities, reporting changes
+                let (bd, compat) = saved.into_bank::<PAD_COUNT, MAX_PHRASE_LEN>();
+                match bank {
+                    Bank::A => self.bank_a.pools = bd.pools.len(),
+                    Bank::B => self.bank_b.pools = bd.pools.len(),
+                }
+                for scene in bd.scenes.iter() {
+                    if !self.named_scenes.contains(&scene.name) {
+                        self.named_scenes.push(scene.name.clone());
+                    }
+                }
+                self.tui_tx
+                    .send(tui_bank_cmd!(bank, LoadBank, tui::Bank::from_audio(&bd)))?;
+                let analyzed = bd.clone();
+                self.audio_tx
+                    .send(audio_bank_cmd!(bank, LoadBank, Box::new(bd)))?;
+                // analyze onset peaks in background, trimming once done
+                let audio_tx = self.audio_tx.clone();
+                let tui_tx = self.tui_tx.clone();
+                std::thread::spawn(move || -> Result<()> {
+                    let peaks = analyzed.peaks(&mut crate::fs::LinuxFileHandler {})?;
+                    crate::fs::assert_open(0);
+                    let peaks = Box::new(peaks);
+                    audio_tx.send(audio_bank_cmd!(bank, Normalize, peaks))?;
+                    tui_tx.send(tui::Cmd::Log(format!(
+                        "normalized bank {}",
+                        audio::Source::Bank(bank).name()
+                    )))?;
+                    Ok(())
+                });
+                self.tui_tx.send(tui::Cmd::Log(if compat.is_clean() {
+                    std::format!("load {}!", path)
+                } else {
+                    std::format!("load {} ({})", path, compat)
+                }))?;
+            }
+            Err(e) => self.tui_tx.send(tui::Cmd::Log(e.to_string()))?,
+        }
+        Ok(())
+    }
+
+    /// load rd beside wav at `path`, or default (loop file) if none
+    fn load_rd(&mut self, path: &str) -> Result<()> {
+        let wav = Wav {
+            steps: None,
+            path: path.to_string(),
+        };
+        // onsets are converted to samples at the wav's rate
+        let rate = match wav.rate(&mut crate::fs::LinuxFileHandler {}) {
+            Ok(rate) => rate,
+            Err(e) => {
+                self.tui_tx
+                    .send(tui::Cmd::Log(format!("no wav read: {}", e)))?;
+                return Ok(());
+            }
+        };
+        let rd = match std::fs::read(Path::new(path).with_extension("rd")) {
+            Ok(bytes) => match angry_surgeon_core::Rd::from_json(&bytes, rate) {
+                Ok(rd) => rd,
+                Err(_) => {
+                    self.tui_tx.send(tui::Cmd::Log("bad .rd".to_string()))?;
+                    return Ok(());
+                }
+            },
+            Err(_) => angry_surgeon_core::Rd::default(),
+        };
+        self.state = GlobalState::LoadOnset {
+            rd,
+            onset_index: 0,
+            edited: false,
+        };
+        self.send_onset()
+    }
+
     fn decrement(&mut self) -> Result<()> {
         match &mut self.state {
             GlobalState::LoadBd { .. } => {
-                let cx = self.bd_cx.as_mut().unwrap();
-                dec!(&mut cx.file_index, cx.paths.len());
-                self.tui_tx.send(tui::Cmd::LoadBd(to_fs!(
-                    cx.dir.parent(),
-                    cx.paths,
-                    cx.file_index
-                )))?;
+                let browser = self.bd_browser.as_mut().unwrap();
+                browser.decrement();
+                self.tui_tx.send(tui::Cmd::LoadBd(listing(browser)))?;
             }
             GlobalState::LoadRd => {
-                let cx = self.rd_cx.as_mut().unwrap();
-                dec!(&mut cx.file_index, cx.paths.len());
-                self.tui_tx.send(tui::Cmd::LoadRd(to_fs!(
-                    cx.dir.parent(),
-                    cx.paths,
-                    cx.file_index
-                )))?;
+                let browser = self.rd_browser.as_mut().unwrap();
+                browser.decrement();
+                self.tui_tx.send(tui::Cmd::LoadRd(listing(browser)))?;
             }
-            GlobalState::LoadOnset { rd, onset_index } => {
-                let cx = self.rd_cx.as_ref().unwrap();
+            GlobalState::LoadOnset {
+                rd, onset_index, ..
+            } => {
                 dec!(onset_index, rd.onsets.len());
-                self.tui_tx.send(tui::Cmd::LoadOnset {
-                    name: to_fs!(cx.paths[cx.file_index]),
-                    index: *onset_index,
-                    count: rd.onsets.len(),
-                })?;
+                self.send_onset()?;
             }
             _ => (),
         }
@@ -1127,34 +2476,186 @@ impl InputHandler {
     fn increment(&mut self) -> Result<()> {
         match &mut self.state {
             GlobalState::LoadBd { .. } => {
-                let cx = self.bd_cx.as_mut().unwrap();
-                inc!(&mut cx.file_index, cx.paths.len());
-                self.tui_tx.send(tui::Cmd::LoadBd(to_fs!(
-                    cx.dir.parent(),
-                    cx.paths,
-                    cx.file_index
-                )))?;
+                let browser = self.bd_browser.as_mut().unwrap();
+                browser.increment();
+                self.tui_tx.send(tui::Cmd::LoadBd(listing(browser)))?;
             }
             GlobalState::LoadRd => {
-                let cx = self.rd_cx.as_mut().unwrap();
-                inc!(&mut cx.file_index, cx.paths.len());
-                self.tui_tx.send(tui::Cmd::LoadRd(to_fs!(
-                    cx.dir.parent(),
-                    cx.paths,
-                    cx.file_index
-                )))?;
+                let browser = self.rd_browser.as_mut().unwrap();
+                browser.increment();
+                self.tui_tx.send(tui::Cmd::LoadRd(listing(browser)))?;
             }
-            GlobalState::LoadOnset { rd, onset_index } => {
-                let cx = self.rd_cx.as_ref().unwrap();
+            GlobalState::LoadOnset {
+                rd, onset_index, ..
+            } => {
                 inc!(onset_index, rd.onsets.len());
-                self.tui_tx.send(tui::Cmd::LoadOnset {
-                    name: to_fs!(cx.paths[cx.file_index]),
-                    index: *onset_index,
-                    count: rd.onsets.len(),
-                })?;
+                self.send_onset()?;
             }
             _ => (),
         }
         Ok(())
     }
+
+    /// send onset now loaded to tui, if any
+    fn send_onset(&self) -> Result<()> {
+        if let GlobalState::LoadOnset {
+            rd,
+            onset_index,
+            edited,
+        } = &self.state
+        {
+            let browser = self.rd_browser.as_ref().unwrap();
+            self.tui_tx.send(tui::Cmd::LoadOnset {
+                name: browser.name().unwrap_or_default().to_string(),
+                index: *onset_index,
+                count: rd.onsets.len(),
+                start: rd.onsets[*onset_index],
+                edited: *edited,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// assign opening onsets of rd now loaded over pads of loaded kit of
+    /// `bank`
+    fn spread(&mut self, bank: Bank) -> Result<()> {
+        let GlobalState::LoadOnset { rd, .. } = &self.state else {
+            return Ok(());
+        };
+        if refuse(self.locked, &mut self.tui_tx)? {
+            return Ok(());
+        }
+        let path = self.rd_browser.as_ref().and_then(Browser::path);
+        let Some(path) = path.filter(|v| Path::new(v).exists()) else {
+            self.tui_tx
+                .send(tui::Cmd::Log("no wav found".to_string()))?;
+            return Ok(());
+        };
+        let wav = Wav {
+            steps: rd.steps,
+            path,
+        };
+        let count = rd.onsets.len().min(PAD_COUNT);
+        self.audio_tx.send(audio_bank_cmd!(
+            bank,
+            Spread,
+            Box::new(rd.clone()),
+            Box::new(wav)
+        ))?;
+        self.tui_tx.send(tui_bank_cmd!(bank, Spread, count))?;
+        self.tui_tx.send(tui::Cmd::Log(format!(
+            "spread {} onsets over bank {}",
+            count,
+            audio::Source::Bank(bank).name()
+        )))?;
+        Ok(())
+    }
+
+    /// nudge length in samples while editing onsets with shift held, if any
+    fn nudge_len(&self) -> Option<i64> {
+        match self.state {
+            GlobalState::LoadOnset { .. } if self.bank_b.shift => Some(NUDGE_COARSE),
+            GlobalState::LoadOnset { .. } if self.bank_a.shift => Some(NUDGE_FINE),
+            _ => None,
+        }
+    }
+
+    fn nudge_onset(&mut self, delta: i64) -> Result<()> {
+        if let GlobalState::LoadOnset {
+            rd,
+            onset_index,
+            edited,
+        } = &mut self.state
+        {
+            *edited |= rd.nudge(*onset_index, delta).is_some();
+            self.send_onset()?;
+        }
+        Ok(())
+    }
+
+    /// add onset halfway between onset now loaded and the next, or the end
+    /// of wav if last
+    fn split_onset(&mut self) -> Result<()> {
+        if let GlobalState::LoadOnset {
+            rd,
+            onset_index,
+            edited,
+        } = &mut self.state
+        {
+            let start = rd.onsets[*onset_index];
+            let end = if let Some(next) = rd.onsets.get(*onset_index + 1) {
+                *next
+            } else {
+                let browser = self.rd_browser.as_ref().unwrap();
+                let wav = Wav {
+                    steps: rd.steps,
+                    path: browser.path().unwrap_or_default(),
+                };
+                match wav.samples(&mut crate::fs::LinuxFileHandler {}) {
+                    Ok(samples) => samples,
+                    Err(e) => {
+                        self.tui_tx
+                            .send(tui::Cmd::Log(format!("no wav read: {}", e)))?;
+                        return Ok(());
+                    }
+                }
+            };
+            if end > start + 1 {
+                *onset_index = rd.insert(start + (end - start) / 2);
+                *edited = true;
+                self.send_onset()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_onset(&mut self) -> Result<()> {
+        if let GlobalState::LoadOnset {
+            rd,
+            onset_index,
+            edited,
+        } = &mut self.state
+        {
+            if rd.remove(*onset_index).is_some() {
+                *onset_index = (*onset_index).min(rd.onsets.len() - 1);
+                *edited = true;
+                self.send_onset()?;
+            } else {
+                self.tui_tx
+                    .send(tui::Cmd::Log("last onset kept".to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// write rd now loaded beside its wav, tagging the wav with tempo of its
+    /// steps
+    fn save_rd(&mut self) -> Result<()> {
+        let GlobalState::LoadOnset { rd, edited, .. } = &mut self.state else {
+            return Ok(());
+        };
+        let fs = &mut crate::fs::LinuxFileHandler {};
+        let wav = Wav {
+            steps: rd.steps,
+            path: self.rd_browser.as_ref().unwrap().path().unwrap_or_default(),
+        };
+        let path = Path::new(&wav.path).with_extension("rd");
+        let path = path.to_str().unwrap();
+        if let Err(e) = rd.save(path, fs) {
+            self.tui_tx.send(tui::Cmd::Log(e.to_string()))?;
+            return Ok(());
+        }
+        *edited = false;
+        if let (Some(steps), Ok(samples), Ok(rate)) = (wav.steps, wav.samples(fs), wav.rate(fs)) {
+            // keep any other tags
+            let mut tags = Tags::load(&wav.path, fs).ok().flatten().unwrap_or_default();
+            tags.bpm = Some(bpm(steps, samples, rate));
+            if let Err(e) = tags.save(&wav.path, fs) {
+                self.tui_tx
+                    .send(tui::Cmd::Log(format!("no tags saved: {}", e)))?;
+            }
+        }
+        self.tui_tx.send(tui::Cmd::Log(format!("save {}!", path)))?;
+        self.send_onset()
+    }
 }
@@ -0,0 +1,188 @@
+//! native jack backend: audio and midi ports of its own, with the step clock
+//! following jack transport, which cpal's jack host can't offer
+
+use crate::{alloc_check, audio, input, latency, params::Params, sched::Sched, transport, tui};
+use color_eyre::Result;
+use jack::PortSpec;
+use std::sync::{mpsc::Sender, Arc};
+
+const CLIENT_NAME: &str = "angry-surgeon";
+/// captured channels, for latency probe, dry throughput and live capture
+const CAPTURE_COUNT: usize = 2;
+/// midi messages queued by the process callback before the input thread
+/// drains them; further ones dropped rather than allocating
+const MIDI_QUEUE_LEN: usize = 256;
+/// longest midi message passed on; sysex dropped
+const MIDI_LEN: usize = 3;
+
+/// whether `--jack` requested in `args`
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|v| v == "--jack")
+}
+
+/// open client on a running server, at whatever rate it runs; errs if that
+/// differs from a `requested` rate
+pub fn open(requested: Option<u32>) -> Result<jack::Client> {
+    let (client, _) = jack::Client::new(CLIENT_NAME, jack::ClientOptions::NO_START_SERVER)?;
+    let sample_rate = client.sample_rate() as u32;
+    if let Some(requested) = requested.filter(|v| *v != sample_rate) {
+        return Err(color_eyre::Report::msg(format!(
+            "jack runs at {} hz; {} hz requested",
+            sample_rate, requested
+        )));
+    }
+    Ok(client)
+}
+
+/// where jack transport stands, none unless rolling with bbt known
+fn position(transport: &jack::Transport) -> Result<Option<transport::Position>> {
+    let state = transport.query()?;
+    let (jack::TransportState::Rolling, Some(bbt)) = (state.state, state.pos.bbt()) else {
+        return Ok(None);
+    };
+    let beats = (bbt.bar - 1) as f64 * bbt.sig_num as f64
+        + (bbt.beat - 1) as f64
+        + bbt.tick as f64 / bbt.ticks_per_beat;
+    Ok(Some(transport::Position {
+        beats,
+        bpm: bbt.bpm,
+    }))
+}
+
+/// connect audio ports of `client` named `ours` to physical ports in order, as
+/// far as both go; `inputs` whether ours capture
+fn connect(client: &jack::Client, ours: &[String], inputs: bool) {
+    let flags = match inputs {
+        true => jack::PortFlags::IS_OUTPUT,
+        false => jack::PortFlags::IS_INPUT,
+    };
+    let spec = jack::AudioIn;
+    let physical = client.ports(
+        None,
+        Some(spec.jack_port_type()),
+        flags | jack::PortFlags::IS_PHYSICAL,
+    );
+    for (ours, physical) in ours.iter().zip(physical.iter()) {
+        let _ = if inputs {
+            client.connect_ports_by_name(physical, ours)
+        } else {
+            client.connect_ports_by_name(ours, physical)
+        };
+    }
+}
+
+/// register ports and run `handler` from the jack process callback until
+/// unparked, connecting audio to physical ports; midi input handed to
+/// `input_handler` on a thread of its own
+#[allow(clippy::too_many_arguments)]
+pub fn play(
+    client: jack::Client,
+    mut handler: audio::SystemHandler,
+    input_handler: input::InputHandler,
+    params: Arc<Params>,
+    probe: Arc<latency::Probe>,
+    sched: Sched,
+    tui_tx: Sender<tui::Cmd>,
+    channels: usize,
+) -> Result<()> {
+    let mut out_ports = (0..channels)
+        .map(|i| client.register_port(&format!("out_{}", i + 1), jack::AudioOut))
+        .collect::<Result<Vec<_>, _>>()?;
+    let in_ports = (0..CAPTURE_COUNT)
+        .map(|i| client.register_port(&format!("in_{}", i + 1), jack::AudioIn))
+        .collect::<Result<Vec<_>, _>>()?;
+    let midi_port = client.register_port("midi_in", jack::MidiIn)?;
+    let out_names = out_ports
+        .iter()
+        .map(|v| v.name())
+        .collect::<Result<Vec<_>, _>>()?;
+    let in_names = in_ports
+        .iter()
+        .map(|v| v.name())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (midi_tx, midi_rx) =
+        std::sync::mpsc::sync_channel::<([u8; MIDI_LEN], usize)>(MIDI_QUEUE_LEN);
+    std::thread::spawn(move || -> Result<()> {
+        let mut input_handler = input_handler;
+        while let Ok((message, len)) = midi_rx.recv() {
+            input_handler.push_midi(&message[..len])?;
+        }
+        Ok(())
+    });
+
+    let transport = client.transport();
+    let sample_rate = client.sample_rate() as u32;
+    crate::fs::CAPTURE.assign_sample_rate(sample_rate);
+    let mut follow = transport::Follow::new(sample_rate);
+    let mut ticks = heapless::Vec::new();
+    let mut sched = Some(sched);
+    let max_len = client.buffer_size() as usize;
+    let mut scratch = Vec::with_capacity(audio::SCRATCH_LEN.max(max_len * channels));
+    let mut capture = Vec::with_capacity(max_len * CAPTURE_COUNT);
+    let process = move |_: &jack::Client, ps: &jack::ProcessScope| -> jack::Control {
+        // schedule process thread on first callback
+        if let Some(msg) = sched.take().and_then(|v| v.apply()) {
+            let _ = tui_tx.send(tui::Cmd::Log(msg));
+        }
+        let start = std::time::Instant::now();
+        let len = ps.n_frames() as usize;
+        for raw in midi_port.iter(ps) {
+            let mut message = [0; MIDI_LEN];
+            if let Some(dst) = message.get_mut(..raw.bytes.len()) {
+                dst.copy_from_slice(raw.bytes);
+                let _ = midi_tx.try_send((message, raw.bytes.len()));
+            }
+        }
+        capture.clear();
+        for frame in 0..len {
+            capture.extend(in_ports.iter().map(|v| v.as_slice(ps)[frame]));
+        }
+        probe.capture(&capture, CAPTURE_COUNT, sample_rate);
+        crate::fs::CAPTURE.push(&capture, CAPTURE_COUNT);
+        params.dry.push(&capture, CAPTURE_COUNT);
+        let (ret, allocs) = alloc_check::guard(|| {
+            let position = position(&transport)?;
+            follow.cycle(position, len, &mut handler, &tui_tx, &mut ticks)?;
+            scratch.resize(len * channels, 0.);
+            handler.tick_at(&mut scratch, channels, &ticks)?;
+            for (channel, port) in out_ports.iter_mut().enumerate() {
+                let frames = scratch.chunks_exact(channels);
+                for (dst, frame) in port.as_mut_slice(ps).iter_mut().zip(frames) {
+                    *dst = frame[channel];
+                }
+            }
+            Ok::<_, color_eyre::Report>(())
+        });
+        ret.unwrap();
+        // rendering slower than realtime starves the server
+        // this cycle plus whatever lies downstream of the out ports
+        let downstream = out_ports
+            .first()
+            .map_or(0, |v| v.get_latency_range(jack::LatencyType::Playback).1);
+        let latency = (len as u64 + downstream as u64) * 1_000_000 / sample_rate as u64;
+        params
+            .buffer_frames
+            .store(len as u32, std::sync::atomic::Ordering::Relaxed);
+        params
+            .latency
+            .store(latency as u32, std::sync::atomic::Ordering::Relaxed);
+        if start.elapsed().as_secs_f32() > len as f32 / sample_rate as f32 {
+            params
+                .underruns
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        if allocs > 0 {
+            let msg = format!("{} allocations in audio callback", allocs);
+            let _ = tui_tx.send(tui::Cmd::Log(msg));
+        }
+        jack::Control::Continue
+    };
+    let client = client.activate_async((), jack::ClosureProcessHandler::new(process))?;
+    connect(client.as_client(), &out_names, false);
+    connect(client.as_client(), &in_names, true);
+
+    std::thread::park();
+    client.deactivate()?;
+    Ok(())
+}
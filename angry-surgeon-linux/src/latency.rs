@@ -0,0 +1,62 @@
+//! loopback round-trip latency measurement
+
+use crate::tui;
+use std::{
+    sync::{mpsc::Sender, Mutex},
+    time::{Duration, Instant},
+};
+
+/// captured sample magnitude recognized as click
+const THRESHOLD: f32 = 0.25;
+/// time after which unreturned click is abandoned
+const TIMEOUT: Duration = Duration::from_millis(1000);
+/// click length in frames
+pub const CLICK_LEN: usize = 8;
+
+pub struct Probe {
+    /// instant click was written to output, if awaiting capture
+    sent: Mutex<Option<Instant>>,
+    tui_tx: Mutex<Sender<tui::Cmd>>,
+}
+
+impl Probe {
+    pub fn new(tui_tx: Sender<tui::Cmd>) -> Self {
+        Self {
+            sent: Mutex::new(None),
+            tui_tx: Mutex::new(tui_tx),
+        }
+    }
+
+    /// mark click written to output now
+    pub fn send(&self) {
+        if let Ok(mut sent) = self.sent.try_lock() {
+            *sent = Some(Instant::now());
+        }
+    }
+
+    /// scan captured input for click, reporting round trip once found
+    pub fn capture(&self, data: &[f32], channels: usize, sample_rate: u32) {
+        let Ok(mut sent) = self.sent.try_lock() else {
+            return;
+        };
+        let Some(start) = *sent else {
+            return;
+        };
+        let msg = if let Some(index) = data.iter().position(|v| v.abs() > THRESHOLD) {
+            // discount frames captured after click
+            let after = (data.len() - index) / channels;
+            let elapsed = start
+                .elapsed()
+                .saturating_sub(Duration::from_secs_f32(after as f32 / sample_rate as f32));
+            format!("round trip {:.1} ms", elapsed.as_secs_f32() * 1000.)
+        } else if start.elapsed() > TIMEOUT {
+            "no click captured; check loopback".to_string()
+        } else {
+            return;
+        };
+        *sent = None;
+        if let Ok(tui_tx) = self.tui_tx.lock() {
+            let _ = tui_tx.send(tui::Cmd::Log(msg));
+        }
+    }
+}
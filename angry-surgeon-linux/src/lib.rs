@@ -0,0 +1,25 @@
+//! engine of the linux host as a library: the audio thread's `SystemHandler`,
+//! input handling and the parameters shared between them, so other frontends
+//! such as plugin builds drive the same mangler as the terminal binary
+#![allow(clippy::uninlined_format_args)]
+
+pub mod alloc_check;
+pub mod audio;
+pub mod demo;
+pub mod dry;
+pub mod fs;
+pub mod input;
+pub mod jack_host;
+pub mod latency;
+pub mod mapping;
+pub mod midi_out;
+pub mod osc;
+pub mod output;
+pub mod params;
+pub mod record;
+pub mod resample;
+pub mod scene;
+pub mod sched;
+pub mod soak;
+pub mod transport;
+pub mod tui;
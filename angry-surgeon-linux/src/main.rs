@@ -1,155 +1,472 @@
 #![allow(clippy::uninlined_format_args)]
 
-mod audio;
-mod fs;
-mod input;
-mod tui;
+mod cli;
 
+use angry_surgeon_linux::{
+    alloc_check, audio, demo, fs, input, jack_host, latency, mapping, midi_out, osc, output,
+    params, sched, soak, tui,
+};
+use clap::Parser;
 use color_eyre::Result;
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    FromSample, SizedSample,
+    SizedSample,
 };
-use std::io::Write;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
 
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("demo") {
+        // write demo content instead of running
+        let root = args.get(2).map(String::as_str).unwrap_or(".");
+        return demo::generate(std::path::Path::new(root));
+    }
+    let cli = cli::Cli::parse();
+    let sched = sched::Sched::from_args(&args[1..])?;
+    let output = output::Output::from_args(&args[1..])?;
+    let soak = soak::Soak::from_args(&args[1..])?;
+    let mapping = mapping::Mapping::from_args(&args[1..])?;
+    let osc = osc::Osc::from_args(&args[1..])?;
+
     let (audio_tx, audio_rx) = std::sync::mpsc::channel::<audio::Cmd>();
     let (input_tx, input_rx) = std::sync::mpsc::channel::<input::Cmd>();
     let (tui_tx, tui_rx) = std::sync::mpsc::channel::<tui::Cmd>();
 
-    let hosts = cpal::available_hosts();
-    let id = match hosts.len() {
-        0 => return Err(color_eyre::Report::msg("no audio host found")),
-        1 => {
-            println!("selected only available audio host: {}", hosts[0].name());
-            hosts[0]
-        }
-        _ => {
-            println!("available audio hosts:");
-            for (i, h) in hosts.iter().enumerate() {
-                println!("{}: {}", i, h.name());
-            }
-            print!("select an audio host: ");
-            std::io::stdout().flush()?;
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            *hosts
-                .get(input.trim().parse::<usize>()?)
-                .ok_or(color_eyre::Report::msg("invalid audio host selected"))?
+    let params = std::sync::Arc::new(params::Params::new());
+    // loopback latency probe and dry throughput on default input device, or
+    // jack in ports
+    let probe = std::sync::Arc::new(latency::Probe::new(tui_tx.clone()));
+    let input_handler = input::InputHandler::new(
+        params.clone(),
+        audio_tx.clone(),
+        tui_tx.clone(),
+        input_rx,
+        mapping,
+    );
+    let jack = jack_host::requested(&args[1..]);
+    let (backend, midi_in, in_stream) = if jack {
+        let client = jack_host::open(cli.sample_rate)?;
+        let sample_rate = client.sample_rate() as u32;
+        params
+            .sample_rate
+            .store(sample_rate, std::sync::atomic::Ordering::Relaxed);
+        if cli.buffer_size.is_some() {
+            println!("\njack server sets buffer size; --buffer-size ignored");
         }
+        println!("\nopened jack client; its in ports feed latency probe and dry throughput");
+        (Backend::Jack(client, Box::new(input_handler)), None, None)
+    } else {
+        let (host, device) = select_output(&cli, &sched)?;
+        let (config, format) = negotiate(&device, &cli, &output)?;
+        params
+            .sample_rate
+            .store(config.sample_rate.0, std::sync::atomic::Ordering::Relaxed);
+        let midi_in = connect_midi_in(&cli, input_handler)?;
+        let in_stream = open_input(&host, probe.clone(), params.clone())?;
+        let backend = Backend::Cpal(device, config, format);
+        (backend, Some(midi_in), in_stream)
     };
-    let host = cpal::host_from_id(id)?;
-    let devices = host
-        .output_devices()
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
-    let device = match devices.len() {
-        0 => return Err(color_eyre::Report::msg("no audio device found")),
-        1 => {
-            println!(
-                "\nselected only available audio device: {}",
-                devices[0].name()?,
-            );
-            devices[0].clone()
-        }
-        _ => {
-            println!("\navailable audio devices:");
-            for (i, d) in devices.iter().enumerate() {
-                println!("{}: {}", i, d.name()?)
-            }
-            print!("select an audio device: ");
-            std::io::stdout().flush()?;
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            devices
-                .get(input.trim().parse::<usize>()?)
-                .ok_or(color_eyre::Report::msg("invalid audio device selected"))?
-                .clone()
-        }
+    let midi_tx = if midi_out::requested(&args[1..]) {
+        let midi_out = midir::MidiOutput::new("angry-surgeon")?;
+        let out_ports = midi_out.ports();
+        let names = out_ports
+            .iter()
+            .map(|v| midi_out.port_name(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        let out_port = &out_ports[cli::select("output port", &names, None)?];
+        let conn = midi_out
+            .connect(out_port, "angry-surgeon")
+            .map_err(|_| color_eyre::Report::msg("failed to connect to midi output"))?;
+        let (midi_tx, midi_rx) = std::sync::mpsc::sync_channel(midi_out::QUEUE_LEN);
+        midi_out::spawn(conn, midi_rx);
+        Some(midi_tx)
+    } else {
+        None
     };
-    let midi_in = midir::MidiInput::new("angry-surgeon")?;
-    let in_ports = midi_in.ports();
-    let in_port = match in_ports.len() {
-        0 => return Err(color_eyre::Report::msg("no midi input port found")),
-        1 => {
-            println!(
-                "\nselected only available input port: {}",
-                midi_in.port_name(&in_ports[0]).unwrap()
-            );
-            &in_ports[0]
-        }
-        _ => {
-            println!("\navailable input ports:");
-            for (i, p) in in_ports.iter().enumerate() {
-                println!("{}: {}", i, midi_in.port_name(p).unwrap());
-            }
-            print!("select an input port: ");
-            std::io::stdout().flush()?;
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            in_ports
-                .get(input.trim().parse::<usize>()?)
-                .ok_or(color_eyre::Report::msg("invalid input port selected"))?
-        }
-    };
-    let input_handler = input::InputHandler::new(audio_tx.clone(), tui_tx, input_rx);
-    let midi_in = midi_in
-        .connect(
-            in_port,
-            "angry-surgeon",
-            move |_, message, input_handler: &mut input::InputHandler| {
-                input_handler.push_midi(message).unwrap();
-            },
-            input_handler,
-        )
-        .map_err(|_| color_eyre::Report::msg("failed to connect to midi input"))?;
+
+    if let Some(soak) = soak {
+        println!("\nsoaking; logging to ./soak.log");
+        soak.spawn(params.clone(), audio_tx.clone(), tui_tx.clone());
+    }
+    if let Some(osc) = osc {
+        println!("\nlistening for osc on udp port {}", osc.port());
+        osc.spawn(params.clone(), audio_tx.clone(), tui_tx.clone())?;
+    }
 
     println!("\nplease make some noise <3");
     std::thread::sleep(std::time::Duration::from_millis(1000));
 
+    let sched_tx = tui_tx.clone();
+    let audio_params = params.clone();
+    let audio_probe = probe.clone();
     let audio_handle = std::thread::spawn(move || -> Result<()> {
-        let config = device
-            .supported_output_configs()?
-            .find(|v| v.channels() == 2 && v.sample_format() == cpal::SampleFormat::F32)
-            .ok_or(color_eyre::Report::msg(
-                "failed to init desired audio output",
-            ))?;
-        let config = config.with_sample_rate(cpal::SampleRate(audio::SAMPLE_RATE));
-        let handler = audio::SystemHandler::new(audio_rx).unwrap();
-        play::<f32>(&device, &config.into(), handler)?;
+        let (device, config, sample_format) = match backend {
+            Backend::Cpal(device, config, format) => (device, config, format),
+            Backend::Jack(client, input_handler) => {
+                // a port per channel, so routing always honored
+                let channels = output.channels();
+                audio_params
+                    .channels
+                    .store(channels, std::sync::atomic::Ordering::Relaxed);
+                let play_params = audio_params.clone();
+                let handler = audio::SystemHandler::new(
+                    audio_params,
+                    audio_rx,
+                    tui_tx.clone(),
+                    midi_tx,
+                    output,
+                )?;
+                return jack_host::play(
+                    client,
+                    handler,
+                    *input_handler,
+                    play_params,
+                    audio_probe,
+                    sched,
+                    tui_tx,
+                    channels as usize,
+                );
+            }
+        };
+        audio_params
+            .channels
+            .store(config.channels, std::sync::atomic::Ordering::Relaxed);
+        if config.channels < output.channels() {
+            let _ = tui_tx.send(tui::Cmd::Log(format!(
+                "{} output channels; routing falls back to every pair",
+                config.channels
+            )));
+        }
+        let play_params = audio_params.clone();
+        let handler =
+            audio::SystemHandler::new(audio_params, audio_rx, tui_tx, midi_tx, output).unwrap();
+        match sample_format {
+            cpal::SampleFormat::F32 => play(
+                &device,
+                &config,
+                handler,
+                play_params,
+                sched,
+                sched_tx,
+                |src, dst: &mut [f32]| dst.copy_from_slice(src),
+            )?,
+            cpal::SampleFormat::I32 => {
+                let mut dither = output.dither();
+                let shift = 32 - dither.depth.bits();
+                play(
+                    &device,
+                    &config,
+                    handler,
+                    play_params,
+                    sched,
+                    sched_tx,
+                    move |src, dst: &mut [i32]| {
+                        for (dst, src) in dst.iter_mut().zip(src) {
+                            *dst = dither.quantize(*src) << shift;
+                        }
+                    },
+                )?
+            }
+            _ => {
+                let mut dither = angry_surgeon_core::Dither::new(
+                    angry_surgeon_core::BitDepth::Sixteen,
+                    output.dither,
+                );
+                play(
+                    &device,
+                    &config,
+                    handler,
+                    play_params,
+                    sched,
+                    sched_tx,
+                    move |src, dst: &mut [i16]| {
+                        for (dst, src) in dst.iter_mut().zip(src) {
+                            *dst = dither.quantize(*src) as i16;
+                        }
+                    },
+                )?
+            }
+        }
         Ok(())
     });
 
+    let fade_tx = audio_tx.clone();
     let mut terminal = ratatui::init();
-    tui::TuiHandler::new(audio_tx, input_tx)?.run(&mut terminal, tui_rx)?;
+    tui::TuiHandler::new(
+        params,
+        audio_tx,
+        input_tx,
+        (in_stream.is_some() || jack).then_some(probe),
+        output,
+    )?
+    .run(&mut terminal, tui_rx)?;
 
     ratatui::restore();
-    // pads thread completes once audio_tx held by input_handler dropped in midi_in thread
+    // let master fade out, with a buffer of slack, before dropping stream
+    if fade_tx.send(audio::Cmd::FadeOut).is_ok() {
+        let fade_len = angry_surgeon_core::MASTER_FADE_LEN as u64 * 2;
+        std::thread::sleep(std::time::Duration::from_millis(fade_len));
+    }
+    std::mem::drop(fade_tx);
+    std::mem::drop(in_stream);
+    // pads thread completes once audio_tx held by input_handler dropped in midi_in thread,
+    // or under jack once its midi thread hangs up on deactivating
     std::mem::drop(midi_in);
     audio_handle.thread().unpark();
     audio_handle.join().unwrap()?;
     Ok(())
 }
 
+/// render into float scratch, then `write` it out in device format
 fn play<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     mut handler: audio::SystemHandler,
+    params: std::sync::Arc<params::Params>,
+    sched: sched::Sched,
+    tui_tx: std::sync::mpsc::Sender<tui::Cmd>,
+    mut write: impl FnMut(&[f32], &mut [T]) + Send + 'static,
 ) -> Result<()>
 where
-    T: SizedSample + FromSample<f32>,
+    T: SizedSample,
 {
     let channels = config.channels as usize;
-    let out_fn = move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-        handler.tick(data, channels).unwrap();
+    let sample_rate = config.sample_rate.0;
+    let mut sched = Some(sched);
+    let mut scratch = Vec::with_capacity(audio::SCRATCH_LEN);
+    let callback_params = params.clone();
+    let out_fn = move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+        // schedule render thread on first callback
+        if let Some(msg) = sched.take().and_then(|v| v.apply()) {
+            let _ = tui_tx.send(tui::Cmd::Log(msg));
+        }
+        let start = std::time::Instant::now();
+        let (ret, allocs) = alloc_check::guard(|| {
+            scratch.resize(data.len(), 0.);
+            let ret = handler.tick(&mut scratch, channels);
+            write(&scratch, data);
+            ret
+        });
+        ret.unwrap();
+        // rendering slower than realtime starves the device
+        let frames = data.len() / channels;
+        let len = frames as f32 / sample_rate as f32;
+        // hosts without playback timestamps assume a buffer ahead
+        let timestamp = info.timestamp();
+        let latency = timestamp
+            .playback
+            .duration_since(&timestamp.callback)
+            .map_or(len, |v| v.as_secs_f32());
+        callback_params
+            .buffer_frames
+            .store(frames as u32, std::sync::atomic::Ordering::Relaxed);
+        callback_params
+            .latency
+            .store((latency * 1e6) as u32, std::sync::atomic::Ordering::Relaxed);
+        if start.elapsed().as_secs_f32() > len {
+            callback_params
+                .underruns
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        if allocs > 0 {
+            let _ = tui_tx.send(tui::Cmd::Log(format!("{} allocations in audio callback", allocs)));
+        }
+    };
+    let err_fn = move |_| {
+        params.underruns.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     };
-    let err_fn = |_| {};
     let stream = device.build_output_stream(config, out_fn, err_fn, None)?;
 
     stream.play()?;
     std::thread::park();
     Ok(())
 }
+
+/// audio backend selected at startup, with whatever only it holds
+enum Backend {
+    /// device, granted stream config and sample format it takes
+    Cpal(cpal::Device, cpal::StreamConfig, cpal::SampleFormat),
+    /// midi input handled from a jack port of the client's own
+    Jack(jack::Client, Box<input::InputHandler>),
+}
+
+/// host and output device, by flag or prompted for where more than one on
+/// offer
+fn select_output(cli: &cli::Cli, sched: &sched::Sched) -> Result<(cpal::Host, cpal::Device)> {
+    let hosts = cpal::available_hosts();
+    let names = hosts
+        .iter()
+        .map(|v| v.name().to_string())
+        .collect::<Vec<_>>();
+    let id = hosts[cli::select("audio host", &names, cli.host.as_deref())?];
+    let host = cpal::host_from_id(id)?;
+    let devices = host
+        .output_devices()
+        .into_iter()
+        .flatten()
+        .filter(|v| v.name().is_ok_and(|v| sched.lists(&v)))
+        .collect::<Vec<_>>();
+    let names = devices
+        .iter()
+        .map(|v| v.name())
+        .collect::<Result<Vec<_>, _>>()?;
+    let device = devices[cli::select("audio device", &names, cli.output.as_deref())?].clone();
+    Ok((host, device))
+}
+
+/// stream config of `device` and the sample format it takes: fewest channels
+/// honoring routing where offered, else any channel count mapped from stereo;
+/// float where offered, else widest integer format. rate as requested, else
+/// `SAMPLE_RATE` where offered, else the highest offered; buffer size as
+/// requested, clamped into what is offered
+fn negotiate(
+    device: &cpal::Device,
+    cli: &cli::Cli,
+    output: &output::Output,
+) -> Result<(cpal::StreamConfig, cpal::SampleFormat)> {
+    let configs = device.supported_output_configs()?.collect::<Vec<_>>();
+    let formats = [
+        cpal::SampleFormat::F32,
+        cpal::SampleFormat::I32,
+        cpal::SampleFormat::I16,
+    ];
+    let channels = output.channels();
+    let rate = cpal::SampleRate(cli.sample_rate.unwrap_or(audio::SAMPLE_RATE));
+    let pick = |rated: bool| {
+        [true, false]
+            .into_iter()
+            .flat_map(|routed| formats.map(|format| (routed, format)))
+            .find_map(|(routed, format)| {
+                configs
+                    .iter()
+                    .filter(|v| {
+                        (!routed || v.channels() >= channels)
+                            && v.sample_format() == format
+                            && (!rated || v.try_with_sample_rate(rate).is_some())
+                    })
+                    .min_by_key(|v| v.channels().abs_diff(channels))
+                    .copied()
+            })
+    };
+    let config = match (pick(true), cli.sample_rate) {
+        (Some(config), _) => config.with_sample_rate(rate),
+        (None, Some(rate)) => {
+            let msg = format!("audio device offers no {} hz output", rate);
+            return Err(color_eyre::Report::msg(msg));
+        }
+        (None, None) => {
+            let msg = "failed to init desired audio output";
+            let config = pick(false)
+                .ok_or(color_eyre::Report::msg(msg))?
+                .with_max_sample_rate();
+            println!(
+                "\naudio device offers no {} hz output; running at {} hz",
+                rate.0,
+                config.sample_rate().0
+            );
+            config
+        }
+    };
+    let buffer_size = match (cli.buffer_size, config.buffer_size()) {
+        (None, _) => cpal::BufferSize::Default,
+        (Some(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+            let granted = frames.clamp(*min, *max);
+            if granted != frames {
+                println!("\naudio device offers {} to {} frame buffers", min, max);
+            }
+            cpal::BufferSize::Fixed(granted)
+        }
+        (Some(frames), cpal::SupportedBufferSize::Unknown) => cpal::BufferSize::Fixed(frames),
+    };
+    let format = config.sample_format();
+    let mut config = cpal::StreamConfig::from(config);
+    config.buffer_size = buffer_size;
+    Ok((config, format))
+}
+
+/// connect `input_handler` to a midi input port, by flag or prompted for
+/// where more than one on offer
+fn connect_midi_in(
+    cli: &cli::Cli,
+    input_handler: input::InputHandler,
+) -> Result<midir::MidiInputConnection<input::InputHandler>> {
+    let midi_in = midir::MidiInput::new("angry-surgeon")?;
+    let in_ports = midi_in.ports();
+    let names = in_ports
+        .iter()
+        .map(|v| midi_in.port_name(v))
+        .collect::<Result<Vec<_>, _>>()?;
+    let in_port = &in_ports[cli::select("input port", &names, cli.midi_in.as_deref())?];
+    midi_in
+        .connect(
+            in_port,
+            "angry-surgeon",
+            move |_, message, input_handler: &mut input::InputHandler| {
+                input_handler.push_midi(message).unwrap();
+            },
+            input_handler,
+        )
+        .map_err(|_| color_eyre::Report::msg("failed to connect to midi input"))
+}
+
+/// loopback latency probe, dry throughput and live capture on default input
+/// device of `host`, if any; at the output rate where offered
+fn open_input(
+    host: &cpal::Host,
+    probe: std::sync::Arc<latency::Probe>,
+    params: std::sync::Arc<params::Params>,
+) -> Result<Option<cpal::Stream>> {
+    Ok(match host.default_input_device() {
+        Some(device) => {
+            println!(
+                "\nselected default input device for latency probe and dry throughput: {}",
+                device.name()?
+            );
+            let output_rate = params
+                .sample_rate
+                .load(std::sync::atomic::Ordering::Relaxed);
+            let matched = device.supported_input_configs()?.find_map(|v| {
+                (v.sample_format() == cpal::SampleFormat::F32)
+                    .then(|| v.try_with_sample_rate(cpal::SampleRate(output_rate)))
+                    .flatten()
+            });
+            let config = match matched {
+                Some(config) => config,
+                None => device.default_input_config()?,
+            };
+            if config.sample_format() == cpal::SampleFormat::F32 {
+                let channels = config.channels() as usize;
+                let sample_rate = config.sample_rate().0;
+                if sample_rate != output_rate {
+                    println!(
+                        "input rate {} hz differs from output; dry throughput disabled",
+                        sample_rate
+                    );
+                }
+                fs::CAPTURE.assign_sample_rate(sample_rate);
+                let stream = device.build_input_stream(
+                    &config.into(),
+                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                        probe.capture(data, channels, sample_rate);
+                        fs::CAPTURE.push(data, channels);
+                        if sample_rate == output_rate {
+                            params.dry.push(data, channels);
+                        }
+                    },
+                    |_| {},
+                    None,
+                )?;
+                stream.play()?;
+                Some(stream)
+            } else {
+                println!("input device not f32; latency probe and dry throughput disabled");
+                None
+            }
+        }
+        None => {
+            println!("\nno input device found; latency probe and dry throughput disabled");
+            None
+        }
+    })
+}
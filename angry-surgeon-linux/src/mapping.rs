@@ -0,0 +1,276 @@
+//! midi controller mapping: the note or cc each control answers to, and the
+//! parameter pressure drives per bank, loaded from a json file at startup and
+//! rewritten by midi learn
+
+use crate::audio::{Bank, BANK_COUNT};
+use crate::input::{ctrl, keys};
+use color_eyre::Result;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// mapping file unless requested otherwise
+const MAPPING_PATH: &str = "mapping.json";
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Kind {
+    Key,
+    Ctrl,
+}
+
+/// parameter channel and per-note pressure offsets while pads are held
+#[derive(Copy, Clone, PartialEq)]
+pub enum Pressure {
+    Pitch,
+    Filter,
+    LoopDiv,
+}
+
+impl Pressure {
+    pub const COUNT: usize = 3;
+    const NAMES: [&str; Self::COUNT] = ["pitch", "filter", "loop_div"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Pitch, Self::Filter, Self::LoopDiv]
+            .into_iter()
+            .find(|v| Self::NAMES[*v as usize] == name)
+    }
+
+    fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+/// pressure routing entries by bank
+const PRESSURE: [&str; BANK_COUNT] = ["pressure_a", "pressure_b"];
+
+/// mappable controls by name, and the note or cc each answers to unmapped;
+/// pads numbered from 1 in pad order
+const CONTROLS: &[(&str, Kind, u8)] = &[
+    ("open", Kind::Key, keys::OPEN),
+    ("dry", Kind::Key, keys::DRY),
+    ("freeze_a", Kind::Key, keys::FREEZE_A),
+    ("tape_a", Kind::Key, keys::TAPE_A),
+    ("stutter_a", Kind::Key, keys::STUTTER_A),
+    ("stereo_a", Kind::Key, keys::STEREO_A),
+    ("resample_a", Kind::Key, keys::RESAMPLE_A),
+    ("accent_a", Kind::Key, keys::ACCENT_A),
+    ("motion_a", Kind::Key, keys::MOTION_A),
+    ("kit_a", Kind::Key, keys::KIT_A),
+    ("hold_a", Kind::Key, keys::HOLD_A),
+    ("reverse_a", Kind::Key, keys::REVERSE_A),
+    ("shift_a", Kind::Key, keys::SHIFT_A),
+    ("a1", Kind::Key, keys::BANK_A.start + 7),
+    ("a2", Kind::Key, keys::BANK_A.start + 6),
+    ("a3", Kind::Key, keys::BANK_A.start + 5),
+    ("a4", Kind::Key, keys::BANK_A.start + 4),
+    ("a5", Kind::Key, keys::BANK_A.start + 3),
+    ("a6", Kind::Key, keys::BANK_A.start + 2),
+    ("a7", Kind::Key, keys::BANK_A.start + 1),
+    ("a8", Kind::Key, keys::BANK_A.start),
+    ("b1", Kind::Key, keys::BANK_B.start),
+    ("b2", Kind::Key, keys::BANK_B.start + 1),
+    ("b3", Kind::Key, keys::BANK_B.start + 2),
+    ("b4", Kind::Key, keys::BANK_B.start + 3),
+    ("b5", Kind::Key, keys::BANK_B.start + 4),
+    ("b6", Kind::Key, keys::BANK_B.start + 5),
+    ("b7", Kind::Key, keys::BANK_B.start + 6),
+    ("b8", Kind::Key, keys::BANK_B.start + 7),
+    ("shift_b", Kind::Key, keys::SHIFT_B),
+    ("reverse_b", Kind::Key, keys::REVERSE_B),
+    ("hold_b", Kind::Key, keys::HOLD_B),
+    ("kit_b", Kind::Key, keys::KIT_B),
+    ("motion_b", Kind::Key, keys::MOTION_B),
+    ("accent_b", Kind::Key, keys::ACCENT_B),
+    ("resample_b", Kind::Key, keys::RESAMPLE_B),
+    ("stereo_b", Kind::Key, keys::STEREO_B),
+    ("stutter_b", Kind::Key, keys::STUTTER_B),
+    ("tape_b", Kind::Key, keys::TAPE_B),
+    ("freeze_b", Kind::Key, keys::FREEZE_B),
+    ("gain_oneshot", Kind::Ctrl, ctrl::GAIN_ONESHOT),
+    ("dry_level", Kind::Ctrl, ctrl::DRY_LEVEL),
+    ("master_width", Kind::Ctrl, ctrl::MASTER_WIDTH),
+    ("delay_feedback", Kind::Ctrl, ctrl::DELAY_FEEDBACK),
+    ("delay_division", Kind::Ctrl, ctrl::DELAY_DIVISION),
+    ("drive", Kind::Ctrl, ctrl::DRIVE),
+    ("ceiling", Kind::Ctrl, ctrl::CEILING),
+    ("morph", Kind::Ctrl, ctrl::MORPH),
+    ("master_gain", Kind::Ctrl, ctrl::MASTER_GAIN),
+    ("scene_fade", Kind::Ctrl, ctrl::SCENE_FADE),
+    ("crossfade", Kind::Ctrl, ctrl::CROSSFADE),
+    ("texture", Kind::Ctrl, ctrl::TEXTURE),
+    ("gain_a", Kind::Ctrl, ctrl::GAIN_A),
+    ("speed_a", Kind::Ctrl, ctrl::SPEED_A),
+    ("drift_a", Kind::Ctrl, ctrl::DRIFT_A),
+    ("drift_mode_a", Kind::Ctrl, ctrl::DRIFT_MODE_A),
+    ("release_a", Kind::Ctrl, ctrl::RELEASE_A),
+    ("humanize_a", Kind::Ctrl, ctrl::HUMANIZE_A),
+    ("accent_level_a", Kind::Ctrl, ctrl::ACCENT_A),
+    ("envelope_a", Kind::Ctrl, ctrl::ENVELOPE_A),
+    ("filter_a", Kind::Ctrl, ctrl::FILTER_A),
+    ("filter_mode_a", Kind::Ctrl, ctrl::FILTER_MODE_A),
+    ("send_a", Kind::Ctrl, ctrl::SEND_A),
+    ("follow_a", Kind::Ctrl, ctrl::FOLLOW_A),
+    ("slip_a", Kind::Ctrl, ctrl::SLIP_A),
+    ("velocity_a", Kind::Ctrl, ctrl::VELOCITY_A),
+    ("density_a", Kind::Ctrl, ctrl::DENSITY_A),
+    ("loop_fine_a", Kind::Ctrl, ctrl::LOOP_FINE_A),
+    ("stutter_len_a", Kind::Ctrl, ctrl::STUTTER_A),
+    ("tape_len_a", Kind::Ctrl, ctrl::TAPE_A),
+    ("freeze_size_a", Kind::Ctrl, ctrl::FREEZE_SIZE_A),
+    ("freeze_jitter_a", Kind::Ctrl, ctrl::FREEZE_JITTER_A),
+    ("tail_len_a", Kind::Ctrl, ctrl::TAIL_A),
+    ("launch_a", Kind::Ctrl, ctrl::LAUNCH_A),
+    ("gain_b", Kind::Ctrl, ctrl::GAIN_B),
+    ("speed_b", Kind::Ctrl, ctrl::SPEED_B),
+    ("drift_b", Kind::Ctrl, ctrl::DRIFT_B),
+    ("drift_mode_b", Kind::Ctrl, ctrl::DRIFT_MODE_B),
+    ("release_b", Kind::Ctrl, ctrl::RELEASE_B),
+    ("humanize_b", Kind::Ctrl, ctrl::HUMANIZE_B),
+    ("accent_level_b", Kind::Ctrl, ctrl::ACCENT_B),
+    ("envelope_b", Kind::Ctrl, ctrl::ENVELOPE_B),
+    ("filter_b", Kind::Ctrl, ctrl::FILTER_B),
+    ("filter_mode_b", Kind::Ctrl, ctrl::FILTER_MODE_B),
+    ("send_b", Kind::Ctrl, ctrl::SEND_B),
+    ("follow_b", Kind::Ctrl, ctrl::FOLLOW_B),
+    ("slip_b", Kind::Ctrl, ctrl::SLIP_B),
+    ("velocity_b", Kind::Ctrl, ctrl::VELOCITY_B),
+    ("density_b", Kind::Ctrl, ctrl::DENSITY_B),
+    ("loop_fine_b", Kind::Ctrl, ctrl::LOOP_FINE_B),
+    ("stutter_len_b", Kind::Ctrl, ctrl::STUTTER_B),
+    ("tape_len_b", Kind::Ctrl, ctrl::TAPE_B),
+    ("freeze_size_b", Kind::Ctrl, ctrl::FREEZE_SIZE_B),
+    ("freeze_jitter_b", Kind::Ctrl, ctrl::FREEZE_JITTER_B),
+    ("tail_len_b", Kind::Ctrl, ctrl::TAIL_B),
+    ("launch_b", Kind::Ctrl, ctrl::LAUNCH_B),
+];
+
+/// note or cc each control answers to, if any, in `CONTROLS` order
+pub struct Mapping {
+    numbers: Vec<Option<u8>>,
+    /// parameter pressure drives per bank, if any
+    pressure: [Option<Pressure>; BANK_COUNT],
+    /// file loaded from and learned to
+    path: String,
+}
+
+impl Mapping {
+    /// parse `--mapping <path>`, reading mapping from path, else from
+    /// ./mapping.json if there; defaults to the built-in layout
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut path = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--mapping" {
+                path = Some(
+                    args.next()
+                        .ok_or(color_eyre::Report::msg("--mapping expects a path"))?
+                        .clone(),
+                );
+            }
+        }
+        let mut mapping = Self {
+            numbers: CONTROLS.iter().map(|v| Some(v.2)).collect(),
+            pressure: [None; BANK_COUNT],
+            path: path.clone().unwrap_or(MAPPING_PATH.to_string()),
+        };
+        if path.is_some() || std::path::Path::new(MAPPING_PATH).exists() {
+            let file = std::fs::read(&mapping.path)?;
+            let file = serde_json::from_slice::<BTreeMap<String, Value>>(&file)?;
+            mapping.read(&file)?;
+        }
+        Ok(mapping)
+    }
+
+    /// bind controls named in `file`, unbinding any others on their numbers,
+    /// and route pressure; errs on unknown names, numbers past 127, numbers
+    /// bound twice and unknown pressure parameters
+    fn read(&mut self, file: &BTreeMap<String, Value>) -> Result<()> {
+        let mut read: Vec<(Kind, u8, &str)> = Vec::new();
+        for (name, value) in file.iter() {
+            if let Some(bank) = PRESSURE.iter().position(|v| v == name) {
+                let Some(pressure) = value.as_str().and_then(Pressure::from_name) else {
+                    return Err(color_eyre::Report::msg(format!(
+                        "{} expects one of {}",
+                        name,
+                        Pressure::NAMES.join(", ")
+                    )));
+                };
+                self.pressure[bank] = Some(pressure);
+                continue;
+            }
+            let Some(number) = value.as_u64() else {
+                let msg = format!("{} expects a number", name);
+                return Err(color_eyre::Report::msg(msg));
+            };
+            let index = CONTROLS
+                .iter()
+                .position(|v| v.0 == name)
+                .ok_or(color_eyre::Report::msg(format!("unknown control {}", name)))?;
+            let kind = CONTROLS[index].1;
+            if number > 127 {
+                return Err(color_eyre::Report::msg(format!("{} mapped past 127", name)));
+            }
+            let number = number as u8;
+            if let Some((_, _, other)) = read.iter().find(|v| v.0 == kind && v.1 == number) {
+                return Err(color_eyre::Report::msg(format!(
+                    "{} and {} both mapped to {}",
+                    other, name, number
+                )));
+            }
+            self.bind(index, number);
+            read.push((kind, number, name));
+        }
+        Ok(())
+    }
+
+    /// write every control bound and pressure routed to file loaded from
+    pub fn save(&self) -> Result<()> {
+        let mut file = CONTROLS
+            .iter()
+            .zip(self.numbers.iter())
+            .filter_map(|(control, number)| Some((control.0, Value::from((*number)?))))
+            .collect::<BTreeMap<_, _>>();
+        for (name, pressure) in PRESSURE.iter().zip(self.pressure.iter()) {
+            if let Some(pressure) = pressure {
+                file.insert(name, Value::from(pressure.name()));
+            }
+        }
+        serde_json::to_writer_pretty(std::fs::File::create(&self.path)?, &file)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// bind control at `index` to `number`, unbinding any other of its kind
+    /// there
+    pub fn bind(&mut self, index: usize, number: u8) {
+        let kind = CONTROLS[index].1;
+        for (control, bound) in CONTROLS.iter().zip(self.numbers.iter_mut()) {
+            if control.1 == kind && *bound == Some(number) {
+                *bound = None;
+            }
+        }
+        self.numbers[index] = Some(number);
+    }
+
+    /// built-in note or cc of control `kind` bound to `number`, if any
+    pub fn resolve(&self, kind: Kind, number: u8) -> Option<u8> {
+        CONTROLS
+            .iter()
+            .zip(self.numbers.iter())
+            .find(|(control, bound)| control.1 == kind && **bound == Some(number))
+            .map(|(control, _)| control.2)
+    }
+
+    /// parameter pressure drives in `bank`, if any
+    pub fn pressure(&self, bank: Bank) -> Option<Pressure> {
+        self.pressure[bank as u8 as usize]
+    }
+
+    /// name and kind of control at `index`, if any
+    pub fn control(&self, index: usize) -> Option<(&'static str, Kind)> {
+        CONTROLS.get(index).map(|v| (v.0, v.1))
+    }
+}
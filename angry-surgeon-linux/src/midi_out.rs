@@ -0,0 +1,134 @@
+//! midi output: clock spaced out between step ticks and a note per pad hit,
+//! so external gear can follow and be triggered by the mangler
+
+use crate::audio::{BANK_COUNT, PPQ, TICKS_PER_STEP};
+use color_eyre::Result;
+use std::{
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Instant,
+};
+
+/// messages queued by the audio thread before the output thread drains them;
+/// further ones dropped rather than allocating
+pub const QUEUE_LEN: usize = 64;
+/// note of first pad; pads follow chromatically, each bank on its own channel
+const BASE_NOTE: u8 = 36;
+const VELOCITY: u8 = 100;
+/// midi clocks per core tick
+const CLOCKS_PER_TICK: u32 = (PPQ / TICKS_PER_STEP) as u32;
+
+const CLOCK: u8 = 0xf8;
+const START: u8 = 0xfa;
+const STOP: u8 = 0xfc;
+
+#[derive(Copy, Clone)]
+pub enum Msg {
+    Tick,
+    Stop,
+    /// pad hit in bank, by index
+    Hit(usize, u8),
+}
+
+/// whether `--midi-out` requested in `args`
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|v| v == "--midi-out")
+}
+
+/// send clock and notes from a thread of its own until the audio thread hangs
+/// up; clocks after the first of each tick are spaced over the last tick
+/// interval, notes held until a tick has passed
+pub fn spawn(
+    mut conn: midir::MidiOutputConnection,
+    rx: Receiver<Msg>,
+) -> std::thread::JoinHandle<Result<()>> {
+    std::thread::spawn(move || -> Result<()> {
+        let mut send = |message: &[u8]| {
+            conn.send(message)
+                .map_err(|e| color_eyre::Report::msg(e.to_string()))
+        };
+        let mut running = false;
+        let mut last_tick: Option<Instant> = None;
+        let mut spacing = None;
+        // clocks owed this tick, and when next is due
+        let mut owed = 0;
+        let mut due = Instant::now();
+        // note sounding per bank, and whether a tick has passed since hit
+        let mut sounding: [Option<(u8, bool)>; BANK_COUNT] = [None; BANK_COUNT];
+        loop {
+            let msg = if owed > 0 {
+                match rx.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                    Ok(msg) => Some(msg),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match rx.recv() {
+                    Ok(msg) => Some(msg),
+                    Err(_) => break,
+                }
+            };
+            match msg {
+                None => {
+                    send(&[CLOCK])?;
+                    owed -= 1;
+                    due += spacing.unwrap_or_default();
+                }
+                Some(Msg::Tick) => {
+                    // settle clocks owed from a tick come early
+                    for _ in 0..owed {
+                        send(&[CLOCK])?;
+                    }
+                    if !running {
+                        send(&[START])?;
+                        running = true;
+                    }
+                    let now = Instant::now();
+                    spacing = last_tick.map(|v| now.duration_since(v) / CLOCKS_PER_TICK);
+                    last_tick = Some(now);
+                    send(&[CLOCK])?;
+                    // unspaced until a tick interval is known
+                    owed = if spacing.is_some() {
+                        CLOCKS_PER_TICK - 1
+                    } else {
+                        0
+                    };
+                    due = now + spacing.unwrap_or_default();
+                    for (channel, note) in sounding.iter_mut().enumerate() {
+                        match note {
+                            Some((key, true)) => {
+                                send(&[0x80 | channel as u8, *key, 0])?;
+                                *note = None;
+                            }
+                            Some((_, ticked)) => *ticked = true,
+                            None => (),
+                        }
+                    }
+                }
+                Some(Msg::Stop) => {
+                    owed = 0;
+                    last_tick = None;
+                    spacing = None;
+                    for (channel, note) in sounding.iter_mut().enumerate() {
+                        if let Some((key, _)) = note.take() {
+                            send(&[0x80 | channel as u8, key, 0])?;
+                        }
+                    }
+                    if running {
+                        send(&[STOP])?;
+                        running = false;
+                    }
+                }
+                Some(Msg::Hit(bank, pad)) => {
+                    let channel = bank as u8;
+                    if let Some((key, _)) = sounding[bank].take() {
+                        send(&[0x80 | channel, key, 0])?;
+                    }
+                    let key = BASE_NOTE + pad;
+                    send(&[0x90 | channel, key, VELOCITY])?;
+                    sounding[bank] = Some((key, false));
+                }
+            }
+        }
+        Ok(())
+    })
+}
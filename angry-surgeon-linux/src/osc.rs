@@ -0,0 +1,272 @@
+//! osc control surface: udp messages translated into audio commands and knob
+//! parameters, so touch layouts and generative patches can play alongside
+//! midi
+//!
+//! addresses, banks `a` or `b`, pads and kits numbered from 1, levels in 0..=1:
+//! - `/bank/<bank>/pad/<pad> <velocity>`: hit pad, releasing on 0
+//! - `/bank/<bank>/loop/<pad> <len>`: loop from pad, len in steps, fractions
+//!   held to 1/256 step
+//! - `/bank/<bank>/reverse <on>`
+//! - `/bank/<bank>/stutter <on>`: hold micro-loop, releasing on 0
+//! - `/bank/<bank>/tape_stop <on>`: ramp to a stop, spinning back up on 0
+//! - `/bank/<bank>/freeze <on>`: hold playhead, scrubbing about it
+//! - `/bank/<bank>/kit <kit>`
+//! - `/bank/<bank>/<param> <level>`: gain, width, speed, roll, drift,
+//!   phrase_drift, humanize, cutoff, resonance, accent, send, sensitivity,
+//!   density, loop_fine, stutter_len, tape_len, freeze_size, freeze_jitter,
+//!   tail_len
+//! - `/master/<param> <level>`: oneshot, width, feedback, drive, ceiling,
+//!   crossfade, texture
+//! - `/tempo <bpm>`
+
+use crate::{
+    audio::{self, Bank, PAD_COUNT},
+    params::{BankParams, Param, Params},
+    tui,
+};
+use angry_surgeon_core::{Event, LOOP_STEP};
+use color_eyre::Result;
+use std::{
+    net::UdpSocket,
+    sync::{mpsc::Sender, Arc},
+};
+
+/// largest datagram read; longer ones truncated and so dropped
+const PACKET_LEN: usize = 1536;
+const BUNDLE: &[u8] = b"#bundle\0";
+
+/// osc server requested on the command line
+#[derive(Clone, Copy)]
+pub struct Osc {
+    port: u16,
+}
+
+impl Osc {
+    /// parse `--osc <port>` from `args`
+    pub fn from_args(args: &[String]) -> Result<Option<Self>> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--osc" {
+                return match args.next().and_then(|v| v.parse::<u16>().ok()) {
+                    Some(port) => Ok(Some(Self { port })),
+                    None => Err(color_eyre::Report::msg("--osc expects a udp port")),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// bind port, then serve messages from a thread of its own until the
+    /// audio thread hangs up; bundles applied on arrival, timetags ignored
+    pub fn spawn(
+        self,
+        params: Arc<Params>,
+        audio_tx: Sender<audio::Cmd>,
+        tui_tx: Sender<tui::Cmd>,
+    ) -> Result<std::thread::JoinHandle<Result<()>>> {
+        let socket = UdpSocket::bind(("0.0.0.0", self.port))?;
+        Ok(std::thread::spawn(move || -> Result<()> {
+            let mut packet = [0; PACKET_LEN];
+            loop {
+                let len = socket.recv(&mut packet)?;
+                let mut messages = Vec::new();
+                if !decode(&packet[..len], &mut messages) {
+                    let _ = tui_tx.send(tui::Cmd::Log("osc: malformed packet".into()));
+                    continue;
+                }
+                for (address, args) in messages {
+                    if !apply(address, &args, &params, &audio_tx, &tui_tx)? {
+                        let msg = format!("osc: unknown message {}", address);
+                        let _ = tui_tx.send(tui::Cmd::Log(msg));
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// null-terminated string at `pos`, passing it and its padding
+fn string<'a>(packet: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+    let len = packet.get(*pos..)?.iter().position(|&v| v == 0)?;
+    let string = core::str::from_utf8(&packet[*pos..*pos + len]).ok()?;
+    *pos += (len + 4) & !3;
+    Some(string)
+}
+
+/// big-endian word at `pos`, passing it
+fn word(packet: &[u8], pos: &mut usize) -> Option<[u8; 4]> {
+    let word = packet.get(*pos..*pos + 4)?.try_into().ok()?;
+    *pos += 4;
+    Some(word)
+}
+
+/// push address and numeric arguments of every message in `packet`, bundles
+/// unpacked in order; whether well-formed. strings and blobs unsupported
+fn decode<'a>(packet: &'a [u8], messages: &mut Vec<(&'a str, Vec<f32>)>) -> bool {
+    if let Some(mut elements) = packet.strip_prefix(BUNDLE) {
+        // timetag
+        let Some(rest) = elements.get(8..) else {
+            return false;
+        };
+        elements = rest;
+        while !elements.is_empty() {
+            let mut pos = 0;
+            let Some(len) = word(elements, &mut pos) else {
+                return false;
+            };
+            let len = u32::from_be_bytes(len) as usize;
+            let Some(element) = elements.get(pos..pos + len) else {
+                return false;
+            };
+            if !decode(element, messages) {
+                return false;
+            }
+            elements = &elements[pos + len..];
+        }
+        return true;
+    }
+    let mut pos = 0;
+    let Some(address) = string(packet, &mut pos) else {
+        return false;
+    };
+    // tags omitted by some older senders
+    let tags = string(packet, &mut pos).unwrap_or(",");
+    let Some(tags) = tags.strip_prefix(',') else {
+        return false;
+    };
+    let mut args = Vec::new();
+    for tag in tags.chars() {
+        let arg = match tag {
+            'i' => word(packet, &mut pos).map(|v| i32::from_be_bytes(v) as f32),
+            'f' => word(packet, &mut pos).map(f32::from_be_bytes),
+            'T' => Some(1.),
+            'F' => Some(0.),
+            _ => None,
+        };
+        let Some(arg) = arg else {
+            return false;
+        };
+        args.push(arg);
+    }
+    messages.push((address, args));
+    true
+}
+
+/// knob parameter of bank named `name` and its scale from 0..=1, if any
+fn bank_param<'a>(params: &'a BankParams, name: &str) -> Option<(&'a Param, f32)> {
+    Some(match name {
+        "gain" => (&params.gain, 1.),
+        "width" => (&params.width, 1.),
+        "speed" => (&params.pitch, 2.),
+        "roll" => (&params.roll, 1.),
+        "drift" => (&params.kit_drift, 1.),
+        "phrase_drift" => (&params.phrase_drift, 1.),
+        "humanize" => (&params.humanize, 1.),
+        "cutoff" => (&params.cutoff, 1.),
+        "resonance" => (&params.resonance, 1.),
+        "accent" => (&params.accent, 1.),
+        "send" => (&params.send, 1.),
+        "sensitivity" => (&params.sensitivity, 1.),
+        "density" => (&params.density, 1.),
+        "loop_fine" => (&params.loop_fine, 1.),
+        "stutter_len" => (&params.stutter, 1.),
+        "tape_len" => (&params.tape_len, 2.),
+        "freeze_size" => (&params.freeze_size, 1.),
+        "freeze_jitter" => (&params.freeze_jitter, 1.),
+        "tail_len" => (&params.tail_len, 2.),
+        _ => return None,
+    })
+}
+
+/// pad or kit index of 1-based `number`, if in range
+fn index(number: u32) -> Option<u8> {
+    (1..=PAD_COUNT as u32)
+        .contains(&number)
+        .then(|| number as u8 - 1)
+}
+
+/// send or write what message at `address` asks; whether understood
+fn apply(
+    address: &str,
+    args: &[f32],
+    params: &Params,
+    audio_tx: &Sender<audio::Cmd>,
+    tui_tx: &Sender<tui::Cmd>,
+) -> Result<bool> {
+    let path = address.split('/').skip(1).collect::<Vec<_>>();
+    let Some(&arg) = args.first() else {
+        return Ok(false);
+    };
+    match path[..] {
+        ["tempo"] if arg > 0. => audio_tx.send(audio::Cmd::AssignTempo(arg))?,
+        ["master", name] => {
+            let param = match name {
+                "oneshot" => &params.gain_oneshot,
+                "width" => &params.master_width,
+                "feedback" => &params.delay_feedback,
+                "drive" => &params.drive,
+                "ceiling" => &params.ceiling,
+                "crossfade" => &params.crossfade,
+                "texture" => &params.texture,
+                _ => return Ok(false),
+            };
+            param.write(arg.clamp(0., 1.));
+        }
+        ["bank", bank, ref rest @ ..] => {
+            let bank = match bank {
+                "a" => Bank::A,
+                "b" => Bank::B,
+                _ => return Ok(false),
+            };
+            let bank_cmd = |cmd| audio::Cmd::Bank(bank, cmd);
+            match rest {
+                ["pad", number] => {
+                    let Some(index) = number.parse().ok().and_then(index) else {
+                        return Ok(false);
+                    };
+                    if arg > 0. {
+                        let event = Event::Hold { index };
+                        let velocity = arg.min(1.);
+                        audio_tx.send(bank_cmd(audio::BankCmd::PushHit(event, velocity)))?;
+                    } else {
+                        audio_tx.send(bank_cmd(audio::BankCmd::PushRelease))?;
+                    }
+                    let _ = tui_tx.send(tui::Cmd::Bank(bank, tui::BankCmd::Pad(index, arg > 0.)));
+                }
+                ["loop", number] if arg * LOOP_STEP as f32 >= 1. => {
+                    let Some(index) = number.parse().ok().and_then(index) else {
+                        return Ok(false);
+                    };
+                    let event = Event::Loop {
+                        index,
+                        len: (arg * LOOP_STEP as f32) as u16,
+                    };
+                    audio_tx.send(bank_cmd(audio::BankCmd::PushHit(event, 1.)))?;
+                }
+                ["reverse"] => audio_tx.send(bank_cmd(audio::BankCmd::PushReverse(arg > 0.)))?,
+                ["stutter"] => audio_tx.send(bank_cmd(audio::BankCmd::PushStutter(arg > 0.)))?,
+                ["tape_stop"] => audio_tx.send(bank_cmd(audio::BankCmd::PushTapeStop(arg > 0.)))?,
+                ["freeze"] => audio_tx.send(bank_cmd(audio::BankCmd::AssignFreeze(arg > 0.)))?,
+                ["kit"] => {
+                    let Some(index) = index(arg as u32) else {
+                        return Ok(false);
+                    };
+                    audio_tx.send(bank_cmd(audio::BankCmd::LoadKit(index)))?;
+                }
+                [name] => {
+                    let Some((param, scale)) = bank_param(params.bank(bank), name) else {
+                        return Ok(false);
+                    };
+                    param.write(arg.clamp(0., 1.) * scale);
+                }
+                _ => return Ok(false),
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
@@ -0,0 +1,167 @@
+//! integer sample format of device output and recordings
+
+use crate::audio::{BANK_COUNT, CHANNEL_COUNT};
+use angry_surgeon_core::{
+    BitDepth, Dither, Interpolation, MAX_CLOCK_OFFSET, MAX_REHEARSAL_BARS, MAX_VOICES,
+};
+use color_eyre::Result;
+
+/// voices per bank unless requested otherwise
+const VOICE_COUNT: usize = 4;
+
+/// bit depth, dither, bank, click and cue routing, loudness target,
+/// rehearsal length, voice count, clock offset, parameter smoothing and
+/// grain interpolation requested on the command line
+#[derive(Clone, Copy)]
+pub struct Output {
+    /// depth of recordings and 32-bit integer devices
+    pub depth: BitDepth,
+    pub dither: bool,
+    /// output pair per bank, counted from 0; every pair if none
+    pub routes: [Option<usize>; BANK_COUNT],
+    /// output pair given over to metronome click, counted from 0, which
+    /// starts it sounding; mixed into every pair if none
+    pub click: Option<usize>,
+    /// output pair quantized hits pre-listen on, counted from 0; none if none
+    pub cue: Option<usize>,
+    /// integrated loudness to monitor against in lufs; core default if none
+    pub lufs_target: Option<f32>,
+    /// bars looped in rehearsal; core default if none
+    pub rehearse_bars: Option<u16>,
+    /// voices per bank displaced onsets ring out in; monophonic at 0
+    pub voices: usize,
+    /// ms external clock ticks are delayed by; ahead of time if negative
+    pub clock_offset: f32,
+    /// ms gain, width and speed ramp over; core default if none
+    pub smoothing: Option<f32>,
+    /// of every grain reader
+    pub interpolation: Interpolation,
+}
+
+impl Output {
+    /// parse `--bits <16|24>`, `--no-dither`, `--route-a <pair>`,
+    /// `--route-b <pair>`, `--click <pair>` and `--cue <pair>`, pairs counted
+    /// from 1, `--lufs-target <lufs>`, `--rehearse-bars <bars>`,
+    /// `--voices <count>`, `--clock-offset <ms>`, `--smoothing <ms>` and
+    /// `--interpolation <linear|hermite>` from `args`
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut output = Self {
+            depth: BitDepth::Sixteen,
+            dither: true,
+            routes: [None; BANK_COUNT],
+            click: None,
+            cue: None,
+            lufs_target: None,
+            rehearse_bars: None,
+            voices: VOICE_COUNT,
+            clock_offset: 0.,
+            smoothing: None,
+            interpolation: Interpolation::Hermite,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bits" => {
+                    output.depth = match args.next().map(String::as_str) {
+                        Some("16") => BitDepth::Sixteen,
+                        Some("24") => BitDepth::TwentyFour,
+                        _ => return Err(color_eyre::Report::msg("--bits expects 16 or 24")),
+                    }
+                }
+                "--no-dither" => output.dither = false,
+                "--route-a" | "--route-b" => {
+                    let bank = (arg.as_bytes()[8] - b'a') as usize;
+                    output.routes[bank] = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(pair @ 1..) => Some(pair - 1),
+                        _ => return Err(color_eyre::Report::msg("--route expects a pair from 1")),
+                    }
+                }
+                "--click" => {
+                    output.click = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(pair @ 1..) => Some(pair - 1),
+                        _ => return Err(color_eyre::Report::msg("--click expects a pair from 1")),
+                    }
+                }
+                "--cue" => {
+                    output.cue = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(pair @ 1..) => Some(pair - 1),
+                        _ => return Err(color_eyre::Report::msg("--cue expects a pair from 1")),
+                    }
+                }
+                "--lufs-target" => {
+                    output.lufs_target = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(lufs @ ..=0.) => Some(lufs),
+                        _ => return Err(color_eyre::Report::msg("--lufs-target expects lufs")),
+                    }
+                }
+                "--rehearse-bars" => {
+                    output.rehearse_bars = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(bars @ 1..=MAX_REHEARSAL_BARS) => Some(bars as u16),
+                        _ => {
+                            return Err(color_eyre::Report::msg(format!(
+                                "--rehearse-bars expects 1 to {}",
+                                MAX_REHEARSAL_BARS
+                            )))
+                        }
+                    }
+                }
+                "--voices" => {
+                    output.voices = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(count @ ..=MAX_VOICES) => count,
+                        _ => {
+                            return Err(color_eyre::Report::msg(format!(
+                                "--voices expects 0 to {}",
+                                MAX_VOICES
+                            )))
+                        }
+                    }
+                }
+                "--clock-offset" => {
+                    output.clock_offset = match args.next().and_then(|v| v.parse::<f32>().ok()) {
+                        Some(ms) if ms.abs() <= MAX_CLOCK_OFFSET => ms,
+                        _ => {
+                            return Err(color_eyre::Report::msg(format!(
+                                "--clock-offset expects -{0} to {0} ms",
+                                MAX_CLOCK_OFFSET
+                            )))
+                        }
+                    }
+                }
+                "--smoothing" => {
+                    output.smoothing = match args.next().and_then(|v| v.parse().ok()) {
+                        Some(ms @ 0.0..) => Some(ms),
+                        _ => return Err(color_eyre::Report::msg("--smoothing expects ms")),
+                    }
+                }
+                "--interpolation" => {
+                    output.interpolation = match args.next().map(String::as_str) {
+                        Some("linear") => Interpolation::Linear,
+                        Some("hermite") => Interpolation::Hermite,
+                        _ => {
+                            return Err(color_eyre::Report::msg(
+                                "--interpolation expects linear or hermite",
+                            ))
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(output)
+    }
+
+    /// channels needed to honor routing
+    pub fn channels(&self) -> u16 {
+        self.routes
+            .iter()
+            .chain([&self.click, &self.cue])
+            .flatten()
+            .map(|v| (*v as u16 + 1) * 2)
+            .fold(CHANNEL_COUNT, u16::max)
+    }
+
+    /// quantizer at requested depth
+    pub fn dither(&self) -> Dither {
+        Dither::new(self.depth, self.dither)
+    }
+}
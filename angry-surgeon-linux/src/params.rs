@@ -0,0 +1,311 @@
+//! lock-free state shared between control, audio and tui threads
+
+use crate::{
+    audio::{Bank, BANK_COUNT},
+    dry::Throughput,
+    mapping::Pressure,
+};
+use angry_surgeon_core::{Activity, Loudness};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// unset parameter sentinel
+const UNSET: u32 = f32::NAN.to_bits();
+
+/// f32 parameter mailbox; writes coalesce until taken by the audio thread
+pub struct Param {
+    value: AtomicU32,
+    /// latest value written, kept across takes for scene capture
+    last: AtomicU32,
+}
+
+impl Default for Param {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Param {
+    pub fn new() -> Self {
+        Self {
+            value: AtomicU32::new(UNSET),
+            last: AtomicU32::new(UNSET),
+        }
+    }
+
+    pub fn write(&self, value: f32) {
+        self.value.store(value.to_bits(), Ordering::Relaxed);
+        self.last.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// latest value written since last take, if any
+    pub fn take(&self) -> Option<f32> {
+        let value = f32::from_bits(self.value.swap(UNSET, Ordering::Relaxed));
+        (!value.is_nan()).then_some(value)
+    }
+
+    /// latest value ever written, if any
+    pub fn last(&self) -> Option<f32> {
+        let value = f32::from_bits(self.last.load(Ordering::Relaxed));
+        (!value.is_nan()).then_some(value)
+    }
+}
+
+/// latest phrase activity, written by the audio thread without allocating
+pub struct ActivitySlot(AtomicU64);
+
+impl ActivitySlot {
+    /// flags packed activity as written
+    const SET: u64 = 1 << 63;
+
+    fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn write(&self, activity: Activity) {
+        let packed = Self::SET
+            | activity.step_index as u64
+            | (activity.len as u64) << 16
+            | (activity.fired as u64) << 32
+            | (activity.drifted as u64) << 33;
+        self.0.store(packed, Ordering::Relaxed);
+    }
+
+    /// latest activity written since last take, if any
+    pub fn take(&self) -> Option<Activity> {
+        let packed = self.0.swap(0, Ordering::Relaxed);
+        (packed & Self::SET != 0).then_some(Activity {
+            step_index: packed as u16,
+            len: (packed >> 16) as u16,
+            fired: packed & 1 << 32 != 0,
+            drifted: packed & 1 << 33 != 0,
+        })
+    }
+}
+
+/// master loudness in lufs, written by the audio thread for the meter
+pub struct LoudnessSlot {
+    momentary: AtomicU32,
+    integrated: AtomicU32,
+    target: AtomicU32,
+}
+
+impl LoudnessSlot {
+    fn new() -> Self {
+        Self {
+            momentary: AtomicU32::new(UNSET),
+            integrated: AtomicU32::new(UNSET),
+            target: AtomicU32::new(UNSET),
+        }
+    }
+
+    pub fn write(&self, loudness: &Loudness) {
+        let bits = |v: Option<f32>| v.unwrap_or(f32::NAN).to_bits();
+        self.momentary
+            .store(bits(loudness.momentary()), Ordering::Relaxed);
+        self.integrated
+            .store(bits(loudness.integrated()), Ordering::Relaxed);
+        self.target
+            .store(loudness.target.to_bits(), Ordering::Relaxed);
+    }
+
+    /// momentary and integrated loudness, if any, and target
+    pub fn read(&self) -> (Option<f32>, Option<f32>, f32) {
+        let value = |v: &AtomicU32| {
+            let value = f32::from_bits(v.load(Ordering::Relaxed));
+            (!value.is_nan()).then_some(value)
+        };
+        (
+            value(&self.momentary),
+            value(&self.integrated),
+            f32::from_bits(self.target.load(Ordering::Relaxed)),
+        )
+    }
+}
+
+pub struct BankParams {
+    pub gain: Param,
+    pub width: Param,
+    pub pitch: Param,
+    pub roll: Param,
+    pub kit_drift: Param,
+    pub phrase_drift: Param,
+    pub humanize: Param,
+    pub cutoff: Param,
+    pub resonance: Param,
+    /// gain multiplier on accented steps
+    pub accent: Param,
+    /// onset envelope lengths in seconds
+    pub attack: Param,
+    pub release: Param,
+    /// delay send level
+    pub send: Param,
+    /// velocity sensitivity of pad hits
+    pub sensitivity: Param,
+    /// read-ahead in whole steps
+    pub slip: Param,
+    /// chance per step of a generated trigger
+    pub density: Param,
+    /// fine loop division in 0..=1, unity at center
+    pub loop_fine: Param,
+    /// micro-loop length in 0..=1, halving from half a step
+    pub stutter: Param,
+    /// tape stop and spin-up length in seconds
+    pub tape_len: Param,
+    /// freeze window width in 0..=1 of a grain
+    pub freeze_size: Param,
+    /// randomness of freeze window edges
+    pub freeze_jitter: Param,
+    /// length in seconds ended events ring out over
+    pub tail_len: Param,
+    /// pressure on held pads in 0..=1, by parameter it drives
+    pub pressure: [Param; Pressure::COUNT],
+    /// whether knob writes record into motion loops
+    pub motion: AtomicBool,
+    /// reported back from the audio thread for the heatmap
+    pub activity: ActivitySlot,
+}
+
+impl BankParams {
+    fn new() -> Self {
+        Self {
+            gain: Param::new(),
+            width: Param::new(),
+            pitch: Param::new(),
+            roll: Param::new(),
+            kit_drift: Param::new(),
+            phrase_drift: Param::new(),
+            humanize: Param::new(),
+            cutoff: Param::new(),
+            resonance: Param::new(),
+            accent: Param::new(),
+            attack: Param::new(),
+            release: Param::new(),
+            send: Param::new(),
+            sensitivity: Param::new(),
+            slip: Param::new(),
+            density: Param::new(),
+            loop_fine: Param::new(),
+            stutter: Param::new(),
+            tape_len: Param::new(),
+            freeze_size: Param::new(),
+            freeze_jitter: Param::new(),
+            tail_len: Param::new(),
+            pressure: core::array::from_fn(|_| Param::new()),
+            motion: AtomicBool::new(false),
+            activity: ActivitySlot::new(),
+        }
+    }
+}
+
+/// knob and bend parameters, sent outside the ordered `audio::Cmd` queue so
+/// control floods cost the audio thread at most one write per parameter
+pub struct Params {
+    /// output channels of running stream
+    pub channels: AtomicU16,
+    /// output rate granted by the device
+    pub sample_rate: AtomicU32,
+    /// frames of the latest output buffer
+    pub buffer_frames: AtomicU32,
+    /// output latency of the latest buffer in us, from render to playback
+    pub latency: AtomicU32,
+    /// render callbacks overrunning their buffer, plus stream errors
+    pub underruns: AtomicU32,
+    /// grain reads that missed prefetch and waited on storage
+    pub prefetch_misses: AtomicUsize,
+    pub loudness: LoudnessSlot,
+    pub gain_oneshot: Param,
+    /// pitch bend offset of second bank
+    pub pitch_offset: Param,
+    /// master mid/side width
+    pub master_width: Param,
+    /// master gain into soft clip
+    pub master_gain: Param,
+    /// equal-power mix from bank a at 0 to bank b at 1
+    pub crossfade: Param,
+    /// grain length from shortest at 0 to longest at 1
+    pub texture: Param,
+    pub delay_feedback: Param,
+    /// delay time in beats
+    pub delay_division: Param,
+    /// master soft clip drive and ceiling
+    pub drive: Param,
+    pub ceiling: Param,
+    pub banks: [BankParams; BANK_COUNT],
+    pub dry: Throughput,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self {
+            channels: AtomicU16::new(crate::audio::CHANNEL_COUNT),
+            sample_rate: AtomicU32::new(crate::audio::SAMPLE_RATE),
+            buffer_frames: AtomicU32::new(0),
+            latency: AtomicU32::new(0),
+            underruns: AtomicU32::new(0),
+            prefetch_misses: AtomicUsize::new(0),
+            loudness: LoudnessSlot::new(),
+            gain_oneshot: Param::new(),
+            pitch_offset: Param::new(),
+            master_width: Param::new(),
+            master_gain: Param::new(),
+            crossfade: Param::new(),
+            texture: Param::new(),
+            delay_feedback: Param::new(),
+            delay_division: Param::new(),
+            drive: Param::new(),
+            ceiling: Param::new(),
+            banks: core::array::from_fn(|_| BankParams::new()),
+            dry: Throughput::new(),
+        }
+    }
+
+    pub fn bank(&self, bank: Bank) -> &BankParams {
+        &self.banks[bank as u8 as usize]
+    }
+
+    /// params interpolated by scene morph, in fixed order
+    pub fn continuous(&self) -> impl Iterator<Item = &Param> {
+        [
+            &self.gain_oneshot,
+            &self.master_width,
+            &self.master_gain,
+            &self.crossfade,
+            &self.delay_feedback,
+            &self.drive,
+            &self.ceiling,
+            &self.dry.level,
+        ]
+        .into_iter()
+        .chain(self.banks.iter().flat_map(|v| {
+            [
+                &v.gain,
+                &v.width,
+                &v.pitch,
+                &v.roll,
+                &v.kit_drift,
+                &v.phrase_drift,
+                &v.humanize,
+                &v.cutoff,
+                &v.resonance,
+                &v.accent,
+                &v.attack,
+                &v.release,
+                &v.send,
+            ]
+        }))
+    }
+
+    /// params switched rather than interpolated by scene morph, in fixed
+    /// order
+    pub fn discrete(&self) -> impl Iterator<Item = &Param> {
+        [&self.delay_division]
+            .into_iter()
+            .chain(self.banks.iter().map(|v| &v.slip))
+    }
+}
@@ -0,0 +1,363 @@
+//! record bus: frames pushed by the audio thread into a preallocated ring and
+//! drained to an integer pcm wav by a writer thread per take, so the callback
+//! never waits on disk
+
+use crate::audio::{Mark, Source};
+use angry_surgeon_core::Dither;
+use color_eyre::Result;
+use std::{
+    io::{Seek, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// seconds of output the ring holds ahead of the writer, riding out disk
+/// stalls
+const RING_SECS: usize = 2;
+/// how often the writer drains the ring
+const POLL: Duration = Duration::from_millis(10);
+/// data bytes a take stops at, leaving a MiB under the 4 GiB riff size field
+/// for header and markers
+const MAX_DATA_LEN: u32 = u32::MAX - (1 << 20);
+
+/// lock-free sample ring, written only by the audio thread and read only by
+/// the writer
+struct Ring {
+    samples: Box<[AtomicU32]>,
+    /// samples written
+    head: AtomicUsize,
+    /// samples read
+    tail: AtomicUsize,
+    /// writer failed or take is full, so pushes are pointless
+    failed: AtomicBool,
+    /// audio thread outran writer and dropped frames
+    overrun: AtomicBool,
+}
+
+impl Ring {
+    fn new(len: usize) -> Self {
+        Self {
+            samples: (0..len.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            failed: AtomicBool::new(false),
+            overrun: AtomicBool::new(false),
+        }
+    }
+
+    /// push all of `data`, or none of it if it doesn't fit; whether pushed
+    fn push(&self, data: &[f32]) -> bool {
+        let len = self.samples.len();
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Relaxed);
+        if head - tail + data.len() > len {
+            return false;
+        }
+        for (i, sample) in data.iter().enumerate() {
+            self.samples[(head + i) % len].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        self.head.store(head + data.len(), Ordering::Release);
+        true
+    }
+
+    /// pop every sample written into `f`, stopping at its first error
+    fn drain(
+        &self,
+        mut f: impl FnMut(f32) -> Result<(), std::io::Error>,
+    ) -> Result<(), std::io::Error> {
+        let len = self.samples.len();
+        let head = self.head.load(Ordering::Acquire);
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        while tail < head {
+            f(f32::from_bits(
+                self.samples[tail % len].load(Ordering::Relaxed),
+            ))?;
+            tail += 1;
+        }
+        self.tail.store(tail, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// what only the audio thread tracks, handed to the writer on finish
+struct Finish {
+    /// frame position and label of each marker
+    markers: Vec<(u32, Mark)>,
+    steps: Vec<u32>,
+    tui_tx: Sender<crate::tui::Cmd>,
+}
+
+/// writer thread end of a take; sizes patched in and markers appended as a
+/// cue chunk on finish
+struct Writer {
+    source: Source,
+    path: String,
+    file: std::io::BufWriter<std::fs::File>,
+    /// frames on disk
+    frames: u32,
+    channels: u16,
+    sample_rate: u32,
+    dither: Dither,
+}
+
+impl Writer {
+    const HEADER_LEN: u32 = 44;
+
+    fn write_header(&mut self, file_len: u32) -> Result<(), std::io::Error> {
+        let block_align = self.channels * self.dither.depth.bytes();
+        // within `MAX_DATA_LEN`, as drains stop there
+        let data_len = self.frames * u32::from(block_align);
+        self.file.write_all(b"RIFF")?;
+        self.file.write_all(&(file_len - 8).to_le_bytes())?;
+        self.file.write_all(b"WAVE")?;
+        self.file.write_all(b"fmt ")?;
+        self.file.write_all(&16u32.to_le_bytes())?; // `fmt ` chunk size
+        self.file.write_all(&1u16.to_le_bytes())?; // pcm integer format
+        self.file.write_all(&self.channels.to_le_bytes())?;
+        self.file.write_all(&self.sample_rate.to_le_bytes())?;
+        self.file
+            .write_all(&(self.sample_rate * u32::from(block_align)).to_le_bytes())?;
+        self.file.write_all(&block_align.to_le_bytes())?;
+        self.file
+            .write_all(&(self.dither.depth.bits() as u16).to_le_bytes())?; // bits/sample
+        self.file.write_all(b"data")?;
+        self.file.write_all(&data_len.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// write `cue ` chunk and `LIST` `adtl` chunk of marker labels
+    fn write_markers(&mut self, markers: &[(u32, Mark)]) -> Result<(), std::io::Error> {
+        if markers.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(b"cue ")?;
+        self.file
+            .write_all(&(4 + 24 * markers.len() as u32).to_le_bytes())?;
+        self.file.write_all(&(markers.len() as u32).to_le_bytes())?;
+        for (id, (frame, _)) in markers.iter().enumerate() {
+            self.file.write_all(&(id as u32 + 1).to_le_bytes())?;
+            self.file.write_all(&frame.to_le_bytes())?; // position
+            self.file.write_all(b"data")?;
+            self.file.write_all(&0u32.to_le_bytes())?; // chunk start
+            self.file.write_all(&0u32.to_le_bytes())?; // block start
+            self.file.write_all(&frame.to_le_bytes())?; // sample offset
+        }
+        // null-terminated labels padded to even length
+        let labl_len = |label: &Mark| 4 + label.len() + 1;
+        let list_len = 4 + markers
+            .iter()
+            .map(|(_, label)| 8 + ((labl_len(label) + 1) & !1))
+            .sum::<u32>();
+        self.file.write_all(b"LIST")?;
+        self.file.write_all(&list_len.to_le_bytes())?;
+        self.file.write_all(b"adtl")?;
+        for (id, (_, label)) in markers.iter().enumerate() {
+            self.file.write_all(b"labl")?;
+            self.file.write_all(&labl_len(label).to_le_bytes())?;
+            self.file.write_all(&(id as u32 + 1).to_le_bytes())?;
+            write!(self.file, "{}", label)?;
+            self.file.write_all(&[0])?;
+            if labl_len(label) % 2 == 1 {
+                self.file.write_all(&[0])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// frames left before the take reaches `MAX_DATA_LEN`
+    fn room(&self) -> u32 {
+        let block_align = u32::from(self.channels * self.dither.depth.bytes());
+        MAX_DATA_LEN / block_align - self.frames
+    }
+
+    /// quantize and write every sample pushed so far, dropping any past
+    /// `MAX_DATA_LEN` and failing the ring once full so the take is finished
+    fn drain(&mut self, ring: &Ring) -> Result<(), std::io::Error> {
+        let bytes = self.dither.depth.bytes() as usize;
+        let room = self.room() as usize * self.channels as usize;
+        let mut samples = 0;
+        let (file, dither) = (&mut self.file, &mut self.dither);
+        let ret = ring.drain(|sample| {
+            if samples == room {
+                return Ok(());
+            }
+            samples += 1;
+            file.write_all(&dither.quantize(sample).to_le_bytes()[..bytes])
+        });
+        // at most `room` frames, so fits
+        self.frames += (samples / self.channels as usize) as u32;
+        if self.room() == 0 {
+            ring.failed.store(true, Ordering::Relaxed);
+        }
+        ret
+    }
+
+    fn finish(
+        mut self,
+        markers: &[(u32, Mark)],
+        steps: Vec<u32>,
+    ) -> Result<crate::resample::Take, std::io::Error> {
+        self.write_markers(markers)?;
+        let file_len = u32::try_from(self.file.stream_position()?)
+            .map_err(|_| std::io::Error::other("past 4 GiB wav limit"))?;
+        self.file.seek(std::io::SeekFrom::Start(0))?;
+        self.write_header(file_len)?;
+        self.file.flush()?;
+        Ok(crate::resample::Take {
+            path: self.path,
+            channels: self.channels,
+            depth: self.dither.depth,
+            sample_rate: self.sample_rate,
+            frames: self.frames,
+            steps,
+        })
+    }
+
+    /// drain `ring` until finished, then close the file out and report the
+    /// take, or why it stopped, to the tui
+    fn run(mut self, ring: Arc<Ring>, finish_rx: Receiver<Finish>) {
+        let mut failed = None;
+        let finish = loop {
+            if failed.is_none() {
+                if let Err(e) = self.drain(&ring) {
+                    ring.failed.store(true, Ordering::Relaxed);
+                    failed = Some(e);
+                }
+            }
+            match finish_rx.recv_timeout(POLL) {
+                Ok(finish) => break Some(finish),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break None,
+            }
+        };
+        let source = self.source;
+        let (markers, steps, tui_tx) = match finish {
+            Some(Finish {
+                markers,
+                steps,
+                tui_tx,
+            }) => (markers, steps, Some(tui_tx)),
+            None => (Vec::new(), Vec::new(), None),
+        };
+        let ret = match failed {
+            Some(e) => Err(e),
+            None => self.drain(&ring).and_then(|_| self.finish(&markers, steps)),
+        };
+        let cmd = match ret {
+            Ok(_) if ring.overrun.load(Ordering::Relaxed) => {
+                crate::tui::Cmd::RecordFailed(source, "disk fell behind".to_string())
+            }
+            // only a full take fails the ring without erring
+            Ok(_) if ring.failed.load(Ordering::Relaxed) => {
+                crate::tui::Cmd::RecordFailed(source, "reached 4 GiB wav limit".to_string())
+            }
+            Ok(take) => crate::tui::Cmd::Recorded(take),
+            Err(e) => crate::tui::Cmd::RecordFailed(source, e.to_string()),
+        };
+        if let Some(tui_tx) = tui_tx {
+            let _ = tui_tx.send(cmd);
+        }
+    }
+}
+
+/// audio thread end of a take; markers and steps kept here, as only the
+/// audio thread knows where they fall, and handed over on finish
+pub struct Recorder {
+    ring: Arc<Ring>,
+    /// frames pushed
+    frames: u32,
+    channels: u16,
+    /// frame position and label of each marker
+    markers: Vec<(u32, Mark)>,
+    /// frame position of each step clocked, for slicing on resample
+    steps: Vec<u32>,
+    finish_tx: SyncSender<Finish>,
+    writer: std::thread::JoinHandle<()>,
+}
+
+impl Recorder {
+    /// markers beyond are dropped rather than grow on the audio thread
+    const MAX_MARKERS: usize = 1024;
+    /// steps beyond are dropped likewise
+    const MAX_STEPS: usize = 65536;
+
+    /// create at `path` for `source` on control thread and spawn its writer,
+    /// as buffers allocate
+    pub fn new(
+        source: Source,
+        path: String,
+        channels: u16,
+        sample_rate: u32,
+        dither: Dither,
+    ) -> Result<Self> {
+        let mut writer = Writer {
+            source,
+            file: std::io::BufWriter::new(std::fs::File::create(&path)?),
+            path,
+            frames: 0,
+            channels,
+            sample_rate,
+            dither,
+        };
+        writer.write_header(Writer::HEADER_LEN)?;
+        let ring = Arc::new(Ring::new(
+            sample_rate as usize * channels as usize * RING_SECS,
+        ));
+        let (finish_tx, finish_rx) = std::sync::mpsc::sync_channel(1);
+        let writer = {
+            let ring = ring.clone();
+            std::thread::spawn(move || writer.run(ring, finish_rx))
+        };
+        Ok(Self {
+            ring,
+            frames: 0,
+            channels,
+            markers: Vec::with_capacity(Self::MAX_MARKERS),
+            steps: Vec::with_capacity(Self::MAX_STEPS),
+            finish_tx,
+            writer,
+        })
+    }
+
+    pub(crate) fn mark(&mut self, mark: Mark) {
+        if self.markers.len() < Self::MAX_MARKERS {
+            self.markers.push((self.frames, mark));
+        }
+    }
+
+    pub(crate) fn step(&mut self) {
+        if self.steps.len() < Self::MAX_STEPS {
+            self.steps.push(self.frames);
+        }
+    }
+
+    /// push interleaved `buffer` for the writer; false once it failed or fell
+    /// behind, when the take should be finished
+    pub(crate) fn write(&mut self, buffer: &[f32]) -> bool {
+        if self.ring.failed.load(Ordering::Relaxed) {
+            return false;
+        }
+        if !self.ring.push(buffer) {
+            self.ring.overrun.store(true, Ordering::Relaxed);
+            return false;
+        }
+        self.frames += (buffer.len() / self.channels as usize) as u32;
+        true
+    }
+
+    /// hand markers and steps to the writer to close the file out, reporting
+    /// the take or its failure to `tui_tx`; join the handle returned to wait
+    /// on it, never from the audio thread
+    pub(crate) fn finish(self, tui_tx: &Sender<crate::tui::Cmd>) -> std::thread::JoinHandle<()> {
+        let _ = self.finish_tx.try_send(Finish {
+            markers: self.markers,
+            steps: self.steps,
+            tui_tx: tui_tx.clone(),
+        });
+        self.writer
+    }
+}
@@ -0,0 +1,84 @@
+//! finished record takes resampled into onsets: mixed down to the 16-bit mono
+//! wav the core reads, sliced at each step clocked while recording, and
+//! browsed from ./onsets like any other, so takes go straight back onto pads
+
+use angry_surgeon_core::{BitDepth, Rd, Tags};
+use color_eyre::Result;
+use std::path::Path;
+
+/// where resampled takes are written, under the onset browser root
+const DIR: &str = "onsets/resampled";
+/// bytes of recorder wav header ahead of pcm
+const HEADER_LEN: usize = 44;
+
+/// finished record take, handed back by its recorder
+pub struct Take {
+    pub path: String,
+    pub channels: u16,
+    pub depth: BitDepth,
+    pub sample_rate: u32,
+    pub frames: u32,
+    /// frame position of each step clocked while recording
+    pub steps: Vec<u32>,
+}
+
+/// write `take` mixed down under `DIR` by the same name, with rd beside it
+/// slicing at each step, else whole, and tagged with tempo of its steps if
+/// clocked; returns path written
+pub fn resample(take: &Take) -> Result<String> {
+    let bytes = take.depth.bytes() as usize;
+    let full_scale = ((1 << (take.depth.bits() - 1)) - 1) as f32;
+    let file = std::fs::read(&take.path)?;
+    let pcm = file.get(HEADER_LEN..).unwrap_or_default();
+    let samples = pcm
+        .chunks_exact(bytes * take.channels as usize)
+        .take(take.frames as usize)
+        .map(|frame| {
+            let sum = frame
+                .chunks_exact(bytes)
+                .map(|v| {
+                    // sign extended down from the top of a word
+                    let mut word = [0; 4];
+                    word[4 - bytes..].copy_from_slice(v);
+                    (i32::from_le_bytes(word) >> (32 - 8 * bytes)) as f32 / full_scale
+                })
+                .sum::<f32>();
+            sum / take.channels as f32
+        })
+        .collect::<Vec<_>>();
+
+    let name = Path::new(&take.path)
+        .file_name()
+        .ok_or(color_eyre::Report::msg("take has no file name"))?;
+    std::fs::create_dir_all(DIR)?;
+    let path = Path::new(DIR).join(name);
+    crate::demo::write_wav(&path, &samples, take.sample_rate)?;
+    let path = path.to_str().unwrap().to_string();
+
+    // tempo from mean step length, over the whole take
+    let steps = match (take.steps.first(), take.steps.last()) {
+        (Some(first), Some(last)) if last > first => {
+            let step_len = (last - first) as f32 / (take.steps.len() - 1) as f32;
+            Some((take.frames as f32 / step_len).round().min(u16::MAX as f32) as u16)
+        }
+        _ => None,
+    };
+    let rd = Rd {
+        steps,
+        rate: Some(take.sample_rate),
+        onsets: match take.steps.is_empty() {
+            true => vec![0],
+            false => take.steps.iter().map(|v| *v as u64).collect(),
+        },
+    };
+    let fs = &mut crate::fs::LinuxFileHandler {};
+    let rd_path = Path::new(&path).with_extension("rd");
+    rd.save(rd_path.to_str().unwrap(), fs)?;
+    let tags = Tags {
+        bpm: steps.map(|v| crate::input::bpm(v, samples.len() as u64, take.sample_rate)),
+        kind: Some("resample".to_string()),
+        ..Default::default()
+    };
+    tags.save(&path, fs)?;
+    Ok(path)
+}
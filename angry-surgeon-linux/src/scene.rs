@@ -0,0 +1,72 @@
+//! parameter scenes, morphed between by a single control
+
+use crate::{audio::BANK_COUNT, params::Params};
+use angry_surgeon_core::{DriftMode, FilterMode, Resample, Stereo};
+
+#[derive(Copy, Clone)]
+pub enum Slot {
+    X,
+    Y,
+}
+
+impl Slot {
+    pub fn name(self) -> &'static str {
+        match self {
+            Slot::X => "x",
+            Slot::Y => "y",
+        }
+    }
+}
+
+/// discrete bank settings, switched rather than morphed
+#[derive(Copy, Clone, PartialEq)]
+pub struct Modes {
+    pub filter: FilterMode,
+    pub drift: DriftMode,
+    pub stereo: Stereo,
+    pub resample: Resample,
+}
+
+/// captured parameter values of master and every bank
+#[derive(Clone)]
+pub struct Scene {
+    /// latest value of each param, as ordered by `Params::continuous`
+    continuous: Vec<Option<f32>>,
+    /// as above, by `Params::discrete`
+    discrete: Vec<Option<f32>>,
+    pub modes: [Modes; BANK_COUNT],
+}
+
+impl Scene {
+    pub fn capture(params: &Params, modes: [Modes; BANK_COUNT]) -> Self {
+        Self {
+            continuous: params.continuous().map(|v| v.last()).collect(),
+            discrete: params.discrete().map(|v| v.last()).collect(),
+            modes,
+        }
+    }
+
+    /// write params `t` of the way from `x` to `y`, discrete ones switching
+    /// halfway; params set in only one scene hold their value throughout
+    pub fn morph(x: &Self, y: &Self, t: f32, params: &Params) {
+        let t = t.clamp(0., 1.);
+        for (param, (x, y)) in params
+            .continuous()
+            .zip(x.continuous.iter().zip(y.continuous.iter()))
+        {
+            match (x, y) {
+                (Some(x), Some(y)) => param.write(x + (y - x) * t),
+                (Some(v), None) | (None, Some(v)) => param.write(*v),
+                (None, None) => (),
+            }
+        }
+        for (param, (x, y)) in params
+            .discrete()
+            .zip(x.discrete.iter().zip(y.discrete.iter()))
+        {
+            if let Some(v) = if t < 0.5 { x.or(*y) } else { y.or(*x) } {
+                param.write(v);
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! render thread scheduling: realtime priority and cpu pinning
+
+use color_eyre::Result;
+
+/// render thread scheduling requested on the command line
+#[derive(Clone, Copy, Default)]
+pub struct Sched {
+    /// SCHED_FIFO priority in 1..=99, if any
+    pub priority: Option<i32>,
+    /// cpu to pin render thread to, if any
+    pub cpu: Option<usize>,
+    /// list only raw hardware devices, bypassing software mixing
+    pub raw: bool,
+}
+
+impl Sched {
+    /// parse `--priority <n>`, `--cpu <n>` and `--raw` from `args`
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut sched = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--priority" => {
+                    let priority = Self::value(arg, args.next())?;
+                    if !(1..=99).contains(&priority) {
+                        return Err(color_eyre::Report::msg("priority must be in 1..=99"));
+                    }
+                    sched.priority = Some(priority as i32);
+                }
+                "--cpu" => sched.cpu = Some(Self::value(arg, args.next())?),
+                "--raw" => sched.raw = true,
+                _ => (),
+            }
+        }
+        Ok(sched)
+    }
+
+    fn value(arg: &str, value: Option<&String>) -> Result<usize> {
+        value
+            .and_then(|v| v.parse().ok())
+            .ok_or(color_eyre::Report::msg(format!("{} expects a number", arg)))
+    }
+
+    /// whether device `name` is listed under raw mode
+    pub fn lists(&self, name: &str) -> bool {
+        !self.raw || name.starts_with("hw:")
+    }
+
+    /// apply to calling thread; returns failures as message, as render thread
+    /// can't bail
+    pub fn apply(&self) -> Option<String> {
+        let mut failed = Vec::new();
+        if let Some(priority) = self.priority {
+            let param = libc::sched_param {
+                sched_priority: priority,
+            };
+            // SAFETY: param outlives call; 0 targets calling thread
+            if unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) } != 0 {
+                failed.push(format!(
+                    "realtime priority {}: {}",
+                    priority,
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        if let Some(cpu) = self.cpu {
+            // SAFETY: cpu_set_t is plain data, zeroed is empty set
+            let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+            // SAFETY: set outlives calls; 0 targets calling thread
+            let ok = unsafe {
+                libc::CPU_SET(cpu, &mut set);
+                libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+            };
+            if !ok {
+                failed.push(format!(
+                    "pin to cpu {}: {}",
+                    cpu,
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        if failed.is_empty() {
+            None
+        } else {
+            Some(format!("render thread {}", failed.join("; ")))
+        }
+    }
+}
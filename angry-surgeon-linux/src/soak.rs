@@ -0,0 +1,116 @@
+//! unattended soak test: internal clock driving random play, logging memory,
+//! underrun and file handle high-water marks
+
+use crate::{audio, params::Params, tui};
+use color_eyre::Result;
+use std::{
+    io::Write,
+    sync::{atomic::Ordering, mpsc::Sender, Arc},
+    time::{Duration, Instant},
+};
+
+/// clock tempo while soaking, in bpm
+const TEMPO: f32 = 120.;
+/// seconds between log lines
+const LOG_INTERVAL: u64 = 60;
+const LOG_PATH: &str = "soak.log";
+
+/// soak test requested on the command line
+#[derive(Clone, Copy)]
+pub struct Soak(angry_surgeon_core::Soak);
+
+impl Soak {
+    /// parse `--soak <intensity>`, intensity in 0..=1, from `args`
+    pub fn from_args(args: &[String]) -> Result<Option<Self>> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--soak" {
+                return match args.next().and_then(|v| v.parse::<f32>().ok()) {
+                    Some(intensity @ 0.0..=1.0) => {
+                        Ok(Some(Self(angry_surgeon_core::Soak { intensity })))
+                    }
+                    _ => Err(color_eyre::Report::msg("--soak expects an intensity in 0..=1")),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// clock random play from a thread of its own until the audio thread hangs
+    /// up, appending marks to `soak.log` each interval; run without midi clock
+    pub fn spawn(
+        self,
+        params: Arc<Params>,
+        audio_tx: Sender<audio::Cmd>,
+        tui_tx: Sender<tui::Cmd>,
+    ) -> std::thread::JoinHandle<Result<()>> {
+        std::thread::spawn(move || -> Result<()> {
+            let mut log = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(LOG_PATH)?;
+            writeln!(log, "soak at intensity {}", self.0.intensity)?;
+            let step = Duration::from_secs_f32(60. / TEMPO / audio::TICKS_PER_STEP as f32);
+            let start = Instant::now();
+            let mut next_step = start;
+            let mut next_log = start;
+            let mut marks = Marks::default();
+            audio_tx.send(audio::Cmd::AssignTempo(TEMPO))?;
+            while audio_tx.send(audio::Cmd::Soak(self.0)).is_ok()
+                && audio_tx.send(audio::Cmd::Tick).is_ok()
+            {
+                let _ = tui_tx.send(tui::Cmd::Clock);
+                marks.update(&params);
+                if Instant::now() >= next_log {
+                    marks.log(&mut log, start)?;
+                    next_log += Duration::from_secs(LOG_INTERVAL);
+                }
+                next_step += step;
+                std::thread::sleep(next_step.saturating_duration_since(Instant::now()));
+            }
+            marks.log(&mut log, start)
+        })
+    }
+}
+
+/// resource high-water marks since soak start
+#[derive(Default)]
+struct Marks {
+    /// peak resident memory in kib, as reported by the kernel
+    rss: u64,
+    files: usize,
+    max_files: usize,
+    underruns: u32,
+    prefetch_misses: usize,
+}
+
+impl Marks {
+    fn update(&mut self, params: &Params) {
+        self.rss = std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                let line = status.lines().find(|v| v.starts_with("VmHWM:"))?;
+                line.split_whitespace().nth(1)?.parse().ok()
+            })
+            .unwrap_or(self.rss);
+        // less the handle listing them
+        self.files = std::fs::read_dir("/proc/self/fd").map_or(0, |v| v.count() - 1);
+        self.max_files = self.max_files.max(self.files);
+        self.underruns = params.underruns.load(Ordering::Relaxed);
+        self.prefetch_misses = params.prefetch_misses.load(Ordering::Relaxed);
+    }
+
+    fn log(&self, log: &mut std::fs::File, start: Instant) -> Result<()> {
+        writeln!(
+            log,
+            "{}s: peak rss {} kib, {} underruns, {} prefetch misses, {} open files (peak {})",
+            start.elapsed().as_secs(),
+            self.rss,
+            self.underruns,
+            self.prefetch_misses,
+            self.files,
+            self.max_files,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! host transport followed onto the step clock, for hosts owning the timeline:
+//! jack transport, or a plugin host's
+
+use crate::{
+    audio::{self, TICKS_PER_STEP},
+    tui,
+};
+use color_eyre::Result;
+use std::sync::mpsc::Sender;
+
+/// core ticks falling due in one cycle at most; further ones dropped
+pub const MAX_CYCLE_TICKS: usize = 32;
+/// core ticks per transport beat, as sent per quarter of midi clock
+const TICKS_PER_BEAT: f64 = TICKS_PER_STEP as f64;
+/// transport drift from where rolling would have taken it, in ticks, past
+/// which it counts as a jump
+const JUMP_TOLERANCE: f64 = 0.05;
+
+/// where a rolling transport stands at the start of a cycle
+pub struct Position {
+    /// quarter notes since song start
+    pub beats: f64,
+    pub bpm: f64,
+}
+
+/// host transport followed onto the step clock
+#[derive(Default)]
+pub struct Follow {
+    sample_rate: f64,
+    /// transport position in ticks expected next cycle while rolling, so
+    /// jumps are told apart from rolling on
+    next: Option<f64>,
+    tempo: f64,
+}
+
+impl Follow {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate as f64,
+            ..Default::default()
+        }
+    }
+
+    /// follow transport at `position`, none while stopped, over a cycle of
+    /// `len` frames, filling `ticks` with frame offsets of ticks falling due
+    /// in it. stops and jumps are applied to `handler` here
+    pub fn cycle(
+        &mut self,
+        position: Option<Position>,
+        len: usize,
+        handler: &mut audio::SystemHandler,
+        tui_tx: &Sender<tui::Cmd>,
+        ticks: &mut heapless::Vec<usize, MAX_CYCLE_TICKS>,
+    ) -> Result<()> {
+        ticks.clear();
+        let Some(Position { beats, bpm }) = position.filter(|v| v.bpm > 0.) else {
+            if self.next.take().is_some() {
+                // as on a midi stop
+                handler.stop();
+                handler.clear_sequences()?;
+                let _ = tui_tx.send(tui::Cmd::Stop);
+            }
+            return Ok(());
+        };
+        if bpm != self.tempo {
+            self.tempo = bpm;
+            handler.assign_tempo(bpm as f32);
+        }
+        let position = beats * TICKS_PER_BEAT;
+        let frames_per_tick = self.sample_rate * 60. / bpm / TICKS_PER_BEAT;
+        let first = position.ceil();
+        if self
+            .next
+            .is_none_or(|v| (v - position).abs() > JUMP_TOLERANCE)
+        {
+            // started or jumped; next tick lands on the first due
+            handler.locate(first as u32);
+        }
+        let mut tick = first;
+        loop {
+            let offset = ((tick - position) * frames_per_tick) as usize;
+            if offset >= len || ticks.push(offset).is_err() {
+                break;
+            }
+            let _ = tui_tx.send(tui::Cmd::Clock);
+            tick += 1.;
+        }
+        self.next = Some(position + len as f64 / frames_per_tick);
+        Ok(())
+    }
+}
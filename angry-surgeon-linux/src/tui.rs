@@ -1,4 +1,5 @@
-use crate::audio::{MAX_PHRASE_COUNT, MAX_PHRASE_LEN, PAD_COUNT};
+use crate::audio::{BANK_COUNT, MAX_PHRASE_COUNT, MAX_PHRASE_LEN, PAD_COUNT, SOURCE_COUNT};
+use angry_surgeon_core::{Activity, BdError, Curve, Level, Quantize, Rate, MAX_CLOCK_OFFSET};
 
 use color_eyre::eyre::Result;
 use crossterm::event::{self, KeyCode, KeyEvent, KeyEventKind};
@@ -6,7 +7,7 @@ use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Flex, Layout, Rect},
     style::Stylize,
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{Block, Padding, Paragraph, Widget, Wrap},
     DefaultTerminal, Frame,
 };
@@ -17,20 +18,51 @@ use std::{
 
 pub const FILE_COUNT: usize = 5;
 const LOG_DURATION: std::time::Duration = std::time::Duration::from_millis(1000);
+/// steps over which phrase heatmap cells cool
+const HEAT_STEPS: u8 = 16;
+/// phrase heatmap size in cells
+const HEAT_WIDTH: usize = 12;
+const HEAT_HEIGHT: usize = 2;
+/// rehearsal loop speeds cycled, from core default
+const REHEARSAL_SPEEDS: [f32; 3] = [0.5, 0.75, 1.];
+/// clock offset nudge in ms
+const CLOCK_OFFSET_STEP: f32 = 1.;
 
 pub enum Cmd {
     Log(String),
     Clock,
     Stop,
     Yield,
+    /// whether rehearsal loop engaged
+    Rehearse(bool),
+    /// whether midi learn running
+    Learn(bool),
+    /// segments of long take chained after the first, and dropped for want
+    /// of free pads
+    Chained {
+        linked: usize,
+        dropped: usize,
+    },
     LoadBd([String; FILE_COUNT]),
     LoadRd([String; FILE_COUNT]),
     LoadOnset {
         name: String,
         index: usize,
         count: usize,
+        /// start in samples
+        start: u64,
+        /// whether rd differs from the one on disk
+        edited: bool,
     },
     Bank(crate::audio::Bank, BankCmd),
+    /// bank encoded by the audio thread, to be written to path
+    SaveBank(String, Result<Vec<u8>, BdError>),
+    /// record take finished, to be resampled
+    Recorded(crate::resample::Take),
+    /// record of source stopped early, and why
+    RecordFailed(crate::audio::Source, String),
+    /// output level of each bank, then of master
+    Meter([Level; BANK_COUNT], Level),
 }
 
 pub enum BankCmd {
@@ -38,12 +70,26 @@ pub enum BankCmd {
     LoadBank(Bank),
     Mangle,
     LoadKit(Option<u8>),
+    CopyKit(u8, u8),
+    /// swap two pads of loaded kit
+    SwapPads(u8, u8),
+    /// empty loaded kit
+    ClearKit,
+    /// onsets spread over opening pads of loaded kit, the rest emptied
+    Spread(usize),
     TrimRecord(Option<u8>, u16),
+    QuantizeRecord(Quantize),
+    RateRecord(Rate),
+    CaptureSequence(u8, u16),
+    /// segment of long take chained to pad
+    Chain(u8),
     ClearSequence,
     PushSequence(Option<u8>),
+    SavePool,
+    RecallPool(usize),
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Kit {
     pub onsets: [bool; PAD_COUNT],
 }
@@ -52,6 +98,7 @@ pub struct Kit {
 pub struct Bank {
     pub kits: [Option<Kit>; PAD_COUNT],
     pub phrases: [bool; PAD_COUNT],
+    pub pools: Vec<Vec<u8>>,
 }
 
 impl Bank {
@@ -74,6 +121,7 @@ impl Bank {
                 ret.phrases[i] = true;
             }
         }
+        ret.pools = bank.pools.iter().map(|v| v.phrases.clone()).collect();
         ret
     }
 }
@@ -90,13 +138,22 @@ enum GlobalState {
         name: String,
         index: usize,
         count: usize,
+        /// start in samples
+        start: u64,
+        /// whether rd differs from the one on disk
+        edited: bool,
     },
 }
 
 enum BankState {
     Mangle,
     LoadKit { index: Option<u8> },
-    TrimRecord { index: Option<u8>, len: u16 },
+    TrimRecord {
+        index: Option<u8>,
+        len: u16,
+        quantize: Quantize,
+        rate: Rate,
+    },
     BuildSequence { index: Option<u8> },
 }
 
@@ -105,6 +162,14 @@ struct BankHandler {
     bank: Bank,
     downs: heapless::Vec<u8, PAD_COUNT>,
     sequence: heapless::Deque<u8, MAX_PHRASE_COUNT>,
+    /// index of groove in ./grooves, if any
+    groove: Option<usize>,
+    /// recent firing of audible phrase steps, cooling per step
+    heat: [u8; MAX_PHRASE_LEN],
+    /// whether step last fired as drift substitution
+    drifted: [bool; MAX_PHRASE_LEN],
+    /// length of audible phrase
+    heat_len: u16,
     state: BankState,
 }
 
@@ -115,6 +180,10 @@ impl BankHandler {
             bank: Bank::default(),
             downs: heapless::Vec::new(),
             sequence: heapless::Deque::new(),
+            groove: None,
+            heat: [0; MAX_PHRASE_LEN],
+            drifted: [false; MAX_PHRASE_LEN],
+            heat_len: 0,
             state: BankState::Mangle,
         }
     }
@@ -125,9 +194,62 @@ impl BankHandler {
             BankCmd::LoadBank(bank) => self.bank = bank,
             BankCmd::Mangle => self.mangle(),
             BankCmd::LoadKit(index) => self.load_kit(index),
-            BankCmd::TrimRecord(index, len) => self.state = BankState::TrimRecord { index, len },
+            BankCmd::CopyKit(from, to) => {
+                self.bank.kits[to as usize] = self.bank.kits[from as usize].clone()
+            }
+            BankCmd::SwapPads(a, b) => {
+                if let Some(kit) = self.bank.kits[self.kit_index].as_mut() {
+                    kit.onsets.swap(a as usize, b as usize);
+                }
+            }
+            BankCmd::ClearKit => self.bank.kits[self.kit_index] = None,
+            BankCmd::Spread(count) => {
+                let kit = self.bank.kits[self.kit_index].get_or_insert_default();
+                for (index, onset) in kit.onsets.iter_mut().enumerate() {
+                    *onset = index < count;
+                }
+            }
+            BankCmd::TrimRecord(index, len) => self.trim_record(index, len),
+            BankCmd::QuantizeRecord(quantize) => {
+                if let BankState::TrimRecord { quantize: q, .. } = &mut self.state {
+                    *q = quantize;
+                }
+            }
+            BankCmd::RateRecord(rate) => {
+                if let BankState::TrimRecord { rate: r, .. } = &mut self.state {
+                    *r = rate;
+                }
+            }
             BankCmd::PushSequence(index) => self.push_sequence(index),
+            BankCmd::CaptureSequence(index, _) | BankCmd::Chain(index) => {
+                self.bank.phrases[index as usize] = true
+            }
             BankCmd::ClearSequence => self.sequence.clear(),
+            BankCmd::SavePool => self.bank.pools.push(self.sequence.iter().copied().collect()),
+            BankCmd::RecallPool(index) => {
+                if let Some(pool) = self.bank.pools.get(index) {
+                    self.sequence.clear();
+                    for &phrase in pool.iter() {
+                        let _ = self.sequence.push_back(phrase);
+                    }
+                }
+            }
+        }
+    }
+
+    fn activity(&mut self, activity: Activity) {
+        if activity.len != self.heat_len {
+            self.heat_len = activity.len;
+            self.heat.fill(0);
+            self.drifted.fill(false);
+        }
+        for heat in self.heat.iter_mut() {
+            *heat = heat.saturating_sub(1);
+        }
+        if activity.fired {
+            let index = activity.step_index as usize;
+            self.heat[index] = HEAT_STEPS;
+            self.drifted[index] = activity.drifted;
         }
     }
 
@@ -152,6 +274,20 @@ impl BankHandler {
         self.state = BankState::Mangle;
     }
 
+    fn trim_record(&mut self, index: Option<u8>, len: u16) {
+        let (quantize, rate) = if let BankState::TrimRecord { quantize, rate, .. } = self.state {
+            (quantize, rate)
+        } else {
+            (Quantize::Off, Rate::Normal)
+        };
+        self.state = BankState::TrimRecord {
+            index,
+            len,
+            quantize,
+            rate,
+        };
+    }
+
     fn load_kit(&mut self, index: Option<u8>) {
         if let Some(index) = index {
             self.kit_index = index as usize;
@@ -172,9 +308,12 @@ impl BankHandler {
         match self.state {
             BankState::Mangle => self.render_mangle(flex, area, buf),
             BankState::LoadKit { index } => self.render_load_kit(index, flex, area, buf),
-            BankState::TrimRecord { index, len } => {
-                self.render_bake_record(index, len, flex, area, buf)
-            }
+            BankState::TrimRecord {
+                index,
+                len,
+                quantize,
+                rate,
+            } => self.render_bake_record(index, len, quantize, rate, flex, area, buf),
             BankState::BuildSequence { index } => self.render_sequence(index, area, buf),
         }
     }
@@ -194,10 +333,51 @@ impl BankHandler {
         if let Some(index) = self.downs.first() {
             pads[*index as usize] = '@';
         }
+        let block = Block::bordered().bold();
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let [pad_area, heat_area] =
+            Layout::vertical(Constraint::from_maxes([2, HEAT_HEIGHT as u16 + 1]))
+                .flex(Flex::SpaceBetween)
+                .areas(inner);
         Paragraph::new(Text::raw(String::from_iter(pads)))
-            .block(Block::bordered().bold().padding(Padding::horizontal(4)))
+            .block(Block::new().padding(Padding::horizontal(4)))
             .wrap(Wrap { trim: false })
-            .render(area, buf);
+            .render(pad_area, buf);
+        self.render_heat(heat_area, buf);
+    }
+
+    /// render audible phrase steps bucketed into cells, hotter if fired
+    /// recently, red if drift substituted
+    fn render_heat(&self, area: Rect, buf: &mut Buffer) {
+        if self.heat_len == 0 {
+            return;
+        }
+        let len = self.heat_len as usize;
+        let cells = HEAT_WIDTH * HEAT_HEIGHT;
+        let lines = (0..HEAT_HEIGHT)
+            .map(|row| {
+                Line::from_iter((0..HEAT_WIDTH).map(|col| {
+                    let cell = row * HEAT_WIDTH + col;
+                    let start = cell * len / cells;
+                    let steps = start..((cell + 1) * len / cells).clamp(start + 1, len);
+                    let heat = self.heat[steps.clone()].iter().copied().max().unwrap_or(0);
+                    let glyph = match heat * 4 / (HEAT_STEPS + 1) {
+                        _ if heat == 0 => "·",
+                        0 => "░",
+                        1 => "▒",
+                        2 => "▓",
+                        _ => "█",
+                    };
+                    if heat > 0 && self.drifted[steps].iter().any(|v| *v) {
+                        Span::raw(glyph).red()
+                    } else {
+                        Span::raw(glyph)
+                    }
+                }))
+            })
+            .collect::<Vec<_>>();
+        Paragraph::new(Text::from(lines)).centered().render(area, buf);
     }
 
     fn render_load_kit(&self, index: Option<u8>, flex: Flex, area: Rect, buf: &mut Buffer) {
@@ -226,10 +406,13 @@ impl BankHandler {
             .render(area, buf);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn render_bake_record(
         &self,
         index: Option<u8>,
         len: u16,
+        quantize: Quantize,
+        rate: Rate,
         flex: Flex,
         area: Rect,
         buf: &mut Buffer,
@@ -262,8 +445,18 @@ impl BankHandler {
                 .wrap(Wrap { trim: false })
                 .render(pad_area, buf);
         }
-        // render length
-        Paragraph::new(Text::raw(format!("length: {:>3}", len)).left_aligned())
+        // render length, playback quantization and rate
+        let quantize = match quantize {
+            Quantize::Off => "free",
+            Quantize::Step => "step",
+            Quantize::HalfStep => "half",
+        };
+        let rate = match rate {
+            Rate::Half => "1/2",
+            Rate::Normal => "1x",
+            Rate::Double => "2x",
+        };
+        Paragraph::new(Text::raw(format!("{:>3} {} {:>3}", len, quantize, rate)).left_aligned())
             .block(Block::new().padding(Padding::new(2, 2, 0, 1)))
             .wrap(Wrap { trim: false })
             .render(len_area, buf);
@@ -340,18 +533,52 @@ pub struct TuiHandler {
     bank_b: BankHandler,
 
     deafen: bool,
+    /// whether performance lock engaged
+    locked: bool,
+    /// whether midi learn running
+    learning: bool,
+    /// whether quantized hits pre-listen on cue pair
+    cueing: bool,
+    clicking: bool,
+    /// armed record bus sources
+    recording: [bool; SOURCE_COUNT],
+    /// take name shared by sources recorded in one pass, stamped with local
+    /// time armed
+    take: String,
     log: Option<(std::time::Instant, String)>,
     clock: bool,
+    /// master loudness readout, as last drawn, and whether over target
+    meter: (String, bool),
+    /// output level of each bank, then of master, as last reported
+    levels: ([Level; BANK_COUNT], Level),
+    /// output latency readout, as last drawn
+    latency: String,
+    rehearsing: bool,
+    /// index into `REHEARSAL_SPEEDS`
+    rehearsal_speed: usize,
+    /// ms external clock ticks are delayed by; ahead of time if negative
+    clock_offset: f32,
     state: GlobalState,
 
+    /// loop division control response of both banks
+    roll_curve: Curve,
+    /// loopback latency probe, if input device found
+    probe: Option<std::sync::Arc<crate::latency::Probe>>,
+    /// recording sample format
+    output: crate::output::Output,
+    params: std::sync::Arc<crate::params::Params>,
+
     audio_tx: Sender<crate::audio::Cmd>,
     input_tx: Sender<crate::input::Cmd>,
 }
 
 impl TuiHandler {
     pub fn new(
+        params: std::sync::Arc<crate::params::Params>,
         audio_tx: Sender<crate::audio::Cmd>,
         input_tx: Sender<crate::input::Cmd>,
+        probe: Option<std::sync::Arc<crate::latency::Probe>>,
+        output: crate::output::Output,
     ) -> Result<Self> {
         Ok(Self {
             oneshots: Oneshots::new(),
@@ -360,10 +587,27 @@ impl TuiHandler {
             bank_b: BankHandler::new(),
 
             deafen: false,
+            locked: false,
+            learning: false,
+            cueing: false,
+            clicking: output.click.is_some(),
+            recording: [false; SOURCE_COUNT],
+            take: String::new(),
             log: None,
             clock: false,
+            meter: (String::new(), false),
+            levels: Default::default(),
+            latency: String::new(),
+            rehearsing: false,
+            rehearsal_speed: 0,
+            clock_offset: output.clock_offset,
             state: GlobalState::Yield,
 
+            roll_curve: Curve::Linear,
+            probe,
+            output,
+
+            params,
             audio_tx,
             input_tx,
         })
@@ -385,6 +629,26 @@ impl TuiHandler {
                 }
                 flush = true;
             }
+            // poll master loudness left by audio thread
+            let meter = Self::meter(self.params.loudness.read());
+            if meter != self.meter {
+                self.meter = meter;
+                flush = true;
+            }
+            // poll output latency left by audio thread
+            let latency = self.latency_readout();
+            if latency != self.latency {
+                self.latency = latency;
+                flush = true;
+            }
+            // poll heatmap activity left by audio thread
+            let banks = [&mut self.bank_a, &mut self.bank_b];
+            for (params, bank_h) in self.params.banks.iter().zip(banks) {
+                if let Some(activity) = params.activity.take() {
+                    bank_h.activity(activity);
+                    flush = true;
+                }
+            }
             match input_rx.try_recv() {
                 Ok(cmd) => {
                     self.cmd(cmd);
@@ -446,32 +710,293 @@ impl TuiHandler {
                 code: KeyCode::Char(' '),
                 kind: KeyEventKind::Press,
                 ..
-            }) => {
-                if !self.oneshots.paths.is_empty() {
-                    if let Some(i) = self.oneshots.index.as_mut() {
-                        if *i < self.oneshots.paths.len() - 1 {
-                            *i += 1;
-                        } else {
-                            self.oneshots.index = None;
-                        }
+            }) if !self.oneshots.paths.is_empty() => {
+                if let Some(i) = self.oneshots.index.as_mut() {
+                    if *i < self.oneshots.paths.len() - 1 {
+                        *i += 1;
                     } else {
-                        self.oneshots.index = Some(0);
-                    }
-                    if let Some(index) = self.oneshots.index {
-                        self.audio_tx
-                            .send(crate::audio::Cmd::LoadOneshot(std::fs::File::open(
-                                self.oneshots.paths[index].clone(),
-                            )?))?;
-                        self.log = Some((
-                            std::time::Instant::now(),
-                            format!("oneshot {:>3}/{:>3}", index, self.oneshots.paths.len()),
-                        ));
-                    } else {
-                        self.audio_tx.send(crate::audio::Cmd::StopOneshot)?;
-                        self.log =
-                            Some((std::time::Instant::now(), "oneshots exhausted".to_string()));
+                        self.oneshots.index = None;
                     }
+                } else {
+                    self.oneshots.index = Some(0);
                 }
+                if let Some(index) = self.oneshots.index {
+                    self.audio_tx
+                        .send(crate::audio::Cmd::LoadOneshot(std::fs::File::open(
+                            self.oneshots.paths[index].clone(),
+                        )?))?;
+                    self.log = Some((
+                        std::time::Instant::now(),
+                        format!("oneshot {:>3}/{:>3}", index, self.oneshots.paths.len()),
+                    ));
+                } else {
+                    self.audio_tx.send(crate::audio::Cmd::StopOneshot)?;
+                    self.log =
+                        Some((std::time::Instant::now(), "oneshots exhausted".to_string()));
+                }
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('r'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.toggle_record(crate::audio::Source::Master)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('o'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.toggle_record(crate::audio::Source::Oneshot)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('a'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.toggle_record(crate::audio::Source::Bank(crate::audio::Bank::A))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('b'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.toggle_record(crate::audio::Source::Bank(crate::audio::Bank::B))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let (curve, name) = match self.roll_curve {
+                    Curve::Linear => (Curve::Exponential, "exponential"),
+                    Curve::Exponential => (Curve::Musical, "musical"),
+                    Curve::Musical => (Curve::Linear, "linear"),
+                };
+                self.roll_curve = curve;
+                for bank in [crate::audio::Bank::A, crate::audio::Bank::B] {
+                    self.audio_tx.send(crate::audio::Cmd::Bank(
+                        bank,
+                        crate::audio::BankCmd::AssignRollCurve(curve),
+                    ))?;
+                }
+                self.log = Some((std::time::Instant::now(), format!("roll curve: {}", name)));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('e'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                for bank in [crate::audio::Bank::A, crate::audio::Bank::B] {
+                    self.audio_tx.send(crate::audio::Cmd::Bank(
+                        bank,
+                        crate::audio::BankCmd::ArmRecord,
+                    ))?;
+                }
+                let msg = "long take armed; next record keeps all since".to_string();
+                self.log = Some((std::time::Instant::now(), msg));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('l'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                if let Some(probe) = self.probe.as_ref() {
+                    self.audio_tx
+                        .send(crate::audio::Cmd::ProbeLatency(probe.clone()))?;
+                    self.log = Some((std::time::Instant::now(), "probing latency".to_string()));
+                } else {
+                    self.log = Some((
+                        std::time::Instant::now(),
+                        "no input device to probe latency".to_string(),
+                    ));
+                }
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('g'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.cycle_groove(crate::audio::Bank::A)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('h'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.cycle_groove(crate::audio::Bank::B)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('x'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx
+                    .send(crate::input::Cmd::StoreScene(crate::scene::Slot::X))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('y'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx
+                    .send(crate::input::Cmd::StoreScene(crate::scene::Slot::Y))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('X'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::CaptureScene)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('Y'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::RecallScene)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('t'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::CycleTag)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('j'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::MergeBd)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('k'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::SplitBd)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('p'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.locked = !self.locked;
+                self.input_tx.send(crate::input::Cmd::Lock(self.locked))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('n'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                // learn starts on next midi message; skips once running
+                if !self.learning {
+                    self.log = Some((
+                        std::time::Instant::now(),
+                        "touch any control to start midi learn".to_string(),
+                    ));
+                }
+                self.input_tx.send(crate::input::Cmd::Learn)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('N'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.input_tx.send(crate::input::Cmd::EndLearn)?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char(c @ ('[' | ']')),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let step = if c == '[' {
+                    -CLOCK_OFFSET_STEP
+                } else {
+                    CLOCK_OFFSET_STEP
+                };
+                self.clock_offset =
+                    (self.clock_offset + step).clamp(-MAX_CLOCK_OFFSET, MAX_CLOCK_OFFSET);
+                self.audio_tx
+                    .send(crate::audio::Cmd::AssignClockOffset(self.clock_offset))?;
+                self.log = Some((
+                    std::time::Instant::now(),
+                    format!("clock offset {:+.0} ms", self.clock_offset),
+                ));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('u'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let log = if self.output.cue.is_none() {
+                    "no cue pair; pass --cue <pair>".to_string()
+                } else {
+                    self.cueing = !self.cueing;
+                    self.audio_tx.send(crate::audio::Cmd::Cue(self.cueing))?;
+                    format!("cue pre-listen {}", if self.cueing { "on" } else { "off" })
+                };
+                self.log = Some((std::time::Instant::now(), log));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('M'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.clicking = !self.clicking;
+                self.audio_tx
+                    .send(crate::audio::Cmd::Click(self.clicking))?;
+                let log = match (self.clicking, self.output.click) {
+                    (false, _) => "metronome off".to_string(),
+                    (true, Some(pair)) => format!("metronome on pair {}", pair + 1),
+                    (true, None) => "metronome on, mixed".to_string(),
+                };
+                self.log = Some((std::time::Instant::now(), log));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('i'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                let log = if self.probe.is_none() {
+                    "no input to grab".to_string()
+                } else {
+                    let grabbed = !crate::fs::CAPTURE.grabbed();
+                    crate::fs::CAPTURE.grab(grabbed);
+                    format!("live input {}", if grabbed { "grabbed" } else { "rolling" })
+                };
+                self.log = Some((std::time::Instant::now(), log));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('m'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.audio_tx.send(crate::audio::Cmd::ResetLoudness)?;
+                self.log = Some((std::time::Instant::now(), "loudness reset".to_string()));
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('v'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.audio_tx
+                    .send(crate::audio::Cmd::Rehearse(!self.rehearsing))?;
+            }
+            event::Event::Key(KeyEvent {
+                code: KeyCode::Char('V'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                self.rehearsal_speed = (self.rehearsal_speed + 1) % REHEARSAL_SPEEDS.len();
+                let speed = REHEARSAL_SPEEDS[self.rehearsal_speed];
+                self.audio_tx
+                    .send(crate::audio::Cmd::AssignRehearsalSpeed(speed))?;
+                self.log = Some((
+                    std::time::Instant::now(),
+                    format!("rehearsal speed {:.2}", speed),
+                ));
             }
             event::Event::Key(KeyEvent {
                 code: KeyCode::Enter,
@@ -486,11 +1011,141 @@ impl TuiHandler {
         Ok(false)
     }
 
+    /// arm/disarm a record bus source; sources armed together share a take
+    fn toggle_record(&mut self, source: crate::audio::Source) -> Result<()> {
+        let armed = &mut self.recording[source.index()];
+        if *armed {
+            *armed = false;
+            self.audio_tx.send(crate::audio::Cmd::StopRecord(source))?;
+            self.log = Some((
+                std::time::Instant::now(),
+                format!("stop record {}", source.name()),
+            ));
+        } else {
+            if !self.recording.iter().any(|v| *v) {
+                // new take; suffixed if armed twice within a second
+                std::fs::create_dir_all("recordings")?;
+                let stamp = timestamp();
+                self.take = stamp.clone();
+                let mut suffix = 1;
+                while std::fs::read_dir("recordings")?
+                    .filter_map(|v| v.ok())
+                    .any(|v| {
+                        v.file_name()
+                            .to_str()
+                            .is_some_and(|v| v.starts_with(&format!("{}_", self.take)))
+                    })
+                {
+                    suffix += 1;
+                    self.take = format!("{}-{}", stamp, suffix);
+                }
+            }
+            let path = format!("recordings/{}_{}.wav", self.take, source.name());
+            let channels = self
+                .params
+                .channels
+                .load(std::sync::atomic::Ordering::Relaxed);
+            let sample_rate = self
+                .params
+                .sample_rate
+                .load(std::sync::atomic::Ordering::Relaxed);
+            let recorder = crate::record::Recorder::new(
+                source,
+                path.clone(),
+                channels,
+                sample_rate,
+                self.output.dither(),
+            )?;
+            self.audio_tx
+                .send(crate::audio::Cmd::StartRecord(source, recorder))?;
+            self.recording[source.index()] = true;
+            self.log = Some((std::time::Instant::now(), format!("record ./{}", path)));
+        }
+        Ok(())
+    }
+
+    /// cycle bank groove through ./grooves, then none
+    fn cycle_groove(&mut self, bank: crate::audio::Bank) -> Result<()> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir("grooves")?.filter_map(|v| v.ok()) {
+            let path = entry.path();
+            if entry.metadata()?.is_file()
+                && path.extension().is_some_and(|v| v.to_str() == Some("json"))
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let my_bank = match bank {
+            crate::audio::Bank::A => &mut self.bank_a,
+            crate::audio::Bank::B => &mut self.bank_b,
+        };
+        my_bank.groove = match my_bank.groove {
+            Some(i) if i + 1 < paths.len() => Some(i + 1),
+            Some(_) => None,
+            None if !paths.is_empty() => Some(0),
+            None => None,
+        };
+        let name = crate::audio::Source::Bank(bank).name();
+        if let Some(index) = my_bank.groove {
+            let groove = serde_json::from_slice::<angry_surgeon_core::Groove>(&std::fs::read(
+                &paths[index],
+            )?)?;
+            self.audio_tx.send(crate::audio::Cmd::Bank(
+                bank,
+                crate::audio::BankCmd::AssignGroove(Some(Box::new(groove))),
+            ))?;
+            self.log = Some((
+                std::time::Instant::now(),
+                format!("groove {}: {}", name, paths[index].display()),
+            ));
+        } else {
+            self.audio_tx.send(crate::audio::Cmd::Bank(
+                bank,
+                crate::audio::BankCmd::AssignGroove(None),
+            ))?;
+            self.log = Some((std::time::Instant::now(), format!("groove {}: none", name)));
+        }
+        Ok(())
+    }
+
     fn cmd(&mut self, cmd: Cmd) {
         match cmd {
             Cmd::Log(msg) => self.log = Some((std::time::Instant::now(), msg)),
             Cmd::Clock => self.clock = !self.clock,
             Cmd::Stop => self.clock = false,
+            Cmd::Rehearse(looping) => {
+                let msg = match (self.rehearsing, looping) {
+                    (false, false) => "nothing captured to rehearse",
+                    (_, true) => "rehearsing last bars",
+                    (true, false) => "rehearsal released",
+                };
+                self.rehearsing = looping;
+                self.log = Some((std::time::Instant::now(), msg.to_string()));
+            }
+            Cmd::Learn(learning) => self.learning = learning,
+            Cmd::Meter(banks, master) => self.levels = (banks, master),
+            Cmd::SaveBank(path, bytes) => {
+                let msg = match write_bank(&path, bytes) {
+                    Ok(()) => format!("saved to ./{}!", path),
+                    Err(e) => format!("not saved to ./{}: {}", path, e),
+                };
+                self.log = Some((std::time::Instant::now(), msg));
+            }
+            Cmd::Recorded(take) => {
+                let msg = match crate::resample::resample(&take) {
+                    Ok(path) => format!("resampled ./{}", path),
+                    Err(e) => format!("no resample: {}", e),
+                };
+                self.log = Some((std::time::Instant::now(), msg));
+            }
+            Cmd::Chained { linked, dropped } => {
+                let mut msg = format!("long take chained over {} more pads", linked);
+                if dropped > 0 {
+                    msg += &format!("; {} segments dropped for want of free pads", dropped);
+                }
+                self.log = Some((std::time::Instant::now(), msg));
+            }
             Cmd::Yield => {
                 self.state = GlobalState::Yield;
                 self.bank_a.state = BankState::Mangle;
@@ -498,8 +1153,20 @@ impl TuiHandler {
             }
             Cmd::LoadBd(paths) => self.state = GlobalState::LoadBd { paths },
             Cmd::LoadRd(paths) => self.state = GlobalState::LoadRd { paths },
-            Cmd::LoadOnset { name, index, count } => {
-                self.state = GlobalState::LoadOnset { name, index, count }
+            Cmd::LoadOnset {
+                name,
+                index,
+                count,
+                start,
+                edited,
+            } => {
+                self.state = GlobalState::LoadOnset {
+                    name,
+                    index,
+                    count,
+                    start,
+                    edited,
+                }
             }
             Cmd::Bank(bank, cmd) => {
                 let my_bank = match bank {
@@ -515,6 +1182,11 @@ impl TuiHandler {
                 }
                 my_bank.cmd(cmd);
             }
+            Cmd::RecordFailed(source, reason) => {
+                self.recording[source.index()] = false;
+                let msg = format!("record {} stopped: {}", source.name(), reason);
+                self.log = Some((std::time::Instant::now(), msg));
+            }
         }
     }
 
@@ -525,6 +1197,98 @@ impl TuiHandler {
     fn render_log(&self, area: Rect, buf: &mut Buffer) {
         if let Some((_, msg)) = &self.log {
             Paragraph::new(Text::raw(msg)).centered().render(area, buf);
+        } else if self.recording.iter().any(|v| *v) {
+            let sources = [
+                crate::audio::Source::Master,
+                crate::audio::Source::Oneshot,
+                crate::audio::Source::Bank(crate::audio::Bank::A),
+                crate::audio::Source::Bank(crate::audio::Bank::B),
+            ]
+            .into_iter()
+            .filter(|v| self.recording[v.index()])
+            .map(|v| v.name())
+            .collect::<Vec<_>>()
+            .join(" ");
+            Paragraph::new(Text::raw(format!("rec {}: {}", self.take, sources)))
+                .centered()
+                .render(area, buf);
+        }
+    }
+
+    /// readout of momentary and integrated loudness against target, and
+    /// whether integrated over target
+    fn meter((momentary, integrated, target): (Option<f32>, Option<f32>, f32)) -> (String, bool) {
+        let lufs = |v: Option<f32>| v.map_or("-inf".to_string(), |v| format!("{:.1}", v));
+        (
+            format!(
+                "m {} i {} / {:.1} lufs",
+                lufs(momentary),
+                lufs(integrated),
+                target
+            ),
+            integrated.is_some_and(|v| v > target),
+        )
+    }
+
+    /// readout of output latency, buffer size and rate granted; empty until
+    /// the first buffer
+    fn latency_readout(&self) -> String {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        let load = |v: &AtomicU32| v.load(Ordering::Relaxed);
+        match load(&self.params.buffer_frames) {
+            0 => String::new(),
+            frames => format!(
+                "out {:.1} ms {} @ {} hz",
+                load(&self.params.latency) as f32 / 1000.,
+                frames,
+                load(&self.params.sample_rate)
+            ),
+        }
+    }
+
+    fn render_meter(&self, area: Rect, buf: &mut Buffer) {
+        let (readout, over) = &self.meter;
+        let mut readout = readout.clone();
+        if !self.latency.is_empty() {
+            readout = format!("{}  {}", readout, self.latency);
+        }
+        if self.locked {
+            readout = format!("{}  locked", readout);
+        }
+        let paragraph = Paragraph::new(Text::raw(readout)).centered();
+        if *over {
+            paragraph.reversed().render(area, buf);
+        } else {
+            paragraph.render(area, buf);
+        }
+    }
+
+    /// peak and rms of each bank and master in dbfs, reversed if any peak is
+    /// over full scale
+    fn render_levels(&self, area: Rect, buf: &mut Buffer) {
+        let dbfs = |v: f32| {
+            if v > 0. {
+                format!("{:.0}", 20. * v.log10())
+            } else {
+                "-inf".to_string()
+            }
+        };
+        let (banks, master) = &self.levels;
+        let readout = [
+            crate::audio::Source::Bank(crate::audio::Bank::A).name(),
+            crate::audio::Source::Bank(crate::audio::Bank::B).name(),
+            crate::audio::Source::Master.name(),
+        ]
+        .into_iter()
+        .zip(banks.iter().chain([master]))
+        .map(|(name, v)| format!("{} {}/{}", name, dbfs(v.peak), dbfs(v.rms)))
+        .collect::<Vec<_>>()
+        .join("  ");
+        let paragraph = Paragraph::new(Text::raw(readout + " dbfs")).centered();
+        if banks.iter().chain([master]).any(|v| v.peak > 1.) {
+            paragraph.reversed().render(area, buf);
+        } else {
+            paragraph.render(area, buf);
         }
     }
 
@@ -685,11 +1449,14 @@ impl TuiHandler {
             .render(arrow_area, buf);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn render_load_onset(
         &self,
         name: &str,
         index: usize,
         count: usize,
+        start: u64,
+        edited: bool,
         area: Rect,
         buf: &mut Buffer,
     ) {
@@ -747,12 +1514,12 @@ impl TuiHandler {
         {
             let mut lines: [_; FILE_COUNT] = core::array::from_fn(|_| Line::raw(""));
             lines[FILE_COUNT / 2] = Line::raw(name).reversed();
-            lines[FILE_COUNT - 1] = Line::raw(format!("{:>3}/{:>3}", index, count));
+            lines[FILE_COUNT - 1] = Line::raw(format!("{:>3}/{:>3} @{}", index, count, start));
             Paragraph::new(Text::from(lines.to_vec()))
                 .left_aligned()
                 .block(
                     Block::bordered()
-                        .title(" load onset ")
+                        .title(if edited { " load onset* " } else { " load onset " })
                         .padding(Padding::horizontal(1)),
                 )
                 .render(onset_area, buf);
@@ -766,14 +1533,16 @@ impl TuiHandler {
 
 impl Widget for &TuiHandler {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let [area] = Layout::vertical(vec![Constraint::Max(FILE_COUNT as u16 + 5)])
+        let [area] = Layout::vertical(vec![Constraint::Max(FILE_COUNT as u16 + 7)])
             .flex(Flex::Center)
             .areas(area);
-        let [clock_area, area, log_area] =
-            Layout::vertical(Constraint::from_maxes([2, FILE_COUNT as u16 + 2, 1]))
+        let [clock_area, area, log_area, meter_area, levels_area] =
+            Layout::vertical(Constraint::from_maxes([2, FILE_COUNT as u16 + 2, 1, 1, 1]))
                 .flex(Flex::Center)
                 .areas(area);
         self.render_log(log_area, buf);
+        self.render_meter(meter_area, buf);
+        self.render_levels(levels_area, buf);
         self.render_clock(clock_area, buf);
         match &self.state {
             GlobalState::Yield => {
@@ -785,9 +1554,35 @@ impl Widget for &TuiHandler {
             }
             GlobalState::LoadBd { paths } => self.render_load_bd(paths, area, buf),
             GlobalState::LoadRd { paths } => self.render_load_rd(paths, area, buf),
-            GlobalState::LoadOnset { name, index, count } => {
-                self.render_load_onset(name, *index, *count, area, buf)
-            }
+            GlobalState::LoadOnset {
+                name,
+                index,
+                count,
+                start,
+                edited,
+            } => self.render_load_onset(name, *index, *count, *start, *edited, area, buf),
         }
     }
 }
+
+/// write bank encoded by the audio thread to `path`, replacing any there
+pub fn write_bank(path: &str, bytes: Result<Vec<u8>, BdError>) -> Result<()> {
+    std::fs::write(path, bytes?)?;
+    Ok(())
+}
+
+/// local time as `yyyymmdd-hhmmss`, sorting takes in order armed
+fn timestamp() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}